@@ -0,0 +1,10 @@
+-- Track ledger commit status of the last batch submitted for each property
+
+ALTER TABLE properties
+    ADD COLUMN IF NOT EXISTS sync_state VARCHAR(20) NOT NULL DEFAULT 'unsynced',
+    ADD COLUMN IF NOT EXISTS blockchain_hash VARCHAR(128),
+    ADD COLUMN IF NOT EXISTS last_sync TIMESTAMP WITH TIME ZONE,
+    ADD COLUMN IF NOT EXISTS sync_error TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_properties_sync_state
+    ON properties(sync_state);
@@ -0,0 +1,28 @@
+-- Create reconciliation_runs table
+CREATE TABLE reconciliation_runs (
+    id SERIAL PRIMARY KEY,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    properties_checked INTEGER NOT NULL DEFAULT 0,
+    drift_count INTEGER NOT NULL DEFAULT 0,
+    error TEXT
+);
+
+-- Create ledger_drift table
+CREATE TABLE ledger_drift (
+    id SERIAL PRIMARY KEY,
+    run_id INTEGER NOT NULL,
+    property_id INTEGER NOT NULL,
+    drift_type VARCHAR(50) NOT NULL,
+    database_value TEXT,
+    ledger_value TEXT,
+    resubmitted BOOLEAN NOT NULL DEFAULT false,
+    detected_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (run_id) REFERENCES reconciliation_runs(id) ON DELETE CASCADE,
+    FOREIGN KEY (property_id) REFERENCES properties(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_ledger_drift_run
+    ON ledger_drift(run_id);
+CREATE INDEX IF NOT EXISTS idx_ledger_drift_property
+    ON ledger_drift(property_id);
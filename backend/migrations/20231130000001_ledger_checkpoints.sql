@@ -0,0 +1,11 @@
+-- Create ledger_checkpoints table
+CREATE TABLE ledger_checkpoints (
+    block_id VARCHAR(128) PRIMARY KEY,
+    block_num BIGINT NOT NULL,
+    state_root_hash VARCHAR(128) NOT NULL,
+    previous_block_id VARCHAR(128) NOT NULL,
+    recorded_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_ledger_checkpoints_block_num
+    ON ledger_checkpoints(block_num DESC);
@@ -0,0 +1,3 @@
+-- Record which transaction carried each outbox entry
+ALTER TABLE ledger_outbox
+    ADD COLUMN IF NOT EXISTS transaction_id VARCHAR(128);
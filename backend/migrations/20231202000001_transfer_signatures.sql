@@ -0,0 +1,12 @@
+-- Create transfer_signatures table
+CREATE TABLE IF NOT EXISTS transfer_signatures (
+    id SERIAL PRIMARY KEY,
+    transfer_id INTEGER NOT NULL REFERENCES transfers(id) ON DELETE CASCADE,
+    role VARCHAR(16) NOT NULL,
+    public_key VARCHAR(64) NOT NULL,
+    signature VARCHAR(128) NOT NULL,
+    signed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (transfer_id, role)
+);
+
+CREATE INDEX IF NOT EXISTS idx_transfer_signatures_transfer ON transfer_signatures(transfer_id);
@@ -0,0 +1,19 @@
+-- Create certificates table
+CREATE TABLE IF NOT EXISTS certificates (
+    serial VARCHAR(64) PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    issuer VARCHAR(255) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    public_key VARCHAR(64) NOT NULL,
+    unit_id VARCHAR(255) NOT NULL,
+    roles TEXT[] NOT NULL DEFAULT '{}',
+    valid_from TIMESTAMP WITH TIME ZONE NOT NULL,
+    valid_until TIMESTAMP WITH TIME ZONE,
+    signature VARCHAR(128) NOT NULL,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    revocation_reason VARCHAR(32),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_certificates_user ON certificates(user_id);
+CREATE INDEX IF NOT EXISTS idx_certificates_issuer_revoked ON certificates(issuer, revoked_at);
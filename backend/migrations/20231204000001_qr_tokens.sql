@@ -0,0 +1,16 @@
+-- One-time QR transfer tokens, keyed by the nonce carried in the signed QR payload
+CREATE TABLE IF NOT EXISTS qr_tokens (
+    nonce UUID PRIMARY KEY,
+    property_id INTEGER NOT NULL REFERENCES properties(id),
+    custodian_id VARCHAR(255) NOT NULL,
+    issued_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    consumed_at TIMESTAMP WITH TIME ZONE,
+    consumed_by INTEGER REFERENCES users(id),
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    revocation_reason VARCHAR(32)
+);
+
+CREATE INDEX IF NOT EXISTS idx_qr_tokens_property_live
+    ON qr_tokens(property_id)
+    WHERE consumed_at IS NULL AND revoked_at IS NULL;
@@ -0,0 +1,19 @@
+-- Background jobs generating QR codes for many properties at once
+CREATE TABLE IF NOT EXISTS qr_batch_jobs (
+    id SERIAL PRIMARY KEY,
+    requested_by INTEGER NOT NULL REFERENCES users(id),
+    filter JSONB NOT NULL DEFAULT '{}'::jsonb,
+    format VARCHAR(32) NOT NULL,
+    template VARCHAR(64),
+    status VARCHAR(32) NOT NULL DEFAULT 'queued',
+    total INTEGER NOT NULL DEFAULT 0,
+    processed INTEGER NOT NULL DEFAULT 0,
+    output BYTEA,
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    started_at TIMESTAMP WITH TIME ZONE,
+    completed_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_qr_batch_jobs_requested_by
+    ON qr_batch_jobs(requested_by, created_at DESC);
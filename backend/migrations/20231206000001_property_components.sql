@@ -0,0 +1,18 @@
+-- Components and basic issue items of end items, with shortages
+CREATE TABLE IF NOT EXISTS property_components (
+    id SERIAL PRIMARY KEY,
+    property_id INTEGER NOT NULL REFERENCES properties(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    nsn VARCHAR(32),
+    kind VARCHAR(32) NOT NULL,
+    unit_of_issue VARCHAR(8) NOT NULL DEFAULT 'EA',
+    required_quantity INTEGER NOT NULL CHECK (required_quantity >= 0),
+    on_hand_quantity INTEGER NOT NULL CHECK (on_hand_quantity >= 0),
+    notes TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK (on_hand_quantity <= required_quantity)
+);
+
+CREATE INDEX IF NOT EXISTS idx_property_components_property
+    ON property_components(property_id);
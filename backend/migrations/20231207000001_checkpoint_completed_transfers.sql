@@ -0,0 +1,3 @@
+-- Transfers each applied block completed, so a fork can restore them
+ALTER TABLE ledger_checkpoints
+    ADD COLUMN IF NOT EXISTS completed_transfers JSONB NOT NULL DEFAULT '[]';
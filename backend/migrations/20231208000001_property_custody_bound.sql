@@ -0,0 +1,8 @@
+-- Whether ledger custody of the property is bound to its holder's key. Until
+-- it is, the ledger asks for an approver on every transfer
+ALTER TABLE properties
+    ADD COLUMN IF NOT EXISTS custody_bound BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Every applied transfer binds custody to the receiving key
+UPDATE properties SET custody_bound = TRUE
+WHERE id IN (SELECT property_id FROM transfers WHERE status = 'completed');
@@ -0,0 +1,82 @@
+use actix_web::{web, HttpResponse};
+use serde_json::json;
+use crate::{
+    domain::reconciliation::repository::ReconciliationRepository,
+    infrastructure::blockchain::reconciliation::ReconciliationJob,
+    types::security::SecurityContext,
+    error::api::ApiError,
+};
+use std::sync::Arc;
+
+fn require_audit_access(context: &SecurityContext) -> Result<(), ApiError> {
+    if !context.can_view_audit_log() {
+        return Err(ApiError::AuthorizationError("Cannot view reconciliation reports".into()));
+    }
+    Ok(())
+}
+
+pub async fn get_latest_reconciliation(
+    repository: web::Data<Arc<dyn ReconciliationRepository>>,
+    context: web::ReqData<SecurityContext>,
+) -> Result<HttpResponse, ApiError> {
+    require_audit_access(&context)?;
+
+    let report = repository.get_latest_report()
+        .await
+        .map_err(|e| ApiError::InternalError(e.to_string()))?
+        .ok_or_else(|| ApiError::NotFound("No reconciliation runs".into()))?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+pub async fn list_reconciliation_runs(
+    repository: web::Data<Arc<dyn ReconciliationRepository>>,
+    context: web::ReqData<SecurityContext>,
+    query: web::Query<ListRunsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    require_audit_access(&context)?;
+
+    let runs = repository.list_runs(query.limit.unwrap_or(20).clamp(1, 100))
+        .await
+        .map_err(|e| ApiError::InternalError(e.to_string()))?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "runs": runs,
+    })))
+}
+
+pub async fn get_reconciliation_report(
+    repository: web::Data<Arc<dyn ReconciliationRepository>>,
+    context: web::ReqData<SecurityContext>,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    require_audit_access(&context)?;
+
+    let report = repository.get_report(*id)
+        .await
+        .map_err(|e| ApiError::InternalError(e.to_string()))?
+        .ok_or_else(|| ApiError::NotFound(format!("Reconciliation run {}", id)))?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+pub async fn run_reconciliation(
+    job: web::Data<Arc<ReconciliationJob>>,
+    context: web::ReqData<SecurityContext>,
+) -> Result<HttpResponse, ApiError> {
+    require_audit_access(&context)?;
+    if !context.is_officer() {
+        return Err(ApiError::AuthorizationError("Only officers can start reconciliation".into()));
+    }
+
+    let report = job.run()
+        .await
+        .map_err(|e| ApiError::InternalError(e.to_string()))?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ListRunsQuery {
+    pub limit: Option<i64>,
+}
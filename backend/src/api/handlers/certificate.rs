@@ -0,0 +1,125 @@
+use actix_web::{web, HttpResponse};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use crate::{
+    domain::certificate::RevocationReason,
+    error::{api::ApiError, blockchain::BlockchainError},
+    infrastructure::blockchain::{
+        certificate::{ROLE_COMMANDER, ROLE_SUPPLY_OFFICER},
+        certificate_authority::CertificateAuthority,
+    },
+    types::security::SecurityContext,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct EnrollRequest {
+    /// Hex Ed25519 public key generated on the user's device.
+    pub public_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevokeRequest {
+    pub reason: RevocationReason,
+}
+
+fn map_error(e: BlockchainError) -> ApiError {
+    match e {
+        BlockchainError::ValidationError(message) => ApiError::ValidationError(message),
+        other => ApiError::InternalError(other.to_string()),
+    }
+}
+
+/// Certificate roles the caller's account entitles them to.
+fn certificate_roles(context: &SecurityContext) -> Vec<String> {
+    let mut roles = Vec::new();
+    if context.is_officer() {
+        roles.push(ROLE_COMMANDER.to_string());
+    }
+    if context.can_approve_transfers() {
+        roles.push(ROLE_SUPPLY_OFFICER.to_string());
+    }
+    roles
+}
+
+/// Issues a certificate for a key generated on the caller's device, binding
+/// it to their current unit. Used on first login, after a PCS and after a
+/// lost device; any earlier certificate of the caller is superseded.
+pub async fn enroll(
+    authority: web::Data<Arc<CertificateAuthority>>,
+    context: web::ReqData<SecurityContext>,
+    req: web::Json<EnrollRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let record = authority
+        .enroll(
+            context.user_id,
+            format!("user-{}", context.user_id),
+            &req.public_key,
+            context.unit_code.clone(),
+            certificate_roles(&context),
+        )
+        .await
+        .map_err(map_error)?;
+
+    Ok(HttpResponse::Created().json(json!({
+        "certificate": record.certificate,
+        "issuer": authority.certificate(),
+    })))
+}
+
+pub async fn list_certificates(
+    authority: web::Data<Arc<CertificateAuthority>>,
+    context: web::ReqData<SecurityContext>,
+) -> Result<HttpResponse, ApiError> {
+    let records = authority
+        .list_for_user(context.user_id)
+        .await
+        .map_err(map_error)?;
+
+    Ok(HttpResponse::Ok().json(records))
+}
+
+/// Revokes a certificate. Holders may revoke their own, e.g. after losing a
+/// device; officers may revoke those within their command, e.g. when a
+/// soldier leaves the unit.
+pub async fn revoke_certificate(
+    authority: web::Data<Arc<CertificateAuthority>>,
+    context: web::ReqData<SecurityContext>,
+    serial: web::Path<String>,
+    req: web::Json<RevokeRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let record = authority
+        .get(&serial)
+        .await
+        .map_err(map_error)?
+        .ok_or_else(|| ApiError::NotFound(format!("Certificate {} not found", serial)))?;
+
+    let commands_unit = context.is_officer()
+        && authority.is_within_command(&context.unit_code, &record.certificate.unit_id);
+    if record.user_id != context.user_id && !commands_unit {
+        return Err(ApiError::AuthorizationError(
+            "Only the holder or an officer of their command can revoke a certificate".to_string(),
+        ));
+    }
+
+    let revoked = authority
+        .revoke(&serial, req.reason)
+        .await
+        .map_err(map_error)?
+        .ok_or_else(|| ApiError::BadRequest(format!("Certificate {} is already revoked", serial)))?;
+
+    Ok(HttpResponse::Ok().json(revoked))
+}
+
+/// Signed revocation list of the authority, polled by nodes and devices.
+pub async fn get_revocation_list(
+    authority: web::Data<Arc<CertificateAuthority>>,
+    _context: web::ReqData<SecurityContext>,
+) -> Result<HttpResponse, ApiError> {
+    let crl = authority.revocation_list().await.map_err(map_error)?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "digest": hex::encode(crl.digest()),
+        "revocation_list": crl,
+    })))
+}
@@ -0,0 +1,97 @@
+use actix_web::{web, HttpResponse};
+use serde::Deserialize;
+use std::sync::Arc;
+use crate::{
+    error::{api::ApiError, blockchain::BlockchainError},
+    infrastructure::blockchain::explorer::{LedgerExplorer, DEFAULT_BLOCK_PAGE_SIZE},
+    types::security::SecurityContext,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct BlockPageQuery {
+    /// Block id to page back from; the head when omitted.
+    pub start: Option<String>,
+    pub limit: Option<usize>,
+}
+
+fn map_error(e: BlockchainError) -> ApiError {
+    ApiError::InternalError(e.to_string())
+}
+
+/// The ledger shows custody of every property, so browsing it is limited to
+/// accounts that may read the audit log.
+fn require_auditor(context: &SecurityContext) -> Result<(), ApiError> {
+    if context.can_view_audit_log() {
+        Ok(())
+    } else {
+        Err(ApiError::AuthorizationError(
+            "Viewing the ledger requires audit log access".to_string(),
+        ))
+    }
+}
+
+pub async fn list_blocks(
+    explorer: web::Data<Arc<LedgerExplorer>>,
+    context: web::ReqData<SecurityContext>,
+    query: web::Query<BlockPageQuery>,
+) -> Result<HttpResponse, ApiError> {
+    require_auditor(&context)?;
+    let page = explorer
+        .list_blocks(query.start.as_deref(), query.limit.unwrap_or(DEFAULT_BLOCK_PAGE_SIZE))
+        .await
+        .map_err(map_error)?
+        .ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "Unknown paging start {}",
+                query.start.as_deref().unwrap_or_default()
+            ))
+        })?;
+
+    Ok(HttpResponse::Ok().json(page))
+}
+
+pub async fn get_block(
+    explorer: web::Data<Arc<LedgerExplorer>>,
+    context: web::ReqData<SecurityContext>,
+    id: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    require_auditor(&context)?;
+    let block = explorer
+        .get_block(&id)
+        .await
+        .map_err(map_error)?
+        .ok_or_else(|| ApiError::NotFound(format!("Block {} not found", id)))?;
+
+    Ok(HttpResponse::Ok().json(block))
+}
+
+pub async fn get_transaction(
+    explorer: web::Data<Arc<LedgerExplorer>>,
+    context: web::ReqData<SecurityContext>,
+    id: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    require_auditor(&context)?;
+    let transaction = explorer
+        .get_transaction(&id)
+        .await
+        .map_err(map_error)?
+        .ok_or_else(|| ApiError::NotFound(format!("Transaction {} not found", id)))?;
+
+    Ok(HttpResponse::Ok().json(transaction))
+}
+
+/// On-chain state of a property, including its full custody history.
+pub async fn get_property_state(
+    explorer: web::Data<Arc<LedgerExplorer>>,
+    context: web::ReqData<SecurityContext>,
+    property_id: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    require_auditor(&context)?;
+    let state = explorer
+        .get_property_state(&property_id)
+        .await
+        .map_err(map_error)?
+        .ok_or_else(|| ApiError::NotFound(format!("Property {} has no ledger state", property_id)))?;
+
+    Ok(HttpResponse::Ok().json(state))
+}
@@ -59,7 +59,8 @@ pub async fn get_property_details(
             "nsn": property.nsn(),
             "serial_number": property.serial_number(),
         }),
-    );
+    )
+    .with_category(property.category);
 
     let qr_code = qr_service
         .generate_qr(&qr_data, QRFormat::PNG, &context)
@@ -1,4 +1,8 @@
+pub mod admin;
+pub mod certificate;
+pub mod ledger;
 pub mod property;
+pub mod qr;
 pub mod transfer;
 pub mod user;
 
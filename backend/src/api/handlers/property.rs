@@ -1,12 +1,26 @@
-use actix_web::{web, HttpResponse};
+use actix_multipart::Multipart;
+use actix_web::{http::header, web, HttpResponse};
+use futures::TryStreamExt;
 use serde_json::json;
 use crate::{
-    domain::property::{
-        entity::{Property, PropertyCategory, PropertyStatus},
-        service::PropertyService,
+    application::property::{
+        components::{ComponentService, NewComponent},
+        import::{ImportColumnMapping, PropertyImport, MAX_IMPORT_BYTES},
+    },
+    domain::{
+        models::qr::{render_png, render_svg, QRRenderOptions},
+        property::{
+            entity::{Property, PropertyCategory, PropertyStatus},
+            filter::PropertyFilter,
+            service::PropertyService,
+        },
+    },
+    infrastructure::{
+        documents::{HandReceiptService, LabelSheetService, LabelTemplate},
+        export::{ExportFormat, ExportService},
     },
     types::security::SecurityContext,
-    error::api::ApiError,
+    error::{api::ApiError, validation::ValidationError, CoreError},
 };
 use std::sync::Arc;
 
@@ -150,18 +164,99 @@ pub async fn update_property(
     })))
 }
 
+/// Encoded QR payload as JSON, or rendered as an image with `format`.
 pub async fn generate_qr(
     property_service: web::Data<Arc<dyn PropertyService>>,
     context: web::ReqData<SecurityContext>,
     id: web::Path<i32>,
+    query: web::Query<QRImageQuery>,
 ) -> Result<HttpResponse, ApiError> {
+    let options = QRRenderOptions::new(query.scale, query.quiet_zone)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
     let qr_data = property_service.generate_qr(*id, &context)
         .await
         .map_err(|e| ApiError::InternalError(e.to_string()))?;
 
-    Ok(HttpResponse::Ok().json(json!({
-        "qr_data": qr_data,
-    })))
+    let render_error = |e: CoreError| ApiError::InternalError(e.to_string());
+    match query.format.as_deref() {
+        None => Ok(HttpResponse::Ok().json(json!({
+            "qr_data": qr_data,
+        }))),
+        Some("png") => Ok(HttpResponse::Ok()
+            .content_type("image/png")
+            .body(render_png(&qr_data, &options).map_err(render_error)?)),
+        Some("svg") => Ok(HttpResponse::Ok()
+            .content_type("image/svg+xml")
+            .body(render_svg(&qr_data, &options).map_err(render_error)?)),
+        Some(format) => Err(ApiError::BadRequest(format!("Unknown QR image format: {}", format))),
+    }
+}
+
+/// Printable label sheet (PDF) for the caller's unit property.
+pub async fn get_label_sheet(
+    label_service: web::Data<Arc<LabelSheetService>>,
+    context: web::ReqData<SecurityContext>,
+    query: web::Query<LabelSheetQuery>,
+) -> Result<HttpResponse, ApiError> {
+    if !context.is_officer() && !context.is_nco() {
+        return Err(ApiError::AuthorizationError(
+            "Only Officers and NCOs can print property labels".to_string(),
+        ));
+    }
+
+    let template_name = query.template.as_deref().unwrap_or("avery-5160");
+    let template = LabelTemplate::by_name(template_name)
+        .ok_or_else(|| ApiError::BadRequest(format!("Unknown label template: {}", template_name)))?;
+    let ids = query
+        .ids
+        .as_deref()
+        .map(|ids| {
+            ids.split(',')
+                .map(|id| id.trim().parse::<i32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| ApiError::BadRequest(format!("Invalid property ids: {}", ids)))
+        })
+        .transpose()?;
+
+    let pdf = label_service
+        .unit_sheets(&template, ids.as_deref(), &context)
+        .await
+        .map_err(|e| ApiError::InternalError(e.to_string()))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/pdf")
+        .insert_header(("Content-Disposition", "inline; filename=\"property-labels.pdf\""))
+        .body(pdf))
+}
+
+/// DA Form 2062 (PDF) for the property signed to `holder_id` and/or carried
+/// on `hand_receipt_number`. The document's SHA-256, also recorded in the
+/// audit log, is returned in the `X-Document-SHA256` header.
+pub async fn get_hand_receipt(
+    hand_receipts: web::Data<Arc<HandReceiptService>>,
+    context: web::ReqData<SecurityContext>,
+    query: web::Query<HandReceiptQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let query = query.into_inner();
+    let generated = hand_receipts
+        .generate(query.holder_id, query.hand_receipt_number, &context)
+        .await
+        .map_err(|e| match e {
+            CoreError::Validation(msg) => ApiError::BadRequest(msg),
+            CoreError::NotFound(msg) => ApiError::NotFound(msg),
+            CoreError::Authorization(msg) => ApiError::AuthorizationError(msg),
+            e => ApiError::InternalError(e.to_string()),
+        })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/pdf")
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", generated.filename()),
+        ))
+        .insert_header(("X-Document-SHA256", generated.sha256))
+        .body(generated.pdf))
 }
 
 pub async fn get_sync_status(
@@ -177,9 +272,202 @@ pub async fn get_sync_status(
         "is_synced": status.is_synced,
         "last_sync": status.last_sync,
         "blockchain_hash": status.blockchain_hash,
+        "state": status.state,
+        "error": status.error,
+    })))
+}
+
+/// Imports a property book from a CSV or XLSX upload. The multipart body
+/// holds the sheet in a `file` field and, optionally, an
+/// [`ImportColumnMapping`] as JSON in a `mapping` field. Imports are dry runs
+/// unless `dry_run=false`; a file with any bad row is never committed.
+pub async fn import_properties(
+    import: web::Data<Arc<PropertyImport>>,
+    context: web::ReqData<SecurityContext>,
+    query: web::Query<ImportQuery>,
+    mut payload: Multipart,
+) -> Result<HttpResponse, ApiError> {
+    let mut file = None;
+    let mut mapping = ImportColumnMapping::default();
+    while let Some(mut field) = payload
+        .try_next()
+        .await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field
+            .try_next()
+            .await
+            .map_err(|e| ApiError::BadRequest(e.to_string()))?
+        {
+            if bytes.len() + chunk.len() > MAX_IMPORT_BYTES {
+                return Err(ApiError::BadRequest(format!(
+                    "Imports must be smaller than {} bytes",
+                    MAX_IMPORT_BYTES
+                )));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        match name.as_str() {
+            "file" => file = Some(bytes),
+            "mapping" => {
+                mapping = serde_json::from_slice(&bytes)
+                    .map_err(|e| ApiError::BadRequest(format!("Invalid column mapping: {}", e)))?
+            }
+            _ => {}
+        }
+    }
+    let file = file.ok_or_else(|| ApiError::BadRequest("No file uploaded".to_string()))?;
+
+    let report = import
+        .import(&file, &mapping, query.dry_run.unwrap_or(true), &context)
+        .await
+        .map_err(|e| match e {
+            ValidationError::Authorization(msg) => ApiError::AuthorizationError(msg),
+            ValidationError::InsufficientPermissions => ApiError::AuthorizationError(e.to_string()),
+            ValidationError::InvalidField(msg) => ApiError::ValidationError(msg),
+            e => ApiError::InternalError(e.to_string()),
+        })?;
+
+    Ok(if report.committed {
+        HttpResponse::Created().json(report)
+    } else if report.dry_run {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::UnprocessableEntity().json(report)
+    })
+}
+
+fn component_error(e: ValidationError) -> ApiError {
+    match e {
+        ValidationError::Authorization(msg) => ApiError::AuthorizationError(msg),
+        ValidationError::InsufficientPermissions => ApiError::AuthorizationError(e.to_string()),
+        ValidationError::NotFound(msg) => ApiError::NotFound(msg),
+        ValidationError::InvalidField(msg) | ValidationError::InvalidState(msg) => ApiError::ValidationError(msg),
+        e => ApiError::InternalError(e.to_string()),
+    }
+}
+
+/// Component listing of an end item, with each component's shortage.
+pub async fn list_components(
+    components: web::Data<Arc<ComponentService>>,
+    context: web::ReqData<SecurityContext>,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let components = components
+        .list_components(*id, &context)
+        .await
+        .map_err(component_error)?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "components": components
+            .iter()
+            .map(|component| json!({ "component": component, "shortage": component.shortage() }))
+            .collect::<Vec<_>>(),
     })))
 }
 
+pub async fn add_component(
+    components: web::Data<Arc<ComponentService>>,
+    context: web::ReqData<SecurityContext>,
+    id: web::Path<i32>,
+    req: web::Json<NewComponent>,
+) -> Result<HttpResponse, ApiError> {
+    let component = components
+        .add_component(*id, req.into_inner(), &context)
+        .await
+        .map_err(component_error)?;
+
+    Ok(HttpResponse::Created().json(component))
+}
+
+pub async fn remove_component(
+    components: web::Data<Arc<ComponentService>>,
+    context: web::ReqData<SecurityContext>,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, component_id) = path.into_inner();
+    components
+        .remove_component(id, component_id, &context)
+        .await
+        .map_err(component_error)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// The end item's shortage annex: only components with something missing.
+pub async fn get_shortage_annex(
+    components: web::Data<Arc<ComponentService>>,
+    context: web::ReqData<SecurityContext>,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let annex = components
+        .shortage_annex(*id, &context)
+        .await
+        .map_err(component_error)?;
+
+    Ok(HttpResponse::Ok().json(annex))
+}
+
+/// Records part of a component as missing.
+pub async fn record_shortage(
+    components: web::Data<Arc<ComponentService>>,
+    context: web::ReqData<SecurityContext>,
+    path: web::Path<(i32, i32)>,
+    req: web::Json<ComponentQuantityRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, component_id) = path.into_inner();
+    let component = components
+        .record_shortage(id, component_id, req.quantity, &context)
+        .await
+        .map_err(component_error)?;
+
+    Ok(HttpResponse::Ok().json(component))
+}
+
+/// Records a shortage as filled.
+pub async fn record_fill(
+    components: web::Data<Arc<ComponentService>>,
+    context: web::ReqData<SecurityContext>,
+    path: web::Path<(i32, i32)>,
+    req: web::Json<ComponentQuantityRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, component_id) = path.into_inner();
+    let component = components
+        .record_fill(id, component_id, req.quantity, &context)
+        .await
+        .map_err(component_error)?;
+
+    Ok(HttpResponse::Ok().json(component))
+}
+
+/// Streams the property book in the requested format. Takes the same
+/// filter fields as QR batches (`unit`, `category`, `status`,
+/// `hand_receipt_number`, `holder_id`) as query parameters.
+pub async fn export_properties(
+    export: web::Data<Arc<ExportService>>,
+    context: web::ReqData<SecurityContext>,
+    query: web::Query<ExportQuery>,
+    filter: web::Query<PropertyFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let format = query.format.unwrap_or(ExportFormat::Csv);
+    let body = export
+        .export_properties(format, filter.into_inner(), &context)
+        .map_err(|e| match e {
+            CoreError::Authorization(msg) => ApiError::AuthorizationError(msg),
+            e => ApiError::InternalError(e.to_string()),
+        })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"properties.{}\"", format.extension()),
+        ))
+        .streaming(body))
+}
+
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct CreatePropertyRequest {
     pub name: String,
@@ -196,6 +484,47 @@ pub struct CreatePropertyRequest {
     pub requires_approval: Option<bool>,
 }
 
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct QRImageQuery {
+    /// `png` or `svg`; the encoded payload is returned as JSON if absent.
+    pub format: Option<String>,
+    /// Pixels per module, at most `QRRenderOptions::MAX_SCALE`.
+    pub scale: Option<u32>,
+    /// Border in modules, at most `QRRenderOptions::MAX_QUIET_ZONE`.
+    pub quiet_zone: Option<u32>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct LabelSheetQuery {
+    /// Sheet layout, e.g. `avery-5160` (default) or `avery-5163`.
+    pub template: Option<String>,
+    /// Comma separated property ids; all of the unit's property if absent.
+    pub ids: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ComponentQuantityRequest {
+    pub quantity: i32,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct HandReceiptQuery {
+    pub holder_id: Option<i32>,
+    pub hand_receipt_number: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ExportQuery {
+    /// `csv` (default), `jsonl` or `parquet`.
+    pub format: Option<ExportFormat>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ImportQuery {
+    /// Only report what would be imported; defaults to true.
+    pub dry_run: Option<bool>,
+}
+
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct UpdatePropertyRequest {
     pub name: Option<String>,
@@ -215,9 +544,19 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/properties")
             .route("", web::post().to(create_property))
+            .route("/labels", web::get().to(get_label_sheet))
+            .route("/hand-receipt", web::get().to(get_hand_receipt))
+            .route("/import", web::post().to(import_properties))
+            .route("/export", web::get().to(export_properties))
             .route("/{id}", web::get().to(get_property))
             .route("/{id}", web::put().to(update_property))
             .route("/{id}/qr", web::get().to(generate_qr))
             .route("/{id}/sync", web::get().to(get_sync_status))
+            .route("/{id}/components", web::get().to(list_components))
+            .route("/{id}/components", web::post().to(add_component))
+            .route("/{id}/components/{component_id}", web::delete().to(remove_component))
+            .route("/{id}/components/{component_id}/shortage", web::post().to(record_shortage))
+            .route("/{id}/components/{component_id}/fill", web::post().to(record_fill))
+            .route("/{id}/shortages", web::get().to(get_shortage_annex))
     );
 }
@@ -0,0 +1,175 @@
+use actix_multipart::Multipart;
+use actix_web::{http::header, web, HttpResponse};
+use futures::TryStreamExt;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use crate::{
+    domain::{
+        models::qr_image::{decode_qr_image, QRImageService, MAX_QR_IMAGES, MAX_QR_IMAGE_BYTES},
+        property::filter::PropertyFilter,
+        qr_batch::entity::{QRBatchFormat, QRBatchJob},
+    },
+    infrastructure::documents::QRBatchService,
+    types::{permissions::Permission, security::SecurityContext},
+    error::{api::ApiError, CoreError},
+};
+
+fn map_error(e: CoreError) -> ApiError {
+    match e {
+        CoreError::Validation(message) => ApiError::ValidationError(message),
+        CoreError::NotFound(message) => ApiError::NotFound(message),
+        CoreError::Authorization(message) => ApiError::AuthorizationError(message),
+        other => ApiError::InternalError(other.to_string()),
+    }
+}
+
+/// Decodes the QR codes in uploaded PNG or JPEG images. Each multipart file
+/// field is one image; every code found is verified and resolved to its
+/// property. Unreadable images and rejected codes are reported per item.
+pub async fn decode_qr_images(
+    qr_images: web::Data<Arc<QRImageService>>,
+    context: web::ReqData<SecurityContext>,
+    mut payload: Multipart,
+) -> Result<HttpResponse, ApiError> {
+    if !context.has_permission(&Permission::ViewProperty) {
+        return Err(ApiError::AuthorizationError(
+            "Decoding QR codes requires permission to view property".to_string(),
+        ));
+    }
+
+    let mut images = Vec::new();
+    while let Some(mut field) = payload
+        .try_next()
+        .await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?
+    {
+        let filename = field
+            .content_disposition()
+            .and_then(|disposition| disposition.get_filename())
+            .map(str::to_string);
+        let is_image = field
+            .content_type()
+            .is_some_and(|mime| mime.type_() == mime::IMAGE);
+        if filename.is_none() && !is_image {
+            // Plain form fields carry no image
+            continue;
+        }
+        if images.len() == MAX_QR_IMAGES {
+            return Err(ApiError::BadRequest(format!(
+                "At most {} images can be decoded per request",
+                MAX_QR_IMAGES
+            )));
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field
+            .try_next()
+            .await
+            .map_err(|e| ApiError::BadRequest(e.to_string()))?
+        {
+            if bytes.len() + chunk.len() > MAX_QR_IMAGE_BYTES {
+                return Err(ApiError::BadRequest(format!(
+                    "Images must be smaller than {} bytes",
+                    MAX_QR_IMAGE_BYTES
+                )));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        // Image decoding is CPU bound; keep it off the async workers
+        let decoded = web::block(move || decode_qr_image(&bytes))
+            .await
+            .map_err(|e| ApiError::InternalError(e.to_string()))?;
+        images.push(qr_images.resolve_image(filename, decoded, &context).await);
+    }
+
+    if images.is_empty() {
+        return Err(ApiError::BadRequest("No images uploaded".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(json!({ "images": images })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateQRBatchRequest {
+    #[serde(default)]
+    pub filter: PropertyFilter,
+    pub format: QRBatchFormat,
+    /// Label template for `label_sheet`, e.g. `avery-5160`.
+    pub template: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListQRBatchesQuery {
+    pub limit: Option<i64>,
+}
+
+fn job_json(job: &QRBatchJob) -> serde_json::Value {
+    json!({
+        "job": job,
+        "progress": job.progress(),
+    })
+}
+
+/// Starts generating QR codes for every property matching the filter. The
+/// job runs in the background; poll it and download the result when done.
+pub async fn create_qr_batch(
+    batches: web::Data<Arc<QRBatchService>>,
+    context: web::ReqData<SecurityContext>,
+    req: web::Json<CreateQRBatchRequest>,
+) -> Result<HttpResponse, ApiError> {
+    if !context.can_generate_qr() {
+        return Err(ApiError::AuthorizationError(
+            "Generating QR codes requires QR code permission".to_string(),
+        ));
+    }
+    let req = req.into_inner();
+
+    let job = batches
+        .submit(req.filter, req.format, req.template, &context)
+        .await
+        .map_err(map_error)?;
+
+    Ok(HttpResponse::Accepted().json(job_json(&job)))
+}
+
+pub async fn list_qr_batches(
+    batches: web::Data<Arc<QRBatchService>>,
+    context: web::ReqData<SecurityContext>,
+    query: web::Query<ListQRBatchesQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let jobs = batches
+        .list_jobs(query.limit.unwrap_or(20).clamp(1, 100), &context)
+        .await
+        .map_err(map_error)?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "jobs": jobs.iter().map(job_json).collect::<Vec<_>>(),
+    })))
+}
+
+pub async fn get_qr_batch(
+    batches: web::Data<Arc<QRBatchService>>,
+    context: web::ReqData<SecurityContext>,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let job = batches.get_job(*id, &context).await.map_err(map_error)?;
+
+    Ok(HttpResponse::Ok().json(job_json(&job)))
+}
+
+pub async fn download_qr_batch(
+    batches: web::Data<Arc<QRBatchService>>,
+    context: web::ReqData<SecurityContext>,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let (job, output) = batches.download(*id, &context).await.map_err(map_error)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(job.format.content_type())
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", job.filename()),
+        ))
+        .body(output))
+}
@@ -1,18 +1,25 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{http::header, web, HttpResponse};
 use serde_json::json;
 use crate::{
     domain::{
         transfer::{
             entity::{Transfer, TransferStatus},
+            filter::TransferFilter,
             service::TransferService,
         },
         models::location::Location,
     },
+    api::handlers::property::ExportQuery,
+    infrastructure::{
+        blockchain::{proof::TransferProofService, signatures::TransferSignatureService},
+        export::{ExportFormat, ExportService},
+    },
     types::security::SecurityContext,
-    error::api::ApiError,
+    error::{api::ApiError, blockchain::BlockchainError, CoreError, RepositoryError},
 };
 use std::sync::Arc;
 use chrono::{DateTime, Utc};
+use handreceipt_protocol::signing::TransferSignature;
 
 pub async fn create_transfer(
     transfer_service: web::Data<Arc<dyn TransferService>>,
@@ -92,11 +99,15 @@ pub async fn scan_qr_transfer(
     context: web::ReqData<SecurityContext>,
     req: web::Json<ScanQRRequest>,
 ) -> Result<HttpResponse, ApiError> {
-    let transfer = transfer_service.scan_qr_transfer(&req.qr_data, &req.location, &context)
+    let scan = transfer_service.scan_qr_transfer(&req.qr_data, &req.location, &context)
         .await
-        .map_err(|e| ApiError::InternalError(e.to_string()))?;
+        .map_err(|e| match e {
+            RepositoryError::Validation(message) => ApiError::ValidationError(message),
+            RepositoryError::NotFound(message) => ApiError::NotFound(message),
+            other => ApiError::InternalError(other.to_string()),
+        })?;
 
-    Ok(HttpResponse::Ok().json(transfer))
+    Ok(HttpResponse::Created().json(scan))
 }
 
 pub async fn get_pending_transfers(
@@ -130,6 +141,82 @@ pub async fn get_transfer_status(
     }
 }
 
+pub async fn get_transfer_proof(
+    transfer_service: web::Data<Arc<dyn TransferService>>,
+    proof_service: web::Data<Arc<TransferProofService>>,
+    context: web::ReqData<SecurityContext>,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let transfer = transfer_service.get_transfer(*id, &context)
+        .await
+        .map_err(|e| ApiError::InternalError(e.to_string()))?
+        .ok_or_else(|| ApiError::NotFound(format!("Transfer {} not found", id)))?;
+
+    let inclusion = proof_service.prove_transfer(transfer.id)
+        .await
+        .map_err(|e| ApiError::InternalError(e.to_string()))?
+        .ok_or_else(|| ApiError::NotFound(format!("Transfer {} has not been committed to the ledger", transfer.id)))?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "transfer_id": transfer.id,
+        "batch_id": inclusion.batch_id,
+        "transaction_id": inclusion.transaction_id,
+        "batcher_public_key": inclusion.batcher_public_key,
+        "transaction_ids": inclusion.transaction_ids,
+        "proof": inclusion.proof,
+    })))
+}
+
+pub async fn get_transfer_digest(
+    transfer_service: web::Data<Arc<dyn TransferService>>,
+    signature_service: web::Data<Arc<TransferSignatureService>>,
+    context: web::ReqData<SecurityContext>,
+    id: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let transfer = transfer_service.get_transfer(*id, &context)
+        .await
+        .map_err(|e| ApiError::InternalError(e.to_string()))?
+        .ok_or_else(|| ApiError::NotFound(format!("Transfer {} not found", id)))?;
+
+    let digest = signature_service.digest(transfer.id)
+        .await
+        .map_err(|e| ApiError::InternalError(e.to_string()))?
+        .ok_or_else(|| ApiError::NotFound(format!("Transfer {} not found", transfer.id)))?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "transfer_id": transfer.id,
+        "digest": hex::encode(digest),
+        "annex_digest": transfer.annex_digest(),
+        "shortage_annex": transfer.shortage_annex(),
+    })))
+}
+
+pub async fn add_transfer_signature(
+    transfer_service: web::Data<Arc<dyn TransferService>>,
+    signature_service: web::Data<Arc<TransferSignatureService>>,
+    context: web::ReqData<SecurityContext>,
+    id: web::Path<i32>,
+    req: web::Json<TransferSignature>,
+) -> Result<HttpResponse, ApiError> {
+    let transfer = transfer_service.get_transfer(*id, &context)
+        .await
+        .map_err(|e| ApiError::InternalError(e.to_string()))?
+        .ok_or_else(|| ApiError::NotFound(format!("Transfer {} not found", id)))?;
+
+    let signatures = signature_service.add_signature(transfer.id, &context, req.into_inner())
+        .await
+        .map_err(|e| match e {
+            BlockchainError::ValidationError(message) => ApiError::ValidationError(message),
+            other => ApiError::InternalError(other.to_string()),
+        })?
+        .ok_or_else(|| ApiError::NotFound(format!("Transfer {} not found", transfer.id)))?;
+
+    Ok(HttpResponse::Created().json(json!({
+        "transfer_id": transfer.id,
+        "signatures": signatures,
+    })))
+}
+
 pub async fn get_property_transfers(
     transfer_service: web::Data<Arc<dyn TransferService>>,
     context: web::ReqData<SecurityContext>,
@@ -157,10 +244,36 @@ pub struct ScanQRRequest {
     pub location: Location,
 }
 
+/// Streams transfer history in the requested format, filtered by the
+/// `property_id`, `status`, `holder_id`, `since` and `until` query parameters.
+pub async fn export_transfers(
+    export: web::Data<Arc<ExportService>>,
+    context: web::ReqData<SecurityContext>,
+    query: web::Query<ExportQuery>,
+    filter: web::Query<TransferFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let format = query.format.unwrap_or(ExportFormat::Csv);
+    let body = export
+        .export_transfers(format, filter.into_inner(), &context)
+        .map_err(|e| match e {
+            CoreError::Authorization(msg) => ApiError::AuthorizationError(msg),
+            e => ApiError::InternalError(e.to_string()),
+        })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"transfers.{}\"", format.extension()),
+        ))
+        .streaming(body))
+}
+
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/transfers")
             .route("", web::post().to(create_transfer))
+            .route("/export", web::get().to(export_transfers))
             .route("/{id}", web::get().to(get_transfer))
             .route("/{id}/approve", web::post().to(approve_transfer))
             .route("/scan-qr", web::post().to(scan_qr_transfer))
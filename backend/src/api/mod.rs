@@ -2,6 +2,7 @@ pub mod routes;
 pub mod types;
 pub mod handlers;
 pub mod auth;
+pub mod services;
 
 // Re-export route modules directly for convenience
 pub use routes::*;
@@ -22,9 +23,11 @@ pub use handlers::{
 // Re-export auth module
 pub use auth::*;
 
+pub use services::ApiServices;
+
 use actix_web::web;
 use actix_cors::Cors;
-use crate::api::routes::{mobile, property, transfer, user};
+use crate::api::routes::{admin, mobile, property, transfer, user};
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     // Configure CORS
@@ -39,8 +42,12 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api")
             .wrap(cors)
+            .configure(admin::configure_routes)
+            .configure(certificate::configure_routes)
+            .configure(ledger::configure_routes)
             .configure(mobile::configure_routes)
             .configure(property::configure_routes)
+            .configure(qr::configure_routes)
             .configure(transfer::configure_routes)
             .configure(user::configure_routes)
     );
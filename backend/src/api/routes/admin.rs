@@ -0,0 +1,12 @@
+use actix_web::web;
+use crate::api::handlers::admin;
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/admin")
+            .route("/reconciliation", web::get().to(admin::get_latest_reconciliation))
+            .route("/reconciliation", web::post().to(admin::run_reconciliation))
+            .route("/reconciliation/runs", web::get().to(admin::list_reconciliation_runs))
+            .route("/reconciliation/runs/{id}", web::get().to(admin::get_reconciliation_report))
+    );
+}
@@ -0,0 +1,12 @@
+use actix_web::web;
+use crate::api::handlers::certificate;
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/certificates")
+            .route("", web::get().to(certificate::list_certificates))
+            .route("/enroll", web::post().to(certificate::enroll))
+            .route("/revocations", web::get().to(certificate::get_revocation_list))
+            .route("/{serial}/revoke", web::post().to(certificate::revoke_certificate))
+    );
+}
@@ -0,0 +1,12 @@
+use actix_web::web;
+use crate::api::handlers::ledger;
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/ledger")
+            .route("/blocks", web::get().to(ledger::list_blocks))
+            .route("/blocks/{id}", web::get().to(ledger::get_block))
+            .route("/transactions/{id}", web::get().to(ledger::get_transaction))
+            .route("/state/{property_id}", web::get().to(ledger::get_property_state))
+    );
+}
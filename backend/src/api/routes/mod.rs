@@ -1,14 +1,22 @@
 use actix_web::web;
 use actix_web::web::ServiceConfig;
 
+pub mod admin;
+pub mod certificate;
+pub mod ledger;
 pub mod mobile;
 pub mod property;
+pub mod qr;
 pub mod transfer;
 pub mod user;
 
 pub fn configure_routes(cfg: &mut actix_web::web::ServiceConfig) {
+    admin::configure_routes(cfg);
+    certificate::configure_routes(cfg);
+    ledger::configure_routes(cfg);
     mobile::configure_routes(cfg);
     property::configure_routes(cfg);
+    qr::configure_routes(cfg);
     transfer::configure_routes(cfg);
     user::configure_routes(cfg);
 }
@@ -5,9 +5,19 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/properties")
             .route("", web::post().to(property::create_property))
+            .route("/labels", web::get().to(property::get_label_sheet))
+            .route("/hand-receipt", web::get().to(property::get_hand_receipt))
+            .route("/import", web::post().to(property::import_properties))
+            .route("/export", web::get().to(property::export_properties))
             .route("/{id}", web::get().to(property::get_property))
             .route("/{id}", web::put().to(property::update_property))
             .route("/{id}/qr", web::get().to(property::generate_qr))
             .route("/{id}/sync", web::get().to(property::get_sync_status))
+            .route("/{id}/components", web::get().to(property::list_components))
+            .route("/{id}/components", web::post().to(property::add_component))
+            .route("/{id}/components/{component_id}", web::delete().to(property::remove_component))
+            .route("/{id}/components/{component_id}/shortage", web::post().to(property::record_shortage))
+            .route("/{id}/components/{component_id}/fill", web::post().to(property::record_fill))
+            .route("/{id}/shortages", web::get().to(property::get_shortage_annex))
     );
 } 
\ No newline at end of file
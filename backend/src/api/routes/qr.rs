@@ -0,0 +1,13 @@
+use actix_web::web;
+use crate::api::handlers::qr;
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/qr")
+            .route("/decode", web::post().to(qr::decode_qr_images))
+            .route("/batches", web::post().to(qr::create_qr_batch))
+            .route("/batches", web::get().to(qr::list_qr_batches))
+            .route("/batches/{id}", web::get().to(qr::get_qr_batch))
+            .route("/batches/{id}/download", web::get().to(qr::download_qr_batch))
+    );
+}
@@ -5,11 +5,15 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/transfers")
             .route("", web::post().to(transfer::create_transfer))
+            .route("/export", web::get().to(transfer::export_transfers))
             .route("/{id}", web::get().to(transfer::get_transfer))
             .route("/{id}/approve", web::post().to(transfer::approve_transfer))
             .route("/scan-qr", web::post().to(transfer::scan_qr_transfer))
             .route("/pending", web::get().to(transfer::get_pending_transfers))
             .route("/{id}/status", web::get().to(transfer::get_transfer_status))
+            .route("/{id}/proof", web::get().to(transfer::get_transfer_proof))
+            .route("/{id}/digest", web::get().to(transfer::get_transfer_digest))
+            .route("/{id}/signatures", web::post().to(transfer::add_transfer_signature))
             .route("/property/{property_id}", web::get().to(transfer::get_property_transfers))
     );
 }
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use actix_web::web;
+
+use crate::{
+    application::property::{components::ComponentService, import::PropertyImport},
+    domain::{
+        models::{qr::QRCodeService, qr_image::QRImageService},
+        property::service::PropertyService,
+        reconciliation::repository::ReconciliationRepository,
+    },
+    infrastructure::{
+        blockchain::{
+            certificate_authority::CertificateAuthority, explorer::LedgerExplorer,
+            proof::TransferProofService, reconciliation::ReconciliationJob,
+            signatures::TransferSignatureService,
+        },
+        documents::{HandReceiptService, LabelSheetService, QRBatchService},
+        export::ExportService,
+    },
+};
+
+/// Services the handlers extract as `web::Data<Arc<_>>`. A service left
+/// unset is not registered, and its routes answer that it is not configured.
+#[derive(Clone, Default)]
+pub struct ApiServices {
+    pub property_service: Option<Arc<dyn PropertyService>>,
+    pub qr_service: Option<Arc<dyn QRCodeService>>,
+    pub label_sheets: Option<Arc<LabelSheetService>>,
+    pub qr_images: Option<Arc<QRImageService>>,
+    pub qr_batches: Option<Arc<QRBatchService>>,
+    pub certificate_authority: Option<Arc<CertificateAuthority>>,
+    pub explorer: Option<Arc<LedgerExplorer>>,
+    pub transfer_proofs: Option<Arc<TransferProofService>>,
+    pub transfer_signatures: Option<Arc<TransferSignatureService>>,
+    pub property_import: Option<Arc<PropertyImport>>,
+    pub exports: Option<Arc<ExportService>>,
+    pub hand_receipts: Option<Arc<HandReceiptService>>,
+    pub components: Option<Arc<ComponentService>>,
+    pub reconciliation: Option<Arc<dyn ReconciliationRepository>>,
+    /// Running reconciliation job, if a validator is configured.
+    pub reconciliation_job: Option<Arc<ReconciliationJob>>,
+}
+
+impl ApiServices {
+    /// Registers every configured service as app data.
+    pub fn register(&self, cfg: &mut web::ServiceConfig) {
+        if let Some(properties) = &self.property_service {
+            cfg.app_data(web::Data::new(properties.clone()));
+        }
+        if let Some(qr_service) = &self.qr_service {
+            cfg.app_data(web::Data::new(qr_service.clone()));
+        }
+        if let Some(label_sheets) = &self.label_sheets {
+            cfg.app_data(web::Data::new(label_sheets.clone()));
+        }
+        if let Some(qr_images) = &self.qr_images {
+            cfg.app_data(web::Data::new(qr_images.clone()));
+        }
+        if let Some(qr_batches) = &self.qr_batches {
+            cfg.app_data(web::Data::new(qr_batches.clone()));
+        }
+        if let Some(authority) = &self.certificate_authority {
+            cfg.app_data(web::Data::new(authority.clone()));
+        }
+        if let Some(explorer) = &self.explorer {
+            cfg.app_data(web::Data::new(explorer.clone()));
+        }
+        if let Some(proofs) = &self.transfer_proofs {
+            cfg.app_data(web::Data::new(proofs.clone()));
+        }
+        if let Some(signatures) = &self.transfer_signatures {
+            cfg.app_data(web::Data::new(signatures.clone()));
+        }
+        if let Some(import) = &self.property_import {
+            cfg.app_data(web::Data::new(import.clone()));
+        }
+        if let Some(exports) = &self.exports {
+            cfg.app_data(web::Data::new(exports.clone()));
+        }
+        if let Some(hand_receipts) = &self.hand_receipts {
+            cfg.app_data(web::Data::new(hand_receipts.clone()));
+        }
+        if let Some(components) = &self.components {
+            cfg.app_data(web::Data::new(components.clone()));
+        }
+        if let Some(reconciliation) = &self.reconciliation {
+            cfg.app_data(web::Data::new(reconciliation.clone()));
+        }
+        if let Some(job) = &self.reconciliation_job {
+            cfg.app_data(web::Data::new(job.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{dev::Service, http::StatusCode, test, App, HttpMessage};
+    use crate::domain::models::location::Location;
+    use crate::domain::property::entity::{Property, PropertyCategory};
+    use crate::test_support::{MockComponents, MockProperties};
+    use crate::types::security::SecurityContext;
+
+    async fn list_components(services: ApiServices) -> StatusCode {
+        let app = test::init_service(
+            App::new()
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(SecurityContext::new(7));
+                    srv.call(req)
+                })
+                .configure(|cfg| services.register(cfg))
+                .configure(crate::api::configure),
+        )
+        .await;
+        let req = test::TestRequest::get().uri("/api/properties/1/components").to_request();
+        test::call_service(&app, req).await.status()
+    }
+
+    #[actix_web::test]
+    async fn test_registered_services_reach_handlers() {
+        let mut truck = Property::new(
+            "M1083 Truck".to_string(),
+            "Cargo truck".to_string(),
+            PropertyCategory::Vehicle,
+            7,
+            Location::default(),
+        );
+        truck.id = 1;
+        let services = ApiServices {
+            components: Some(Arc::new(ComponentService::new(
+                Arc::new(MockProperties::new(vec![truck])),
+                Arc::new(MockComponents::default()),
+            ))),
+            ..Default::default()
+        };
+
+        assert_eq!(list_components(services).await, StatusCode::OK);
+        assert_eq!(list_components(ApiServices::default()).await, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -1,19 +1,58 @@
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use actix_web::web;
+use ed25519_dalek::SigningKey;
+use serde::de::DeserializeOwned;
 use sqlx::PgPool;
+use tracing::{info, warn};
 
 use crate::{
-    api::auth::{
-        AuditServiceImpl, EncryptionServiceImpl,
-        SecurityServiceImpl,
+    api::{
+        auth::{AuditServiceImpl, EncryptionServiceImpl, SecurityServiceImpl},
+        services::ApiServices,
+    },
+    application::property::{
+        components::ComponentService, import::PropertyImport, service::PropertyServiceImpl,
     },
     domain::{
-        property::repository::PropertyRepository,
+        certificate::repository::CertificateRepository,
+        component::repository::ComponentRepository,
+        models::{
+            qr::{QRCodeService, QRCodeServiceImpl},
+            qr_image::QRImageService,
+            qr_token::QRTokenPolicy,
+        },
+        property::{repository::PropertyRepository, service::PropertyService},
+        qr_batch::repository::QRBatchRepository,
         transfer::repository::TransferRepository,
+        reconciliation::repository::ReconciliationRepository,
+    },
+    infrastructure::blockchain::{
+        certificate::MilitaryCertificate,
+        certificate_authority::CertificateAuthority,
+        explorer::LedgerExplorer,
+        outbox::{OutboxRelay, OutboxRelayConfig},
+        proof::TransferProofService,
+        signatures::TransferSignatureService,
+        reconciliation::ReconciliationJob,
+        sawtooth::{LedgerEventSubscriber, SawtoothClient},
     },
+    infrastructure::documents::{
+        HandReceiptService, LabelSheetService, QRBatchService, INTERRUPTED_JOB_ERROR,
+    },
+    infrastructure::export::ExportService,
     infrastructure::persistence::{
         postgres::{
+            audit_log_repository::PgAuditLogRepository,
+            certificate_repository::PgCertificateRepository,
+            component_repository::PgComponentRepository,
+            ledger_checkpoint_repository::PgLedgerCheckpointRepository,
+            outbox_repository::PgOutboxRepository,
             property_repository::PgPropertyRepository,
+            qr_batch_repository::PgQRBatchRepository,
+            qr_token_repository::PgQRTokenRepository,
+            reconciliation_repository::PgReconciliationRepository,
             transfer_repository::PgTransferRepository,
         },
         DatabaseConfig as PersistenceConfig,
@@ -50,16 +89,243 @@ impl AppBuilder {
         result
     }
 
+    /// Reads a number of seconds from `name`, or `default` if it is unset.
+    fn env_secs(name: &str, default: u64) -> Result<u64, String> {
+        match std::env::var(name) {
+            Ok(secs) => secs.parse().map_err(|e| format!("Invalid {}: {}", name, e)),
+            Err(_) => Ok(default),
+        }
+    }
+
+    /// Reads the hex Ed25519 seed in `name`, or `None` if it is unset.
+    fn signing_key(name: &str) -> Result<Option<SigningKey>, String> {
+        let seed = match std::env::var(name) {
+            Ok(seed) => seed,
+            Err(_) => return Ok(None),
+        };
+        let seed: [u8; 32] = hex::decode(seed.trim())
+            .ok()
+            .and_then(|seed| seed.try_into().ok())
+            .ok_or_else(|| format!("{} must be 32 hex-encoded bytes", name))?;
+        Ok(Some(SigningKey::from_bytes(&seed)))
+    }
+
+    /// Reads and parses the JSON file at the path in `name`.
+    fn read_json<T: DeserializeOwned>(name: &str, path: &str) -> Result<T, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {} {}: {}", name, path, e))?;
+        serde_json::from_str(&contents).map_err(|e| format!("Invalid {} {}: {}", name, path, e))
+    }
+
+    /// Ledger client for `SAWTOOTH_REST_URL`, signing with
+    /// `SAWTOOTH_PRIVATE_KEY`, or `None` if no ledger is configured.
+    fn ledger_client() -> Result<Option<Arc<SawtoothClient>>, String> {
+        let url = match std::env::var("SAWTOOTH_REST_URL") {
+            Ok(url) => url,
+            Err(_) => {
+                info!("SAWTOOTH_REST_URL is unset, ledger jobs are disabled");
+                return Ok(None);
+            }
+        };
+        let private_key = std::env::var("SAWTOOTH_PRIVATE_KEY")
+            .map_err(|_| "SAWTOOTH_PRIVATE_KEY must be set with SAWTOOTH_REST_URL".to_string())?;
+        let client = SawtoothClient::new(url, private_key)
+            .map_err(|e| format!("Failed to create ledger client: {}", e))?;
+        Ok(Some(Arc::new(client)))
+    }
+
+    /// Certificate authority signing with the hex Ed25519 seed in
+    /// `CA_SIGNING_KEY`, or `None` if that is unset.
+    ///
+    /// `CA_CERTIFICATE` is the path of the authority's own certificate as
+    /// JSON, and the optional `CA_UNIT_HIERARCHY` the path of a JSON object
+    /// mapping each unit to its subordinate units. Revocation lists are
+    /// anchored through `ledger` when one is configured.
+    fn certificate_authority(
+        certificates: Arc<dyn CertificateRepository>,
+        ledger: Option<Arc<SawtoothClient>>,
+    ) -> Result<Option<Arc<CertificateAuthority>>, String> {
+        let signing_key = match Self::signing_key("CA_SIGNING_KEY")? {
+            Some(key) => key,
+            None => {
+                info!("CA_SIGNING_KEY is unset, the certificate authority is disabled");
+                return Ok(None);
+            }
+        };
+        let path = std::env::var("CA_CERTIFICATE")
+            .map_err(|_| "CA_CERTIFICATE must be set with CA_SIGNING_KEY".to_string())?;
+        let certificate: MilitaryCertificate = Self::read_json("CA_CERTIFICATE", &path)?;
+
+        let mut authority = CertificateAuthority::new(
+            signing_key,
+            certificate,
+            certificates,
+        )
+        .map_err(|e| format!("Failed to create certificate authority: {}", e))?;
+        if let Ok(path) = std::env::var("CA_UNIT_HIERARCHY") {
+            let hierarchy: HashMap<String, Vec<String>> = Self::read_json("CA_UNIT_HIERARCHY", &path)?;
+            authority = authority.with_unit_hierarchy(hierarchy);
+        }
+        if let Some(client) = ledger {
+            authority = authority.with_ledger(client);
+        }
+        Ok(Some(Arc::new(authority)))
+    }
+
+    /// Starts the ledger background jobs against `client`.
+    ///
+    /// The outbox relay runs every `OUTBOX_RELAY_INTERVAL_SECS` (default 5);
+    /// `RECONCILIATION_INTERVAL_SECS` (default 3600) and
+    /// `RECONCILIATION_RESUBMIT` tune the reconciliation job. Transfers only
+    /// complete once the event subscriber sees them committed, so
+    /// `SAWTOOTH_VALIDATOR_URL` (the validator's ZMQ endpoint, e.g.
+    /// `tcp://validator:4004`) is required as well.
+    fn start_ledger_jobs(
+        client: Arc<SawtoothClient>,
+        db_pool: &PgPool,
+        property_repo: Arc<dyn PropertyRepository>,
+        transfer_repo: Arc<dyn TransferRepository>,
+        reconciliation_repo: Arc<dyn ReconciliationRepository>,
+    ) -> Result<Arc<ReconciliationJob>, String> {
+        let validator_url = std::env::var("SAWTOOTH_VALIDATOR_URL")
+            .map_err(|_| "SAWTOOTH_VALIDATOR_URL must be set with SAWTOOTH_REST_URL".to_string())?;
+
+        let relay = Arc::new(OutboxRelay::new(
+            client.clone(),
+            Arc::new(PgOutboxRepository::new(db_pool.clone())),
+            property_repo.clone(),
+            OutboxRelayConfig::default(),
+        ));
+        relay.spawn(Duration::from_secs(Self::env_secs("OUTBOX_RELAY_INTERVAL_SECS", 5)?));
+
+        let subscriber = Arc::new(LedgerEventSubscriber::new(
+            validator_url,
+            property_repo.clone(),
+            transfer_repo,
+            Arc::new(PgLedgerCheckpointRepository::new(db_pool.clone())),
+        ));
+        subscriber.spawn();
+
+        let interval = Self::env_secs("RECONCILIATION_INTERVAL_SECS", 3600)?;
+        let resubmit = std::env::var("RECONCILIATION_RESUBMIT")
+            .map(|value| value == "true")
+            .unwrap_or(false);
+
+        let reconciliation = Arc::new(ReconciliationJob::new(
+            client,
+            property_repo,
+            reconciliation_repo,
+            resubmit,
+        ));
+        reconciliation.clone().spawn(Duration::from_secs(interval));
+
+        Ok(reconciliation)
+    }
+
+    /// Fails QR batch jobs left unfinished by the previous process, which
+    /// would otherwise show as queued or running forever.
+    async fn fail_interrupted_jobs(db_pool: &PgPool) -> Result<(), String> {
+        let failed = PgQRBatchRepository::new(db_pool.clone())
+            .fail_unfinished_jobs(INTERRUPTED_JOB_ERROR)
+            .await
+            .map_err(|e| format!("Failed to recover QR batch jobs: {}", e))?;
+        if failed > 0 {
+            warn!("Marked {} interrupted QR batch job(s) as failed", failed);
+        }
+        Ok(())
+    }
+
     pub async fn build(db_config: PersistenceConfig, encryption_key: String) -> Result<web::Data<AppState>, String> {
         let connection_string = Self::build_connection_string(&db_config);
         let db_pool = PgPool::connect(&connection_string)
             .await
             .map_err(|e| format!("Failed to connect to database: {}", e))?;
+        Self::fail_interrupted_jobs(&db_pool).await?;
 
         let property_repo: Arc<dyn PropertyRepository + Send + Sync> = 
             Arc::new(PgPropertyRepository::new(db_pool.clone()));
         let transfer_repo: Arc<dyn TransferRepository + Send + Sync> = 
             Arc::new(PgTransferRepository::new(db_pool.clone()));
+        let reconciliation_repo: Arc<dyn ReconciliationRepository> =
+            Arc::new(PgReconciliationRepository::new(db_pool.clone()));
+
+        // Printed labels stay valid for months, so the key that signs QR
+        // codes has to survive restarts
+        let qr_key = Self::signing_key("QR_SIGNING_KEY")?
+            .ok_or_else(|| "QR_SIGNING_KEY must be set".to_string())?;
+        let qr_service: Arc<dyn QRCodeService> = Arc::new(QRCodeServiceImpl::new(
+            qr_key,
+            Arc::new(PgQRTokenRepository::new(db_pool.clone())),
+            QRTokenPolicy::default(),
+        ));
+        let property_service: Arc<dyn PropertyService> =
+            Arc::new(PropertyServiceImpl::new(property_repo.clone(), qr_service.clone()));
+        let certificate_repo: Arc<dyn CertificateRepository> =
+            Arc::new(PgCertificateRepository::new(db_pool.clone()));
+        let component_repo: Arc<dyn ComponentRepository> =
+            Arc::new(PgComponentRepository::new(db_pool.clone()));
+
+        let ledger = Self::ledger_client()?;
+        let reconciliation_job = match &ledger {
+            Some(client) => Some(Self::start_ledger_jobs(
+                client.clone(),
+                &db_pool,
+                property_repo.clone(),
+                transfer_repo.clone(),
+                reconciliation_repo.clone(),
+            )?),
+            None => None,
+        };
+
+        let services = ApiServices {
+            property_service: Some(property_service.clone()),
+            qr_service: Some(qr_service.clone()),
+            label_sheets: Some(Arc::new(LabelSheetService::new(
+                property_service.clone(),
+                qr_service.clone(),
+            ))),
+            qr_images: Some(Arc::new(QRImageService::new(
+                qr_service.clone(),
+                property_service.clone(),
+            ))),
+            qr_batches: Some(Arc::new(QRBatchService::new(
+                Arc::new(PgQRBatchRepository::new(db_pool.clone())),
+                property_service.clone(),
+                qr_service.clone(),
+            ))),
+            certificate_authority: Self::certificate_authority(certificate_repo.clone(), ledger.clone())?,
+            explorer: ledger.clone().map(|client| Arc::new(LedgerExplorer::new(client))),
+            transfer_proofs: ledger.clone().map(|client| {
+                Arc::new(TransferProofService::new(
+                    client,
+                    Arc::new(PgOutboxRepository::new(db_pool.clone())),
+                ))
+            }),
+            transfer_signatures: Some(Arc::new(TransferSignatureService::new(
+                transfer_repo.clone(),
+                certificate_repo.clone(),
+            ))),
+            property_import: Some(Arc::new(PropertyImport::new(
+                property_repo.clone(),
+                certificate_repo.clone(),
+            ))),
+            exports: Some(Arc::new(ExportService::new(
+                property_repo.clone(),
+                transfer_repo.clone(),
+            ))),
+            hand_receipts: Some(Arc::new(HandReceiptService::new(
+                property_service.clone(),
+                transfer_repo.clone(),
+                component_repo.clone(),
+                Arc::new(PgAuditLogRepository::new(db_pool.clone())),
+            ))),
+            components: Some(Arc::new(ComponentService::new(
+                property_repo.clone(),
+                component_repo.clone(),
+            ))),
+            reconciliation: Some(reconciliation_repo.clone()),
+            reconciliation_job,
+        };
 
         let encryption_key_bytes = Self::convert_encryption_key(&encryption_key);
         let encryption = Arc::new(EncryptionServiceImpl::new(&encryption_key_bytes));
@@ -89,6 +355,8 @@ impl AppBuilder {
             security,
             property_repo,
             transfer_repo,
+            reconciliation_repo,
+            services,
         }))
     }
 }
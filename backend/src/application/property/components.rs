@@ -0,0 +1,331 @@
+//! Component listings and shortage annexes of end items.
+//!
+//! An end item (a vehicle, a weapon system) lists the components and basic
+//! issue items that should be with it. Whatever is missing is carried as a
+//! shortage on its annex until it is filled, and the annex travels with the
+//! end item on hand receipts and transfers.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain::{
+        component::{
+            entity::{ComponentKind, PropertyComponent, ShortageAnnex},
+            repository::ComponentRepository,
+        },
+        property::{entity::Property, repository::PropertyRepository},
+    },
+    error::{validation::ValidationError, RepositoryError},
+    types::security::SecurityContext,
+};
+use super::validation::is_valid_nsn;
+
+/// Column limits of the `property_components` table.
+const MAX_NAME_LENGTH: usize = 255;
+const MAX_UNIT_OF_ISSUE_LENGTH: usize = 8;
+
+/// A component to add to an end item's listing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewComponent {
+    pub name: String,
+    pub nsn: Option<String>,
+    pub kind: ComponentKind,
+    /// Defaults to `EA`.
+    pub unit_of_issue: Option<String>,
+    pub required_quantity: i32,
+    /// Defaults to the required quantity, i.e. nothing short.
+    pub on_hand_quantity: Option<i32>,
+    pub notes: Option<String>,
+}
+
+impl NewComponent {
+    fn into_component(self, property_id: i32) -> Result<PropertyComponent, ValidationError> {
+        let name = self.name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+            return Err(ValidationError::InvalidField(format!(
+                "Name must be 1 to {} characters",
+                MAX_NAME_LENGTH
+            )));
+        }
+        if let Some(nsn) = self.nsn.as_deref().filter(|nsn| !is_valid_nsn(nsn)) {
+            return Err(ValidationError::InvalidField(format!(
+                "NSN {} must be 13 digits, e.g. 1005-01-231-0973",
+                nsn
+            )));
+        }
+        if self.required_quantity < 1 {
+            return Err(ValidationError::InvalidField("Required quantity must be at least 1".to_string()));
+        }
+        let on_hand = self.on_hand_quantity.unwrap_or(self.required_quantity);
+        if !(0..=self.required_quantity).contains(&on_hand) {
+            return Err(ValidationError::InvalidField(format!(
+                "On hand quantity must be between 0 and {}",
+                self.required_quantity
+            )));
+        }
+        let unit_of_issue = self.unit_of_issue.as_deref().unwrap_or("EA").trim().to_uppercase();
+        if unit_of_issue.is_empty()
+            || unit_of_issue.len() > MAX_UNIT_OF_ISSUE_LENGTH
+            || !unit_of_issue.chars().all(|c| c.is_ascii_alphabetic())
+        {
+            return Err(ValidationError::InvalidField(format!(
+                "Unit of issue must be 1 to {} letters, e.g. EA",
+                MAX_UNIT_OF_ISSUE_LENGTH
+            )));
+        }
+
+        let mut component = PropertyComponent::new(property_id, name.to_string(), self.kind, self.required_quantity);
+        component.nsn = self.nsn;
+        component.unit_of_issue = unit_of_issue;
+        component.on_hand_quantity = on_hand;
+        component.notes = self.notes;
+        Ok(component)
+    }
+}
+
+fn repository_error(error: RepositoryError) -> ValidationError {
+    match error {
+        RepositoryError::Validation(message) => ValidationError::InvalidState(message),
+        RepositoryError::NotFound(message) => ValidationError::NotFound(message),
+        other => ValidationError::Repository(other.to_string()),
+    }
+}
+
+/// Maintains component listings and records shortages and fills.
+pub struct ComponentService {
+    properties: Arc<dyn PropertyRepository>,
+    components: Arc<dyn ComponentRepository>,
+}
+
+impl ComponentService {
+    pub fn new(properties: Arc<dyn PropertyRepository>, components: Arc<dyn ComponentRepository>) -> Self {
+        Self { properties, components }
+    }
+
+    async fn end_item(&self, property_id: i32, context: &SecurityContext) -> Result<Property, ValidationError> {
+        if !context.can_read_property() {
+            return Err(ValidationError::InsufficientPermissions);
+        }
+        self.properties
+            .get_property(property_id)
+            .await
+            .map_err(repository_error)?
+            .ok_or_else(|| ValidationError::NotFound(format!("Property {} not found", property_id)))
+    }
+
+    async fn component(&self, property_id: i32, component_id: i32) -> Result<PropertyComponent, ValidationError> {
+        self.components
+            .get_component(component_id)
+            .await
+            .map_err(repository_error)?
+            .filter(|component| component.property_id == property_id)
+            .ok_or_else(|| {
+                ValidationError::NotFound(format!("Component {} of property {} not found", component_id, property_id))
+            })
+    }
+
+    pub async fn add_component(
+        &self,
+        property_id: i32,
+        component: NewComponent,
+        context: &SecurityContext,
+    ) -> Result<PropertyComponent, ValidationError> {
+        self.end_item(property_id, context).await?;
+        if !context.can_manage_property() {
+            return Err(ValidationError::Authorization(
+                "Changing component listings requires permission to manage property".to_string(),
+            ));
+        }
+        let component = component.into_component(property_id)?;
+
+        self.components
+            .create_component(&component)
+            .await
+            .map_err(repository_error)
+    }
+
+    pub async fn list_components(
+        &self,
+        property_id: i32,
+        context: &SecurityContext,
+    ) -> Result<Vec<PropertyComponent>, ValidationError> {
+        self.end_item(property_id, context).await?;
+        self.components
+            .list_components(property_id)
+            .await
+            .map_err(repository_error)
+    }
+
+    pub async fn shortage_annex(&self, property_id: i32, context: &SecurityContext) -> Result<ShortageAnnex, ValidationError> {
+        let components = self.list_components(property_id, context).await?;
+        Ok(ShortageAnnex::new(property_id, &components))
+    }
+
+    pub async fn remove_component(
+        &self,
+        property_id: i32,
+        component_id: i32,
+        context: &SecurityContext,
+    ) -> Result<(), ValidationError> {
+        self.end_item(property_id, context).await?;
+        if !context.can_manage_property() {
+            return Err(ValidationError::Authorization(
+                "Changing component listings requires permission to manage property".to_string(),
+            ));
+        }
+        self.component(property_id, component_id).await?;
+
+        self.components
+            .delete_component(component_id)
+            .await
+            .map_err(repository_error)
+    }
+
+    /// Records `quantity` of a component as missing from its end item.
+    pub async fn record_shortage(
+        &self,
+        property_id: i32,
+        component_id: i32,
+        quantity: i32,
+        context: &SecurityContext,
+    ) -> Result<PropertyComponent, ValidationError> {
+        self.adjust(property_id, component_id, -quantity, quantity, context).await
+    }
+
+    /// Records `quantity` of a short component as replaced.
+    pub async fn record_fill(
+        &self,
+        property_id: i32,
+        component_id: i32,
+        quantity: i32,
+        context: &SecurityContext,
+    ) -> Result<PropertyComponent, ValidationError> {
+        self.adjust(property_id, component_id, quantity, quantity, context).await
+    }
+
+    async fn adjust(
+        &self,
+        property_id: i32,
+        component_id: i32,
+        delta: i32,
+        quantity: i32,
+        context: &SecurityContext,
+    ) -> Result<PropertyComponent, ValidationError> {
+        let property = self.end_item(property_id, context).await?;
+        // The holder reports on what they hold; supply can adjust anything
+        if property.current_holder_id != context.user_id && !context.can_update_property() {
+            return Err(ValidationError::Authorization(
+                "Only the holder or property managers can record shortages and fills".to_string(),
+            ));
+        }
+        if quantity < 1 {
+            return Err(ValidationError::InvalidField("Quantity must be at least 1".to_string()));
+        }
+        self.component(property_id, component_id).await?;
+
+        self.components
+            .adjust_on_hand(component_id, delta)
+            .await
+            .map_err(repository_error)?
+            .ok_or_else(|| ValidationError::NotFound(format!("Component {} not found", component_id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{models::location::Location, property::entity::PropertyCategory},
+        test_support::{MockComponents, MockProperties},
+        types::security::Role,
+    };
+
+    const HOLDER: i32 = 5;
+
+    fn service() -> ComponentService {
+        let mut truck = Property::new(
+            "Truck, cargo, LMTV".to_string(),
+            String::new(),
+            PropertyCategory::Vehicle,
+            HOLDER,
+            Location::default(),
+        );
+        truck.id = 1;
+        ComponentService::new(Arc::new(MockProperties::new(vec![truck])), Arc::new(MockComponents::default()))
+    }
+
+    fn new_component(name: &str, required_quantity: i32) -> NewComponent {
+        NewComponent {
+            name: name.to_string(),
+            nsn: None,
+            kind: ComponentKind::BasicIssueItem,
+            unit_of_issue: None,
+            required_quantity,
+            on_hand_quantity: None,
+            notes: None,
+        }
+    }
+
+    fn officer() -> SecurityContext {
+        SecurityContext { role: Role::Officer, ..SecurityContext::new(1) }
+    }
+
+    #[tokio::test]
+    async fn test_shortages_and_fills() {
+        let service = service();
+        let cans = service.add_component(1, new_component("Can, water, 5 gal", 2), &officer()).await.unwrap();
+        let cable = service.add_component(1, new_component("Cable, slave", 1), &officer()).await.unwrap();
+        assert!(service.shortage_annex(1, &officer()).await.unwrap().is_empty());
+
+        // The holder reports a missing can and cable
+        let holder = SecurityContext::new(HOLDER);
+        service.record_shortage(1, cans.id, 1, &holder).await.unwrap();
+        service.record_shortage(1, cable.id, 1, &holder).await.unwrap();
+        assert!(matches!(
+            service.record_shortage(1, cable.id, 1, &holder).await,
+            Err(ValidationError::InvalidState(_))
+        ));
+        let annex = service.shortage_annex(1, &holder).await.unwrap();
+        assert_eq!(annex.lines.iter().map(|line| line.shortage).collect::<Vec<_>>(), vec![1, 1]);
+
+        let filled = service.record_fill(1, cans.id, 1, &officer()).await.unwrap();
+        assert_eq!(filled.on_hand_quantity, 2);
+        assert_eq!(service.shortage_annex(1, &holder).await.unwrap().lines.len(), 1);
+        assert!(matches!(
+            service.record_fill(1, cans.id, 1, &officer()).await,
+            Err(ValidationError::InvalidState(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_permissions_and_validation() {
+        let service = service();
+        let soldier = SecurityContext::new(9);
+        assert!(matches!(
+            service.add_component(1, new_component("Jack", 1), &soldier).await,
+            Err(ValidationError::Authorization(_))
+        ));
+        assert!(matches!(
+            service.add_component(1, new_component("Jack", 0), &officer()).await,
+            Err(ValidationError::InvalidField(_))
+        ));
+        assert!(matches!(
+            service.add_component(2, new_component("Jack", 1), &officer()).await,
+            Err(ValidationError::NotFound(_))
+        ));
+
+        let jack = service.add_component(1, new_component("Jack", 1), &officer()).await.unwrap();
+        assert_eq!(jack.unit_of_issue, "EA");
+        // Neither the holder nor a property manager
+        assert!(matches!(
+            service.record_shortage(1, jack.id, 1, &soldier).await,
+            Err(ValidationError::Authorization(_))
+        ));
+        assert!(matches!(
+            service.record_shortage(1, 99, 1, &SecurityContext::new(HOLDER)).await,
+            Err(ValidationError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,674 @@
+//! Property book import.
+//!
+//! Units keep their property books in spreadsheets. An import reads a CSV or
+//! XLSX export, maps its columns onto property fields and checks every row
+//! before anything is written. A dry run only reports; a commit creates all
+//! rows in one transaction, or none if any row is invalid or a duplicate.
+//!
+//! Holders are known by their certificates, so a named holder must have an
+//! active one issued in the importer's unit.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::Arc;
+
+use calamine::{open_workbook_from_rs, Reader, Xlsx};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain::{
+        certificate::repository::CertificateRepository,
+        models::location::Location,
+        property::{
+            entity::{Property, PropertyCategory},
+            repository::PropertyRepository,
+        },
+    },
+    error::validation::ValidationError,
+    types::security::SecurityContext,
+};
+use super::validation::PropertyValidator;
+
+/// Largest accepted upload.
+pub const MAX_IMPORT_BYTES: usize = 10 * 1024 * 1024;
+
+/// Most data rows accepted in one import.
+pub const MAX_IMPORT_ROWS: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Csv,
+    Xlsx,
+}
+
+impl ImportFormat {
+    /// XLSX files are zip archives; anything else is read as CSV.
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(b"PK\x03\x04") {
+            ImportFormat::Xlsx
+        } else {
+            ImportFormat::Csv
+        }
+    }
+}
+
+/// Header of the column holding each field. A field left unset is read from
+/// its default header if the sheet has one; a header that is set must exist.
+/// Headers match ignoring case, spaces and punctuation, so `Serial Number`
+/// finds the default `serial_number`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportColumnMapping {
+    pub name: Option<String>,
+    pub nsn: Option<String>,
+    pub serial_number: Option<String>,
+    pub quantity: Option<String>,
+    pub category: Option<String>,
+    /// User id of the holder; the importer holds rows without one.
+    pub holder: Option<String>,
+    pub hand_receipt_number: Option<String>,
+    /// Yes or no; rows without one are sensitive if they are weapons or
+    /// ammunition.
+    pub sensitive: Option<String>,
+    /// Yes or no; rows without one require approval if they are sensitive.
+    pub requires_approval: Option<String>,
+}
+
+/// Column index of each mapped field.
+struct Columns {
+    name: usize,
+    nsn: Option<usize>,
+    serial_number: Option<usize>,
+    quantity: Option<usize>,
+    category: Option<usize>,
+    holder: Option<usize>,
+    hand_receipt_number: Option<usize>,
+    sensitive: Option<usize>,
+    requires_approval: Option<usize>,
+}
+
+impl Columns {
+    fn resolve(mapping: &ImportColumnMapping, headers: &[String]) -> Result<Self, ValidationError> {
+        let headers: Vec<String> = headers.iter().map(|header| normalize_header(header)).collect();
+        let find = |mapped: &Option<String>, default: &str| -> Result<Option<usize>, ValidationError> {
+            match mapped {
+                Some(header) => headers
+                    .iter()
+                    .position(|candidate| *candidate == normalize_header(header))
+                    .map(Some)
+                    .ok_or_else(|| ValidationError::InvalidField(format!("Column {} not found", header))),
+                None => Ok(headers.iter().position(|candidate| candidate == default)),
+            }
+        };
+
+        Ok(Self {
+            name: find(&mapping.name, "name")?
+                .ok_or_else(|| ValidationError::InvalidField("A name column is required".to_string()))?,
+            nsn: find(&mapping.nsn, "nsn")?,
+            serial_number: find(&mapping.serial_number, "serialnumber")?,
+            quantity: find(&mapping.quantity, "quantity")?,
+            category: find(&mapping.category, "category")?,
+            holder: find(&mapping.holder, "holder")?,
+            hand_receipt_number: find(&mapping.hand_receipt_number, "handreceiptnumber")?,
+            sensitive: find(&mapping.sensitive, "sensitive")?,
+            requires_approval: find(&mapping.requires_approval, "requiresapproval")?,
+        })
+    }
+}
+
+fn normalize_header(header: &str) -> String {
+    header
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Outcome of one data row.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportRow {
+    /// Line in the sheet, counting the header as line 1.
+    pub line: usize,
+    /// The property the row would create, when it could be read.
+    pub property: Option<Property>,
+    pub errors: Vec<String>,
+    /// What the row duplicates, e.g. `line 4` or `property 17`.
+    pub duplicate_of: Option<String>,
+}
+
+impl ImportRow {
+    pub fn is_valid(&self) -> bool {
+        self.property.is_some() && self.errors.is_empty() && self.duplicate_of.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    pub format: ImportFormat,
+    pub dry_run: bool,
+    /// Whether the rows were stored.
+    pub committed: bool,
+    pub total_rows: usize,
+    pub valid_rows: usize,
+    pub invalid_rows: usize,
+    pub duplicate_rows: usize,
+    pub rows: Vec<ImportRow>,
+    /// Ids of the created properties, in row order.
+    pub created: Vec<i32>,
+}
+
+pub struct PropertyImport {
+    repository: Arc<dyn PropertyRepository + Send + Sync>,
+    certificates: Arc<dyn CertificateRepository>,
+    validator: PropertyValidator,
+}
+
+impl PropertyImport {
+    pub fn new(
+        repository: Arc<dyn PropertyRepository + Send + Sync>,
+        certificates: Arc<dyn CertificateRepository>,
+    ) -> Self {
+        Self {
+            repository: repository.clone(),
+            certificates,
+            validator: PropertyValidator::new(repository),
+        }
+    }
+
+    /// Reads and checks every row of `bytes`. If `dry_run` is not set and
+    /// every row is valid, all rows are then created atomically.
+    pub async fn import(
+        &self,
+        bytes: &[u8],
+        mapping: &ImportColumnMapping,
+        dry_run: bool,
+        context: &SecurityContext,
+    ) -> Result<ImportReport, ValidationError> {
+        if !context.can_create_property() {
+            return Err(ValidationError::Authorization("User does not have permission to import properties".to_string()));
+        }
+
+        let format = ImportFormat::detect(bytes);
+        let table = read_table(format, bytes)?;
+        let (headers, records) = table
+            .split_first()
+            .ok_or_else(|| ValidationError::InvalidField("The file is empty".to_string()))?;
+        let columns = Columns::resolve(mapping, headers)?;
+
+        // Line numbers are kept before blank rows are dropped
+        let records: Vec<(usize, &Vec<String>)> = records
+            .iter()
+            .enumerate()
+            .map(|(index, record)| (index + 2, record))
+            .filter(|(_, record)| record.iter().any(|cell| !cell.trim().is_empty()))
+            .collect();
+        if records.is_empty() {
+            return Err(ValidationError::InvalidField("The file has no rows to import".to_string()));
+        }
+        if records.len() > MAX_IMPORT_ROWS {
+            return Err(ValidationError::InvalidField(format!(
+                "At most {} rows can be imported at once",
+                MAX_IMPORT_ROWS
+            )));
+        }
+
+        let existing = self.repository
+            .list_properties()
+            .await
+            .map_err(|e| ValidationError::Repository(e.to_string()))?;
+        let mut seen: HashMap<(Option<String>, String), String> = existing
+            .iter()
+            .filter_map(|property| {
+                duplicate_key(property).map(|key| (key, format!("property {}", property.id)))
+            })
+            .collect();
+
+        let mut holders: HashMap<i32, Option<String>> = HashMap::new();
+        let mut rows = Vec::with_capacity(records.len());
+        for (line, record) in records {
+            let mut row = match read_row(&columns, record, context) {
+                Ok(property) => ImportRow { line, property: Some(property), errors: Vec::new(), duplicate_of: None },
+                Err(errors) => ImportRow { line, property: None, errors, duplicate_of: None },
+            };
+            if let Some(property) = &row.property {
+                if let Err(e) = self.validator.validate_create(property, context).await {
+                    row.errors.push(e.to_string());
+                }
+                let holder = property.current_holder_id;
+                let problem = match holders.get(&holder) {
+                    Some(problem) => problem.clone(),
+                    None => {
+                        let problem = self.holder_problem(holder, context).await?;
+                        holders.entry(holder).or_insert(problem).clone()
+                    }
+                };
+                if let Some(problem) = problem {
+                    row.errors.push(problem);
+                }
+                if let Some(key) = duplicate_key(property) {
+                    match seen.get(&key) {
+                        Some(original) => row.duplicate_of = Some(original.clone()),
+                        None => {
+                            seen.insert(key, format!("line {}", line));
+                        }
+                    }
+                }
+            }
+            rows.push(row);
+        }
+
+        let valid_rows = rows.iter().filter(|row| row.is_valid()).count();
+        let mut report = ImportReport {
+            format,
+            dry_run,
+            committed: false,
+            total_rows: rows.len(),
+            valid_rows,
+            invalid_rows: rows.iter().filter(|row| !row.errors.is_empty()).count(),
+            duplicate_rows: rows.iter().filter(|row| row.duplicate_of.is_some()).count(),
+            rows,
+            created: Vec::new(),
+        };
+        if dry_run || valid_rows < report.total_rows {
+            return Ok(report);
+        }
+
+        let properties = report.rows.iter().filter_map(|row| row.property.clone()).collect();
+        let created = self.repository
+            .create_properties(properties)
+            .await
+            .map_err(|e| ValidationError::Repository(e.to_string()))?;
+        report.created = created.iter().map(|property| property.id).collect();
+        for (row, property) in report.rows.iter_mut().zip(created) {
+            row.property = Some(property);
+        }
+        report.committed = true;
+        Ok(report)
+    }
+
+    /// Why `holder` cannot hold property in the importer's unit, if at all.
+    /// The importer may always hold it.
+    async fn holder_problem(&self, holder: i32, context: &SecurityContext) -> Result<Option<String>, ValidationError> {
+        if holder == context.user_id {
+            return Ok(None);
+        }
+        let records = self.certificates
+            .list_by_user(holder)
+            .await
+            .map_err(|e| ValidationError::Repository(e.to_string()))?;
+        if records.is_empty() {
+            return Ok(Some(format!("Holder {} is not an enrolled user", holder)));
+        }
+        let now = Utc::now();
+        let Some(active) = records
+            .iter()
+            .filter(|record| record.is_active(now))
+            .max_by_key(|record| record.created_at)
+        else {
+            return Ok(Some(format!("Holder {} has no active certificate", holder)));
+        };
+        if !context.unit_code.is_empty() && active.certificate.unit_id != context.unit_code {
+            return Ok(Some(format!("Holder {} is not in unit {}", holder, context.unit_code)));
+        }
+        Ok(None)
+    }
+}
+
+/// Serialized items are duplicates when their serial number repeats under
+/// the same NSN; items without a serial number are never duplicates.
+fn duplicate_key(property: &Property) -> Option<(Option<String>, String)> {
+    let serial_number = property.serial_number.as_ref()?.trim().to_uppercase();
+    let nsn = property.nsn.as_ref().map(|nsn| nsn.replace('-', ""));
+    Some((nsn, serial_number))
+}
+
+/// The sheet as rows of trimmed cells, header first.
+fn read_table(format: ImportFormat, bytes: &[u8]) -> Result<Vec<Vec<String>>, ValidationError> {
+    match format {
+        ImportFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .trim(csv::Trim::All)
+                .from_reader(bytes);
+            reader
+                .records()
+                .map(|record| {
+                    record
+                        .map(|record| record.iter().map(str::to_string).collect())
+                        .map_err(|e| ValidationError::InvalidField(format!("Unreadable CSV: {}", e)))
+                })
+                .collect()
+        }
+        ImportFormat::Xlsx => {
+            let mut workbook: Xlsx<_> = open_workbook_from_rs(Cursor::new(bytes))
+                .map_err(|e| ValidationError::InvalidField(format!("Unreadable XLSX: {}", e)))?;
+            let range = workbook
+                .worksheet_range_at(0)
+                .ok_or_else(|| ValidationError::InvalidField("The workbook has no sheets".to_string()))?
+                .map_err(|e| ValidationError::InvalidField(format!("Unreadable XLSX: {}", e)))?;
+            Ok(range
+                .rows()
+                .map(|row| row.iter().map(|cell| cell.to_string().trim().to_string()).collect())
+                .collect())
+        }
+    }
+}
+
+/// Builds the property a row describes, or every problem with it.
+fn read_row(columns: &Columns, record: &[String], context: &SecurityContext) -> Result<Property, Vec<String>> {
+    let cell = |column: Option<usize>| {
+        column
+            .and_then(|column| record.get(column))
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    };
+    let mut errors = Vec::new();
+
+    let category = match cell(columns.category) {
+        Some(value) => parse_category(value).unwrap_or_else(|| {
+            errors.push(format!("Unknown category {}", value));
+            PropertyCategory::Other
+        }),
+        None => PropertyCategory::Other,
+    };
+    let quantity = match cell(columns.quantity) {
+        Some(value) => parse_whole(value).unwrap_or_else(|| {
+            errors.push(format!("Quantity {} is not a whole number", value));
+            1
+        }),
+        None => 1,
+    };
+    let holder = match cell(columns.holder) {
+        Some(value) => parse_whole(value).unwrap_or_else(|| {
+            errors.push(format!("Holder {} is not a user id", value));
+            context.user_id
+        }),
+        None => context.user_id,
+    };
+    let is_sensitive = match cell(columns.sensitive) {
+        Some(value) => parse_flag(value).unwrap_or_else(|| {
+            errors.push(format!("Sensitive {} is not yes or no", value));
+            false
+        }),
+        None => matches!(category, PropertyCategory::Weapon | PropertyCategory::Ammunition),
+    };
+    let requires_approval = match cell(columns.requires_approval) {
+        Some(value) => parse_flag(value).unwrap_or_else(|| {
+            errors.push(format!("Requires approval {} is not yes or no", value));
+            false
+        }),
+        None => is_sensitive,
+    };
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut property = Property::new(
+        cell(Some(columns.name)).unwrap_or_default().to_string(),
+        String::new(),
+        category,
+        holder,
+        Location::default(),
+    );
+    property.quantity = quantity;
+    property.nsn = cell(columns.nsn).map(str::to_string);
+    property.serial_number = cell(columns.serial_number).map(str::to_string);
+    property.hand_receipt_number = cell(columns.hand_receipt_number).map(str::to_string);
+    property.is_sensitive = is_sensitive;
+    property.requires_approval = requires_approval;
+    if !context.unit_code.is_empty() {
+        property.metadata = serde_json::json!({ "unit": context.unit_code });
+    }
+    Ok(property)
+}
+
+fn parse_category(value: &str) -> Option<PropertyCategory> {
+    match value.to_ascii_lowercase().as_str() {
+        "equipment" => Some(PropertyCategory::Equipment),
+        "vehicle" => Some(PropertyCategory::Vehicle),
+        "weapon" => Some(PropertyCategory::Weapon),
+        "ammunition" => Some(PropertyCategory::Ammunition),
+        "supply" => Some(PropertyCategory::Supply),
+        "other" => Some(PropertyCategory::Other),
+        _ => None,
+    }
+}
+
+/// Spreadsheets mark flags in many ways; `x` is a ticked box.
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "yes" | "y" | "true" | "1" | "x" => Some(true),
+        "no" | "n" | "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Spreadsheets store numbers as floats, so `2.0` reads as 2.
+fn parse_whole(value: &str) -> Option<i32> {
+    value.parse::<i32>().ok().or_else(|| {
+        value
+            .parse::<f64>()
+            .ok()
+            .filter(|number| number.fract() == 0.0 && number.abs() <= i32::MAX as f64)
+            .map(|number| number as i32)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::{write::FileOptions, ZipWriter};
+    use chrono::Duration;
+    use crate::{
+        domain::certificate::entity::{CertificateRecord, RevocationReason},
+        infrastructure::blockchain::certificate::MilitaryCertificate,
+        test_support::{MockCertificates, MockProperties},
+        types::security::Role,
+    };
+
+    fn supply_sergeant() -> SecurityContext {
+        SecurityContext {
+            role: Role::NCO,
+            unit_code: "A-1-502".to_string(),
+            ..SecurityContext::new(7)
+        }
+    }
+
+    /// Certificates enrolling each user in its unit.
+    async fn members(units: &[(i32, &str)]) -> Arc<MockCertificates> {
+        let certificates = Arc::new(MockCertificates::default());
+        for (user_id, unit) in units {
+            let certificate = MilitaryCertificate {
+                serial: format!("cert-{}", user_id),
+                issuer: "root".to_string(),
+                subject: format!("user-{}", user_id),
+                public_key: String::new(),
+                valid_from: Utc::now() - Duration::days(1),
+                valid_until: None,
+                roles: Vec::new(),
+                unit_id: unit.to_string(),
+                signature: String::new(),
+            };
+            certificates.create(&CertificateRecord::new(*user_id, certificate)).await.unwrap();
+        }
+        certificates
+    }
+
+    /// A one-sheet workbook with inline string cells.
+    fn xlsx(rows: &[&[&str]]) -> Vec<u8> {
+        let mut sheet = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8"?><worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#,
+        );
+        for (r, row) in rows.iter().enumerate() {
+            sheet.push_str(&format!(r#"<row r="{}">"#, r + 1));
+            for (c, value) in row.iter().enumerate() {
+                let reference = format!("{}{}", (b'A' + c as u8) as char, r + 1);
+                sheet.push_str(&format!(r#"<c r="{}" t="inlineStr"><is><t>{}</t></is></c>"#, reference, value));
+            }
+            sheet.push_str("</row>");
+        }
+        sheet.push_str("</sheetData></worksheet>");
+
+        let parts = [
+            ("[Content_Types].xml", r#"<?xml version="1.0" encoding="UTF-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/></Types>"#.to_string()),
+            ("_rels/.rels", r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#.to_string()),
+            ("xl/workbook.xml", r#"<?xml version="1.0" encoding="UTF-8"?><workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Property Book" sheetId="1" r:id="rId1"/></sheets></workbook>"#.to_string()),
+            ("xl/_rels/workbook.xml.rels", r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#.to_string()),
+            ("xl/worksheets/sheet1.xml", sheet),
+        ];
+        let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in parts {
+            archive.start_file(name, FileOptions::default()).unwrap();
+            archive.write_all(content.as_bytes()).unwrap();
+        }
+        archive.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_columns_match_loosely() {
+        let headers: Vec<String> = ["Item Description", "NSN", "Serial Number", "QTY", "HR #"]
+            .iter()
+            .map(|header| header.to_string())
+            .collect();
+        let mapping = ImportColumnMapping {
+            name: Some("item description".to_string()),
+            quantity: Some("Qty".to_string()),
+            hand_receipt_number: Some("HR#".to_string()),
+            ..Default::default()
+        };
+        let columns = Columns::resolve(&mapping, &headers).unwrap();
+        assert_eq!(columns.name, 0);
+        assert_eq!(columns.nsn, Some(1));
+        assert_eq!(columns.serial_number, Some(2));
+        assert_eq!(columns.quantity, Some(3));
+        assert_eq!(columns.hand_receipt_number, Some(4));
+        assert_eq!(columns.holder, None);
+
+        let missing = ImportColumnMapping { holder: Some("Holder".to_string()), ..mapping };
+        assert!(Columns::resolve(&missing, &headers).is_err());
+        assert!(Columns::resolve(&ImportColumnMapping::default(), &headers).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_reports_errors_and_duplicates() {
+        let repository = Arc::new(MockProperties::default());
+        let mut issued = Property::new("Rifle, 5.56mm, M4".to_string(), String::new(), PropertyCategory::Weapon, 3, Location::default());
+        issued.id = 17;
+        issued.nsn = Some("1005-01-231-0973".to_string());
+        issued.serial_number = Some("W123".to_string());
+        repository.properties.lock().push(issued);
+        let import = PropertyImport::new(repository.clone(), members(&[(3, "A-1-502")]).await);
+
+        let csv = "Name,NSN,Serial Number,Quantity,Category,Holder,Hand Receipt Number\n\
+                   \"Rifle, 5.56mm, M4\",1005-01-231-0973,W124,1,weapon,3,HR-A\n\
+                   \"Rifle, 5.56mm, M4\",1005012310973,w123,1,Weapon,3,HR-A\n\
+                   ,,,,,,\n\
+                   Night vision goggle,5855-01-534-5931,N1,1,equipment,,HR-A\n\
+                   Night vision goggle,5855-01-534-5931,N1,1,equipment,,HR-A\n\
+                   Cot,123,,two,furniture,,HR-B\n\
+                   Water can,7240-00-089-3827,,10,supply,,HR-B\n";
+        let report = import
+            .import(csv.as_bytes(), &ImportColumnMapping::default(), true, &supply_sergeant())
+            .await
+            .unwrap();
+
+        assert_eq!(report.format, ImportFormat::Csv);
+        assert!(!report.committed);
+        assert_eq!(report.total_rows, 6);
+        assert_eq!(report.valid_rows, 3);
+        assert_eq!(report.duplicate_rows, 2);
+        assert_eq!(report.invalid_rows, 1);
+
+        let lines: Vec<usize> = report.rows.iter().map(|row| row.line).collect();
+        assert_eq!(lines, vec![2, 3, 5, 6, 7, 8]);
+        assert_eq!(report.rows[1].duplicate_of.as_deref(), Some("property 17"));
+        assert_eq!(report.rows[3].duplicate_of.as_deref(), Some("line 5"));
+        assert_eq!(report.rows[4].errors.len(), 2);
+
+        let goggles = report.rows[2].property.as_ref().unwrap();
+        assert_eq!(goggles.current_holder_id, 7);
+        assert_eq!(goggles.category, PropertyCategory::Equipment);
+        assert_eq!(goggles.metadata["unit"], "A-1-502");
+        assert_eq!(report.rows[5].property.as_ref().unwrap().quantity, 10);
+
+        // Committing a file with problems stores nothing
+        let refused = import
+            .import(csv.as_bytes(), &ImportColumnMapping::default(), false, &supply_sergeant())
+            .await
+            .unwrap();
+        assert!(!refused.committed);
+        assert_eq!(repository.properties.lock().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_commits_xlsx_atomically() {
+        let repository = Arc::new(MockProperties::default());
+        let import = PropertyImport::new(repository.clone(), members(&[]).await);
+        let workbook = xlsx(&[
+            &["Nomenclature", "NSN", "SN", "Qty", "Type", "HR"],
+            &["Rifle, 5.56mm, M4", "1005-01-231-0973", "W200", "1", "Weapon", "HR-A"],
+            &["Compass, lensatic", "6605-01-196-6971", "", "4", "Equipment", "HR-A"],
+        ]);
+        let mapping = ImportColumnMapping {
+            name: Some("Nomenclature".to_string()),
+            serial_number: Some("SN".to_string()),
+            quantity: Some("Qty".to_string()),
+            category: Some("Type".to_string()),
+            hand_receipt_number: Some("HR".to_string()),
+            ..Default::default()
+        };
+
+        let report = import.import(&workbook, &mapping, false, &supply_sergeant()).await.unwrap();
+        assert_eq!(report.format, ImportFormat::Xlsx);
+        assert!(report.committed);
+        assert_eq!(report.created, vec![1, 2]);
+        assert_eq!(report.rows[0].property.as_ref().unwrap().serial_number.as_deref(), Some("W200"));
+        assert_eq!(report.rows[1].property.as_ref().unwrap().quantity, 4);
+        assert_eq!(repository.properties.lock().len(), 2);
+        let rifle = report.rows[0].property.as_ref().unwrap();
+        assert!(rifle.is_sensitive && rifle.requires_approval);
+        assert!(!report.rows[1].property.as_ref().unwrap().is_sensitive);
+
+        // Soldiers cannot create property
+        let soldier = SecurityContext::new(9);
+        assert!(matches!(
+            import.import(&workbook, &mapping, true, &soldier).await,
+            Err(ValidationError::Authorization(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_holders_and_flags() {
+        let repository = Arc::new(MockProperties::default());
+        let certificates = members(&[(3, "A-1-502"), (4, "B-2-75"), (5, "A-1-502")]).await;
+        certificates.revoke("cert-5", RevocationReason::UnitChange).await.unwrap();
+        let import = PropertyImport::new(repository.clone(), certificates);
+
+        let csv = "Name,Category,Holder,Sensitive,Requires Approval\n\
+                   Compass,equipment,3,yes,\n\
+                   Bayonet,weapon,3,no,no\n\
+                   Radio,equipment,4,,\n\
+                   Radio,equipment,5,,\n\
+                   Radio,equipment,99,,\n\
+                   Radio,equipment,3,maybe,\n";
+        let report = import
+            .import(csv.as_bytes(), &ImportColumnMapping::default(), true, &supply_sergeant())
+            .await
+            .unwrap();
+        assert_eq!(report.valid_rows, 2);
+
+        let compass = report.rows[0].property.as_ref().unwrap();
+        assert!(compass.is_sensitive && compass.requires_approval);
+        let bayonet = report.rows[1].property.as_ref().unwrap();
+        assert!(!bayonet.is_sensitive && !bayonet.requires_approval);
+
+        assert_eq!(report.rows[2].errors, vec!["Holder 4 is not in unit A-1-502".to_string()]);
+        assert_eq!(report.rows[3].errors, vec!["Holder 5 has no active certificate".to_string()]);
+        assert_eq!(report.rows[4].errors, vec!["Holder 99 is not an enrolled user".to_string()]);
+        assert_eq!(report.rows[5].errors, vec!["Sensitive maybe is not yes or no".to_string()]);
+    }
+}
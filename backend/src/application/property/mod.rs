@@ -1,5 +1,11 @@
 pub mod commands;
+pub mod components;
+pub mod import;
+pub mod service;
 pub mod validation;
 
 pub use commands::PropertyCommand;
+pub use components::{ComponentService, NewComponent};
+pub use import::{ImportColumnMapping, ImportReport, PropertyImport};
+pub use service::PropertyServiceImpl;
 pub use validation::PropertyValidator;
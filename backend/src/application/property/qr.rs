@@ -11,6 +11,7 @@ use crate::{
         QRResponse,
         VerifyQRRequest,
     },
+    domain::models::qr_token::{QRTokenRevocation, QRUsage},
     error::CoreError,
     types::security::SecurityContext,
 };
@@ -58,6 +59,18 @@ impl PropertyQRService {
 
 #[async_trait]
 impl QRCodeService for PropertyQRService {
+    async fn issue_qr(
+        &self,
+        data: &QRData,
+        usage: QRUsage,
+        context: &SecurityContext,
+    ) -> Result<String, CoreError> {
+        // Check permissions
+        self.check_qr_permissions(context).await?;
+
+        self.qr_service.issue_qr(data, usage, context).await
+    }
+
     async fn generate_qr(
         &self,
         data: &QRData,
@@ -85,4 +98,31 @@ impl QRCodeService for PropertyQRService {
         // Validate QR code using underlying service
         self.qr_service.validate_qr(request, context).await
     }
+
+    async fn consume_qr(
+        &self,
+        request: VerifyQRRequest,
+        context: &SecurityContext,
+    ) -> Result<QRData, CoreError> {
+        // Check permissions
+        self.check_scan_permissions(context).await?;
+
+        // Validate location if provided
+        self.validate_location(request.location.as_deref(), context).await?;
+
+        // Redeem QR code using underlying service
+        self.qr_service.consume_qr(request, context).await
+    }
+
+    async fn revoke_property_qr(
+        &self,
+        property_id: i32,
+        reason: QRTokenRevocation,
+        context: &SecurityContext,
+    ) -> Result<u64, CoreError> {
+        // Only those who can issue codes can revoke them
+        self.check_qr_permissions(context).await?;
+
+        self.qr_service.revoke_property_qr(property_id, reason, context).await
+    }
 }
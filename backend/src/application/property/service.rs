@@ -0,0 +1,169 @@
+//! Repository-backed [`PropertyService`] the property handlers and document
+//! services are built on.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::{
+    domain::{
+        models::{
+            qr::{QRCodeService, QRData},
+            qr_token::QRUsage,
+        },
+        property::{
+            entity::Property,
+            repository::PropertyRepository,
+            service::{PropertyService, SyncState, SyncStatus},
+        },
+    },
+    error::{validation::ValidationError, CoreError, RepositoryError},
+    types::security::SecurityContext,
+};
+use super::commands::PropertyCommand;
+
+pub struct PropertyServiceImpl {
+    repository: Arc<dyn PropertyRepository + Send + Sync>,
+    commands: PropertyCommand,
+    qr_service: Arc<dyn QRCodeService>,
+}
+
+impl PropertyServiceImpl {
+    pub fn new(
+        repository: Arc<dyn PropertyRepository + Send + Sync>,
+        qr_service: Arc<dyn QRCodeService>,
+    ) -> Self {
+        Self {
+            commands: PropertyCommand::new(repository.clone()),
+            repository,
+            qr_service,
+        }
+    }
+
+    async fn find(&self, id: i32, context: &SecurityContext) -> Result<Property, RepositoryError> {
+        self.get_property(id, context)
+            .await?
+            .ok_or_else(|| RepositoryError::NotFound(format!("Property {}", id)))
+    }
+}
+
+fn repository_error(error: ValidationError) -> RepositoryError {
+    match error {
+        ValidationError::NotFound(message) => RepositoryError::NotFound(message),
+        ValidationError::Repository(message) => RepositoryError::Database(message),
+        error => RepositoryError::Validation(error.to_string()),
+    }
+}
+
+#[async_trait]
+impl PropertyService for PropertyServiceImpl {
+    async fn create_property(&self, property: &Property, context: &SecurityContext) -> Result<Property, RepositoryError> {
+        self.commands
+            .create_property(property.clone(), context)
+            .await
+            .map_err(repository_error)
+    }
+
+    async fn get_property(&self, id: i32, context: &SecurityContext) -> Result<Option<Property>, RepositoryError> {
+        self.commands.get_property(id, context).await.map_err(repository_error)
+    }
+
+    async fn update_property(&self, property: &Property, context: &SecurityContext) -> Result<Property, RepositoryError> {
+        self.commands
+            .update_property(property, context)
+            .await
+            .map_err(repository_error)?;
+        // Re-read for the columns the database maintains, e.g. `updated_at`
+        self.find(property.id, context).await
+    }
+
+    async fn delete_property(&self, id: i32, context: &SecurityContext) -> Result<(), RepositoryError> {
+        self.commands.delete_property(id, context).await.map_err(repository_error)
+    }
+
+    async fn list_properties(&self, context: &SecurityContext) -> Result<Vec<Property>, RepositoryError> {
+        self.commands.list_properties(context).await.map_err(repository_error)
+    }
+
+    /// Issues a one-time transfer code for the property's current holder.
+    async fn generate_qr(&self, id: i32, context: &SecurityContext) -> Result<String, RepositoryError> {
+        if !context.can_generate_qr() {
+            return Err(RepositoryError::Validation(
+                "User does not have permission to generate QR codes".to_string(),
+            ));
+        }
+        let property = self.find(id, context).await?;
+
+        let data = QRData::new(
+            property.id,
+            property.current_holder_id.to_string(),
+            serde_json::json!({}),
+        )
+        .with_category(property.category);
+        self.qr_service
+            .issue_qr(&data, QRUsage::Transfer, context)
+            .await
+            .map_err(|e| match e {
+                CoreError::NotFound(message) => RepositoryError::NotFound(message),
+                e => RepositoryError::Query(e.to_string()),
+            })
+    }
+
+    /// A property never submitted to the ledger reports `unsynced`.
+    async fn get_sync_status(&self, id: i32, context: &SecurityContext) -> Result<SyncStatus, RepositoryError> {
+        self.find(id, context).await?;
+        Ok(self.repository.get_sync_status(id).await?.unwrap_or(SyncStatus {
+            is_synced: false,
+            last_sync: None,
+            blockchain_hash: None,
+            state: SyncState::Unsynced,
+            error: None,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::location::Location;
+    use crate::domain::property::entity::PropertyCategory;
+    use crate::test_support::{MockProperties, StubQRService};
+    use crate::types::security::Role;
+
+    fn properties() -> Arc<MockProperties> {
+        let mut rifle = Property::new(
+            "M4 Carbine".to_string(),
+            "Rifle".to_string(),
+            PropertyCategory::Weapon,
+            7,
+            Location::default(),
+        );
+        rifle.id = 1;
+        Arc::new(MockProperties::new(vec![rifle]))
+    }
+
+    #[tokio::test]
+    async fn test_unsynced_property_status() {
+        let service = PropertyServiceImpl::new(properties(), Arc::new(StubQRService::default()));
+
+        let status = service.get_sync_status(1, &SecurityContext::new(7)).await.unwrap();
+        assert_eq!(status.state, SyncState::Unsynced);
+        assert!(status.last_sync.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_generate_qr_issues_transfer_code() {
+        let qr = Arc::new(StubQRService::default());
+        let service = PropertyServiceImpl::new(properties(), qr.clone());
+        let mut context = SecurityContext::new(7);
+
+        assert!(matches!(
+            service.generate_qr(1, &context).await,
+            Err(RepositoryError::Validation(_))
+        ));
+
+        context.role = Role::NCO;
+        assert_eq!(service.generate_qr(1, &context).await.unwrap(), "HR1:1");
+        assert_eq!(*qr.issued.lock(), vec![QRUsage::Transfer]);
+    }
+}
@@ -8,6 +8,18 @@ use crate::{
     types::security::SecurityContext,
 };
 
+/// Column limits of the `properties` table.
+const MAX_NAME_LENGTH: usize = 255;
+const MAX_CODE_LENGTH: usize = 100;
+
+/// National Stock Numbers are 13 digits, usually written `NNNN-NN-NNN-NNNN`.
+pub fn is_valid_nsn(nsn: &str) -> bool {
+    let digits: Vec<char> = nsn.chars().filter(|c| *c != '-').collect();
+    digits.len() == 13
+        && digits.iter().all(char::is_ascii_digit)
+        && (!nsn.contains('-') || nsn.split('-').map(str::len).eq([4, 2, 3, 4]))
+}
+
 pub struct PropertyValidator {
     repository: Arc<dyn PropertyRepository + Send + Sync>,
 }
@@ -23,7 +35,58 @@ impl PropertyValidator {
             return Err(ValidationError::Authorization("User does not have permission to create properties".to_string()));
         }
 
-        // Add any additional validation rules here
+        self.validate_fields(property)
+    }
+
+    /// Checks the fields a property record must carry, independent of who
+    /// is creating it.
+    pub fn validate_fields(&self, property: &Property) -> Result<(), ValidationError> {
+        let name = property.name.trim();
+        if name.is_empty() {
+            return Err(ValidationError::InvalidField("Name is required".to_string()));
+        }
+        if name.chars().count() > MAX_NAME_LENGTH {
+            return Err(ValidationError::InvalidField(format!(
+                "Name must be at most {} characters",
+                MAX_NAME_LENGTH
+            )));
+        }
+        if property.quantity < 1 {
+            return Err(ValidationError::InvalidField("Quantity must be at least 1".to_string()));
+        }
+        if let Some(nsn) = &property.nsn {
+            if !is_valid_nsn(nsn) {
+                return Err(ValidationError::InvalidField(format!(
+                    "NSN {} must be 13 digits, e.g. 1005-01-231-0973",
+                    nsn
+                )));
+            }
+        }
+        if let Some(serial_number) = &property.serial_number {
+            if serial_number.trim().is_empty() || serial_number.len() > MAX_CODE_LENGTH {
+                return Err(ValidationError::InvalidField(format!(
+                    "Serial number must be 1 to {} characters",
+                    MAX_CODE_LENGTH
+                )));
+            }
+            // A serial number identifies exactly one item
+            if property.quantity != 1 {
+                return Err(ValidationError::InvalidField(
+                    "Serialized items must have a quantity of 1".to_string(),
+                ));
+            }
+        }
+        if property
+            .hand_receipt_number
+            .as_ref()
+            .is_some_and(|number| number.len() > MAX_CODE_LENGTH)
+        {
+            return Err(ValidationError::InvalidField(format!(
+                "Hand receipt number must be at most {} characters",
+                MAX_CODE_LENGTH
+            )));
+        }
+
         Ok(())
     }
 
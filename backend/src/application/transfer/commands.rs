@@ -1,29 +1,33 @@
 use std::sync::Arc;
 use crate::{
     domain::{
+        models::{qr::QRCodeService, qr_token::QRTokenRevocation},
         transfer::{
-            entity::Transfer,
+            entity::{Transfer, TransferStatus},
             repository::TransferRepository,
         },
     },
     error::validation::ValidationError,
-    types::security::SecurityContext,
+    types::{permissions::Permission, security::SecurityContext},
 };
 use super::validation::TransferValidator;
 
 pub struct TransferCommand {
     repository: Arc<dyn TransferRepository>,
     validator: Arc<dyn TransferValidator>,
+    qr_service: Arc<dyn QRCodeService>,
 }
 
 impl TransferCommand {
     pub fn new(
         repository: Arc<dyn TransferRepository>,
         validator: Arc<dyn TransferValidator>,
+        qr_service: Arc<dyn QRCodeService>,
     ) -> Self {
         Self {
             repository,
             validator,
+            qr_service,
         }
     }
 
@@ -52,7 +56,7 @@ impl TransferCommand {
 
         // Update status
         let mut updated = transfer.clone();
-        updated.status = crate::domain::transfer::entity::TransferStatus::Approved;
+        updated.status = TransferStatus::Approved;
 
         // Update in repository
         self.repository
@@ -61,4 +65,68 @@ impl TransferCommand {
             .map(|_| updated)
             .map_err(|e| ValidationError::Repository(e.to_string()))
     }
+
+    /// Completes an approved transfer. The property now has a new holder,
+    /// so QR codes naming the old one are revoked.
+    pub async fn complete_transfer(
+        &self,
+        transfer: &Transfer,
+        context: &SecurityContext,
+    ) -> Result<Transfer, ValidationError> {
+        if transfer.status != TransferStatus::Approved {
+            return Err(ValidationError::InvalidState("transfer must be approved".to_string()));
+        }
+        if !context.has_permission(&Permission::ApproveTransfer) {
+            return Err(ValidationError::InsufficientPermissions);
+        }
+
+        let mut updated = transfer.clone();
+        updated.complete();
+        self.repository
+            .update_transfer(&updated)
+            .await
+            .map_err(|e| ValidationError::Repository(e.to_string()))?;
+
+        self.revoke_qr_codes(updated.property_id, QRTokenRevocation::HolderChanged, context)
+            .await?;
+        Ok(updated)
+    }
+
+    /// Cancels an open transfer and revokes the QR codes printed for it.
+    pub async fn cancel_transfer(
+        &self,
+        transfer: &Transfer,
+        context: &SecurityContext,
+    ) -> Result<Transfer, ValidationError> {
+        if !matches!(transfer.status, TransferStatus::Pending | TransferStatus::Approved) {
+            return Err(ValidationError::InvalidState("transfer is already closed".to_string()));
+        }
+        if !context.has_permission(&Permission::CreateTransfer) {
+            return Err(ValidationError::InsufficientPermissions);
+        }
+
+        let mut updated = transfer.clone();
+        updated.cancel();
+        self.repository
+            .update_transfer(&updated)
+            .await
+            .map_err(|e| ValidationError::Repository(e.to_string()))?;
+
+        self.revoke_qr_codes(updated.property_id, QRTokenRevocation::TransferCancelled, context)
+            .await?;
+        Ok(updated)
+    }
+
+    async fn revoke_qr_codes(
+        &self,
+        property_id: i32,
+        reason: QRTokenRevocation,
+        context: &SecurityContext,
+    ) -> Result<(), ValidationError> {
+        self.qr_service
+            .revoke_property_qr(property_id, reason, context)
+            .await
+            .map(|_| ())
+            .map_err(|e| ValidationError::Repository(e.to_string()))
+    }
 }
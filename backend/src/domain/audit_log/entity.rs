@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A row in the application audit log: who did what, with the details
+/// needed to check it later.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    pub id: i32,
+    pub event_type: String,
+    pub description: String,
+    pub user_id: Option<i32>,
+    pub metadata: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditLogEntry {
+    pub fn new(
+        event_type: impl Into<String>,
+        description: impl Into<String>,
+        user_id: Option<i32>,
+        metadata: serde_json::Value,
+    ) -> Self {
+        Self {
+            id: 0,
+            event_type: event_type.into(),
+            description: description.into(),
+            user_id,
+            metadata,
+            created_at: Utc::now(),
+        }
+    }
+}
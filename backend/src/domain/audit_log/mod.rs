@@ -0,0 +1,5 @@
+pub mod entity;
+pub mod repository;
+
+pub use entity::AuditLogEntry;
+pub use repository::AuditLogRepository;
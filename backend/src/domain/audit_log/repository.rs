@@ -0,0 +1,8 @@
+use async_trait::async_trait;
+use crate::error::RepositoryError;
+use super::entity::AuditLogEntry;
+
+#[async_trait]
+pub trait AuditLogRepository: Send + Sync {
+    async fn record(&self, entry: &AuditLogEntry) -> Result<AuditLogEntry, RepositoryError>;
+}
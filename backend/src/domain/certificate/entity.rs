@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::infrastructure::blockchain::certificate::MilitaryCertificate;
+
+/// Why a certificate was revoked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevocationReason {
+    /// The holder left the unit (PCS, ETS, reassignment).
+    UnitChange,
+    /// The device holding the private key was lost or compromised.
+    KeyCompromise,
+    /// Replaced by a newer certificate for the same holder.
+    Superseded,
+}
+
+impl RevocationReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RevocationReason::UnitChange => "unit_change",
+            RevocationReason::KeyCompromise => "key_compromise",
+            RevocationReason::Superseded => "superseded",
+        }
+    }
+}
+
+impl std::str::FromStr for RevocationReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unit_change" => Ok(RevocationReason::UnitChange),
+            "key_compromise" => Ok(RevocationReason::KeyCompromise),
+            "superseded" => Ok(RevocationReason::Superseded),
+            _ => Err(format!("Unknown revocation reason: {}", s)),
+        }
+    }
+}
+
+/// A certificate issued by the internal CA and the user it was issued to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificateRecord {
+    pub user_id: i32,
+    pub certificate: MilitaryCertificate,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub revocation_reason: Option<RevocationReason>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl CertificateRecord {
+    pub fn new(user_id: i32, certificate: MilitaryCertificate) -> Self {
+        Self {
+            user_id,
+            certificate,
+            revoked_at: None,
+            revocation_reason: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn serial(&self) -> &str {
+        &self.certificate.serial
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+
+    /// Returns true if the certificate is unrevoked and valid at `at`.
+    pub fn is_active(&self, at: DateTime<Utc>) -> bool {
+        !self.is_revoked() && self.certificate.check_validity(at).is_ok()
+    }
+}
@@ -0,0 +1,20 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use crate::error::RepositoryError;
+use super::entity::{CertificateRecord, RevocationReason};
+
+#[async_trait]
+pub trait CertificateRepository: Send + Sync {
+    async fn create(&self, record: &CertificateRecord) -> Result<CertificateRecord, RepositoryError>;
+    async fn get(&self, serial: &str) -> Result<Option<CertificateRecord>, RepositoryError>;
+    async fn list_by_user(&self, user_id: i32) -> Result<Vec<CertificateRecord>, RepositoryError>;
+    /// Revokes an active certificate. Returns `None` if it does not exist or
+    /// was already revoked.
+    async fn revoke(
+        &self,
+        serial: &str,
+        reason: RevocationReason,
+    ) -> Result<Option<CertificateRecord>, RepositoryError>;
+    /// Revoked serials of `issuer` and when they were revoked.
+    async fn list_revoked(&self, issuer: &str) -> Result<Vec<(String, DateTime<Utc>)>, RepositoryError>;
+}
@@ -0,0 +1,176 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// How a component belongs to its end item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentKind {
+    /// Component of end item (COEI), issued with and part of the item.
+    Component,
+    /// Basic issue item (BII) needed to operate the end item.
+    BasicIssueItem,
+}
+
+impl ComponentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ComponentKind::Component => "component",
+            ComponentKind::BasicIssueItem => "basic_issue_item",
+        }
+    }
+}
+
+impl std::str::FromStr for ComponentKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "component" => Ok(ComponentKind::Component),
+            "basic_issue_item" => Ok(ComponentKind::BasicIssueItem),
+            _ => Err(format!("Unknown component kind: {}", s)),
+        }
+    }
+}
+
+/// An item on an end item's component listing, with how many the end item
+/// should have and how many are actually with it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertyComponent {
+    pub id: i32,
+    /// The end item this component belongs to.
+    pub property_id: i32,
+    pub name: String,
+    pub nsn: Option<String>,
+    pub kind: ComponentKind,
+    pub unit_of_issue: String,
+    pub required_quantity: i32,
+    pub on_hand_quantity: i32,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PropertyComponent {
+    /// A complete component: everything required is on hand.
+    pub fn new(property_id: i32, name: String, kind: ComponentKind, required_quantity: i32) -> Self {
+        let now = Utc::now();
+        Self {
+            id: 0,
+            property_id,
+            name,
+            nsn: None,
+            kind,
+            unit_of_issue: "EA".to_string(),
+            required_quantity,
+            on_hand_quantity: required_quantity,
+            notes: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// How many are missing.
+    pub fn shortage(&self) -> i32 {
+        (self.required_quantity - self.on_hand_quantity).max(0)
+    }
+}
+
+/// One short component on a shortage annex.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShortageLine {
+    pub component_id: i32,
+    pub name: String,
+    pub nsn: Option<String>,
+    pub kind: ComponentKind,
+    pub unit_of_issue: String,
+    pub required_quantity: i32,
+    pub on_hand_quantity: i32,
+    pub shortage: i32,
+}
+
+/// The components an end item is short, as signed for with the item.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShortageAnnex {
+    pub property_id: i32,
+    pub lines: Vec<ShortageLine>,
+    /// Hex SHA-256 of `lines`, so a signed-for annex can be told apart from
+    /// a later one.
+    pub digest: String,
+}
+
+impl ShortageAnnex {
+    /// Annex of `components` of `property_id`, listing only those short.
+    pub fn new(property_id: i32, components: &[PropertyComponent]) -> Self {
+        let mut lines: Vec<ShortageLine> = components
+            .iter()
+            .filter(|component| component.shortage() > 0)
+            .map(|component| ShortageLine {
+                component_id: component.id,
+                name: component.name.clone(),
+                nsn: component.nsn.clone(),
+                kind: component.kind,
+                unit_of_issue: component.unit_of_issue.clone(),
+                required_quantity: component.required_quantity,
+                on_hand_quantity: component.on_hand_quantity,
+                shortage: component.shortage(),
+            })
+            .collect();
+        lines.sort_by_key(|line| line.component_id);
+
+        let digest = Self::lines_digest(&lines);
+        Self { property_id, lines, digest }
+    }
+
+    /// Hex SHA-256 of `lines`.
+    pub fn lines_digest(lines: &[ShortageLine]) -> String {
+        hex::encode(Sha256::digest(serde_json::to_vec(lines).expect("shortage lines serialize")))
+    }
+
+    /// The annex recorded in a transfer's `metadata`, if any.
+    pub fn from_metadata(metadata: &serde_json::Value) -> Option<Self> {
+        serde_json::from_value(metadata.get("shortage_annex")?.clone()).ok()
+    }
+
+    /// Digest of the annex recorded in a transfer's `metadata`, recomputed
+    /// from its lines, or empty if it has none. This is what the parties
+    /// sign for along with the item.
+    pub fn signed_digest(metadata: &serde_json::Value) -> String {
+        Self::from_metadata(metadata)
+            .map(|annex| Self::lines_digest(&annex.lines))
+            .unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annex_lists_shortages() {
+        let mut cable = PropertyComponent::new(7, "Cable, slave".to_string(), ComponentKind::BasicIssueItem, 1);
+        cable.id = 2;
+        cable.on_hand_quantity = 0;
+        let mut cans = PropertyComponent::new(7, "Can, water, 5 gal".to_string(), ComponentKind::BasicIssueItem, 2);
+        cans.id = 1;
+        cans.on_hand_quantity = 1;
+        let mut jack = PropertyComponent::new(7, "Jack, hydraulic".to_string(), ComponentKind::Component, 1);
+        jack.id = 3;
+
+        let annex = ShortageAnnex::new(7, &[cable.clone(), cans.clone(), jack]);
+        assert_eq!(annex.lines.iter().map(|line| line.component_id).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!((annex.lines[0].required_quantity, annex.lines[0].shortage), (2, 1));
+
+        // The digest follows the lines, not their order or complete components
+        assert_eq!(annex.digest, ShortageAnnex::new(7, &[cans.clone(), cable.clone()]).digest);
+        cans.on_hand_quantity = 2;
+        let filled = ShortageAnnex::new(7, &[cable, cans]);
+        assert_eq!(filled.lines.len(), 1);
+        assert_ne!(filled.digest, annex.digest);
+        assert!(ShortageAnnex::new(7, &[]).is_empty());
+    }
+}
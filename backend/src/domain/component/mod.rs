@@ -0,0 +1,5 @@
+pub mod entity;
+pub mod repository;
+
+pub use entity::{ComponentKind, PropertyComponent, ShortageAnnex, ShortageLine};
+pub use repository::ComponentRepository;
@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+use crate::error::RepositoryError;
+use super::entity::PropertyComponent;
+
+#[async_trait]
+pub trait ComponentRepository: Send + Sync {
+    async fn create_component(&self, component: &PropertyComponent) -> Result<PropertyComponent, RepositoryError>;
+    async fn get_component(&self, id: i32) -> Result<Option<PropertyComponent>, RepositoryError>;
+    /// Components of the end item `property_id`, in id order.
+    async fn list_components(&self, property_id: i32) -> Result<Vec<PropertyComponent>, RepositoryError>;
+    /// Adds `delta` to the on-hand quantity in one statement, refusing to go
+    /// below zero or above the required quantity. Returns `None` if there is
+    /// no such component.
+    async fn adjust_on_hand(&self, id: i32, delta: i32) -> Result<Option<PropertyComponent>, RepositoryError>;
+    async fn delete_component(&self, id: i32) -> Result<(), RepositoryError>;
+}
@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::domain::transfer::entity::TransferStatus;
+
+/// A transfer row a block marked completed, with the status to restore if
+/// the block is orphaned.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompletedTransfer {
+    pub transfer_id: i32,
+    pub previous_status: TransferStatus,
+}
+
+/// A committed block whose handreceipt state changes have been applied to
+/// the database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockCheckpoint {
+    pub block_id: String,
+    pub block_num: i64,
+    pub state_root_hash: String,
+    pub previous_block_id: String,
+    pub recorded_at: DateTime<Utc>,
+    pub completed_transfers: Vec<CompletedTransfer>,
+}
+
+impl BlockCheckpoint {
+    pub fn new(
+        block_id: String,
+        block_num: i64,
+        state_root_hash: String,
+        previous_block_id: String,
+    ) -> Self {
+        Self {
+            block_id,
+            block_num,
+            state_root_hash,
+            previous_block_id,
+            recorded_at: Utc::now(),
+            completed_transfers: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,14 @@
+use async_trait::async_trait;
+use crate::error::RepositoryError;
+use super::entity::BlockCheckpoint;
+
+#[async_trait]
+pub trait LedgerCheckpointRepository: Send + Sync {
+    /// Most recent checkpoints, newest first.
+    async fn recent(&self, limit: i64) -> Result<Vec<BlockCheckpoint>, RepositoryError>;
+    /// Saving a block again keeps the transfers it completed the first time.
+    async fn save(&self, checkpoint: &BlockCheckpoint) -> Result<(), RepositoryError>;
+    /// Forgets checkpoints at or above `block_num` after a fork and returns
+    /// them, newest first.
+    async fn rewind_to(&self, block_num: i64) -> Result<Vec<BlockCheckpoint>, RepositoryError>;
+}
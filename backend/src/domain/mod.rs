@@ -1,7 +1,14 @@
 //! Core domain models and business logic
 
+pub mod audit_log;
+pub mod certificate;
+pub mod component;
+pub mod ledger;
 pub mod models;
+pub mod outbox;
 pub mod property;
+pub mod qr_batch;
+pub mod reconciliation;
 pub mod error;
 pub mod transfer;
 
@@ -2,6 +2,8 @@ pub mod blockchain;
 pub mod history;
 pub mod location;
 pub mod qr;
+pub mod qr_image;
+pub mod qr_token;
 pub mod transfer;
 pub mod types;
 pub mod user;
@@ -11,6 +13,8 @@ pub use blockchain::*;
 pub use history::*;
 pub use location::*;
 pub use qr::*;
+pub use qr_image::*;
+pub use qr_token::*;
 pub use transfer::*;
 pub use types::*;
 pub use user::*;
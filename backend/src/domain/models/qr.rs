@@ -1,13 +1,19 @@
+use crate::domain::models::qr_token::{QRToken, QRTokenPolicy, QRTokenRepository, QRTokenRevocation, QRUsage};
+use crate::domain::property::entity::PropertyCategory;
 use crate::error::CoreError;
 use crate::types::security::SecurityContext;
-use ed25519_dalek::{Signature, SigningKey, Verifier, VerifyingKey, Signer};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use handreceipt_protocol::qr::{QrKeyring, QrPayload, SignedQr, QR_FORMAT_VERSION};
+use parking_lot::RwLock;
 use serde::{Serialize, Deserialize};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use uuid::Uuid;
 use async_trait::async_trait;
-use qrcode::render::{svg, unicode};
-use image::{DynamicImage, ImageBuffer, Rgb};
+use qrcode::{Color, QrCode};
+use image::{GrayImage, Luma};
 use std::fmt;
+use std::fmt::Write as _;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum QRFormat {
@@ -21,6 +27,114 @@ pub struct QRResponse {
     pub data: Vec<u8>,
 }
 
+/// Size of rendered QR images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QRRenderOptions {
+    /// Pixels per module (PNG) or user units per module (SVG).
+    pub scale: u32,
+    /// Light border around the symbol, in modules. Scanners expect 4.
+    pub quiet_zone: u32,
+}
+
+impl Default for QRRenderOptions {
+    fn default() -> Self {
+        Self {
+            scale: 8,
+            quiet_zone: 4,
+        }
+    }
+}
+
+impl QRRenderOptions {
+    /// Largest `scale`; with the largest quiet zone it keeps a version 40
+    /// symbol under 3,400 pixels a side.
+    pub const MAX_SCALE: u32 = 16;
+    pub const MAX_QUIET_ZONE: u32 = 16;
+
+    /// Options from caller input, with the default for anything unset.
+    pub fn new(scale: Option<u32>, quiet_zone: Option<u32>) -> Result<Self, CoreError> {
+        let defaults = Self::default();
+        let options = Self {
+            scale: scale.unwrap_or(defaults.scale),
+            quiet_zone: quiet_zone.unwrap_or(defaults.quiet_zone),
+        };
+        options.validate()?;
+        Ok(options)
+    }
+
+    fn validate(&self) -> Result<(), CoreError> {
+        if !(1..=Self::MAX_SCALE).contains(&self.scale) {
+            return Err(CoreError::Validation(format!(
+                "QR scale must be between 1 and {}",
+                Self::MAX_SCALE
+            )));
+        }
+        if self.quiet_zone > Self::MAX_QUIET_ZONE {
+            return Err(CoreError::Validation(format!(
+                "QR quiet zone must be at most {} modules",
+                Self::MAX_QUIET_ZONE
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Module matrix of the QR symbol for `payload`: the width in modules and
+/// one entry per module, row by row, `true` for dark.
+pub fn qr_modules(payload: &str) -> Result<(usize, Vec<bool>), CoreError> {
+    let code = QrCode::new(payload.as_bytes())?;
+    let modules = code.to_colors().into_iter().map(|color| color == Color::Dark).collect();
+    Ok((code.width(), modules))
+}
+
+/// Renders `payload` as a PNG with every module exactly `scale` pixels.
+pub fn render_png(payload: &str, options: &QRRenderOptions) -> Result<Vec<u8>, CoreError> {
+    options.validate()?;
+    let (width, modules) = qr_modules(payload)?;
+    let (width, scale, quiet_zone) = (width as u32, options.scale, options.quiet_zone);
+    let size = (width + 2 * quiet_zone) * scale;
+
+    let image = GrayImage::from_fn(size, size, |x, y| {
+        let (column, row) = (x / scale, y / scale);
+        let inside = (quiet_zone..quiet_zone + width).contains(&column)
+            && (quiet_zone..quiet_zone + width).contains(&row);
+        let dark = inside && modules[((row - quiet_zone) * width + (column - quiet_zone)) as usize];
+        Luma([if dark { 0 } else { 255 }])
+    });
+
+    let mut buffer = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut buffer), image::ImageFormat::Png)?;
+    Ok(buffer)
+}
+
+/// Renders `payload` as an SVG with one path of unit squares, so it scales
+/// without resampling.
+pub fn render_svg(payload: &str, options: &QRRenderOptions) -> Result<String, CoreError> {
+    options.validate()?;
+    let (width, modules) = qr_modules(payload)?;
+    let quiet_zone = options.quiet_zone as usize;
+    let view = width + 2 * quiet_zone;
+    let size = view as u32 * options.scale;
+
+    let mut path = String::new();
+    for (index, _) in modules.iter().enumerate().filter(|(_, dark)| **dark) {
+        let (x, y) = (index % width + quiet_zone, index / width + quiet_zone);
+        let _ = write!(path, "M{} {}h1v1h-1z", x, y);
+    }
+
+    Ok(format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" "#,
+            r#"viewBox="0 0 {view} {view}" shape-rendering="crispEdges">"#,
+            r##"<rect width="{view}" height="{view}" fill="#ffffff"/>"##,
+            r##"<path d="{path}" fill="#000000"/></svg>"##
+        ),
+        size = size,
+        view = view,
+        path = path
+    ))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerifyQRRequest {
     pub qr_data: String,
@@ -38,7 +152,13 @@ pub struct QRData {
     pub timestamp: DateTime<Utc>,
     pub custodian_id: String,
     pub signature: String,
-    pub public_key: String,
+    /// Identifier of the key that signed the code, see
+    /// [`handreceipt_protocol::key_id`].
+    pub kid: String,
+    /// Picks the token lifetime from [`QRTokenPolicy`]. Not part of the
+    /// signed payload.
+    #[serde(default)]
+    pub category: Option<PropertyCategory>,
 }
 
 impl fmt::Display for QRData {
@@ -60,19 +180,92 @@ impl QRData {
             timestamp: Utc::now(),
             custodian_id,
             signature: String::new(),
-            public_key: String::new(),
+            kid: String::new(),
+            category: None,
         }
     }
 
-    pub fn with_signature(mut self, signature: String, public_key: String) -> Self {
+    pub fn with_category(mut self, category: PropertyCategory) -> Self {
+        self.category = Some(category);
+        self
+    }
+
+    pub fn with_signature(mut self, signature: String, kid: String) -> Self {
         self.signature = signature;
-        self.public_key = public_key;
+        self.kid = kid;
         self
     }
+
+    /// Builds the payload that gets signed. Metadata is serialized with
+    /// sorted keys so equal metadata always yields the same digest.
+    fn to_payload(&self) -> QrPayload {
+        QrPayload {
+            version: QR_FORMAT_VERSION,
+            kid: self.kid.clone(),
+            id: self.id.to_string(),
+            property_id: self.property_id.into(),
+            custodian_id: self.custodian_id.clone(),
+            issued_at: self.timestamp.timestamp_millis(),
+            metadata: canonical_json(&self.metadata),
+        }
+    }
+
+    fn from_signed(signed: SignedQr) -> Result<Self, CoreError> {
+        let payload = signed.payload;
+        let invalid = |field: &str| CoreError::Validation(format!("Invalid QR {}", field));
+
+        Ok(Self {
+            id: Uuid::parse_str(&payload.id).map_err(|_| invalid("id"))?,
+            property_id: i32::try_from(payload.property_id).map_err(|_| invalid("property id"))?,
+            metadata: serde_json::from_str(&payload.metadata).map_err(|_| invalid("metadata"))?,
+            timestamp: Utc
+                .timestamp_millis_opt(payload.issued_at)
+                .single()
+                .ok_or_else(|| invalid("timestamp"))?,
+            custodian_id: payload.custodian_id,
+            signature: hex::encode(signed.signature),
+            kid: payload.kid,
+            category: None,
+        })
+    }
+}
+
+/// Serializes `value` with object keys sorted at every level. The crate
+/// enables `preserve_order`, so plain `to_string` would depend on how the
+/// metadata happened to be built.
+pub fn canonical_json(value: &serde_json::Value) -> String {
+    fn sorted(value: &serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::Object(map) => {
+                let mut entries: Vec<_> = map.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                serde_json::Value::Object(
+                    entries
+                        .into_iter()
+                        .map(|(key, value)| (key.clone(), sorted(value)))
+                        .collect(),
+                )
+            }
+            serde_json::Value::Array(items) => {
+                serde_json::Value::Array(items.iter().map(sorted).collect())
+            }
+            other => other.clone(),
+        }
+    }
+    sorted(value).to_string()
 }
 
 #[async_trait]
 pub trait QRCodeService: Send + Sync {
+    /// Records a one-time token for `data` and returns the signed payload
+    /// to encode, e.g. when laying out label sheets
+    async fn issue_qr(
+        &self,
+        data: &QRData,
+        usage: QRUsage,
+        context: &SecurityContext,
+    ) -> Result<String, CoreError>;
+
     /// Generates a QR code for a property
     async fn generate_qr(
         &self,
@@ -81,155 +274,159 @@ pub trait QRCodeService: Send + Sync {
         context: &SecurityContext,
     ) -> Result<QRResponse, CoreError>;
 
-    /// Verifies a QR code
+    /// Verifies a QR code without redeeming it
     async fn validate_qr(
         &self,
         request: VerifyQRRequest,
         context: &SecurityContext,
     ) -> Result<QRData, CoreError>;
+
+    /// Verifies a QR code and redeems its one-time token. A second call
+    /// with the same code fails.
+    async fn consume_qr(
+        &self,
+        request: VerifyQRRequest,
+        context: &SecurityContext,
+    ) -> Result<QRData, CoreError>;
+
+    /// Revokes every outstanding QR code for a property, returning how many
+    /// were revoked
+    async fn revoke_property_qr(
+        &self,
+        property_id: i32,
+        reason: QRTokenRevocation,
+        context: &SecurityContext,
+    ) -> Result<u64, CoreError>;
 }
 
 pub struct QRCodeServiceImpl {
     signing_key: SigningKey,
+    keyring: RwLock<QrKeyring>,
+    tokens: Arc<dyn QRTokenRepository>,
+    policy: QRTokenPolicy,
+    render: QRRenderOptions,
 }
 
 impl QRCodeServiceImpl {
-    pub fn new(signing_key: SigningKey) -> Self {
-        Self { signing_key }
+    /// Creates a service that signs with `signing_key` and trusts only that
+    /// key until others are added with [`QRCodeServiceImpl::trust_key`].
+    pub fn new(
+        signing_key: SigningKey,
+        tokens: Arc<dyn QRTokenRepository>,
+        policy: QRTokenPolicy,
+    ) -> Self {
+        let mut keyring = QrKeyring::new();
+        keyring.trust(signing_key.verifying_key());
+        Self {
+            signing_key,
+            keyring: RwLock::new(keyring),
+            tokens,
+            policy,
+            render: QRRenderOptions::default(),
+        }
     }
 
-    async fn verify_signature(
+    pub fn with_render_options(mut self, render: QRRenderOptions) -> Self {
+        self.render = render;
+        self
+    }
+
+    /// Kid of the key this service signs with.
+    pub fn kid(&self) -> String {
+        handreceipt_protocol::key_id(&self.signing_key.verifying_key())
+    }
+
+    /// Accepts codes signed by `key`, e.g. another server's key during
+    /// rotation. Returns the key's kid.
+    pub fn trust_key(&self, key: VerifyingKey) -> String {
+        self.keyring.write().trust(key)
+    }
+
+    /// Rejects every code signed by `kid` from now on.
+    pub fn revoke_key(&self, kid: &str) {
+        self.keyring.write().revoke(kid);
+    }
+
+    /// Signs `data` and returns the string to place in the QR code.
+    pub fn encode(&self, data: &QRData) -> Result<String, CoreError> {
+        SignedQr::sign(data.to_payload(), &self.signing_key)
+            .encode()
+            .map_err(|e| CoreError::QRCode(e.to_string()))
+    }
+
+    /// Parses a scanned string and checks it was signed by a trusted,
+    /// unrevoked key.
+    pub fn decode(&self, encoded: &str) -> Result<QRData, CoreError> {
+        let signed = SignedQr::decode(encoded)
+            .map_err(|e| CoreError::Validation(e.to_string()))?;
+        signed
+            .verify(&self.keyring.read())
+            .map_err(|e| CoreError::SecurityError(e.to_string()))?;
+        QRData::from_signed(signed)
+    }
+
+    /// Decodes and verifies `request`, then checks its token is still
+    /// redeemable. Expiry is judged by the server clock, not the scanner's.
+    async fn check_token(
         &self,
-        data: &QRData,
+        request: &VerifyQRRequest,
         context: &SecurityContext,
-    ) -> Result<bool, CoreError> {
-        // Extract the signature and message from QR data
-        let signature_bytes = base64::decode(&data.signature)
-            .map_err(|e| CoreError::Validation(format!("Invalid signature format: {}", e)))?;
-        
-        let message = format!("{}{}{}", 
-            data.property_id, 
-            data.timestamp.to_rfc3339(),
-            data.custodian_id
-        );
+    ) -> Result<QRData, CoreError> {
+        let data = self.decode(&request.qr_data)?;
 
-        // Get the verifying key for the custodian
-        let verifying_key_bytes = base64::decode(&data.public_key)
-            .map_err(|e| CoreError::Validation(format!("Invalid public key format: {}", e)))?;
-        
-        let verifying_key = VerifyingKey::from_bytes(&verifying_key_bytes.try_into().map_err(|_| {
-            CoreError::Validation("Invalid public key length".to_string())
-        })?)
-        .map_err(|e| CoreError::Validation(format!("Invalid public key: {}", e)))?;
-
-        // Create signature object
-        let signature = Signature::try_from(&signature_bytes[..])
-            .map_err(|e| CoreError::Validation(format!("Invalid signature: {}", e)))?;
-
-        // Verify the signature
-        verifying_key
-            .verify(message.as_bytes(), &signature)
-            .map_err(|e| CoreError::Validation(format!("Signature verification failed: {}", e)))?;
-
-        // Verify the custodian has permission to transfer this property
+        let token = self.tokens
+            .get(data.id)
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))?
+            .ok_or_else(|| CoreError::Validation("QR code was not issued by this server".into()))?;
+        if token.property_id != data.property_id || token.custodian_id != data.custodian_id {
+            return Err(CoreError::SecurityError("QR code does not match its issued token".into()));
+        }
+        if let Some(reason) = token.rejection(Utc::now()) {
+            return Err(CoreError::Validation(reason));
+        }
+
+        // Verify the scanner may act on this property
         if !context.can_access_property(data.property_id) {
             return Err(CoreError::Authorization("Unauthorized to transfer this property".to_string()));
         }
 
-        Ok(true)
-    }
-
-    async fn verify_timestamp(
-        &self,
-        timestamp: DateTime<Utc>,
-        scan_time: DateTime<Utc>,
-    ) -> Result<bool, CoreError> {
-        let age = scan_time - timestamp;
-        if age.num_hours() > 24 {
-            return Err(CoreError::Validation("QR code has expired".into()));
-        }
-        Ok(true)
+        Ok(data)
     }
 }
 
 #[async_trait]
 impl QRCodeService for QRCodeServiceImpl {
+    async fn issue_qr(
+        &self,
+        data: &QRData,
+        usage: QRUsage,
+        _context: &SecurityContext,
+    ) -> Result<String, CoreError> {
+        // Record the one-time token, then sign and encode the compact payload
+        let ttl = self.policy.ttl_for(data.category, usage);
+        let token = QRToken::new(data.id, data.property_id, data.custodian_id.clone(), data.timestamp, ttl);
+        self.tokens
+            .create(&token)
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))?;
+        self.encode(data)
+    }
+
     async fn generate_qr(
         &self,
         data: &QRData,
         format: QRFormat,
-        _context: &SecurityContext,
+        context: &SecurityContext,
     ) -> Result<QRResponse, CoreError> {
-        // Create the message to sign
-        let message = format!("{}{}{}", 
-            data.property_id, 
-            data.timestamp.to_rfc3339(),
-            data.custodian_id
-        );
-
-        // Sign the message
-        let signature = self.signing_key.sign(message.as_bytes());
-        
-        // Get the public key
-        let verifying_key = self.signing_key.verifying_key();
-
-        let mut qr_data = data.clone();
-        qr_data.signature = base64::encode(signature.to_bytes());
-        qr_data.public_key = base64::encode(verifying_key.to_bytes());
-
-        // Serialize data to JSON
-        let data_str = serde_json::to_string(&qr_data)
-            .map_err(|e| CoreError::Validation(format!("Failed to serialize QR data: {}", e)))?;
+        let payload = self.issue_qr(data, QRUsage::Transfer, context).await?;
 
         // Generate QR code in requested format
-        match format {
-            QRFormat::PNG => {
-                let code = qrcode::QrCode::new(data_str.as_bytes())
-                    .map_err(|e| CoreError::QRCode(e.to_string()))?;
-                
-                let renderer = code.render::<unicode::Dense1x2>();
-                let image = renderer.build();
-                
-                let mut buffer = Vec::new();
-                image::DynamicImage::ImageRgb8(ImageBuffer::from_fn(
-                    image.len() as u32,
-                    image.len() as u32,
-                    |x, y| {
-                        if image.chars().nth((y as usize * image.len()) + x as usize)
-                            .map(|c| c != ' ')
-                            .unwrap_or(false)
-                        {
-                            Rgb([0, 0, 0])
-                        } else {
-                            Rgb([255, 255, 255])
-                        }
-                    }
-                ))
-                .write_to(&mut std::io::Cursor::new(&mut buffer), image::ImageFormat::Png)
-                .map_err(|e| CoreError::Image(e.to_string()))?;
-                
-                Ok(QRResponse {
-                    format: QRFormat::PNG,
-                    data: buffer,
-                })
-            }
-            QRFormat::SVG => {
-                let code = qrcode::QrCode::new(data_str.as_bytes())
-                    .map_err(|e| CoreError::QRCode(e.to_string()))?;
-                
-                let svg_string = code.render()
-                    .min_dimensions(200, 200)
-                    .dark_color(svg::Color("#000000"))
-                    .light_color(svg::Color("#ffffff"))
-                    .build()
-                    .to_string();
-                
-                Ok(QRResponse {
-                    format: QRFormat::SVG,
-                    data: svg_string.into_bytes(),
-                })
-            }
-        }
+        let data = match format {
+            QRFormat::PNG => render_png(&payload, &self.render)?,
+            QRFormat::SVG => render_svg(&payload, &self.render)?.into_bytes(),
+        };
+        Ok(QRResponse { format, data })
     }
 
     async fn validate_qr(
@@ -237,22 +434,255 @@ impl QRCodeService for QRCodeServiceImpl {
         request: VerifyQRRequest,
         context: &SecurityContext,
     ) -> Result<QRData, CoreError> {
-        // Parse QR data
-        let data: QRData = serde_json::from_str(&request.qr_data)
-            .map_err(|e| CoreError::Validation(format!("Invalid QR data: {}", e)))?;
-
-        // Verify signature if provided
-        if let Some(_) = &request.signature {
-            if !self.verify_signature(&data, context).await? {
-                return Err(CoreError::Validation("Invalid signature".into()));
+        self.check_token(&request, context).await
+    }
+
+    async fn consume_qr(
+        &self,
+        request: VerifyQRRequest,
+        context: &SecurityContext,
+    ) -> Result<QRData, CoreError> {
+        let data = self.check_token(&request, context).await?;
+
+        // The repository re-checks under its own lock, so of two racing
+        // scans only one gets the token back
+        self.tokens
+            .consume(data.id, context.user_id, Utc::now())
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))?
+            .ok_or_else(|| CoreError::Validation("QR code has already been used".into()))?;
+
+        Ok(data)
+    }
+
+    async fn revoke_property_qr(
+        &self,
+        property_id: i32,
+        reason: QRTokenRevocation,
+        _context: &SecurityContext,
+    ) -> Result<u64, CoreError> {
+        self.tokens
+            .revoke_for_property(property_id, reason)
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RepositoryError;
+    use parking_lot::Mutex;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct MockQRTokenRepository {
+        tokens: Mutex<HashMap<Uuid, QRToken>>,
+    }
+
+    #[async_trait]
+    impl QRTokenRepository for MockQRTokenRepository {
+        async fn create(&self, token: &QRToken) -> Result<QRToken, RepositoryError> {
+            self.tokens.lock().insert(token.nonce, token.clone());
+            Ok(token.clone())
+        }
+
+        async fn get(&self, nonce: Uuid) -> Result<Option<QRToken>, RepositoryError> {
+            Ok(self.tokens.lock().get(&nonce).cloned())
+        }
+
+        async fn consume(
+            &self,
+            nonce: Uuid,
+            consumed_by: i32,
+            at: DateTime<Utc>,
+        ) -> Result<Option<QRToken>, RepositoryError> {
+            let mut tokens = self.tokens.lock();
+            match tokens.get_mut(&nonce) {
+                Some(token) if token.is_redeemable(at) => {
+                    token.consumed_at = Some(at);
+                    token.consumed_by = Some(consumed_by);
+                    Ok(Some(token.clone()))
+                }
+                _ => Ok(None),
             }
         }
 
-        // Verify timestamp
-        if !self.verify_timestamp(data.timestamp, request.timestamp).await? {
-            return Err(CoreError::Validation("QR code has expired".into()));
+        async fn revoke_for_property(
+            &self,
+            property_id: i32,
+            reason: QRTokenRevocation,
+        ) -> Result<u64, RepositoryError> {
+            let mut revoked = 0;
+            for token in self.tokens.lock().values_mut() {
+                if token.property_id == property_id && token.consumed_at.is_none() && token.revoked_at.is_none() {
+                    token.revoked_at = Some(Utc::now());
+                    token.revocation_reason = Some(reason);
+                    revoked += 1;
+                }
+            }
+            Ok(revoked)
         }
+    }
 
-        Ok(data)
+    fn new_service(seed: u8) -> QRCodeServiceImpl {
+        QRCodeServiceImpl::new(
+            SigningKey::from_bytes(&[seed; 32]),
+            Arc::new(MockQRTokenRepository::default()),
+            QRTokenPolicy::default(),
+        )
+    }
+
+    fn request(qr_data: String) -> VerifyQRRequest {
+        VerifyQRRequest {
+            qr_data,
+            signature: None,
+            timestamp: Utc::now(),
+            scanner_id: "scanner-1".to_string(),
+            location: None,
+        }
+    }
+
+    /// Issues a token for `data` and returns the encoded payload.
+    async fn issue(service: &QRCodeServiceImpl, data: &QRData) -> String {
+        let token = QRToken::new(
+            data.id,
+            data.property_id,
+            data.custodian_id.clone(),
+            data.timestamp,
+            service.policy.ttl_for(data.category, QRUsage::Transfer),
+        );
+        service.tokens.create(&token).await.unwrap();
+        service.encode(data).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_through_trusted_key() {
+        let service = new_service(1);
+        let data = QRData::new(7, "custodian-1".to_string(), json!({"serial_number": "W123", "name": "Rifle"}));
+
+        let encoded = issue(&service, &data).await;
+        let decoded = service
+            .validate_qr(request(encoded), &SecurityContext::new(1))
+            .await
+            .unwrap();
+        assert_eq!(decoded.id, data.id);
+        assert_eq!(decoded.property_id, 7);
+        assert_eq!(decoded.metadata, data.metadata);
+        assert_eq!(decoded.kid, service.kid());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_untrusted_and_revoked_keys() {
+        let service = new_service(1);
+        let other = new_service(2);
+        let data = QRData::new(7, "custodian-1".to_string(), json!({}));
+        let context = SecurityContext::new(1);
+
+        // Same token store, so only the key decides
+        let foreign = other.encode(&data).unwrap();
+        issue(&service, &data).await;
+        assert!(service.validate_qr(request(foreign.clone()), &context).await.is_err());
+
+        service.trust_key(SigningKey::from_bytes(&[2; 32]).verifying_key());
+        assert!(service.validate_qr(request(foreign.clone()), &context).await.is_ok());
+
+        service.revoke_key(&other.kid());
+        assert!(service.validate_qr(request(foreign), &context).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tokens_are_single_use() {
+        let service = new_service(1);
+        let context = SecurityContext::new(1);
+        let data = QRData::new(7, "custodian-1".to_string(), json!({}));
+        let encoded = issue(&service, &data).await;
+
+        assert!(service.validate_qr(request(encoded.clone()), &context).await.is_ok());
+        assert!(service.consume_qr(request(encoded.clone()), &context).await.is_ok());
+        let replay = service.consume_qr(request(encoded.clone()), &context).await.unwrap_err();
+        assert!(replay.to_string().contains("already been used"));
+        assert!(service.validate_qr(request(encoded), &context).await.is_err());
+
+        // A validly signed code the server never issued a token for
+        let unissued = service.encode(&QRData::new(7, "custodian-1".to_string(), json!({}))).unwrap();
+        assert!(service.consume_qr(request(unissued), &context).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_category_ttl_and_revocation() {
+        let service = new_service(1);
+        let context = SecurityContext::new(1);
+
+        let mut weapon = QRData::new(7, "custodian-1".to_string(), json!({})).with_category(PropertyCategory::Weapon);
+        weapon.timestamp = Utc::now() - chrono::Duration::minutes(20);
+        let expired = issue(&service, &weapon).await;
+        let err = service.consume_qr(request(expired), &context).await.unwrap_err();
+        assert!(err.to_string().contains("expired"));
+
+        let fresh = issue(&service, &QRData::new(7, "custodian-1".to_string(), json!({}))).await;
+        let other_property = issue(&service, &QRData::new(8, "custodian-1".to_string(), json!({}))).await;
+        let revoked = service
+            .revoke_property_qr(7, QRTokenRevocation::HolderChanged, &context)
+            .await
+            .unwrap();
+        assert_eq!(revoked, 2);
+        let err = service.consume_qr(request(fresh), &context).await.unwrap_err();
+        assert!(err.to_string().contains("revoked"));
+        assert!(service.consume_qr(request(other_property), &context).await.is_ok());
+    }
+
+    #[test]
+    fn test_png_is_module_accurate() {
+        let payload = "HR1:TEST";
+        let (width, modules) = qr_modules(payload).unwrap();
+        let options = QRRenderOptions { scale: 3, quiet_zone: 2 };
+        let png = render_png(payload, &options).unwrap();
+
+        let image = image::load_from_memory(&png).unwrap().to_luma8();
+        let size = (width as u32 + 4) * 3;
+        assert_eq!(image.dimensions(), (size, size));
+        // Quiet zone is light, and each module maps to a 3x3 block
+        assert_eq!(image.get_pixel(0, 0)[0], 255);
+        for (index, dark) in modules.iter().enumerate() {
+            let (x, y) = ((index % width) as u32 + 2, (index / width) as u32 + 2);
+            let expected = if *dark { 0 } else { 255 };
+            assert_eq!(image.get_pixel(x * 3, y * 3)[0], expected);
+            assert_eq!(image.get_pixel(x * 3 + 2, y * 3 + 2)[0], expected);
+        }
+    }
+
+    #[test]
+    fn test_svg_dimensions() {
+        let (width, _) = qr_modules("HR1:TEST").unwrap();
+        let svg = render_svg("HR1:TEST", &QRRenderOptions::default()).unwrap();
+        let view = width + 8;
+        assert!(svg.contains(&format!(r#"viewBox="0 0 {} {}""#, view, view)));
+        assert!(svg.contains(&format!(r#"width="{}""#, view * 8)));
+    }
+
+    #[test]
+    fn test_render_options_are_bounded() {
+        assert_eq!(QRRenderOptions::new(None, None).unwrap(), QRRenderOptions::default());
+        assert_eq!(QRRenderOptions::new(Some(16), Some(0)).unwrap().scale, 16);
+        assert!(QRRenderOptions::new(Some(0), None).is_err());
+        assert!(QRRenderOptions::new(Some(u32::MAX), None).is_err());
+        assert!(QRRenderOptions::new(None, Some(17)).is_err());
+
+        // Renderers refuse options built around the bounds
+        let huge = QRRenderOptions { scale: u32::MAX, quiet_zone: 4 };
+        assert!(render_png("HR1:TEST", &huge).is_err());
+        assert!(render_svg("HR1:TEST", &huge).is_err());
+    }
+
+    #[test]
+    fn test_metadata_digest_ignores_key_order() {
+        let mut first = QRData::new(7, "custodian-1".to_string(), json!({"a": 1, "b": {"c": 2, "d": 3}}));
+        let mut second = first.clone();
+        second.metadata = json!({"b": {"d": 3, "c": 2}, "a": 1});
+        first.kid = "kid".to_string();
+        second.kid = "kid".to_string();
+        assert_eq!(first.to_payload().digest(), second.to_payload().digest());
     }
 }
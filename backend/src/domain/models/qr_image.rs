@@ -0,0 +1,251 @@
+//! QR codes read from uploaded photos.
+//!
+//! Kiosks and the web client send images instead of decoded strings. Every
+//! code in an image is read with the same decoder the mobile scanner uses,
+//! then verified like a scanned code and resolved to its property. Codes are
+//! only looked up here, never redeemed.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use chrono::Utc;
+use handreceipt_protocol::scan::decode_qr_codes;
+use image::ImageFormat;
+use serde::Serialize;
+
+use crate::{
+    domain::{
+        models::qr::{QRCodeService, QRData, VerifyQRRequest},
+        property::{entity::Property, service::PropertyService},
+    },
+    error::CoreError,
+    types::security::SecurityContext,
+};
+
+/// Largest accepted upload per image.
+pub const MAX_QR_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+
+/// Most images accepted in one request.
+pub const MAX_QR_IMAGES: usize = 20;
+
+/// Reads every QR code in a PNG or JPEG. Duplicate codes are reported once;
+/// unreadable symbols keep their place as errors.
+pub fn decode_qr_image(bytes: &[u8]) -> Result<Vec<Result<String, String>>, CoreError> {
+    let format = image::guess_format(bytes)
+        .map_err(|_| CoreError::Validation("Unrecognised image format".to_string()))?;
+    if !matches!(format, ImageFormat::Png | ImageFormat::Jpeg) {
+        return Err(CoreError::Validation(format!(
+            "Unsupported image format {:?}, expected PNG or JPEG",
+            format
+        )));
+    }
+    let image = image::load_from_memory_with_format(bytes, format)
+        .map_err(|e| CoreError::Validation(format!("Unreadable image: {}", e)))?
+        .to_luma8();
+
+    let mut seen = HashSet::new();
+    Ok(decode_qr_codes(&image)
+        .into_iter()
+        .filter(|code| code.as_ref().map_or(true, |payload| seen.insert(payload.clone())))
+        .map(|code| code.map_err(|e| e.to_string()))
+        .collect())
+}
+
+/// One code found in an image.
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedQRCode {
+    /// Raw content, absent when the symbol could not be read.
+    pub payload: Option<String>,
+    pub verified: bool,
+    pub qr: Option<QRData>,
+    pub property: Option<Property>,
+    pub error: Option<String>,
+}
+
+impl DecodedQRCode {
+    fn rejected(payload: Option<String>, error: String) -> Self {
+        Self {
+            payload,
+            verified: false,
+            qr: None,
+            property: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// Everything found in one uploaded image.
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedQRImage {
+    pub filename: Option<String>,
+    pub codes: Vec<DecodedQRCode>,
+    /// Set when the image itself could not be read.
+    pub error: Option<String>,
+}
+
+/// Verifies and resolves the codes in uploaded images.
+pub struct QRImageService {
+    qr_service: Arc<dyn QRCodeService>,
+    properties: Arc<dyn PropertyService>,
+}
+
+impl QRImageService {
+    pub fn new(qr_service: Arc<dyn QRCodeService>, properties: Arc<dyn PropertyService>) -> Self {
+        Self { qr_service, properties }
+    }
+
+    /// Resolves each code [`decode_qr_image`] found in an image. Decoding is
+    /// CPU bound, so callers run it off the async executor and pass its
+    /// result in. A bad image or code is reported in the result rather than
+    /// failing the whole upload.
+    pub async fn resolve_image(
+        &self,
+        filename: Option<String>,
+        decoded: Result<Vec<Result<String, String>>, CoreError>,
+        context: &SecurityContext,
+    ) -> DecodedQRImage {
+        let payloads = match decoded {
+            Ok(payloads) => payloads,
+            Err(e) => {
+                return DecodedQRImage {
+                    filename,
+                    codes: Vec::new(),
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+
+        let mut codes = Vec::with_capacity(payloads.len());
+        for payload in payloads {
+            codes.push(match payload {
+                Ok(payload) => self.resolve(payload, context).await,
+                Err(e) => DecodedQRCode::rejected(None, e),
+            });
+        }
+        DecodedQRImage {
+            filename,
+            codes,
+            error: None,
+        }
+    }
+
+    async fn resolve(&self, payload: String, context: &SecurityContext) -> DecodedQRCode {
+        let request = VerifyQRRequest {
+            qr_data: payload.clone(),
+            signature: None,
+            timestamp: Utc::now(),
+            scanner_id: context.user_id.to_string(),
+            location: None,
+        };
+        let qr = match self.qr_service.validate_qr(request, context).await {
+            Ok(qr) => qr,
+            Err(e) => return DecodedQRCode::rejected(Some(payload), e.to_string()),
+        };
+
+        match self.properties.get_property(qr.property_id, context).await {
+            Ok(Some(property)) => DecodedQRCode {
+                payload: Some(payload),
+                verified: true,
+                qr: Some(qr),
+                property: Some(property),
+                error: None,
+            },
+            Ok(None) => DecodedQRCode::rejected(
+                Some(payload),
+                format!("Property {} not found", qr.property_id),
+            ),
+            Err(e) => DecodedQRCode::rejected(Some(payload), e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImage, GrayImage, Luma};
+    use crate::{
+        domain::{
+            models::{
+                location::Location,
+                qr::{render_png, QRRenderOptions},
+            },
+            property::entity::PropertyCategory,
+        },
+        test_support::{MockProperties, StubQRService},
+    };
+
+    /// A photo-like image holding one rendered code per payload, side by side.
+    fn photo(payloads: &[&str], format: ImageFormat) -> Vec<u8> {
+        let codes: Vec<GrayImage> = payloads
+            .iter()
+            .map(|payload| {
+                let png = render_png(payload, &QRRenderOptions::default()).unwrap();
+                image::load_from_memory(&png).unwrap().to_luma8()
+            })
+            .collect();
+        let width = codes.iter().map(|code| code.width()).sum();
+        let height = codes.iter().map(|code| code.height()).max().unwrap();
+
+        let mut canvas = GrayImage::from_pixel(width, height, Luma([255]));
+        let mut x = 0;
+        for code in &codes {
+            canvas.copy_from(code, x, 0).unwrap();
+            x += code.width();
+        }
+        let mut bytes = Vec::new();
+        image::DynamicImage::ImageLuma8(canvas)
+            .write_to(&mut std::io::Cursor::new(&mut bytes), format)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_decodes_png_and_jpeg() {
+        for format in [ImageFormat::Png, ImageFormat::Jpeg] {
+            let mut payloads: Vec<String> = decode_qr_image(&photo(&["HR1:ONE", "HR1:TWO", "HR1:ONE"], format))
+                .unwrap()
+                .into_iter()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            payloads.sort();
+            assert_eq!(payloads, vec!["HR1:ONE".to_string(), "HR1:TWO".to_string()]);
+        }
+
+        let gif = b"GIF89a\x01\x00\x01\x00\x00\x00\x00;";
+        assert!(matches!(decode_qr_image(gif), Err(CoreError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_resolves_verified_codes_only() {
+        let mut rifle = Property::new(
+            "Rifle, 5.56mm, M4".to_string(),
+            "Carbine".to_string(),
+            PropertyCategory::Weapon,
+            1,
+            Location::default(),
+        );
+        rifle.id = 42;
+        let service = QRImageService::new(Arc::new(StubQRService::default()), Arc::new(MockProperties::new(vec![rifle])));
+        let context = SecurityContext::new(2);
+
+        let decoded = decode_qr_image(&photo(&["HR1:42", "HR1:FORGED"], ImageFormat::Png));
+        let image = service
+            .resolve_image(Some("kiosk.png".to_string()), decoded, &context)
+            .await;
+        assert!(image.error.is_none());
+        assert_eq!(image.codes.len(), 2);
+
+        let good = image.codes.iter().find(|code| code.verified).unwrap();
+        assert_eq!(good.payload.as_deref(), Some("HR1:42"));
+        assert_eq!(good.property.as_ref().map(|property| property.id), Some(42));
+
+        let forged = image.codes.iter().find(|code| !code.verified).unwrap();
+        assert_eq!(forged.payload.as_deref(), Some("HR1:FORGED"));
+        assert!(forged.property.is_none());
+        assert!(forged.error.as_deref().unwrap().contains("signature"));
+
+        let unreadable = service.resolve_image(None, decode_qr_image(b"not an image"), &context).await;
+        assert!(unreadable.codes.is_empty());
+        assert!(unreadable.error.is_some());
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    domain::property::entity::PropertyCategory,
+    error::RepositoryError,
+};
+
+/// Why a QR token stopped being usable before it expired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QRTokenRevocation {
+    /// The property changed hands, so codes naming the old holder are stale.
+    HolderChanged,
+    /// The transfer the code was printed for was cancelled.
+    TransferCancelled,
+    /// Revoked by hand, e.g. a label was lost.
+    Manual,
+}
+
+impl QRTokenRevocation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QRTokenRevocation::HolderChanged => "holder_changed",
+            QRTokenRevocation::TransferCancelled => "transfer_cancelled",
+            QRTokenRevocation::Manual => "manual",
+        }
+    }
+}
+
+impl std::str::FromStr for QRTokenRevocation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "holder_changed" => Ok(QRTokenRevocation::HolderChanged),
+            "transfer_cancelled" => Ok(QRTokenRevocation::TransferCancelled),
+            "manual" => Ok(QRTokenRevocation::Manual),
+            _ => Err(format!("Unknown QR token revocation reason: {}", s)),
+        }
+    }
+}
+
+/// What a QR code is issued for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QRUsage {
+    /// Shown on a screen for an immediate hand-off.
+    Transfer,
+    /// Printed on a label that stays on the item until it changes hands.
+    Label,
+}
+
+/// Server-side record of an issued QR code. The nonce is the `id` carried in
+/// the signed payload, so a code can only be redeemed once no matter how many
+/// times it is scanned or copied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QRToken {
+    pub nonce: Uuid,
+    pub property_id: i32,
+    pub custodian_id: String,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub consumed_at: Option<DateTime<Utc>>,
+    pub consumed_by: Option<i32>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub revocation_reason: Option<QRTokenRevocation>,
+}
+
+impl QRToken {
+    pub fn new(
+        nonce: Uuid,
+        property_id: i32,
+        custodian_id: String,
+        issued_at: DateTime<Utc>,
+        ttl: Duration,
+    ) -> Self {
+        Self {
+            nonce,
+            property_id,
+            custodian_id,
+            issued_at,
+            expires_at: issued_at + ttl,
+            consumed_at: None,
+            consumed_by: None,
+            revoked_at: None,
+            revocation_reason: None,
+        }
+    }
+
+    /// Why the token cannot be redeemed at `at`, or `None` if it can.
+    pub fn rejection(&self, at: DateTime<Utc>) -> Option<String> {
+        if let Some(reason) = self.revocation_reason {
+            return Some(format!("QR code has been revoked ({})", reason.as_str()));
+        }
+        if self.revoked_at.is_some() {
+            return Some("QR code has been revoked".to_string());
+        }
+        if self.consumed_at.is_some() {
+            return Some("QR code has already been used".to_string());
+        }
+        if at >= self.expires_at {
+            return Some("QR code has expired".to_string());
+        }
+        None
+    }
+
+    pub fn is_redeemable(&self, at: DateTime<Utc>) -> bool {
+        self.rejection(at).is_none()
+    }
+}
+
+/// How long QR codes stay redeemable. Sensitive categories get short lived
+/// codes by default so a photographed screen or label is of little use.
+/// Printed labels for other categories live much longer; they are revoked
+/// when the holder changes.
+#[derive(Debug, Clone)]
+pub struct QRTokenPolicy {
+    pub default_ttl: Duration,
+    pub category_ttls: HashMap<PropertyCategory, Duration>,
+    pub label_ttl: Duration,
+}
+
+impl Default for QRTokenPolicy {
+    fn default() -> Self {
+        let mut category_ttls = HashMap::new();
+        category_ttls.insert(PropertyCategory::Weapon, Duration::minutes(15));
+        category_ttls.insert(PropertyCategory::Ammunition, Duration::minutes(15));
+        Self {
+            default_ttl: Duration::hours(24),
+            category_ttls,
+            label_ttl: Duration::days(180),
+        }
+    }
+}
+
+impl QRTokenPolicy {
+    pub fn with_ttl(mut self, category: PropertyCategory, ttl: Duration) -> Self {
+        self.category_ttls.insert(category, ttl);
+        self
+    }
+
+    /// A category TTL caps labels as well as transfer codes.
+    pub fn ttl_for(&self, category: Option<PropertyCategory>, usage: QRUsage) -> Duration {
+        let category_ttl = category.and_then(|category| self.category_ttls.get(&category).copied());
+        match usage {
+            QRUsage::Transfer => category_ttl.unwrap_or(self.default_ttl),
+            QRUsage::Label => category_ttl.map_or(self.label_ttl, |ttl| ttl.min(self.label_ttl)),
+        }
+    }
+}
+
+#[async_trait]
+pub trait QRTokenRepository: Send + Sync {
+    async fn create(&self, token: &QRToken) -> Result<QRToken, RepositoryError>;
+    async fn get(&self, nonce: Uuid) -> Result<Option<QRToken>, RepositoryError>;
+    /// Atomically marks a redeemable token as used. Returns `None` if the
+    /// token does not exist or was already consumed, revoked or expired at
+    /// `at`, so two concurrent scans can never both succeed.
+    async fn consume(
+        &self,
+        nonce: Uuid,
+        consumed_by: i32,
+        at: DateTime<Utc>,
+    ) -> Result<Option<QRToken>, RepositoryError>;
+    /// Revokes every unused, unrevoked token for a property. Returns how
+    /// many were revoked.
+    async fn revoke_for_property(
+        &self,
+        property_id: i32,
+        reason: QRTokenRevocation,
+    ) -> Result<u64, RepositoryError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejection_reasons() {
+        let now = Utc::now();
+        let mut token = QRToken::new(Uuid::new_v4(), 1, "custodian-1".to_string(), now, Duration::minutes(15));
+        assert!(token.is_redeemable(now));
+        assert!(token.rejection(now + Duration::minutes(15)).unwrap().contains("expired"));
+
+        token.consumed_at = Some(now);
+        assert!(token.rejection(now).unwrap().contains("already been used"));
+
+        token.revoked_at = Some(now);
+        token.revocation_reason = Some(QRTokenRevocation::HolderChanged);
+        assert!(token.rejection(now).unwrap().contains("holder_changed"));
+    }
+
+    #[test]
+    fn test_policy_ttls() {
+        let policy = QRTokenPolicy::default().with_ttl(PropertyCategory::Vehicle, Duration::hours(2));
+        let transfer = |category| policy.ttl_for(category, QRUsage::Transfer);
+        assert_eq!(transfer(Some(PropertyCategory::Weapon)), Duration::minutes(15));
+        assert_eq!(transfer(Some(PropertyCategory::Vehicle)), Duration::hours(2));
+        assert_eq!(transfer(Some(PropertyCategory::Supply)), Duration::hours(24));
+        assert_eq!(transfer(None), Duration::hours(24));
+        let label = |category| policy.ttl_for(category, QRUsage::Label);
+        assert_eq!(label(Some(PropertyCategory::Weapon)), Duration::minutes(15));
+        assert_eq!(label(Some(PropertyCategory::Supply)), Duration::days(180));
+        assert_eq!(label(None), Duration::days(180));
+    }
+}
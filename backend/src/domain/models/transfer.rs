@@ -23,6 +23,21 @@ impl std::fmt::Display for TransferStatus {
     }
 }
 
+impl std::str::FromStr for TransferStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(TransferStatus::Pending),
+            "approved" => Ok(TransferStatus::Approved),
+            "rejected" => Ok(TransferStatus::Rejected),
+            "completed" => Ok(TransferStatus::Completed),
+            "cancelled" => Ok(TransferStatus::Cancelled),
+            _ => Err(format!("Unknown transfer status: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transfer {
     pub id: i32,
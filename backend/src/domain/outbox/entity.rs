@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Delivery state of a ledger outbox entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutboxStatus {
+    /// Waiting to be (re)submitted by the relay.
+    Pending,
+    /// The batch carrying the entry has been committed.
+    Committed,
+    /// Rejected by the ledger or out of retries.
+    Failed,
+}
+
+impl OutboxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutboxStatus::Pending => "pending",
+            OutboxStatus::Committed => "committed",
+            OutboxStatus::Failed => "failed",
+        }
+    }
+}
+
+impl std::str::FromStr for OutboxStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(OutboxStatus::Pending),
+            "committed" => Ok(OutboxStatus::Committed),
+            "failed" => Ok(OutboxStatus::Failed),
+            _ => Err(format!("Unknown outbox status: {}", s)),
+        }
+    }
+}
+
+/// A ledger payload recorded in the same database transaction as the
+/// change it mirrors.
+///
+/// `nonce` becomes the transaction header nonce, so every resubmission of
+/// an entry produces the same transaction and batch ids.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub id: i64,
+    pub aggregate_type: String,
+    pub aggregate_id: i32,
+    pub nonce: String,
+    pub payload: serde_json::Value,
+    pub status: OutboxStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub batch_id: Option<String>,
+    pub transaction_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub committed_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+use crate::error::RepositoryError;
+use super::entity::OutboxEntry;
+
+/// Relay-side access to the ledger outbox. Entries are written by the
+/// Postgres repositories inside their own transactions.
+#[async_trait]
+pub trait OutboxRepository: Send + Sync {
+    /// Claims up to `limit` due pending entries, hiding them from other
+    /// relays for `lease`. An entry is only claimed once every earlier entry
+    /// for its aggregate has left `pending`.
+    async fn claim_due(&self, limit: i64, lease: Duration) -> Result<Vec<OutboxEntry>, RepositoryError>;
+    async fn record_batch(&self, id: i64, batch_id: &str, transaction_id: &str) -> Result<(), RepositoryError>;
+    async fn mark_committed(&self, id: i64) -> Result<(), RepositoryError>;
+    async fn schedule_retry(
+        &self,
+        id: i64,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), RepositoryError>;
+    async fn mark_failed(&self, id: i64, error: &str) -> Result<(), RepositoryError>;
+    async fn list_failed(&self, limit: i64) -> Result<Vec<OutboxEntry>, RepositoryError>;
+    async fn list_by_aggregate(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: i32,
+    ) -> Result<Vec<OutboxEntry>, RepositoryError>;
+}
@@ -5,7 +5,7 @@ use sqlx::{Decode, Encode, Type};
 use std::fmt;
 use crate::domain::models::location::Location;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, sqlx::Type)]
 #[sqlx(type_name = "property_category", rename_all = "snake_case")]
 pub enum PropertyCategory {
     Equipment,
@@ -101,4 +101,32 @@ impl Property {
             requires_approval: false,
         }
     }
+
+    /// Unit code recorded in the property's metadata.
+    pub fn unit(&self) -> Option<&str> {
+        self.metadata.get("unit").and_then(|unit| unit.as_str())
+    }
+
+    /// Unit of issue (`EA`, `PR`, `SE`, ...) recorded in the property's
+    /// metadata. Items without one are issued each.
+    pub fn unit_of_issue(&self) -> &str {
+        self.metadata
+            .get("unit_of_issue")
+            .and_then(|unit| unit.as_str())
+            .unwrap_or("EA")
+    }
+
+    /// Strips what identifies or locates a sensitive item, for callers not
+    /// cleared to handle it. The unit and unit of issue are kept so the item
+    /// still counts toward its property book.
+    pub fn redact(&mut self) {
+        let kept: serde_json::Map<String, serde_json::Value> = ["unit", "unit_of_issue"]
+            .into_iter()
+            .filter_map(|key| Some((key.to_string(), self.metadata.get(key)?.clone())))
+            .collect();
+        self.serial_number = None;
+        self.location = Location::default();
+        self.notes = None;
+        self.metadata = serde_json::Value::Object(kept);
+    }
 }
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use super::entity::{Property, PropertyCategory, PropertyStatus};
+
+/// Which properties a QR batch or export covers. Empty fields match
+/// everything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PropertyFilter {
+    /// Unit code, matched against the property's `unit` metadata.
+    pub unit: Option<String>,
+    pub category: Option<PropertyCategory>,
+    pub status: Option<PropertyStatus>,
+    pub hand_receipt_number: Option<String>,
+    pub holder_id: Option<i32>,
+}
+
+impl PropertyFilter {
+    pub fn matches(&self, property: &Property) -> bool {
+        self.unit.as_deref().is_none_or(|wanted| property.unit() == Some(wanted))
+            && self.category.is_none_or(|wanted| property.category == wanted)
+            && self.status.is_none_or(|wanted| property.status == wanted)
+            && self
+                .hand_receipt_number
+                .as_deref()
+                .is_none_or(|wanted| property.hand_receipt_number.as_deref() == Some(wanted))
+            && self.holder_id.is_none_or(|wanted| property.current_holder_id == wanted)
+    }
+}
@@ -1,7 +1,9 @@
 pub mod entity;
+pub mod filter;
 pub mod repository;
 pub mod service;
 
 pub use entity::{Property, PropertyCategory, PropertyStatus};
+pub use filter::PropertyFilter;
 pub use repository::PropertyRepository;
 pub use service::PropertyService;
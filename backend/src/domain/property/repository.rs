@@ -1,12 +1,27 @@
 use async_trait::async_trait;
 use crate::error::RepositoryError;
 use super::entity::Property;
+use super::filter::PropertyFilter;
+use super::service::SyncStatus;
 
 #[async_trait]
 pub trait PropertyRepository: Send + Sync {
     async fn create_property(&self, property: Property) -> Result<Property, RepositoryError>;
+    /// Creates all of `properties` in one transaction; nothing is stored if
+    /// any of them fails.
+    async fn create_properties(&self, properties: Vec<Property>) -> Result<Vec<Property>, RepositoryError>;
     async fn update_property(&self, property: &Property) -> Result<(), RepositoryError>;
     async fn delete_property(&self, id: i32) -> Result<(), RepositoryError>;
     async fn get_property(&self, id: i32) -> Result<Option<Property>, RepositoryError>;
     async fn list_properties(&self) -> Result<Vec<Property>, RepositoryError>;
+    /// Up to `limit` properties matching `filter` with ids above
+    /// `after_id`, in id order, for paging through the whole book.
+    async fn list_properties_after(
+        &self,
+        filter: &PropertyFilter,
+        after_id: i32,
+        limit: i64,
+    ) -> Result<Vec<Property>, RepositoryError>;
+    async fn get_sync_status(&self, id: i32) -> Result<Option<SyncStatus>, RepositoryError>;
+    async fn update_sync_status(&self, id: i32, status: &SyncStatus) -> Result<(), RepositoryError>;
 }
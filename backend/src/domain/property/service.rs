@@ -5,11 +5,60 @@ use crate::{
     error::repository::RepositoryError,
 };
 
+/// Ledger state of the last batch submitted for a property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncState {
+    Unsynced,
+    Pending,
+    Committed,
+    Invalid,
+}
+
+impl SyncState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncState::Unsynced => "unsynced",
+            SyncState::Pending => "pending",
+            SyncState::Committed => "committed",
+            SyncState::Invalid => "invalid",
+        }
+    }
+}
+
+impl std::str::FromStr for SyncState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unsynced" => Ok(SyncState::Unsynced),
+            "pending" => Ok(SyncState::Pending),
+            "committed" => Ok(SyncState::Committed),
+            "invalid" => Ok(SyncState::Invalid),
+            _ => Err(format!("Unknown sync state: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct SyncStatus {
     pub is_synced: bool,
     pub last_sync: Option<chrono::DateTime<chrono::Utc>>,
     pub blockchain_hash: Option<String>,
+    pub state: SyncState,
+    pub error: Option<String>,
+}
+
+impl SyncStatus {
+    pub fn new(state: SyncState, batch_id: Option<String>, error: Option<String>) -> Self {
+        Self {
+            is_synced: state == SyncState::Committed,
+            last_sync: Some(chrono::Utc::now()),
+            blockchain_hash: batch_id,
+            state,
+            error,
+        }
+    }
 }
 
 #[async_trait]
@@ -0,0 +1,144 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::domain::property::filter::PropertyFilter;
+
+/// What a batch job produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QRBatchFormat {
+    /// A zip holding one PNG per property.
+    PngZip,
+    /// A zip holding one SVG per property.
+    SvgZip,
+    /// One PDF of label sheets covering every property.
+    LabelSheet,
+}
+
+impl QRBatchFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QRBatchFormat::PngZip => "png_zip",
+            QRBatchFormat::SvgZip => "svg_zip",
+            QRBatchFormat::LabelSheet => "label_sheet",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            QRBatchFormat::PngZip | QRBatchFormat::SvgZip => "application/zip",
+            QRBatchFormat::LabelSheet => "application/pdf",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            QRBatchFormat::PngZip | QRBatchFormat::SvgZip => "zip",
+            QRBatchFormat::LabelSheet => "pdf",
+        }
+    }
+}
+
+impl std::str::FromStr for QRBatchFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "png_zip" => Ok(QRBatchFormat::PngZip),
+            "svg_zip" => Ok(QRBatchFormat::SvgZip),
+            "label_sheet" => Ok(QRBatchFormat::LabelSheet),
+            _ => Err(format!("Unknown QR batch format: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QRBatchStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl QRBatchStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QRBatchStatus::Queued => "queued",
+            QRBatchStatus::Running => "running",
+            QRBatchStatus::Completed => "completed",
+            QRBatchStatus::Failed => "failed",
+        }
+    }
+}
+
+impl std::str::FromStr for QRBatchStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queued" => Ok(QRBatchStatus::Queued),
+            "running" => Ok(QRBatchStatus::Running),
+            "completed" => Ok(QRBatchStatus::Completed),
+            "failed" => Ok(QRBatchStatus::Failed),
+            _ => Err(format!("Unknown QR batch status: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QRBatchJob {
+    pub id: i32,
+    pub requested_by: i32,
+    pub filter: PropertyFilter,
+    pub format: QRBatchFormat,
+    /// Label template name, for [`QRBatchFormat::LabelSheet`].
+    pub template: Option<String>,
+    pub status: QRBatchStatus,
+    /// Properties matched by the filter, known once the job starts.
+    pub total: i32,
+    pub processed: i32,
+    pub output_size: Option<i64>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl QRBatchJob {
+    pub fn new(
+        requested_by: i32,
+        filter: PropertyFilter,
+        format: QRBatchFormat,
+        template: Option<String>,
+    ) -> Self {
+        Self {
+            id: 0,
+            requested_by,
+            filter,
+            format,
+            template,
+            status: QRBatchStatus::Queued,
+            total: 0,
+            processed: 0,
+            output_size: None,
+            error: None,
+            created_at: Utc::now(),
+            started_at: None,
+            completed_at: None,
+        }
+    }
+
+    /// Percentage of properties processed so far.
+    pub fn progress(&self) -> u8 {
+        match self.status {
+            QRBatchStatus::Completed => 100,
+            _ if self.total <= 0 => 0,
+            _ => ((self.processed.clamp(0, self.total) as i64 * 100) / self.total as i64) as u8,
+        }
+    }
+
+    /// Name offered when downloading the result.
+    pub fn filename(&self) -> String {
+        format!("qr-batch-{}.{}", self.id, self.format.extension())
+    }
+}
@@ -0,0 +1,5 @@
+pub mod entity;
+pub mod repository;
+
+pub use entity::{QRBatchFormat, QRBatchJob, QRBatchStatus};
+pub use repository::QRBatchRepository;
@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+use crate::error::RepositoryError;
+use super::entity::QRBatchJob;
+
+#[async_trait]
+pub trait QRBatchRepository: Send + Sync {
+    async fn create_job(&self, job: &QRBatchJob) -> Result<QRBatchJob, RepositoryError>;
+    async fn get_job(&self, id: i32) -> Result<Option<QRBatchJob>, RepositoryError>;
+    /// Most recent jobs requested by `user_id`, newest first.
+    async fn list_jobs(&self, user_id: i32, limit: i64) -> Result<Vec<QRBatchJob>, RepositoryError>;
+    /// Marks a queued job as running over `total` properties.
+    async fn start_job(&self, id: i32, total: i32) -> Result<(), RepositoryError>;
+    async fn update_progress(&self, id: i32, processed: i32) -> Result<(), RepositoryError>;
+    /// Stores the finished file and marks the job completed.
+    async fn complete_job(&self, id: i32, output: &[u8]) -> Result<(), RepositoryError>;
+    async fn fail_job(&self, id: i32, error: &str) -> Result<(), RepositoryError>;
+    /// Fails every job still queued or running. Jobs run inside the server
+    /// process, so at startup none of these can still be in progress.
+    /// Returns how many were failed.
+    async fn fail_unfinished_jobs(&self, error: &str) -> Result<u64, RepositoryError>;
+    /// The finished file, kept apart from the job row so listing stays cheap.
+    async fn get_output(&self, id: i32) -> Result<Option<Vec<u8>>, RepositoryError>;
+}
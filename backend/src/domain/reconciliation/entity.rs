@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Ways a property row can disagree with its on-chain state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftType {
+    MissingOnLedger,
+    CustodianMismatch,
+    StatusMismatch,
+}
+
+impl DriftType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DriftType::MissingOnLedger => "missing_on_ledger",
+            DriftType::CustodianMismatch => "custodian_mismatch",
+            DriftType::StatusMismatch => "status_mismatch",
+        }
+    }
+}
+
+impl std::str::FromStr for DriftType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "missing_on_ledger" => Ok(DriftType::MissingOnLedger),
+            "custodian_mismatch" => Ok(DriftType::CustodianMismatch),
+            "status_mismatch" => Ok(DriftType::StatusMismatch),
+            _ => Err(format!("Unknown drift type: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerDrift {
+    pub id: i32,
+    pub run_id: i32,
+    pub property_id: i32,
+    pub drift_type: DriftType,
+    pub database_value: Option<String>,
+    pub ledger_value: Option<String>,
+    pub resubmitted: bool,
+    pub detected_at: DateTime<Utc>,
+}
+
+impl LedgerDrift {
+    pub fn new(
+        property_id: i32,
+        drift_type: DriftType,
+        database_value: Option<String>,
+        ledger_value: Option<String>,
+    ) -> Self {
+        Self {
+            id: 0,
+            run_id: 0,
+            property_id,
+            drift_type,
+            database_value,
+            ledger_value,
+            resubmitted: false,
+            detected_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationRun {
+    pub id: i32,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub properties_checked: i32,
+    pub drift_count: i32,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    pub run: ReconciliationRun,
+    pub drift: Vec<LedgerDrift>,
+}
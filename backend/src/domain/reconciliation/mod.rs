@@ -0,0 +1,5 @@
+pub mod entity;
+pub mod repository;
+
+pub use entity::{DriftType, LedgerDrift, ReconciliationReport, ReconciliationRun};
+pub use repository::ReconciliationRepository;
@@ -0,0 +1,22 @@
+use async_trait::async_trait;
+use crate::{domain::property::entity::Property, error::RepositoryError};
+use super::entity::{LedgerDrift, ReconciliationReport, ReconciliationRun};
+
+#[async_trait]
+pub trait ReconciliationRepository: Send + Sync {
+    async fn start_run(&self) -> Result<ReconciliationRun, RepositoryError>;
+    async fn record_drift(&self, run_id: i32, drift: &LedgerDrift) -> Result<LedgerDrift, RepositoryError>;
+    async fn complete_run(
+        &self,
+        run_id: i32,
+        properties_checked: i32,
+        error: Option<&str>,
+    ) -> Result<ReconciliationRun, RepositoryError>;
+    async fn list_runs(&self, limit: i64) -> Result<Vec<ReconciliationRun>, RepositoryError>;
+    async fn get_report(&self, run_id: i32) -> Result<Option<ReconciliationReport>, RepositoryError>;
+    async fn get_latest_report(&self) -> Result<Option<ReconciliationReport>, RepositoryError>;
+    /// Queues the ledger Create of a property missing from the ledger,
+    /// bound to its holder's current key. A Create still pending in the
+    /// outbox is left to the relay.
+    async fn resubmit_property(&self, property: &Property) -> Result<(), RepositoryError>;
+}
@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
-use crate::domain::models::location::Location;
+use handreceipt_protocol::signing::{TransferRole, TransferSignature};
+use crate::domain::{component::entity::ShortageAnnex, models::location::Location};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "transfer_status", rename_all = "snake_case")]
@@ -98,4 +99,79 @@ impl Transfer {
         // For now, we'll return true to be safe
         true
     }
+
+    /// Shortage annex of the end item as it stood when the transfer was
+    /// created, i.e. what the receiver signs for along with the item.
+    pub fn shortage_annex(&self) -> Option<ShortageAnnex> {
+        ShortageAnnex::from_metadata(&self.metadata)
+    }
+
+    /// Digest of the shortage annex that goes into the transfer digest. See
+    /// [`ShortageAnnex::signed_digest`].
+    pub fn annex_digest(&self) -> String {
+        ShortageAnnex::signed_digest(&self.metadata)
+    }
+
+    pub fn set_shortage_annex(&mut self, annex: &ShortageAnnex) {
+        if !self.metadata.is_object() {
+            self.metadata = serde_json::Value::Object(serde_json::Map::new());
+        }
+        self.metadata["shortage_annex"] = serde_json::to_value(annex).expect("shortage annex serializes");
+    }
+
+    /// Strips where and how a sensitive item moved, for callers not cleared
+    /// to handle it. See
+    /// [`Property::redact`](crate::domain::property::entity::Property::redact).
+    pub fn redact(&mut self) {
+        self.location = Location::default();
+        self.notes = None;
+        self.metadata = serde_json::Value::Object(serde_json::Map::new());
+    }
+}
+
+/// What a transfer needs before custody changes hands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalRequirements {
+    /// Whether an approver has to sign off as well as both holders.
+    pub requires_approval: bool,
+    /// Signatures the ledger expects, in signing order.
+    pub required_signatures: Vec<TransferRole>,
+    /// Why approval is needed, for display to the scanner.
+    pub reasons: Vec<String>,
+}
+
+impl ApprovalRequirements {
+    /// Mirrors the ledger's `ApprovalPolicy`: both holders always sign, and
+    /// an approver signs for sensitive items, for property that requires
+    /// approval, and whenever custody is not yet bound to the holder's key.
+    pub fn new(is_sensitive: bool, requires_approval: bool, custody_bound: bool) -> Self {
+        let mut reasons = Vec::new();
+        if is_sensitive {
+            reasons.push("Sensitive item".to_string());
+        }
+        if requires_approval {
+            reasons.push("Property requires command approval".to_string());
+        }
+        if !custody_bound {
+            reasons.push("Custody is not yet bound to the holder's key".to_string());
+        }
+
+        let mut required_signatures = vec![TransferRole::Releasing, TransferRole::Receiving];
+        if !reasons.is_empty() {
+            required_signatures.push(TransferRole::Approver);
+        }
+
+        Self {
+            requires_approval: !reasons.is_empty(),
+            required_signatures,
+            reasons,
+        }
+    }
+
+    /// Returns true once `signatures` cover every required role.
+    pub fn is_satisfied_by(&self, signatures: &[TransferSignature]) -> bool {
+        self.required_signatures
+            .iter()
+            .all(|role| signatures.iter().any(|signature| signature.role == *role))
+    }
 }
@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use super::entity::{Transfer, TransferStatus};
+
+/// Which transfers an export covers. Empty fields match everything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TransferFilter {
+    pub property_id: Option<i32>,
+    pub status: Option<TransferStatus>,
+    /// Matches transfers to or from this holder.
+    pub holder_id: Option<i32>,
+    /// Created at or after.
+    pub since: Option<DateTime<Utc>>,
+    /// Created before.
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl TransferFilter {
+    pub fn matches(&self, transfer: &Transfer) -> bool {
+        self.property_id.is_none_or(|wanted| transfer.property_id == wanted)
+            && self.status.is_none_or(|wanted| transfer.status == wanted)
+            && self.holder_id.is_none_or(|wanted| {
+                transfer.from_holder_id == wanted || transfer.to_holder_id == wanted
+            })
+            && self.since.is_none_or(|since| transfer.created_at >= since)
+            && self.until.is_none_or(|until| transfer.created_at < until)
+    }
+}
@@ -1,7 +1,11 @@
 pub mod entity;
+pub mod filter;
 pub mod repository;
 pub mod service;
+pub mod service_impl;
 
-pub use self::entity::{Transfer, TransferStatus};
-pub use service::TransferService;
+pub use self::entity::{ApprovalRequirements, Transfer, TransferStatus};
+pub use filter::TransferFilter;
+pub use service::{QRScanTransfer, TransferService};
+pub use service_impl::TransferServiceImpl;
 pub use repository::TransferRepository;
@@ -1,13 +1,40 @@
 use async_trait::async_trait;
+use handreceipt_protocol::signing::TransferSignature;
+use uuid::Uuid;
 use crate::error::RepositoryError;
-use super::entity::Transfer;
+use super::entity::{ApprovalRequirements, Transfer, TransferStatus};
+use super::filter::TransferFilter;
 
 #[async_trait]
 pub trait TransferRepository: Send + Sync {
     async fn create_transfer(&self, transfer: Transfer) -> Result<Transfer, RepositoryError>;
+    /// Creates the transfer a QR scan starts, in one transaction that fails
+    /// if the property has changed hands or already has a pending transfer,
+    /// and otherwise redeems the scanned code's token.
+    async fn create_scanned_transfer(&self, transfer: Transfer, qr_nonce: Uuid) -> Result<Transfer, RepositoryError>;
     async fn update_transfer(&self, transfer: &Transfer) -> Result<(), RepositoryError>;
     async fn delete_transfer(&self, id: i32) -> Result<(), RepositoryError>;
     async fn get_transfer(&self, id: i32) -> Result<Option<Transfer>, RepositoryError>;
     async fn list_transfers(&self) -> Result<Vec<Transfer>, RepositoryError>;
+    /// Up to `limit` transfers matching `filter` with ids above `after_id`,
+    /// in id order, each with whether its property is sensitive.
+    async fn list_transfers_after(
+        &self,
+        filter: &TransferFilter,
+        after_id: i32,
+        limit: i64,
+    ) -> Result<Vec<(Transfer, bool)>, RepositoryError>;
     async fn list_by_property(&self, property_id: i32) -> Result<Vec<Transfer>, RepositoryError>;
+    /// Signatures the ledger will require to move `property_id` as it
+    /// stands now.
+    async fn approval_requirements(&self, property_id: i32) -> Result<ApprovalRequirements, RepositoryError>;
+    /// Marks transfers the ledger has applied as completed, without queueing
+    /// them again. Returns the rows that changed with their previous status.
+    async fn complete_transfers(&self, ids: &[i32]) -> Result<Vec<(i32, TransferStatus)>, RepositoryError>;
+
+    /// Stores a party signature. A role is signed once; a second signature
+    /// for it is a validation error. The signature completing a completed
+    /// transfer queues it for the ledger.
+    async fn add_signature(&self, transfer_id: i32, signature: &TransferSignature) -> Result<(), RepositoryError>;
+    async fn list_signatures(&self, transfer_id: i32) -> Result<Vec<TransferSignature>, RepositoryError>;
 } 
\ No newline at end of file
@@ -1,11 +1,20 @@
 use async_trait::async_trait;
+use serde::Serialize;
 use crate::{
-    domain::transfer::entity::Transfer,
+    domain::transfer::entity::{ApprovalRequirements, Transfer},
     types::security::SecurityContext,
     error::repository::RepositoryError,
     domain::models::location::Location,
 };
 
+/// Result of scanning a property's QR code: the pending transfer to the
+/// scanner and what it needs before it can complete.
+#[derive(Debug, Clone, Serialize)]
+pub struct QRScanTransfer {
+    pub transfer: Transfer,
+    pub approval: ApprovalRequirements,
+}
+
 #[async_trait]
 pub trait TransferService: Send + Sync {
     async fn create_transfer(&self, transfer: Transfer, context: &SecurityContext) -> Result<Transfer, RepositoryError>;
@@ -15,5 +24,5 @@ pub trait TransferService: Send + Sync {
     async fn get_property_transfers(&self, property_id: i32, context: &SecurityContext) -> Result<Vec<Transfer>, RepositoryError>;
     
     // QR code scanning
-    async fn scan_qr_transfer(&self, qr_data: &str, location: &Location, context: &SecurityContext) -> Result<Transfer, RepositoryError>;
+    async fn scan_qr_transfer(&self, qr_data: &str, location: &Location, context: &SecurityContext) -> Result<QRScanTransfer, RepositoryError>;
 }
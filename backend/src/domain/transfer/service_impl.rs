@@ -1,218 +1,208 @@
 use std::sync::Arc;
 use async_trait::async_trait;
 use chrono::Utc;
-use uuid::Uuid;
 
 use super::{
-    service::TransferService,
-    repository::{TransferRepository, TransferError},
     entity::{Transfer, TransferStatus},
+    repository::TransferRepository,
+    service::{QRScanTransfer, TransferService},
 };
 use crate::{
-    error::CoreError,
-    types::security::SecurityContext,
-    infrastructure::blockchain::verification::TransferVerification,
+    domain::{
+        component::{entity::ShortageAnnex, repository::ComponentRepository},
+        models::{
+            location::Location,
+            qr::{QRCodeService, QRData, VerifyQRRequest},
+        },
+        property::{entity::Property, repository::PropertyRepository},
+    },
+    error::{CoreError, RepositoryError},
+    types::{permissions::Permission, security::SecurityContext},
 };
 
 pub struct TransferServiceImpl {
-    repository: Arc<dyn TransferRepository>,
-    blockchain: Arc<dyn TransferVerification>,
+    transfers: Arc<dyn TransferRepository>,
+    properties: Arc<dyn PropertyRepository>,
+    components: Arc<dyn ComponentRepository>,
+    qr_service: Arc<dyn QRCodeService>,
 }
 
 impl TransferServiceImpl {
     pub fn new(
-        repository: Arc<dyn TransferRepository>,
-        blockchain: Arc<dyn TransferVerification>,
+        transfers: Arc<dyn TransferRepository>,
+        properties: Arc<dyn PropertyRepository>,
+        components: Arc<dyn ComponentRepository>,
+        qr_service: Arc<dyn QRCodeService>,
     ) -> Self {
-        Self { repository, blockchain }
+        Self {
+            transfers,
+            properties,
+            components,
+            qr_service,
+        }
+    }
+
+    /// Snapshots the end item's shortage annex onto `transfer`, so the
+    /// receiver signs for the components that are missing as well as the
+    /// item. Items without a component listing carry no annex.
+    async fn attach_shortage_annex(&self, transfer: &mut Transfer) -> Result<(), RepositoryError> {
+        let components = self.components.list_components(transfer.property_id).await?;
+        if !components.is_empty() {
+            transfer.set_shortage_annex(&ShortageAnnex::new(transfer.property_id, &components));
+        }
+        Ok(())
     }
 
-    async fn validate_transfer_access(
+    async fn property(&self, id: i32) -> Result<Property, RepositoryError> {
+        self.properties
+            .get_property(id)
+            .await?
+            .ok_or_else(|| RepositoryError::NotFound(format!("Property {} not found", id)))
+    }
+
+    /// Checks the scanned code against the property as it stands now. A
+    /// validly signed code can still name a holder who has since handed the
+    /// item on, e.g. an old printed label.
+    fn check_scan(
         &self,
-        transfer: &Transfer,
+        data: &QRData,
+        property: &Property,
+        pending: &[Transfer],
         context: &SecurityContext,
-    ) -> Result<(), CoreError> {
-        // Check if user can access this transfer
-        if !context.can_handle_sensitive_items() && transfer.is_sensitive() {
-            return Err(CoreError::Authorization(
-                "Cannot access sensitive item transfers".into()
+    ) -> Result<(), RepositoryError> {
+        if property.current_holder_id.to_string() != data.custodian_id {
+            return Err(RepositoryError::Validation(
+                "QR code is stale: property has changed hands since it was issued".to_string(),
             ));
         }
-
-        // Check command access if transfer requires approval
-        if transfer.requires_approval() && !context.is_officer() {
-            return Err(CoreError::Authorization(
-                "Only officers can handle transfers requiring approval".into()
+        if property.current_holder_id == context.user_id {
+            return Err(RepositoryError::Validation(
+                "Scanner already holds this property".to_string(),
+            ));
+        }
+        if pending.iter().any(|t| t.status == TransferStatus::Pending) {
+            return Err(RepositoryError::Validation(
+                "Property already has a pending transfer".to_string(),
             ));
         }
-
         Ok(())
     }
 }
 
-#[async_trait]
-impl TransferService for TransferServiceImpl {
-    async fn initiate_transfer(
-        &self,
-        property_id: Uuid,
-        new_custodian: String,
-        context: &SecurityContext,
-    ) -> Result<Transfer, CoreError> {
-        // Create new transfer
-        let transfer = Transfer::new(
-            property_id,
-            None, // Current custodian will be set from property
-            new_custodian,
-            context.is_officer(), // Officers can self-approve
-            Some(context.user_id),
-            Some(context.unit_code.clone()),
-            Utc::now(),
-        );
-
-        // Save to repository
-        let transfer = self.repository
-            .create(transfer)
-            .await
-            .map_err(|e| CoreError::Transfer(format!("Failed to create transfer: {}", e)))?;
-
-        // Record on blockchain
-        self.blockchain
-            .record_transfer(&transfer.into(), context)
-            .await?;
-
-        Ok(transfer)
+/// Maps QR service failures onto the repository errors this trait returns,
+/// keeping the message the scanner needs to see.
+fn qr_error(error: CoreError) -> RepositoryError {
+    match error {
+        CoreError::Validation(message)
+        | CoreError::SecurityError(message)
+        | CoreError::Authorization(message)
+        | CoreError::QRCode(message) => RepositoryError::Validation(message),
+        CoreError::NotFound(message) => RepositoryError::NotFound(message),
+        other => RepositoryError::Database(other.to_string()),
     }
+}
 
-    async fn approve_transfer(
-        &self,
-        transfer_id: Uuid,
-        context: &SecurityContext,
-    ) -> Result<Transfer, CoreError> {
-        // Get transfer
-        let mut transfer = self.repository
-            .get_by_id(transfer_id)
-            .await
-            .map_err(|e| CoreError::Transfer(format!("Failed to get transfer: {}", e)))?;
-
-        // Validate access
-        self.validate_transfer_access(&transfer, context).await?;
-
-        // Approve transfer
-        transfer.approve(context.user_id, Utc::now())
-            .map_err(|e| CoreError::Transfer(e))?;
-
-        // Update repository
-        let transfer = self.repository
-            .update(transfer)
-            .await
-            .map_err(|e| CoreError::Transfer(format!("Failed to update transfer: {}", e)))?;
-
-        // Verify on blockchain
-        self.blockchain
-            .verify_transfer(&transfer.into(), context)
-            .await?;
-
-        Ok(transfer)
+#[async_trait]
+impl TransferService for TransferServiceImpl {
+    async fn create_transfer(&self, mut transfer: Transfer, context: &SecurityContext) -> Result<Transfer, RepositoryError> {
+        if !context.has_permission(&Permission::CreateTransfer) {
+            return Err(RepositoryError::Validation("Not permitted to create transfers".to_string()));
+        }
+        if !transfer.is_valid() {
+            return Err(RepositoryError::Validation("Transfer is missing property or holders".to_string()));
+        }
+        self.attach_shortage_annex(&mut transfer).await?;
+        self.transfers.create_transfer(transfer).await
     }
 
-    async fn reject_transfer(
-        &self,
-        transfer_id: Uuid,
-        context: &SecurityContext,
-    ) -> Result<Transfer, CoreError> {
-        // Get transfer
-        let mut transfer = self.repository
-            .get_by_id(transfer_id)
-            .await
-            .map_err(|e| CoreError::Transfer(format!("Failed to get transfer: {}", e)))?;
-
-        // Validate access
-        self.validate_transfer_access(&transfer, context).await?;
-
-        // Reject transfer
-        transfer.reject(context.user_id, Utc::now())
-            .map_err(|e| CoreError::Transfer(e))?;
-
-        // Update repository
-        self.repository
-            .update(transfer)
-            .await
-            .map_err(|e| CoreError::Transfer(format!("Failed to update transfer: {}", e)))
+    async fn get_transfer(&self, id: i32, context: &SecurityContext) -> Result<Option<Transfer>, RepositoryError> {
+        // Like the pending queue, only approvers see other people's transfers
+        let approver = context.can_approve_transfers();
+        Ok(self.transfers
+            .get_transfer(id)
+            .await?
+            .filter(|t| approver || t.from_holder_id == context.user_id || t.to_holder_id == context.user_id))
     }
 
-    async fn complete_transfer(
-        &self,
-        transfer_id: Uuid,
-        blockchain_verification: String,
-        context: &SecurityContext,
-    ) -> Result<Transfer, CoreError> {
-        // Get transfer
-        let mut transfer = self.repository
-            .get_by_id(transfer_id)
-            .await
-            .map_err(|e| CoreError::Transfer(format!("Failed to get transfer: {}", e)))?;
-
-        // Validate access
-        self.validate_transfer_access(&transfer, context).await?;
-
-        // Complete transfer with blockchain verification
-        transfer.complete(blockchain_verification, Utc::now())
-            .map_err(|e| CoreError::Transfer(e))?;
+    async fn approve_transfer(&self, id: i32, context: &SecurityContext) -> Result<Transfer, RepositoryError> {
+        if !context.can_approve_transfers() {
+            return Err(RepositoryError::Validation("Not permitted to approve transfers".to_string()));
+        }
+        let mut transfer = self.transfers
+            .get_transfer(id)
+            .await?
+            .ok_or_else(|| RepositoryError::NotFound(format!("Transfer {} not found", id)))?;
+        if transfer.status != TransferStatus::Pending {
+            return Err(RepositoryError::Validation(format!(
+                "Only pending transfers can be approved, transfer is {}",
+                transfer.status
+            )));
+        }
 
-        // Update repository
-        self.repository
-            .update(transfer)
-            .await
-            .map_err(|e| CoreError::Transfer(format!("Failed to update transfer: {}", e)))
+        transfer.approve(context.user_id);
+        self.transfers.update_transfer(&transfer).await?;
+        Ok(transfer)
     }
 
-    async fn cancel_transfer(
-        &self,
-        transfer_id: Uuid,
-        context: &SecurityContext,
-    ) -> Result<Transfer, CoreError> {
-        // Get transfer
-        let mut transfer = self.repository
-            .get_by_id(transfer_id)
-            .await
-            .map_err(|e| CoreError::Transfer(format!("Failed to get transfer: {}", e)))?;
-
-        // Validate access
-        self.validate_transfer_access(&transfer, context).await?;
-
-        // Cancel transfer
-        transfer.cancel(context.user_id, Utc::now())
-            .map_err(|e| CoreError::Transfer(e))?;
-
-        // Update repository
-        self.repository
-            .update(transfer)
-            .await
-            .map_err(|e| CoreError::Transfer(format!("Failed to update transfer: {}", e)))
+    async fn get_pending_transfers(&self, context: &SecurityContext) -> Result<Vec<Transfer>, RepositoryError> {
+        // Approvers see the whole queue, everyone else only their own
+        let approver = context.can_approve_transfers();
+        Ok(self.transfers
+            .list_transfers()
+            .await?
+            .into_iter()
+            .filter(|t| t.status == TransferStatus::Pending)
+            .filter(|t| approver || t.from_holder_id == context.user_id || t.to_holder_id == context.user_id)
+            .collect())
     }
 
-    async fn get_property_transfers(
-        &self,
-        property_id: Uuid,
-        context: &SecurityContext,
-    ) -> Result<Vec<Transfer>, CoreError> {
-        self.repository
-            .get_by_property(property_id)
-            .await
-            .map_err(|e| CoreError::Transfer(format!("Failed to get property transfers: {}", e)))
+    async fn get_property_transfers(&self, property_id: i32, _context: &SecurityContext) -> Result<Vec<Transfer>, RepositoryError> {
+        self.transfers.list_by_property(property_id).await
     }
 
-    async fn get_pending_approvals(
-        &self,
-        context: &SecurityContext,
-    ) -> Result<Vec<Transfer>, CoreError> {
-        if !context.is_officer() {
-            return Err(CoreError::Authorization("Only officers can view pending approvals".into()));
+    async fn scan_qr_transfer(&self, qr_data: &str, location: &Location, context: &SecurityContext) -> Result<QRScanTransfer, RepositoryError> {
+        if !context.has_permission(&Permission::CreateTransfer) {
+            return Err(RepositoryError::Validation("Not permitted to create transfers".to_string()));
         }
 
-        self.repository
-            .get_pending_approvals(&context.user_id.to_string())
+        // Signature, issuing token, expiry and revocation
+        let request = VerifyQRRequest {
+            qr_data: qr_data.to_string(),
+            signature: None,
+            timestamp: Utc::now(),
+            scanner_id: context.user_id.to_string(),
+            location: None,
+        };
+        let data = self.qr_service
+            .validate_qr(request, context)
             .await
-            .map_err(|e| CoreError::Transfer(format!("Failed to get pending approvals: {}", e)))
+            .map_err(qr_error)?;
+
+        let property = self.property(data.property_id).await?;
+        let history = self.transfers.list_by_property(property.id).await?;
+        self.check_scan(&data, &property, &history, context)?;
+
+        let mut transfer = Transfer::new(
+            property.id,
+            property.current_holder_id,
+            context.user_id,
+            location.clone(),
+            None,
+        );
+        transfer.metadata = serde_json::json!({
+            "qr_id": data.id,
+            "qr_kid": data.kid,
+            "qr_issued_at": data.timestamp,
+        });
+        self.attach_shortage_annex(&mut transfer).await?;
+
+        // The checks above give the scanner a clear reason early; the
+        // repository repeats them while redeeming the code and creating the
+        // transfer in one transaction, so racing scans cannot both succeed
+        // and a failed insert leaves the code usable
+        let transfer = self.transfers.create_scanned_transfer(transfer, data.id).await?;
+        let approval = self.transfers.approval_requirements(property.id).await?;
+
+        Ok(QRScanTransfer { transfer, approval })
     }
-} 
\ No newline at end of file
+}
@@ -15,6 +15,13 @@ pub enum BlockchainError {
     ServiceError(String),
     #[error("Serialization error: {0}")]
     SerializationError(String),
+    #[error("Invalid transaction {transaction_id}: {message}")]
+    InvalidTransaction {
+        transaction_id: String,
+        message: String,
+    },
+    #[error("Timed out waiting for batch {0}")]
+    BatchTimeout(String),
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
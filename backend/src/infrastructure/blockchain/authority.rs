@@ -2,27 +2,28 @@ use async_trait::async_trait;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
-use ed25519_dalek::SigningKey;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier};
+use handreceipt_protocol::signing::transfer_digest;
+use parking_lot::RwLock;
 use std::collections::HashMap;
 
 use crate::domain::models::transfer::TransferStatus;
+use super::certificate::{
+    is_within_command, parse_public_key, CertificateError, CertificateRevocationList, CertificateStore,
+    MAX_CHAIN_DEPTH, ROLE_CERTIFICATE_AUTHORITY, ROLE_COMMANDER, ROLE_SUPPLY_OFFICER,
+};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MilitaryCertificate {
-    pub issuer: String,
-    pub subject: String,
-    pub valid_from: DateTime<Utc>,
-    pub valid_until: Option<DateTime<Utc>>,
-    pub roles: Vec<String>,
-    pub unit_id: String,
-}
+pub use super::certificate::MilitaryCertificate;
 
 pub struct AuthorityNode {
     unit_id: String,
     signing_key: SigningKey,
     certificate: MilitaryCertificate,
     is_commander: bool,
+    /// Parent unit id to its direct subordinate units.
     unit_hierarchy: HashMap<String, Vec<String>>,
+    certificates: RwLock<CertificateStore>,
+    transfers: RwLock<HashMap<Uuid, TransferStatus>>,
 }
 
 #[async_trait]
@@ -36,23 +37,70 @@ pub trait AuthorityService: Send + Sync {
 #[async_trait]
 impl AuthorityService for AuthorityNode {
     async fn sign_transfer(&self, transfer: &mut PropertyTransfer, role: SignerRole) -> Result<(), String> {
-        // TODO: Implement signing
+        let signer = hex::encode(self.signing_key.verifying_key().to_bytes());
+        if !self.certificate.public_key.eq_ignore_ascii_case(&signer) {
+            return Err(format!("Certificate {} does not match the signing key", self.certificate.subject));
+        }
+        if matches!(role, SignerRole::Commander) && !self.is_commander {
+            return Err(format!("Node for unit {} is not a commander", self.unit_id));
+        }
+
+        {
+            let store = self.certificates.read();
+            self.check_signer(&store, &self.certificate, &role, transfer)?;
+        }
+
+        let signature = self.signing_key.sign(&transfer.digest());
+
+        // Re-signing replaces this node's earlier signature
+        transfer.signatures.retain(|existing| existing.signer != signer);
+        transfer.signatures.push(TransferSignature {
+            signer,
+            role,
+            timestamp: Utc::now(),
+            signature: hex::encode(signature.to_bytes()),
+        });
         Ok(())
     }
 
     async fn validate_transfer(&self, transfer: &PropertyTransfer) -> Result<(), String> {
-        // TODO: Implement validation
+        if transfer.signatures.is_empty() {
+            return Err("Transfer has no signatures".to_string());
+        }
+
+        let digest = transfer.digest();
+        let store = self.certificates.read();
+        for signature in &transfer.signatures {
+            signature.verify(&digest)?;
+            let certificate = store
+                .find_by_public_key(&signature.signer)
+                .ok_or_else(|| CertificateError::UnknownSigner(signature.signer.clone()).to_string())?;
+            self.check_signer(&store, certificate, &signature.role, transfer)?;
+        }
+
+        if transfer.requires_approval
+            && !transfer
+                .signatures
+                .iter()
+                .any(|signature| matches!(signature.role, SignerRole::Commander | SignerRole::SupplyOfficer))
+        {
+            return Err("Transfer requires a commander or supply officer signature".to_string());
+        }
         Ok(())
     }
 
     async fn record_transfer(&self, transfer: &PropertyTransfer) -> Result<(), String> {
-        // TODO: Implement recording
+        self.validate_transfer(transfer).await?;
+        self.transfers.write().insert(transfer.transfer_id, TransferStatus::Completed);
         Ok(())
     }
 
-    async fn get_transfer_status(&self, _transfer_id: Uuid) -> Result<TransferStatus, String> {
-        // TODO: Implement status check
-        Ok(TransferStatus::Pending)
+    async fn get_transfer_status(&self, transfer_id: Uuid) -> Result<TransferStatus, String> {
+        self.transfers
+            .read()
+            .get(&transfer_id)
+            .copied()
+            .ok_or_else(|| format!("Unknown transfer {}", transfer_id))
     }
 }
 
@@ -64,38 +112,439 @@ impl AuthorityNode {
         is_commander: bool,
         unit_hierarchy: HashMap<String, Vec<String>>,
     ) -> Self {
+        let mut store = CertificateStore::new();
+        store.add_certificate(certificate.clone());
+
         Self {
             unit_id,
             signing_key,
             certificate,
             is_commander,
             unit_hierarchy,
+            certificates: RwLock::new(store),
+            transfers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn certificate(&self) -> &MilitaryCertificate {
+        &self.certificate
+    }
+
+    /// Trusts a self-signed root certificate.
+    pub fn add_trust_anchor(&self, certificate: MilitaryCertificate) {
+        self.certificates.write().add_trust_anchor(certificate);
+    }
+
+    /// Makes an issuer or signer certificate known to this node.
+    pub fn add_certificate(&self, certificate: MilitaryCertificate) {
+        self.certificates.write().add_certificate(certificate);
+    }
+
+    /// Applies a revocation list once its signature checks out against the
+    /// issuer's known certificate.
+    pub fn update_revocation_list(&self, crl: CertificateRevocationList) -> Result<(), CertificateError> {
+        let mut store = self.certificates.write();
+        let issuer = store.issuer(&crl.issuer).ok_or_else(|| CertificateError::UnknownIssuer {
+            subject: crl.issuer.clone(),
+            issuer: crl.issuer.clone(),
+        })?;
+        crl.verify_signature(&issuer.public_key)?;
+        store.update_revocation_list(crl);
+        Ok(())
+    }
+
+    /// Returns true if `unit` is `command` or one of its subordinate units.
+    pub fn is_within_command(&self, command: &str, unit: &str) -> bool {
+        is_within_command(&self.unit_hierarchy, command, unit)
+    }
+
+    /// Validates `certificate` and every issuer above it up to a trust
+    /// anchor: validity windows, revocation, issuer signatures, that each
+    /// issuer is a certificate authority, and that each certificate's unit
+    /// falls within its issuer's command.
+    pub fn validate_certificate(
+        &self,
+        store: &CertificateStore,
+        certificate: &MilitaryCertificate,
+        at: DateTime<Utc>,
+    ) -> Result<(), CertificateError> {
+        let mut current = certificate;
+        for _ in 0..MAX_CHAIN_DEPTH {
+            current.check_validity(at)?;
+            if store.is_revoked(current) {
+                return Err(CertificateError::Revoked(current.subject.clone()));
+            }
+
+            if current.is_self_signed() {
+                let anchor = store
+                    .anchor(&current.subject)
+                    .filter(|anchor| anchor.public_key.eq_ignore_ascii_case(&current.public_key))
+                    .ok_or_else(|| CertificateError::UnknownIssuer {
+                        subject: current.subject.clone(),
+                        issuer: current.issuer.clone(),
+                    })?;
+                return current.verify_signature(&anchor.public_key);
+            }
+
+            let issuer = store.issuer(&current.issuer).ok_or_else(|| CertificateError::UnknownIssuer {
+                subject: current.subject.clone(),
+                issuer: current.issuer.clone(),
+            })?;
+            if !issuer.has_role(ROLE_CERTIFICATE_AUTHORITY) {
+                return Err(CertificateError::NotAnIssuer(issuer.subject.clone()));
+            }
+            if !self.is_within_command(&issuer.unit_id, &current.unit_id) {
+                return Err(CertificateError::UnitOutsideIssuer {
+                    subject: current.subject.clone(),
+                    unit_id: current.unit_id.clone(),
+                });
+            }
+            current.verify_signature(&issuer.public_key)?;
+            current = issuer;
+        }
+        Err(CertificateError::ChainTooLong(certificate.subject.clone()))
+    }
+
+    /// Checks that `certificate` may sign `transfer` as `role`. Revocation
+    /// lists are consulted on every call.
+    fn check_signer(
+        &self,
+        store: &CertificateStore,
+        certificate: &MilitaryCertificate,
+        role: &SignerRole,
+        transfer: &PropertyTransfer,
+    ) -> Result<(), String> {
+        self.validate_certificate(store, certificate, Utc::now())
+            .map_err(|e| e.to_string())?;
+
+        if let Some(required) = role.certificate_role() {
+            if !certificate.has_role(required) {
+                return Err(CertificateError::MissingRole {
+                    subject: certificate.subject.clone(),
+                    role: required.to_string(),
+                }
+                .to_string());
+            }
+        }
+
+        if matches!(role, SignerRole::Commander) && !self.is_within_command(&certificate.unit_id, &transfer.unit_id) {
+            return Err(format!(
+                "Commander {} of {} cannot sign transfers for unit {}",
+                certificate.subject, certificate.unit_id, transfer.unit_id
+            ));
         }
+        Ok(())
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PropertyTransfer {
+    pub transfer_id: Uuid,
     pub property_id: Uuid,
+    /// Unit the property is assigned to.
+    pub unit_id: String,
     pub from_custodian: Option<String>,
     pub to_custodian: String,
     pub initiated_by: String,
     pub requires_approval: bool,
     pub timestamp: DateTime<Utc>,
     pub signatures: Vec<TransferSignature>,
+    /// Digest of the shortage annex signed for, empty if there is none.
+    #[serde(default)]
+    pub annex_digest: String,
+}
+
+impl PropertyTransfer {
+    /// Canonical transfer digest every signer signs.
+    pub fn digest(&self) -> [u8; 32] {
+        transfer_digest(
+            &self.property_id.to_string(),
+            self.from_custodian.as_deref().unwrap_or_default(),
+            &self.to_custodian,
+            &self.transfer_id.to_string(),
+            &self.annex_digest,
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransferSignature {
+    /// Hex Ed25519 public key of the signer.
     pub signer: String,
     pub role: SignerRole,
     pub timestamp: DateTime<Utc>,
+    /// Hex Ed25519 signature over the transfer digest.
     pub signature: String,
 }
 
+impl TransferSignature {
+    pub fn verify(&self, digest: &[u8; 32]) -> Result<(), String> {
+        let invalid = || format!("Invalid signature from {}", self.signer);
+
+        let key = parse_public_key(&self.signer).ok_or_else(invalid)?;
+        let signature_bytes: [u8; 64] = hex::decode(&self.signature)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(invalid)?;
+
+        key.verify(digest, &Signature::from_bytes(&signature_bytes))
+            .map_err(|_| invalid())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SignerRole {
     Commander,
     SupplyOfficer,
     Custodian,
 }
+
+impl SignerRole {
+    /// Certificate role needed to sign as this role, if any.
+    pub fn certificate_role(&self) -> Option<&'static str> {
+        match self {
+            SignerRole::Commander => Some(ROLE_COMMANDER),
+            SignerRole::SupplyOfficer => Some(ROLE_SUPPLY_OFFICER),
+            SignerRole::Custodian => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    const ROOT: u8 = 100;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn public_key(seed: u8) -> String {
+        hex::encode(key(seed).verifying_key().to_bytes())
+    }
+
+    fn hierarchy() -> HashMap<String, Vec<String>> {
+        HashMap::from([
+            ("brigade".to_string(), vec!["bn-1".to_string(), "bn-2".to_string()]),
+            ("bn-1".to_string(), vec!["co-a".to_string()]),
+        ])
+    }
+
+    fn certificate(seed: u8, issuer: u8, unit_id: &str, roles: &[&str]) -> MilitaryCertificate {
+        let mut certificate = MilitaryCertificate {
+            serial: format!("serial-{}", seed),
+            issuer: format!("node-{}", issuer),
+            subject: format!("node-{}", seed),
+            public_key: public_key(seed),
+            valid_from: Utc::now() - Duration::days(1),
+            valid_until: Some(Utc::now() + Duration::days(365)),
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+            unit_id: unit_id.to_string(),
+            signature: String::new(),
+        };
+        certificate.sign(&key(issuer));
+        certificate
+    }
+
+    fn root() -> MilitaryCertificate {
+        certificate(ROOT, ROOT, "brigade", &[ROLE_CERTIFICATE_AUTHORITY])
+    }
+
+    fn node_with(certificate: MilitaryCertificate, seed: u8, is_commander: bool) -> AuthorityNode {
+        let node = AuthorityNode::new(certificate.unit_id.clone(), key(seed), certificate, is_commander, hierarchy());
+        node.add_trust_anchor(root());
+        node
+    }
+
+    fn node(seed: u8, unit_id: &str, roles: &[&str]) -> AuthorityNode {
+        let is_commander = roles.contains(&ROLE_COMMANDER);
+        node_with(certificate(seed, ROOT, unit_id, roles), seed, is_commander)
+    }
+
+    fn transfer(unit_id: &str, requires_approval: bool) -> PropertyTransfer {
+        PropertyTransfer {
+            transfer_id: Uuid::new_v4(),
+            property_id: Uuid::new_v4(),
+            unit_id: unit_id.to_string(),
+            from_custodian: Some("custodian-1".to_string()),
+            to_custodian: "custodian-2".to_string(),
+            initiated_by: "custodian-1".to_string(),
+            requires_approval,
+            timestamp: Utc::now(),
+            signatures: Vec::new(),
+            annex_digest: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signatures_cover_transfer() {
+        let validator = node(3, "co-a", &[]);
+        validator.add_certificate(certificate(1, ROOT, "co-a", &[]));
+        validator.add_certificate(certificate(2, ROOT, "co-a", &[]));
+
+        let mut transfer = transfer("co-a", false);
+        assert!(validator.validate_transfer(&transfer).await.is_err());
+
+        node(1, "co-a", &[]).sign_transfer(&mut transfer, SignerRole::Custodian).await.unwrap();
+        node(2, "co-a", &[]).sign_transfer(&mut transfer, SignerRole::Custodian).await.unwrap();
+        node(1, "co-a", &[]).sign_transfer(&mut transfer, SignerRole::Custodian).await.unwrap();
+        assert_eq!(transfer.signatures.len(), 2);
+        assert!(validator.validate_transfer(&transfer).await.is_ok());
+
+        transfer.to_custodian = "custodian-3".to_string();
+        assert!(validator.validate_transfer(&transfer).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_signer_is_rejected() {
+        let validator = node(3, "co-a", &[]);
+        let mut transfer = transfer("co-a", false);
+        node(1, "co-a", &[]).sign_transfer(&mut transfer, SignerRole::Custodian).await.unwrap();
+        assert!(validator.validate_transfer(&transfer).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_approval_needs_command_signature() {
+        let validator = node(3, "co-a", &[]);
+        validator.add_certificate(certificate(1, ROOT, "co-a", &[]));
+        validator.add_certificate(certificate(2, ROOT, "bn-1", &[ROLE_COMMANDER]));
+
+        let mut transfer = transfer("co-a", true);
+        node(1, "co-a", &[]).sign_transfer(&mut transfer, SignerRole::Custodian).await.unwrap();
+        assert!(validator.validate_transfer(&transfer).await.is_err());
+
+        node(2, "bn-1", &[ROLE_COMMANDER])
+            .sign_transfer(&mut transfer, SignerRole::Commander)
+            .await
+            .unwrap();
+        assert!(validator.validate_transfer(&transfer).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_role_must_be_certified() {
+        let mut transfer = transfer("co-a", true);
+        let officer = node(1, "co-a", &[]);
+        assert!(officer.sign_transfer(&mut transfer, SignerRole::SupplyOfficer).await.is_err());
+
+        let officer = node(1, "co-a", &[ROLE_SUPPLY_OFFICER]);
+        assert!(officer.sign_transfer(&mut transfer, SignerRole::SupplyOfficer).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_commander_limited_to_subtree() {
+        let commander = node(2, "bn-1", &[ROLE_COMMANDER]);
+        assert!(commander.is_within_command("bn-1", "co-a"));
+        assert!(!commander.is_within_command("bn-1", "bn-2"));
+
+        assert!(commander.sign_transfer(&mut transfer("co-a", true), SignerRole::Commander).await.is_ok());
+        assert!(commander.sign_transfer(&mut transfer("bn-2", true), SignerRole::Commander).await.is_err());
+
+        // A commander signature added outside the subtree fails validation too
+        let validator = node(3, "brigade", &[]);
+        validator.add_certificate(commander.certificate().clone());
+        let mut outside = transfer("bn-2", true);
+        outside.signatures.push(TransferSignature {
+            signer: public_key(2),
+            role: SignerRole::Commander,
+            timestamp: Utc::now(),
+            signature: hex::encode(key(2).sign(&outside.digest()).to_bytes()),
+        });
+        assert!(validator.validate_transfer(&outside).await.is_err());
+    }
+
+    #[test]
+    fn test_certificate_chain() {
+        let node = node(3, "co-a", &[]);
+        let store = node.certificates.read();
+        let at = Utc::now();
+
+        assert!(node.validate_certificate(&store, &certificate(1, ROOT, "co-a", &[]), at).is_ok());
+
+        // Expired
+        let mut expired = certificate(1, ROOT, "co-a", &[]);
+        expired.valid_until = Some(at - Duration::hours(1));
+        expired.sign(&key(ROOT));
+        assert_eq!(
+            node.validate_certificate(&store, &expired, at),
+            Err(CertificateError::Expired("node-1".to_string()))
+        );
+
+        // Tampered after signing
+        let mut tampered = certificate(1, ROOT, "co-a", &[]);
+        tampered.roles.push(ROLE_COMMANDER.to_string());
+        assert_eq!(
+            node.validate_certificate(&store, &tampered, at),
+            Err(CertificateError::InvalidSignature("node-1".to_string()))
+        );
+
+        // Issued by someone the node does not know
+        assert!(matches!(
+            node.validate_certificate(&store, &certificate(1, 50, "co-a", &[]), at),
+            Err(CertificateError::UnknownIssuer { .. })
+        ));
+
+        // Self-signed but not a trust anchor
+        assert!(matches!(
+            node.validate_certificate(&store, &certificate(1, 1, "co-a", &[ROLE_CERTIFICATE_AUTHORITY]), at),
+            Err(CertificateError::UnknownIssuer { .. })
+        ));
+    }
+
+    #[test]
+    fn test_intermediate_issuer_scope() {
+        let node = node(3, "co-a", &[]);
+        node.add_certificate(certificate(10, ROOT, "bn-1", &[ROLE_CERTIFICATE_AUTHORITY]));
+        node.add_certificate(certificate(11, ROOT, "bn-2", &[]));
+        let store = node.certificates.read();
+        let at = Utc::now();
+
+        assert!(node.validate_certificate(&store, &certificate(1, 10, "co-a", &[]), at).is_ok());
+        assert!(matches!(
+            node.validate_certificate(&store, &certificate(1, 10, "bn-2", &[]), at),
+            Err(CertificateError::UnitOutsideIssuer { .. })
+        ));
+        assert_eq!(
+            node.validate_certificate(&store, &certificate(1, 11, "bn-2", &[]), at),
+            Err(CertificateError::NotAnIssuer("node-11".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_revoked_signer_is_rejected() {
+        let validator = node(3, "co-a", &[]);
+        validator.add_certificate(certificate(1, ROOT, "co-a", &[]));
+
+        let mut transfer = transfer("co-a", false);
+        node(1, "co-a", &[]).sign_transfer(&mut transfer, SignerRole::Custodian).await.unwrap();
+        assert!(validator.validate_transfer(&transfer).await.is_ok());
+
+        // Unsigned lists are ignored
+        let mut crl = CertificateRevocationList::new(format!("node-{}", ROOT));
+        crl.revoked.insert("serial-1".to_string(), Utc::now());
+        assert!(validator.update_revocation_list(crl.clone()).is_err());
+        assert!(validator.validate_transfer(&transfer).await.is_ok());
+
+        crl.sign(&key(ROOT));
+        validator.update_revocation_list(crl).unwrap();
+        assert!(validator.validate_transfer(&transfer).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_record_transfer() {
+        let validator = node(3, "co-a", &[]);
+        validator.add_certificate(certificate(1, ROOT, "co-a", &[]));
+
+        let mut transfer = transfer("co-a", false);
+        assert!(validator.get_transfer_status(transfer.transfer_id).await.is_err());
+        assert!(validator.record_transfer(&transfer).await.is_err());
+
+        node(1, "co-a", &[]).sign_transfer(&mut transfer, SignerRole::Custodian).await.unwrap();
+        validator.record_transfer(&transfer).await.unwrap();
+        assert_eq!(
+            validator.get_transfer_status(transfer.transfer_id).await.unwrap(),
+            TransferStatus::Completed
+        );
+    }
+}
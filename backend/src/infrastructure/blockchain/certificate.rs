@@ -0,0 +1,281 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Role a certificate needs to issue other certificates.
+pub const ROLE_CERTIFICATE_AUTHORITY: &str = "CERTIFICATE_AUTHORITY";
+/// Role a certificate needs to sign transfers as a commander.
+pub const ROLE_COMMANDER: &str = "COMMANDER";
+/// Role a certificate needs to sign transfers as a supply officer.
+pub const ROLE_SUPPLY_OFFICER: &str = "SUPPLY_OFFICER";
+
+/// Longest issuer chain followed before giving up.
+pub const MAX_CHAIN_DEPTH: usize = 8;
+
+const CERTIFICATE_DIGEST_DOMAIN: &[u8] = b"handreceipt/certificate/v1";
+const REVOCATION_DIGEST_DOMAIN: &[u8] = b"handreceipt/revocations/v1";
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CertificateError {
+    #[error("Certificate {0} is not yet valid")]
+    NotYetValid(String),
+    #[error("Certificate {0} has expired")]
+    Expired(String),
+    #[error("Certificate {0} has been revoked")]
+    Revoked(String),
+    #[error("Certificate {0} has an invalid signature")]
+    InvalidSignature(String),
+    #[error("Certificate {subject} names unknown issuer {issuer}")]
+    UnknownIssuer { subject: String, issuer: String },
+    #[error("Issuer {0} is not a certificate authority")]
+    NotAnIssuer(String),
+    #[error("Certificate {subject} is for unit {unit_id}, outside its issuer's command")]
+    UnitOutsideIssuer { subject: String, unit_id: String },
+    #[error("Certificate {subject} does not hold role {role}")]
+    MissingRole { subject: String, role: String },
+    #[error("Certificate chain for {0} is too long")]
+    ChainTooLong(String),
+    #[error("No certificate for signer {0}")]
+    UnknownSigner(String),
+}
+
+/// Binds an Ed25519 public key to a subject, unit and roles. Signed by the
+/// issuer's key; trust anchors are self-signed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MilitaryCertificate {
+    #[serde(default)]
+    pub serial: String,
+    pub issuer: String,
+    pub subject: String,
+    /// Hex Ed25519 public key of the subject.
+    #[serde(default)]
+    pub public_key: String,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
+    pub roles: Vec<String>,
+    pub unit_id: String,
+    /// Hex Ed25519 signature of the issuer over `digest`.
+    #[serde(default)]
+    pub signature: String,
+}
+
+impl MilitaryCertificate {
+    /// Canonical digest the issuer signs. Fields are length prefixed and
+    /// roles are sorted so the digest does not depend on their order.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut roles = self.roles.clone();
+        roles.sort();
+
+        let valid_from = self.valid_from.timestamp_millis().to_string();
+        let valid_until = self
+            .valid_until
+            .map(|until| until.timestamp_millis().to_string())
+            .unwrap_or_default();
+
+        let mut hasher = Sha256::new();
+        hasher.update(CERTIFICATE_DIGEST_DOMAIN);
+        let fields = [
+            self.serial.as_str(),
+            self.issuer.as_str(),
+            self.subject.as_str(),
+            self.public_key.as_str(),
+            self.unit_id.as_str(),
+            valid_from.as_str(),
+            valid_until.as_str(),
+        ];
+        for field in fields.into_iter().chain(roles.iter().map(String::as_str)) {
+            hasher.update((field.len() as u32).to_be_bytes());
+            hasher.update(field.as_bytes());
+        }
+        hasher.finalize().into()
+    }
+
+    /// Signs the certificate with the issuer's key.
+    pub fn sign(&mut self, issuer_key: &SigningKey) {
+        self.signature = hex::encode(issuer_key.sign(&self.digest()).to_bytes());
+    }
+
+    /// Checks the certificate signature against the issuer's hex public key.
+    pub fn verify_signature(&self, issuer_public_key: &str) -> Result<(), CertificateError> {
+        if verify_hex_signature(issuer_public_key, &self.digest(), &self.signature) {
+            Ok(())
+        } else {
+            Err(CertificateError::InvalidSignature(self.subject.clone()))
+        }
+    }
+
+    pub fn check_validity(&self, at: DateTime<Utc>) -> Result<(), CertificateError> {
+        if at < self.valid_from {
+            return Err(CertificateError::NotYetValid(self.subject.clone()));
+        }
+        if self.valid_until.is_some_and(|until| at > until) {
+            return Err(CertificateError::Expired(self.subject.clone()));
+        }
+        Ok(())
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|held| held.eq_ignore_ascii_case(role))
+    }
+
+    pub fn is_self_signed(&self) -> bool {
+        self.issuer == self.subject
+    }
+}
+
+pub fn parse_public_key(public_key: &str) -> Option<VerifyingKey> {
+    let bytes: [u8; 32] = hex::decode(public_key).ok()?.try_into().ok()?;
+    VerifyingKey::from_bytes(&bytes).ok()
+}
+
+/// Returns true if `unit` is `command` or one of its subordinate units in
+/// `hierarchy`, which maps a parent unit id to its direct subordinates.
+pub fn is_within_command(hierarchy: &HashMap<String, Vec<String>>, command: &str, unit: &str) -> bool {
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::from([command]);
+    while let Some(current) = queue.pop_front() {
+        if current == unit {
+            return true;
+        }
+        if !visited.insert(current) {
+            continue;
+        }
+        if let Some(children) = hierarchy.get(current) {
+            queue.extend(children.iter().map(String::as_str));
+        }
+    }
+    false
+}
+
+fn verify_hex_signature(public_key: &str, message: &[u8], signature: &str) -> bool {
+    let signature: Option<[u8; 64]> = hex::decode(signature).ok().and_then(|bytes| bytes.try_into().ok());
+    match (parse_public_key(public_key), signature) {
+        (Some(key), Some(signature)) => key.verify(message, &Signature::from_bytes(&signature)).is_ok(),
+        _ => false,
+    }
+}
+
+/// Serials an issuer has revoked, signed by the issuer.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CertificateRevocationList {
+    pub issuer: String,
+    /// Revoked serial numbers and when they were revoked.
+    pub revoked: HashMap<String, DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+    /// Hex Ed25519 signature of the issuer over `digest`.
+    #[serde(default)]
+    pub signature: String,
+}
+
+impl CertificateRevocationList {
+    pub fn new(issuer: String) -> Self {
+        Self {
+            issuer,
+            revoked: HashMap::new(),
+            updated_at: Utc::now(),
+            signature: String::new(),
+        }
+    }
+
+    pub fn is_revoked(&self, serial: &str) -> bool {
+        self.revoked.contains_key(serial)
+    }
+
+    /// Canonical digest the issuer signs; entries are sorted by serial.
+    /// This is also the digest anchored on-chain.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut entries: Vec<_> = self.revoked.iter().collect();
+        entries.sort();
+
+        let updated_at = self.updated_at.timestamp_millis().to_string();
+        let mut fields = vec![self.issuer.clone(), updated_at];
+        for (serial, revoked_at) in entries {
+            fields.push(serial.clone());
+            fields.push(revoked_at.timestamp_millis().to_string());
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(REVOCATION_DIGEST_DOMAIN);
+        for field in &fields {
+            hasher.update((field.len() as u32).to_be_bytes());
+            hasher.update(field.as_bytes());
+        }
+        hasher.finalize().into()
+    }
+
+    /// Signs the list with the issuer's key.
+    pub fn sign(&mut self, issuer_key: &SigningKey) {
+        self.signature = hex::encode(issuer_key.sign(&self.digest()).to_bytes());
+    }
+
+    /// Checks the list signature against the issuer's hex public key.
+    pub fn verify_signature(&self, issuer_public_key: &str) -> Result<(), CertificateError> {
+        if verify_hex_signature(issuer_public_key, &self.digest(), &self.signature) {
+            Ok(())
+        } else {
+            Err(CertificateError::InvalidSignature(self.issuer.clone()))
+        }
+    }
+}
+
+/// Trust anchors, known certificates and the latest revocation list of each
+/// issuer.
+#[derive(Debug, Clone, Default)]
+pub struct CertificateStore {
+    anchors: HashMap<String, MilitaryCertificate>,
+    certificates: HashMap<String, MilitaryCertificate>,
+    revocations: HashMap<String, CertificateRevocationList>,
+}
+
+impl CertificateStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trusts a self-signed root certificate.
+    pub fn add_trust_anchor(&mut self, certificate: MilitaryCertificate) {
+        self.anchors.insert(certificate.subject.clone(), certificate);
+    }
+
+    /// Adds an intermediate or end-entity certificate, replacing any earlier
+    /// certificate for the same subject.
+    pub fn add_certificate(&mut self, certificate: MilitaryCertificate) {
+        self.certificates.insert(certificate.subject.clone(), certificate);
+    }
+
+    /// Replaces the issuer's revocation list unless `crl` is older.
+    pub fn update_revocation_list(&mut self, crl: CertificateRevocationList) {
+        match self.revocations.get(&crl.issuer) {
+            Some(current) if current.updated_at > crl.updated_at => {}
+            _ => {
+                self.revocations.insert(crl.issuer.clone(), crl);
+            }
+        }
+    }
+
+    pub fn is_revoked(&self, certificate: &MilitaryCertificate) -> bool {
+        self.revocations
+            .get(&certificate.issuer)
+            .is_some_and(|crl| crl.is_revoked(&certificate.serial))
+    }
+
+    pub fn anchor(&self, subject: &str) -> Option<&MilitaryCertificate> {
+        self.anchors.get(subject)
+    }
+
+    /// Issuer certificate named by `subject`, preferring trust anchors.
+    pub fn issuer(&self, subject: &str) -> Option<&MilitaryCertificate> {
+        self.anchors.get(subject).or_else(|| self.certificates.get(subject))
+    }
+
+    pub fn find_by_public_key(&self, public_key: &str) -> Option<&MilitaryCertificate> {
+        self.certificates
+            .values()
+            .chain(self.anchors.values())
+            .find(|certificate| certificate.public_key.eq_ignore_ascii_case(public_key))
+    }
+}
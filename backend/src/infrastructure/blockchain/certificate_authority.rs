@@ -0,0 +1,429 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use ed25519_dalek::SigningKey;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    domain::certificate::{CertificateRecord, CertificateRepository, RevocationReason},
+    error::blockchain::BlockchainError,
+    infrastructure::blockchain::{
+        certificate::{
+            is_within_command, parse_public_key, CertificateRevocationList, MilitaryCertificate, ROLE_CERTIFICATE_AUTHORITY,
+        },
+        sawtooth::SawtoothClient,
+    },
+};
+
+/// How long an enrolled certificate is valid unless configured otherwise.
+pub const DEFAULT_CERTIFICATE_VALIDITY_DAYS: i64 = 365;
+
+/// Internal certificate authority.
+///
+/// Issues certificates binding a user's Ed25519 key to their unit and roles,
+/// revokes them when a soldier changes station or loses a device, and
+/// publishes a signed revocation list. It only enrolls and revokes within
+/// its own command, the unit of its certificate and the units below it.
+/// When a ledger client is configured the digest of every new revocation
+/// list is also anchored on-chain.
+pub struct CertificateAuthority {
+    signing_key: SigningKey,
+    certificate: MilitaryCertificate,
+    repository: Arc<dyn CertificateRepository>,
+    validity: Duration,
+    /// Parent unit id to its direct subordinate units.
+    unit_hierarchy: HashMap<String, Vec<String>>,
+    ledger: Option<Arc<SawtoothClient>>,
+}
+
+impl CertificateAuthority {
+    /// `certificate` must hold the certificate authority role and be issued
+    /// for `signing_key`.
+    pub fn new(
+        signing_key: SigningKey,
+        certificate: MilitaryCertificate,
+        repository: Arc<dyn CertificateRepository>,
+    ) -> Result<Self, BlockchainError> {
+        let public_key = hex::encode(signing_key.verifying_key().to_bytes());
+        if !certificate.public_key.eq_ignore_ascii_case(&public_key) {
+            return Err(BlockchainError::ValidationError(format!(
+                "Certificate {} does not match the authority key",
+                certificate.subject
+            )));
+        }
+        if !certificate.has_role(ROLE_CERTIFICATE_AUTHORITY) {
+            return Err(BlockchainError::ValidationError(format!(
+                "Certificate {} is not a certificate authority",
+                certificate.subject
+            )));
+        }
+
+        Ok(Self {
+            signing_key,
+            certificate,
+            repository,
+            validity: Duration::days(DEFAULT_CERTIFICATE_VALIDITY_DAYS),
+            unit_hierarchy: HashMap::new(),
+            ledger: None,
+        })
+    }
+
+    pub fn with_validity(mut self, validity: Duration) -> Self {
+        self.validity = validity;
+        self
+    }
+
+    /// Units below each unit, for deciding what falls within a command.
+    pub fn with_unit_hierarchy(mut self, unit_hierarchy: HashMap<String, Vec<String>>) -> Self {
+        self.unit_hierarchy = unit_hierarchy;
+        self
+    }
+
+    /// Anchors revocation lists on-chain through `client`.
+    pub fn with_ledger(mut self, client: Arc<SawtoothClient>) -> Self {
+        self.ledger = Some(client);
+        self
+    }
+
+    /// The authority's own certificate, for clients to add to their chain.
+    pub fn certificate(&self) -> &MilitaryCertificate {
+        &self.certificate
+    }
+
+    /// Returns true if `unit` is `command` or one of its subordinate units.
+    pub fn is_within_command(&self, command: &str, unit: &str) -> bool {
+        is_within_command(&self.unit_hierarchy, command, unit)
+    }
+
+    fn check_unit(&self, unit_id: &str) -> Result<(), BlockchainError> {
+        if self.is_within_command(&self.certificate.unit_id, unit_id) {
+            Ok(())
+        } else {
+            Err(BlockchainError::ValidationError(format!(
+                "Unit {} is outside the command of {}",
+                unit_id, self.certificate.subject
+            )))
+        }
+    }
+
+    fn repository_error(e: impl ToString) -> BlockchainError {
+        BlockchainError::StateError(e.to_string())
+    }
+
+    /// Issues a certificate for `public_key` and supersedes the user's
+    /// earlier certificates, so each user holds one active key.
+    pub async fn enroll(
+        &self,
+        user_id: i32,
+        subject: String,
+        public_key: &str,
+        unit_id: String,
+        roles: Vec<String>,
+    ) -> Result<CertificateRecord, BlockchainError> {
+        if parse_public_key(public_key).is_none() {
+            return Err(BlockchainError::ValidationError("Invalid Ed25519 public key".to_string()));
+        }
+        if roles.iter().any(|role| role.eq_ignore_ascii_case(ROLE_CERTIFICATE_AUTHORITY)) {
+            return Err(BlockchainError::ValidationError(
+                "Enrollment cannot grant the certificate authority role".to_string(),
+            ));
+        }
+        if unit_id.is_empty() {
+            return Err(BlockchainError::ValidationError("Unit is required for enrollment".to_string()));
+        }
+        self.check_unit(&unit_id)?;
+
+        let previous = self
+            .repository
+            .list_by_user(user_id)
+            .await
+            .map_err(Self::repository_error)?;
+
+        let now = Utc::now();
+        let mut certificate = MilitaryCertificate {
+            serial: Uuid::new_v4().to_string(),
+            issuer: self.certificate.subject.clone(),
+            subject,
+            public_key: public_key.to_ascii_lowercase(),
+            valid_from: now,
+            valid_until: Some(now + self.validity),
+            roles,
+            unit_id,
+            signature: String::new(),
+        };
+        certificate.sign(&self.signing_key);
+
+        let record = self
+            .repository
+            .create(&CertificateRecord::new(user_id, certificate))
+            .await
+            .map_err(Self::repository_error)?;
+        info!("Issued certificate {} to user {}", record.serial(), user_id);
+
+        let mut superseded = false;
+        for old in previous.iter().filter(|old| !old.is_revoked()) {
+            superseded |= self
+                .repository
+                .revoke(old.serial(), RevocationReason::Superseded)
+                .await
+                .map_err(Self::repository_error)?
+                .is_some();
+        }
+        if superseded {
+            self.publish_revocations().await;
+        }
+
+        Ok(record)
+    }
+
+    pub async fn get(&self, serial: &str) -> Result<Option<CertificateRecord>, BlockchainError> {
+        self.repository.get(serial).await.map_err(Self::repository_error)
+    }
+
+    pub async fn list_for_user(&self, user_id: i32) -> Result<Vec<CertificateRecord>, BlockchainError> {
+        self.repository.list_by_user(user_id).await.map_err(Self::repository_error)
+    }
+
+    /// Revokes a certificate this authority issued within its command.
+    /// Returns `None` if there is no such active certificate.
+    pub async fn revoke(
+        &self,
+        serial: &str,
+        reason: RevocationReason,
+    ) -> Result<Option<CertificateRecord>, BlockchainError> {
+        match self.get(serial).await? {
+            Some(record) if record.certificate.issuer == self.certificate.subject => {
+                self.check_unit(&record.certificate.unit_id)?;
+            }
+            _ => return Ok(None),
+        }
+
+        let revoked = self
+            .repository
+            .revoke(serial, reason)
+            .await
+            .map_err(Self::repository_error)?;
+        if revoked.is_some() {
+            info!("Revoked certificate {} ({})", serial, reason.as_str());
+            self.publish_revocations().await;
+        }
+        Ok(revoked)
+    }
+
+    /// Current signed revocation list. `updated_at` is the latest
+    /// revocation time, so the list and its digest only change when a
+    /// certificate is revoked.
+    pub async fn revocation_list(&self) -> Result<CertificateRevocationList, BlockchainError> {
+        let revoked = self
+            .repository
+            .list_revoked(&self.certificate.subject)
+            .await
+            .map_err(Self::repository_error)?;
+
+        let mut crl = CertificateRevocationList::new(self.certificate.subject.clone());
+        crl.updated_at = revoked
+            .iter()
+            .map(|(_, revoked_at)| *revoked_at)
+            .max()
+            .unwrap_or(self.certificate.valid_from);
+        crl.revoked = revoked.into_iter().collect();
+        crl.sign(&self.signing_key);
+        Ok(crl)
+    }
+
+    /// Anchors the digest of the current revocation list on-chain. Returns
+    /// the batch id, or `None` if no ledger is configured.
+    pub async fn anchor_revocations(&self) -> Result<Option<String>, BlockchainError> {
+        let ledger = match &self.ledger {
+            Some(ledger) => ledger,
+            None => return Ok(None),
+        };
+
+        let crl = self.revocation_list().await?;
+        let batch_id = ledger
+            .anchor_revocations(
+                crl.issuer.clone(),
+                hex::encode(crl.digest()),
+                crl.revoked.len() as u32,
+                crl.updated_at.timestamp_millis(),
+            )
+            .await?;
+        Ok(Some(batch_id))
+    }
+
+    /// Revocations are already durable in the database; a failed anchor is
+    /// retried with the next revocation.
+    async fn publish_revocations(&self) {
+        if let Err(e) = self.anchor_revocations().await {
+            warn!("Failed to anchor revocation list of {}: {}", self.certificate.subject, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::blockchain::certificate::{CertificateStore, ROLE_SUPPLY_OFFICER};
+    use crate::test_support::MockCertificates;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn public_key(seed: u8) -> String {
+        hex::encode(key(seed).verifying_key().to_bytes())
+    }
+
+    fn root_certificate() -> MilitaryCertificate {
+        let mut certificate = MilitaryCertificate {
+            serial: "root".to_string(),
+            issuer: "brigade-ca".to_string(),
+            subject: "brigade-ca".to_string(),
+            public_key: public_key(100),
+            valid_from: Utc::now() - Duration::days(1),
+            valid_until: None,
+            roles: vec![ROLE_CERTIFICATE_AUTHORITY.to_string()],
+            unit_id: "brigade".to_string(),
+            signature: String::new(),
+        };
+        certificate.sign(&key(100));
+        certificate
+    }
+
+    fn hierarchy() -> HashMap<String, Vec<String>> {
+        HashMap::from([
+            ("brigade".to_string(), vec!["bn-1".to_string()]),
+            ("bn-1".to_string(), vec!["co-a".to_string(), "co-b".to_string()]),
+        ])
+    }
+
+    fn authority() -> CertificateAuthority {
+        CertificateAuthority::new(key(100), root_certificate(), Arc::new(MockCertificates::default()))
+            .unwrap()
+            .with_unit_hierarchy(hierarchy())
+    }
+
+    #[test]
+    fn test_authority_key_must_match_certificate() {
+        let repository = Arc::new(MockCertificates::default());
+        assert!(CertificateAuthority::new(key(1), root_certificate(), repository.clone()).is_err());
+
+        let mut not_ca = root_certificate();
+        not_ca.roles.clear();
+        not_ca.sign(&key(100));
+        assert!(CertificateAuthority::new(key(100), not_ca, repository).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_enroll_issues_signed_certificate() {
+        let authority = authority();
+        let record = authority
+            .enroll(7, "user-7".to_string(), &public_key(1), "co-a".to_string(), vec![ROLE_SUPPLY_OFFICER.to_string()])
+            .await
+            .unwrap();
+
+        let certificate = &record.certificate;
+        assert_eq!(certificate.issuer, "brigade-ca");
+        assert_eq!(certificate.public_key, public_key(1));
+        assert!(certificate.has_role(ROLE_SUPPLY_OFFICER));
+        assert!(certificate.verify_signature(&public_key(100)).is_ok());
+        assert!(certificate.check_validity(Utc::now()).is_ok());
+
+        assert!(authority
+            .enroll(7, "user-7".to_string(), "not-a-key", "co-a".to_string(), Vec::new())
+            .await
+            .is_err());
+        assert!(authority
+            .enroll(
+                7,
+                "user-7".to_string(),
+                &public_key(1),
+                "co-a".to_string(),
+                vec![ROLE_CERTIFICATE_AUTHORITY.to_string()]
+            )
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_scope_is_limited_to_command() {
+        let repository = Arc::new(MockCertificates::default());
+        let authority = CertificateAuthority::new(key(100), root_certificate(), repository.clone())
+            .unwrap()
+            .with_unit_hierarchy(hierarchy());
+
+        assert!(authority
+            .enroll(7, "user-7".to_string(), &public_key(1), "other-brigade".to_string(), Vec::new())
+            .await
+            .is_err());
+
+        let mut outside = root_certificate();
+        outside.serial = "outside".to_string();
+        outside.subject = "user-8".to_string();
+        outside.unit_id = "other-brigade".to_string();
+        repository.create(&CertificateRecord::new(8, outside)).await.unwrap();
+        assert!(authority.revoke("outside", RevocationReason::UnitChange).await.is_err());
+        assert!(!repository.get("outside").await.unwrap().unwrap().is_revoked());
+    }
+
+    #[tokio::test]
+    async fn test_reenrollment_supersedes_old_key() {
+        let authority = authority();
+        let lost = authority
+            .enroll(7, "user-7".to_string(), &public_key(1), "co-a".to_string(), Vec::new())
+            .await
+            .unwrap();
+        let replacement = authority
+            .enroll(7, "user-7".to_string(), &public_key(2), "co-b".to_string(), Vec::new())
+            .await
+            .unwrap();
+
+        let old = authority.get(lost.serial()).await.unwrap().unwrap();
+        assert_eq!(old.revocation_reason, Some(RevocationReason::Superseded));
+
+        let crl = authority.revocation_list().await.unwrap();
+        assert!(crl.is_revoked(lost.serial()));
+        assert!(!crl.is_revoked(replacement.serial()));
+    }
+
+    #[tokio::test]
+    async fn test_revocation_list_is_signed_and_stable() {
+        let authority = authority();
+        let record = authority
+            .enroll(7, "user-7".to_string(), &public_key(1), "co-a".to_string(), Vec::new())
+            .await
+            .unwrap();
+
+        let empty = authority.revocation_list().await.unwrap();
+        assert!(empty.revoked.is_empty());
+        assert_eq!(empty.digest(), authority.revocation_list().await.unwrap().digest());
+
+        let revoked = authority
+            .revoke(record.serial(), RevocationReason::KeyCompromise)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(revoked.is_revoked());
+        assert!(authority
+            .revoke(record.serial(), RevocationReason::KeyCompromise)
+            .await
+            .unwrap()
+            .is_none());
+
+        let crl = authority.revocation_list().await.unwrap();
+        assert!(crl.is_revoked(record.serial()));
+        assert_ne!(crl.digest(), empty.digest());
+        assert!(crl.verify_signature(&public_key(100)).is_ok());
+
+        // The list is honoured by anyone trusting the authority
+        let mut store = CertificateStore::new();
+        store.add_trust_anchor(root_certificate());
+        store.update_revocation_list(crl);
+        assert!(store.is_revoked(&record.certificate));
+
+        // Nothing to anchor without a ledger
+        assert_eq!(authority.anchor_revocations().await.unwrap(), None);
+    }
+}
@@ -0,0 +1,106 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use parking_lot::RwLock;
+use uuid::Uuid;
+
+use crate::{
+    error::CoreError,
+    infrastructure::blockchain::types::{Block, Transaction, ValidationResult},
+    types::security::SecurityContext,
+};
+use super::{ChainHead, ConsensusProtocol, ValidatorInfo, ValidatorStatus};
+
+/// Single-node consensus that seals every proposed block immediately.
+/// Only safe where there is exactly one validator, i.e. tests and local
+/// development.
+pub struct DevModeConsensus {
+    info: RwLock<ValidatorInfo>,
+    head: RwLock<ChainHead>,
+}
+
+impl DevModeConsensus {
+    pub fn new(validator_id: Uuid) -> Self {
+        Self {
+            info: RwLock::new(ValidatorInfo {
+                id: validator_id,
+                status: ValidatorStatus::Active,
+                last_active: Utc::now(),
+                public_key: Vec::new(),
+            }),
+            head: RwLock::new(ChainHead::default()),
+        }
+    }
+
+    pub fn head(&self) -> ChainHead {
+        self.head.read().clone()
+    }
+}
+
+#[async_trait]
+impl ConsensusProtocol for DevModeConsensus {
+    async fn validate_block(
+        &self,
+        block: &Block,
+        _context: &SecurityContext,
+    ) -> Result<ValidationResult, CoreError> {
+        let result = self.head.read().check_extends(block);
+        Ok(ValidationResult {
+            is_valid: result.is_ok(),
+            reason: result.err(),
+            classification: block.classification,
+        })
+    }
+
+    async fn create_block(
+        &self,
+        transactions: Vec<Transaction>,
+        context: &SecurityContext,
+    ) -> Result<Block, CoreError> {
+        if self.info.read().status != ValidatorStatus::Active {
+            return Err(CoreError::Validation("Dev mode validator is not active".to_string()));
+        }
+
+        let mut head = self.head.write();
+        let mut block = head.next_block(transactions, context.classification, 0);
+        block.confirmed_at = Some(Utc::now());
+        head.advance(&block);
+        self.info.write().last_active = Utc::now();
+        Ok(block)
+    }
+
+    async fn get_validator_info(&self) -> Result<ValidatorInfo, CoreError> {
+        Ok(self.info.read().clone())
+    }
+
+    async fn update_validator_status(
+        &self,
+        status: ValidatorStatus,
+        _context: &SecurityContext,
+    ) -> Result<(), CoreError> {
+        let mut info = self.info.write();
+        info.status = status;
+        info.last_active = Utc::now();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::blockchain::consensus::block_hash;
+
+    #[tokio::test]
+    async fn test_blocks_chain_together() {
+        let context = SecurityContext::new(1);
+        let consensus = DevModeConsensus::new(Uuid::new_v4());
+
+        let first = consensus.create_block(Vec::new(), &context).await.unwrap();
+        let second = consensus.create_block(Vec::new(), &context).await.unwrap();
+        assert_eq!(second.block_height, 2);
+        assert_eq!(second.header.previous_hash, block_hash(&first));
+        assert_eq!(consensus.head().hash, block_hash(&second));
+
+        let result = consensus.validate_block(&first, &context).await.unwrap();
+        assert!(!result.is_valid, "stale block no longer extends the head");
+    }
+}
@@ -7,16 +7,16 @@ use async_trait::async_trait;
 
 use crate::{
     error::blockchain::BlockchainError,
+    infrastructure::blockchain::types::{Block, ChainState, ConsensusService, Transaction, ValidationResult},
     types::{
         security::{SecurityContext, SecurityClassification},
-        blockchain::{Block, Transaction, ValidationResult, ChainState},
-        validation::{ValidationContext, ValidationRule, ValidationSeverity},
         signature::{SignatureMetadata, SignatureType, SignatureAlgorithm},
         audit::{AuditEvent, AuditEventType, AuditContext, AuditSeverity},
     },
 };
 
-use super::{ConsensusService, ValidatorInfo, ValidatorStatus, ValidationEngine};
+use super::{ValidatorInfo, ValidatorStatus};
+use super::validator::ValidationEngine;
 
 #[async_trait]
 pub trait AuditTrailHandler: Send + Sync {
@@ -234,29 +234,7 @@ impl ConsensusService for ConsensusMechanism {
         block: &Block,
         context: &SecurityContext,
     ) -> Result<ValidationResult, BlockchainError> {
-        // Create validation context
-        let validation_context = ValidationContext::new(context.classification);
-
-        // Basic validation rules
-        let rules = vec![
-            ValidationRule::new(
-                "block_size".to_string(),
-                "Block size within limits".to_string(),
-                ValidationSeverity::Critical,
-            ),
-            ValidationRule::new(
-                "transaction_count".to_string(),
-                "Transaction count within limits".to_string(),
-                ValidationSeverity::High,
-            ),
-            ValidationRule::new(
-                "block_hash".to_string(),
-                "Block hash is valid".to_string(),
-                ValidationSeverity::Critical,
-            ),
-        ];
-
-        self.validation_engine.validate_block(block, &validation_context, &rules).await
+        self.validation_engine.validate_block(block, context).await
     }
 
     async fn propose_block(
@@ -1,15 +1,134 @@
+pub mod devmode;
+pub mod poet;
+pub mod validator;
+pub mod voting;
+
 use crate::{
-    error::CoreError,
-    types::security::SecurityContext,
+    error::{blockchain::BlockchainError, CoreError},
+    types::security::{SecurityClassification, SecurityContext},
     infrastructure::blockchain::types::{
-        Block, Transaction, ValidationResult, ChainStatus,
+        Block, BlockHeader, Transaction, ValidationResult,
     },
 };
 
 use async_trait::async_trait;
 use chrono::Utc;
+use ed25519_dalek::SigningKey;
+use handreceipt_protocol::merkle::tree_levels;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
 use uuid::Uuid;
 
+use self::devmode::DevModeConsensus;
+use self::poet::{PoETConfig, PoETConsensus};
+use self::validator::transaction_digest;
+use self::voting::{PbftConfig, RaftConfig};
+
+/// Version written into every block header.
+pub const BLOCK_VERSION: u32 = 1;
+
+/// Consensus a node runs with, selected by `BlockchainConfig::consensus`.
+#[derive(Debug, Clone)]
+pub enum ConsensusEngine {
+    /// Single local node that seals every block as soon as it is proposed.
+    /// For tests and local development only.
+    DevMode,
+    /// An existing Sawtooth network; consensus is whatever its validators
+    /// are already configured for.
+    Sawtooth,
+    /// PBFT among a fixed set of unit validators.
+    Pbft(PbftConfig),
+    /// Raft among a fixed set of unit validators.
+    Raft(RaftConfig),
+    /// Proof of Elapsed Time with signed wait certificates.
+    PoET(PoETConfig),
+}
+
+impl ConsensusEngine {
+    /// Sawtooth consensus engine name and version, or `None` if the node
+    /// accepts whatever the network runs.
+    pub fn algorithm(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            ConsensusEngine::DevMode => Some(("Devmode", "0.1")),
+            ConsensusEngine::Sawtooth => None,
+            ConsensusEngine::Pbft(_) => Some(("pbft", "1.0")),
+            ConsensusEngine::Raft(_) => Some(("raft", "0.1")),
+            ConsensusEngine::PoET(_) => Some(("PoET", "0.1")),
+        }
+    }
+
+    /// `sawtooth_settings` entries the validator network needs for this
+    /// engine, for the genesis batch or a settings proposal. Fails if the
+    /// configuration cannot work, e.g. too few PBFT members.
+    pub fn settings(&self) -> Result<Vec<(String, String)>, BlockchainError> {
+        let mut settings = Vec::new();
+        if let Some((name, version)) = self.algorithm() {
+            settings.push((ALGORITHM_NAME_SETTING.to_string(), name.to_string()));
+            settings.push((ALGORITHM_VERSION_SETTING.to_string(), version.to_string()));
+        }
+
+        match self {
+            ConsensusEngine::Pbft(config) => settings.extend(config.settings()?),
+            ConsensusEngine::Raft(config) => settings.extend(config.settings()?),
+            ConsensusEngine::PoET(config) => settings.extend(config.settings()?),
+            ConsensusEngine::DevMode | ConsensusEngine::Sawtooth => {}
+        }
+        Ok(settings)
+    }
+
+    /// Builds the in-process engine for consensus this node runs itself.
+    /// PBFT, Raft and plain Sawtooth run inside the validator network and
+    /// have no in-process engine.
+    pub fn create_protocol(
+        &self,
+        validator_id: Uuid,
+        signing_key: Option<SigningKey>,
+    ) -> Result<Arc<dyn ConsensusProtocol>, BlockchainError> {
+        match self {
+            ConsensusEngine::DevMode => Ok(Arc::new(DevModeConsensus::new(validator_id))),
+            ConsensusEngine::PoET(config) => {
+                let signing_key = signing_key.ok_or_else(|| {
+                    BlockchainError::ValidationError("PoET requires a consensus signing key".to_string())
+                })?;
+                Ok(Arc::new(PoETConsensus::new(config.clone(), validator_id, signing_key)?))
+            }
+            other => Err(BlockchainError::ValidationError(format!(
+                "{} consensus runs in the validator network",
+                other.algorithm().map(|(name, _)| name).unwrap_or("Sawtooth")
+            ))),
+        }
+    }
+}
+
+/// Setting naming the consensus engine validators run.
+pub const ALGORITHM_NAME_SETTING: &str = "sawtooth.consensus.algorithm.name";
+/// Setting pinning the consensus engine version.
+pub const ALGORITHM_VERSION_SETTING: &str = "sawtooth.consensus.algorithm.version";
+
+/// Limits enforced when validating blocks.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ConsensusConfig {
+    pub min_validators: usize,
+    pub max_validators: usize,
+    pub block_time: u64,
+    pub max_block_size: usize,
+    pub min_difficulty: u32,
+    pub max_difficulty: u32,
+}
+
+impl Default for ConsensusConfig {
+    fn default() -> Self {
+        Self {
+            min_validators: 3,
+            max_validators: 10,
+            block_time: 15,
+            max_block_size: 1024 * 1024, // 1MB
+            min_difficulty: 1,
+            max_difficulty: 32,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ValidatorInfo {
     pub id: Uuid,
@@ -25,8 +144,9 @@ pub enum ValidatorStatus {
     Suspended,
 }
 
+/// Block production and validation for consensus run in-process.
 #[async_trait]
-pub trait ConsensusEngine: Send + Sync {
+pub trait ConsensusProtocol: Send + Sync {
     async fn validate_block(
         &self,
         block: &Block,
@@ -48,12 +168,100 @@ pub trait ConsensusEngine: Send + Sync {
     ) -> Result<(), CoreError>;
 }
 
-pub struct ValidationEngine {
+/// Hash and height of the last block a node accepted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChainHead {
+    pub hash: String,
+    pub height: u64,
+    pub total_transactions: u64,
+    /// Header timestamp of that block.
+    pub timestamp: chrono::DateTime<Utc>,
+}
+
+impl ChainHead {
+    /// Unsealed block extending this head.
+    pub fn next_block(
+        &self,
+        transactions: Vec<Transaction>,
+        classification: SecurityClassification,
+        difficulty: u32,
+    ) -> Block {
+        let now = Utc::now();
+        Block {
+            id: Uuid::new_v4(),
+            header: BlockHeader {
+                version: BLOCK_VERSION,
+                previous_hash: self.hash.clone(),
+                merkle_root: merkle_root(&transactions),
+                timestamp: now,
+                difficulty,
+                nonce: 0,
+                wait_certificate: None,
+            },
+            block_height: self.height + 1,
+            total_transactions: self.total_transactions + transactions.len() as u64,
+            transactions,
+            created_at: now,
+            confirmed_at: None,
+            classification,
+        }
+    }
+
+    /// Checks that `block` directly extends this head and that its header
+    /// commits to its transactions.
+    pub fn check_extends(&self, block: &Block) -> Result<(), String> {
+        if block.header.previous_hash != self.hash {
+            return Err(format!("Block does not extend head {}", self.hash));
+        }
+        if block.block_height != self.height + 1 {
+            return Err(format!(
+                "Block height {} does not follow {}",
+                block.block_height, self.height
+            ));
+        }
+        if block.header.merkle_root != merkle_root(&block.transactions) {
+            return Err("Merkle root does not match the block's transactions".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn advance(&mut self, block: &Block) {
+        self.hash = block_hash(block);
+        self.height = block.block_height;
+        self.total_transactions = block.total_transactions;
+        self.timestamp = block.header.timestamp;
+    }
+}
+
+/// Hex SHA-256 of the block header and height.
+pub fn block_hash(block: &Block) -> String {
+    let header = serde_json::to_vec(&block.header).unwrap_or_default();
+    let mut hasher = Sha256::new();
+    hasher.update(block.block_height.to_be_bytes());
+    hasher.update(&header);
+    hex::encode(hasher.finalize())
+}
+
+/// Merkle root over the canonical transaction digests, empty for a block
+/// without transactions.
+pub fn merkle_root(transactions: &[Transaction]) -> String {
+    let leaves = transactions
+        .iter()
+        .map(|transaction| hex::encode(transaction_digest(transaction)))
+        .collect();
+    tree_levels(leaves)
+        .last()
+        .and_then(|root| root.first().cloned())
+        .unwrap_or_default()
+}
+
+/// Context-free checks on a block header.
+pub struct BlockHeaderValidator {
     min_difficulty: u32,
     max_block_size: usize,
 }
 
-impl ValidationEngine {
+impl BlockHeaderValidator {
     pub fn new(min_difficulty: u32, max_block_size: usize) -> Self {
         Self {
             min_difficulty,
@@ -64,18 +272,87 @@ impl ValidationEngine {
     pub fn validate_block_header(&self, block: &Block) -> Result<(), CoreError> {
         // Validate block header fields
         if block.header.difficulty < self.min_difficulty {
-            return Err(CoreError::Blockchain(
-                format!("Block difficulty too low: {}", block.header.difficulty).into()
+            return Err(CoreError::Validation(
+                format!("Block difficulty too low: {}", block.header.difficulty)
             ));
         }
 
         // Validate block size
         if block.transactions.len() > self.max_block_size {
-            return Err(CoreError::Blockchain(
-                format!("Block size exceeds maximum: {}", block.transactions.len()).into()
+            return Err(CoreError::Validation(
+                format!("Block size exceeds maximum: {}", block.transactions.len())
             ));
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::blockchain::types::{TransactionData, TransactionType};
+    use serde_json::json;
+
+    fn transaction(property_id: &str) -> Transaction {
+        Transaction {
+            id: Uuid::new_v4(),
+            transaction_type: TransactionType::PropertyUpdate,
+            data: TransactionData {
+                property_id: property_id.to_string(),
+                metadata: json!({ "name": "Radio", "category": "comms" }),
+                operation: "update".to_string(),
+            },
+            timestamp: Utc::now(),
+            signer: 1,
+            signature: None,
+            classification: SecurityClassification::Unclassified,
+        }
+    }
+
+    #[test]
+    fn test_engine_settings() {
+        assert!(ConsensusEngine::Sawtooth.settings().unwrap().is_empty());
+
+        let settings = ConsensusEngine::DevMode.settings().unwrap();
+        assert_eq!(
+            settings,
+            vec![
+                (ALGORITHM_NAME_SETTING.to_string(), "Devmode".to_string()),
+                (ALGORITHM_VERSION_SETTING.to_string(), "0.1".to_string()),
+            ]
+        );
+
+        let settings = ConsensusEngine::PoET(PoETConfig::default()).settings().unwrap();
+        assert!(settings.iter().any(|(key, _)| key == "sawtooth.poet.target_wait_time"));
+
+        assert!(ConsensusEngine::Pbft(PbftConfig::new(Vec::new())).settings().is_err());
+    }
+
+    #[test]
+    fn test_create_protocol() {
+        let id = Uuid::new_v4();
+        assert!(ConsensusEngine::DevMode.create_protocol(id, None).is_ok());
+        assert!(ConsensusEngine::PoET(PoETConfig::default()).create_protocol(id, None).is_err());
+        assert!(ConsensusEngine::PoET(PoETConfig::default())
+            .create_protocol(id, Some(SigningKey::from_bytes(&[1; 32])))
+            .is_ok());
+        assert!(ConsensusEngine::Raft(RaftConfig::new(Vec::new())).create_protocol(id, None).is_err());
+    }
+
+    #[test]
+    fn test_chain_head_checks_merkle_root() {
+        let head = ChainHead::default();
+        assert!(merkle_root(&[]).is_empty());
+
+        let mut block = head.next_block(
+            vec![transaction("property-1"), transaction("property-2")],
+            SecurityClassification::Unclassified,
+            1,
+        );
+        assert!(head.check_extends(&block).is_ok());
+
+        block.transactions.pop();
+        assert!(head.check_extends(&block).is_err());
+    }
+}
@@ -1,15 +1,83 @@
-use std::time::Duration;
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use parking_lot::RwLock;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 use uuid::Uuid;
 
+use crate::{
+    error::{blockchain::BlockchainError, CoreError},
+    infrastructure::blockchain::types::{Block, Transaction, ValidationResult},
+    types::security::SecurityContext,
+};
+use super::{ChainHead, ConsensusProtocol, ValidatorInfo, ValidatorStatus};
+
+const WAIT_CERTIFICATE_DOMAIN: &[u8] = b"handreceipt/poet/v1";
+
+/// How far a peer's clock may run ahead of ours before its block
+/// timestamps and wait requests are rejected.
+const MAX_CLOCK_SKEW: chrono::Duration = chrono::Duration::seconds(5);
+
 /// Configuration parameters for the PoET consensus mechanism.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PoETConfig {
+    /// Mean wait before a validator's first block after start-up.
     pub initial_wait_time: Duration,
+    /// Mean wait for every later block.
     pub target_wait_time: Duration,
-    pub wait_time_fluctuation: f64, // Percentage
+    /// Fraction of the mean a wait may deviate by, in `[0, 1)`.
+    pub wait_time_fluctuation: f64,
+}
+
+impl Default for PoETConfig {
+    fn default() -> Self {
+        Self {
+            initial_wait_time: Duration::from_secs(30),
+            target_wait_time: Duration::from_secs(20),
+            wait_time_fluctuation: 0.2,
+        }
+    }
+}
+
+impl PoETConfig {
+    pub fn settings(&self) -> Result<Vec<(String, String)>, BlockchainError> {
+        if self.target_wait_time.is_zero() || self.initial_wait_time.is_zero() {
+            return Err(BlockchainError::ValidationError(
+                "PoET wait times must be positive".to_string(),
+            ));
+        }
+        if !(0.0..1.0).contains(&self.wait_time_fluctuation) {
+            return Err(BlockchainError::ValidationError(format!(
+                "PoET wait time fluctuation must be in [0, 1), got {}",
+                self.wait_time_fluctuation
+            )));
+        }
+
+        Ok(vec![
+            (
+                "sawtooth.poet.initial_wait_time".to_string(),
+                self.initial_wait_time.as_secs_f64().to_string(),
+            ),
+            (
+                "sawtooth.poet.target_wait_time".to_string(),
+                self.target_wait_time.as_secs_f64().to_string(),
+            ),
+        ])
+    }
+
+    /// Shortest and longest wait allowed around `local_mean`.
+    pub fn wait_bounds(&self, local_mean: Duration) -> (Duration, Duration) {
+        let mean = local_mean.as_secs_f64();
+        let spread = mean * self.wait_time_fluctuation;
+        (
+            Duration::from_secs_f64(mean - spread),
+            Duration::from_secs_f64(mean + spread),
+        )
+    }
 }
 
 /// Generates random wait durations for validators.
@@ -23,93 +91,419 @@ impl WaitTimer {
         Self { config }
     }
 
-    /// Generates a wait time based on a normal distribution.
-    pub fn generate_wait_time(&self, validator_id: &Uuid) -> Duration {
-        let mut rng = rand::thread_rng();
-        let mean = self.config.target_wait_time.as_secs_f64();
-        let std_dev = mean * self.config.wait_time_fluctuation;
-        let wait_time = rng.sample(rand_distr::Normal::new(mean, std_dev).unwrap());
-        let wait_time = wait_time.max(0.0); // Ensure wait time is non-negative
-        Duration::from_secs_f64(wait_time)
+    /// Draws a wait uniformly from the allowed bounds around `local_mean`.
+    pub fn generate_wait_time(&self, local_mean: Duration) -> Duration {
+        let (min, max) = self.config.wait_bounds(local_mean);
+        if min >= max {
+            return min;
+        }
+        rand::thread_rng().gen_range(min..=max)
     }
 }
 
-/// Proof that a validator has waited the required duration.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Proof that a validator has waited the required duration, signed with
+/// the validator's Ed25519 consensus key.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct WaitCertificate {
     pub validator_id: Uuid,
+    /// Hex Ed25519 public key of the validator.
+    pub validator_key: String,
+    /// Block the validator waited to extend.
+    pub previous_block_hash: String,
     pub wait_duration: Duration,
     pub local_mean: Duration,
     pub request_time: DateTime<Utc>,
-    pub signature: String, // Placeholder, should be a cryptographic signature
+    /// Hex Ed25519 signature over `digest`.
+    pub signature: String,
+}
+
+impl WaitCertificate {
+    /// Domain-separated SHA-256 over every field except the signature.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(WAIT_CERTIFICATE_DOMAIN);
+        hasher.update(self.validator_id.as_bytes());
+        for field in [&self.validator_key, &self.previous_block_hash] {
+            hasher.update((field.len() as u64).to_be_bytes());
+            hasher.update(field.as_bytes());
+        }
+        hasher.update((self.wait_duration.as_nanos() as u64).to_be_bytes());
+        hasher.update((self.local_mean.as_nanos() as u64).to_be_bytes());
+        hasher.update(self.request_time.timestamp_millis().to_be_bytes());
+        hasher.finalize().into()
+    }
+
+    pub fn sign(&mut self, signing_key: &SigningKey) {
+        self.signature = hex::encode(signing_key.sign(&self.digest()).to_bytes());
+    }
+
+    pub fn verify_signature(&self, public_key: &VerifyingKey) -> bool {
+        let signature: Option<[u8; 64]> = hex::decode(&self.signature)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok());
+        signature.is_some_and(|signature| {
+            public_key
+                .verify(&self.digest(), &Signature::from_bytes(&signature))
+                .is_ok()
+        })
+    }
 }
 
 /// Implements the PoET consensus logic.
-#[derive(Debug, Clone)]
 pub struct PoETConsensus {
     config: PoETConfig,
-    // Add other necessary fields, like a key-value store for certificates
+    validator_id: Uuid,
+    signing_key: SigningKey,
+    validators: RwLock<HashMap<Uuid, ValidatorInfo>>,
+    head: RwLock<ChainHead>,
+    /// Validators with a block on the chain, whose waits use the target
+    /// rather than the initial mean.
+    published: RwLock<HashSet<Uuid>>,
 }
 
 impl PoETConsensus {
-    pub fn new(config: PoETConfig) -> Self {
-        Self { config }
+    pub fn new(
+        config: PoETConfig,
+        validator_id: Uuid,
+        signing_key: SigningKey,
+    ) -> Result<Self, BlockchainError> {
+        config.settings()?;
+        let consensus = Self {
+            config,
+            validator_id,
+            signing_key,
+            validators: RwLock::new(HashMap::new()),
+            head: RwLock::new(ChainHead::default()),
+            published: RwLock::new(HashSet::new()),
+        };
+        consensus.register_validator(validator_id, consensus.signing_key.verifying_key());
+        Ok(consensus)
     }
 
-    pub fn initialize(&self) {
-        // Initialize the consensus mechanism (e.g., load existing certificates)
+    /// Adds or replaces a validator whose certificates this node accepts.
+    pub fn register_validator(&self, validator_id: Uuid, public_key: VerifyingKey) {
+        self.validators.write().insert(
+            validator_id,
+            ValidatorInfo {
+                id: validator_id,
+                status: ValidatorStatus::Active,
+                last_active: Utc::now(),
+                public_key: public_key.to_bytes().to_vec(),
+            },
+        );
     }
 
-     /// Creates a new block using PoET consensus.
-    pub fn create_block(
-        &self,
-        validator_id: &Uuid,
-        previous_block_hash: &str,
-        transactions: &[/*BlockchainTransaction*/], // Use actual type
-    ) -> /*Block*/ () // Use actual Block type
-     {
-        // 1. Generate a wait timer.
-        let wait_timer = WaitTimer::new(self.config.clone());
-
-        // 2. Calculate the wait duration.
-        let wait_duration = wait_timer.generate_wait_time(validator_id);
-
-        // 3. Wait for the calculated duration.
-        std::thread::sleep(wait_duration);
-
-        // 4. Create a WaitCertificate.
-        let wait_certificate = WaitCertificate {
-            validator_id: *validator_id,
+    pub fn head(&self) -> ChainHead {
+        self.head.read().clone()
+    }
+
+    /// Waits a random time and returns a signed certificate to extend
+    /// `previous_block_hash`.
+    pub async fn wait_certificate(&self, previous_block_hash: &str) -> WaitCertificate {
+        let local_mean = self.expected_mean(&self.validator_id);
+        let request_time = Utc::now();
+        let wait_duration = WaitTimer::new(self.config.clone()).generate_wait_time(local_mean);
+        tokio::time::sleep(wait_duration).await;
+
+        let mut certificate = WaitCertificate {
+            validator_id: self.validator_id,
+            validator_key: hex::encode(self.signing_key.verifying_key().to_bytes()),
+            previous_block_hash: previous_block_hash.to_string(),
             wait_duration,
-            local_mean: wait_duration, // Simplification for now
-            request_time: Utc::now(),
-            signature: String::from("Placeholder Signature"), // Replace with actual signature
+            local_mean,
+            request_time,
+            signature: String::new(),
+        };
+        certificate.sign(&self.signing_key);
+        certificate
+    }
+
+    /// Mean `validator_id` must wait around for its next block.
+    fn expected_mean(&self, validator_id: &Uuid) -> Duration {
+        if self.published.read().contains(validator_id) {
+            self.config.target_wait_time
+        } else {
+            self.config.initial_wait_time
+        }
+    }
+
+    /// Checks that `certificate` is signed by an active validator, waits a
+    /// permitted time to extend `head`, and had elapsed by `sealed_at`.
+    pub fn verify_certificate(
+        &self,
+        certificate: &WaitCertificate,
+        head: &ChainHead,
+        sealed_at: DateTime<Utc>,
+    ) -> Result<(), String> {
+        let validators = self.validators.read();
+        let validator = validators
+            .get(&certificate.validator_id)
+            .ok_or_else(|| format!("Unknown validator {}", certificate.validator_id))?;
+        if validator.status != ValidatorStatus::Active {
+            return Err(format!("Validator {} is not active", certificate.validator_id));
+        }
+        if hex::encode(&validator.public_key) != certificate.validator_key {
+            return Err("Certificate key does not match the registered validator key".to_string());
+        }
+        let public_key = validator
+            .public_key
+            .as_slice()
+            .try_into()
+            .ok()
+            .and_then(|bytes| VerifyingKey::from_bytes(bytes).ok())
+            .ok_or_else(|| "Registered validator key is invalid".to_string())?;
+        if !certificate.verify_signature(&public_key) {
+            return Err("Invalid wait certificate signature".to_string());
+        }
+
+        if certificate.previous_block_hash != head.hash {
+            return Err("Wait certificate was issued for a different block".to_string());
+        }
+        let expected_mean = self.expected_mean(&certificate.validator_id);
+        if certificate.local_mean != expected_mean {
+            return Err(format!(
+                "Local mean {:?} does not match the expected {:?}",
+                certificate.local_mean, expected_mean
+            ));
+        }
+        // A wait cannot have started before the block it extends was sealed
+        if certificate.request_time + MAX_CLOCK_SKEW < head.timestamp {
+            return Err("Wait was requested before the previous block".to_string());
+        }
+        let (min, max) = self.config.wait_bounds(certificate.local_mean);
+        if certificate.wait_duration < min || certificate.wait_duration > max {
+            return Err(format!(
+                "Wait duration {:?} outside {:?}..={:?}",
+                certificate.wait_duration, min, max
+            ));
+        }
+        let elapsed = chrono::Duration::from_std(certificate.wait_duration)
+            .map_err(|e| e.to_string())?;
+        if sealed_at < certificate.request_time + elapsed {
+            return Err("Block was sealed before the wait elapsed".to_string());
+        }
+        Ok(())
+    }
+
+    fn check_block(&self, block: &Block) -> Result<(), String> {
+        let head = self.head.read().clone();
+        head.check_extends(block)?;
+        if block.header.timestamp < head.timestamp {
+            return Err("Block timestamp precedes the previous block".to_string());
+        }
+        if block.header.timestamp > Utc::now() + MAX_CLOCK_SKEW {
+            return Err("Block timestamp is in the future".to_string());
+        }
+        let certificate = block
+            .header
+            .wait_certificate
+            .as_ref()
+            .ok_or_else(|| "Block has no wait certificate".to_string())?;
+        self.verify_certificate(certificate, &head, block.header.timestamp)
+    }
+
+    /// Validates a block from another validator and makes it the new head.
+    pub fn commit_block(&self, block: &Block) -> Result<(), CoreError> {
+        self.check_block(block).map_err(CoreError::Validation)?;
+        self.head.write().advance(block);
+        if let Some(certificate) = &block.header.wait_certificate {
+            self.published.write().insert(certificate.validator_id);
+            if let Some(validator) = self.validators.write().get_mut(&certificate.validator_id) {
+                validator.last_active = Utc::now();
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ConsensusProtocol for PoETConsensus {
+    async fn validate_block(
+        &self,
+        block: &Block,
+        _context: &SecurityContext,
+    ) -> Result<ValidationResult, CoreError> {
+        let result = self.check_block(block);
+        Ok(ValidationResult {
+            is_valid: result.is_ok(),
+            reason: result.err(),
+            classification: block.classification,
+        })
+    }
+
+    async fn create_block(
+        &self,
+        transactions: Vec<Transaction>,
+        context: &SecurityContext,
+    ) -> Result<Block, CoreError> {
+        let status = self.get_validator_info().await?.status;
+        if status != ValidatorStatus::Active {
+            return Err(CoreError::Validation(format!(
+                "Validator {} is {:?}",
+                self.validator_id, status
+            )));
+        }
+
+        let previous_hash = self.head.read().hash.clone();
+        let certificate = self.wait_certificate(&previous_hash).await;
+        let head = self.head.read().clone();
+        if head.hash != previous_hash {
+            return Err(CoreError::Validation(
+                "Another block was committed while waiting".to_string(),
+            ));
+        }
+
+        let mut block = head.next_block(transactions, context.classification, 1);
+        block.header.wait_certificate = Some(certificate);
+        self.commit_block(&block)?;
+        block.confirmed_at = Some(Utc::now());
+        Ok(block)
+    }
+
+    async fn get_validator_info(&self) -> Result<ValidatorInfo, CoreError> {
+        self.validators
+            .read()
+            .get(&self.validator_id)
+            .cloned()
+            .ok_or_else(|| CoreError::NotFound(format!("Validator {}", self.validator_id)))
+    }
+
+    async fn update_validator_status(
+        &self,
+        status: ValidatorStatus,
+        _context: &SecurityContext,
+    ) -> Result<(), CoreError> {
+        let mut validators = self.validators.write();
+        let validator = validators
+            .get_mut(&self.validator_id)
+            .ok_or_else(|| CoreError::NotFound(format!("Validator {}", self.validator_id)))?;
+        validator.status = status;
+        validator.last_active = Utc::now();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::security::SecurityClassification;
+
+    fn config() -> PoETConfig {
+        PoETConfig {
+            initial_wait_time: Duration::from_millis(20),
+            target_wait_time: Duration::from_millis(10),
+            wait_time_fluctuation: 0.5,
+        }
+    }
+
+    fn consensus(seed: u8) -> PoETConsensus {
+        PoETConsensus::new(config(), Uuid::new_v4(), SigningKey::from_bytes(&[seed; 32])).unwrap()
+    }
+
+    #[test]
+    fn test_wait_time_within_bounds() {
+        let timer = WaitTimer::new(config());
+        let (min, max) = config().wait_bounds(Duration::from_millis(10));
+        for _ in 0..100 {
+            let wait = timer.generate_wait_time(Duration::from_millis(10));
+            assert!(wait >= min && wait <= max);
+        }
+    }
+
+    #[test]
+    fn test_config_rejects_bad_fluctuation() {
+        let config = PoETConfig {
+            wait_time_fluctuation: 1.0,
+            ..config()
         };
+        assert!(config.settings().is_err());
+        assert!(PoETConsensus::new(config, Uuid::new_v4(), SigningKey::from_bytes(&[1; 32])).is_err());
+    }
 
-        // 5. Include the WaitCertificate in the new block.
-        // ... (Logic to create the block, including the certificate)
+    #[tokio::test]
+    async fn test_certificate_signature() {
+        let node = consensus(1);
+        let head = ChainHead {
+            hash: "head".to_string(),
+            ..ChainHead::default()
+        };
+        let mut certificate = node.wait_certificate("head").await;
+        let sealed_at = Utc::now();
+        assert!(node.verify_certificate(&certificate, &head, sealed_at).is_ok());
+        let other = ChainHead {
+            hash: "other".to_string(),
+            ..ChainHead::default()
+        };
+        assert!(node.verify_certificate(&certificate, &other, sealed_at).is_err());
 
-        // 6. Proceed with existing block creation logic (timestamp, Merkle root, etc.).
-        // ...
+        // Requested long before the block it claims to extend
+        let later = ChainHead {
+            timestamp: sealed_at + chrono::Duration::minutes(1),
+            ..head.clone()
+        };
+        assert!(node.verify_certificate(&certificate, &later, sealed_at).is_err());
 
-        println!("Creating block after waiting for {:?}", wait_duration);
+        certificate.wait_duration = Duration::ZERO;
+        assert!(node.verify_certificate(&certificate, &head, sealed_at).is_err());
     }
 
-    /// Validates a block, including its WaitCertificate.
-    pub fn validate_block(&self, _block: &/*Block*/) -> bool {
-        // 1. Check that the certificate is valid.
-        // ...
+    #[tokio::test]
+    async fn test_first_block_uses_initial_mean() {
+        let node = consensus(1);
+        let mut certificate = node.wait_certificate("").await;
+        assert_eq!(certificate.local_mean, config().initial_wait_time);
+
+        // A validator cannot claim the shorter target wait before it publishes
+        certificate.local_mean = config().target_wait_time;
+        certificate.wait_duration = config().target_wait_time;
+        certificate.sign(&SigningKey::from_bytes(&[1; 32]));
+        let sealed_at = Utc::now() + chrono::Duration::seconds(1);
+        assert!(node.verify_certificate(&certificate, &node.head(), sealed_at).is_err());
+
+        let block = node.create_block(Vec::new(), &SecurityContext::new(1)).await.unwrap();
+        assert_eq!(
+            block.header.wait_certificate.unwrap().local_mean,
+            config().initial_wait_time
+        );
+        assert_eq!(node.wait_certificate("").await.local_mean, config().target_wait_time);
+    }
+
+    #[tokio::test]
+    async fn test_blocks_from_peer() {
+        let context = SecurityContext::new(1);
+        let producer = consensus(1);
+        let follower = consensus(2);
+
+        let block = producer.create_block(Vec::new(), &context).await.unwrap();
+        assert_eq!(block.block_height, 1);
+        assert_eq!(producer.head().height, 1);
+
+        let result = follower.validate_block(&block, &context).await.unwrap();
+        assert!(!result.is_valid, "producer is not registered with the follower");
+
+        let producer_info = producer.get_validator_info().await.unwrap();
+        let key = VerifyingKey::from_bytes(producer_info.public_key.as_slice().try_into().unwrap()).unwrap();
+        follower.register_validator(producer_info.id, key);
+        assert!(follower.validate_block(&block, &context).await.unwrap().is_valid);
 
-        // 2. Check that the wait duration is within acceptable limits.
-        // ...
+        let mut forged = block.clone();
+        forged.classification = SecurityClassification::Secret;
+        forged.header.wait_certificate.as_mut().unwrap().previous_block_hash = "x".to_string();
+        assert!(!follower.validate_block(&forged, &context).await.unwrap().is_valid);
 
-        // 3. Verify the signature.
-        // ...
+        let mut future = block.clone();
+        future.header.timestamp = Utc::now() + chrono::Duration::minutes(1);
+        assert!(!follower.validate_block(&future, &context).await.unwrap().is_valid);
 
-        true // Placeholder
+        follower.commit_block(&block).unwrap();
+        assert_eq!(follower.head(), producer.head());
     }
 
-    pub fn update_validator_status(&self, _validator_id: &Uuid) {
-        // Update validator status (if needed)
+    #[tokio::test]
+    async fn test_suspended_validator_cannot_publish() {
+        let context = SecurityContext::new(1);
+        let node = consensus(1);
+        node.update_validator_status(ValidatorStatus::Suspended, &context).await.unwrap();
+        assert!(node.create_block(Vec::new(), &context).await.is_err());
     }
 }
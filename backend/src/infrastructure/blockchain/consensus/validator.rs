@@ -1,14 +1,33 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use parking_lot::{Mutex, RwLock};
+use sawtooth_sdk::signing::secp256k1::{Secp256k1Context, Secp256k1PublicKey};
+use sawtooth_sdk::signing::Context;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 use crate::{
-    error::blockchain::BlockchainError,  // Updated to use correct error path
+    domain::models::qr::canonical_json,
+    error::blockchain::BlockchainError,
+    infrastructure::blockchain::types::{Block, Transaction, TransactionType, ValidationResult},
     types::{
-        security::SecurityContext,
-        validation::ValidationContext,
-        blockchain::{Block, Transaction, ValidationResult},
+        security::{SecurityClassification, SecurityContext},
+        signature::SignatureAlgorithm,
     },
 };
+use super::ConsensusConfig;
+
+/// How long a transaction id is remembered for replay detection. Older
+/// transactions are rejected outright.
+pub const DEFAULT_REPLAY_WINDOW_MINUTES: i64 = 60;
+
+/// How far ahead of the local clock a transaction may be timestamped.
+const MAX_CLOCK_SKEW_SECONDS: i64 = 300;
+
+const TRANSACTION_DIGEST_DOMAIN: &[u8] = b"handreceipt/transaction/v1";
 
 #[async_trait]
 pub trait ValidationEngine: Send + Sync {
@@ -25,19 +44,372 @@ pub trait ValidationEngine: Send + Sync {
     ) -> Result<ValidationResult, BlockchainError>;
 }
 
+/// A single check applied to every transaction. Returns the reason the
+/// transaction is rejected.
+pub trait TransactionRule: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn check(&self, transaction: &Transaction, context: &SecurityContext) -> Result<(), String>;
+}
+
+/// Key a user signs transactions with.
+#[derive(Debug, Clone)]
+pub struct SignerKey {
+    pub algorithm: SignatureAlgorithm,
+    /// Hex encoded public key.
+    pub public_key: String,
+}
+
+/// Canonical digest a transaction signature covers. Metadata keys are sorted
+/// so the digest does not depend on how the JSON was built, and the signer
+/// is included so a signature cannot be claimed by another user.
+pub fn transaction_digest(transaction: &Transaction) -> [u8; 32] {
+    let metadata = canonical_json(&transaction.data.metadata);
+    let signer = transaction.signer.to_string();
+    let timestamp = transaction.timestamp.timestamp_millis().to_string();
+    let id = transaction.id.to_string();
+    let transaction_type = format!("{:?}", transaction.transaction_type);
+    let classification = transaction.classification.to_string();
+
+    let mut hasher = Sha256::new();
+    hasher.update(TRANSACTION_DIGEST_DOMAIN);
+    for field in [
+        id.as_str(),
+        transaction_type.as_str(),
+        transaction.data.property_id.as_str(),
+        transaction.data.operation.as_str(),
+        metadata.as_str(),
+        timestamp.as_str(),
+        classification.as_str(),
+        signer.as_str(),
+    ] {
+        hasher.update((field.len() as u32).to_be_bytes());
+        hasher.update(field.as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+/// Verifies the transaction signature with the key registered for the
+/// transaction's signer.
+#[derive(Default)]
+pub struct SignatureRule {
+    keys: RwLock<HashMap<i32, SignerKey>>,
+}
+
+impl SignatureRule {
+    pub fn register_key(&self, user_id: i32, key: SignerKey) {
+        self.keys.write().insert(user_id, key);
+    }
+
+    fn verify(key: &SignerKey, message: &[u8], signature: &str) -> Result<bool, String> {
+        match key.algorithm {
+            SignatureAlgorithm::Ed25519 => {
+                let public_key: [u8; 32] = hex::decode(&key.public_key)
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or("Invalid Ed25519 public key")?;
+                let public_key = VerifyingKey::from_bytes(&public_key).map_err(|e| e.to_string())?;
+                let signature: [u8; 64] = hex::decode(signature)
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or("Malformed Ed25519 signature")?;
+                Ok(public_key.verify(message, &Signature::from_bytes(&signature)).is_ok())
+            }
+            SignatureAlgorithm::Secp256k1 => {
+                let public_key = Secp256k1PublicKey::from_hex(&key.public_key).map_err(|e| e.to_string())?;
+                Secp256k1Context::new()
+                    .verify(signature, message, &public_key)
+                    .map_err(|e| e.to_string())
+            }
+            other => Err(format!("Unsupported signature algorithm {}", other)),
+        }
+    }
+}
+
+impl TransactionRule for SignatureRule {
+    fn name(&self) -> &'static str {
+        "signature"
+    }
+
+    fn check(&self, transaction: &Transaction, _context: &SecurityContext) -> Result<(), String> {
+        let signature = transaction.signature.as_deref().ok_or("Transaction is not signed")?;
+        let key = self
+            .keys
+            .read()
+            .get(&transaction.signer)
+            .cloned()
+            .ok_or_else(|| format!("No signing key registered for user {}", transaction.signer))?;
+
+        if Self::verify(&key, &transaction_digest(transaction), signature)? {
+            Ok(())
+        } else {
+            Err(format!("Invalid {} signature", key.algorithm))
+        }
+    }
+}
+
+/// Signers may only submit transactions at or below their own clearance.
+pub struct ClassificationRule;
+
+impl TransactionRule for ClassificationRule {
+    fn name(&self) -> &'static str {
+        "classification"
+    }
+
+    fn check(&self, transaction: &Transaction, context: &SecurityContext) -> Result<(), String> {
+        if transaction.classification > context.classification {
+            return Err(format!(
+                "Transaction is {} but signer is cleared for {}",
+                transaction.classification, context.classification
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Checks the payload has the shape its transaction type requires.
+pub struct SchemaRule;
+
+impl SchemaRule {
+    fn operation(transaction_type: &TransactionType) -> &'static str {
+        match transaction_type {
+            TransactionType::PropertyCreation => "create",
+            TransactionType::PropertyTransfer => "transfer",
+            TransactionType::PropertyUpdate => "update",
+            TransactionType::PropertyDeletion => "delete",
+            TransactionType::NetworkSync => "sync",
+            TransactionType::PeerDiscovery => "discover",
+        }
+    }
+
+    /// Metadata fields that must be non-empty strings.
+    fn required_fields(transaction_type: &TransactionType) -> &'static [&'static str] {
+        match transaction_type {
+            TransactionType::PropertyCreation => &["name", "category", "custodian"],
+            TransactionType::PropertyTransfer => &["to_custodian"],
+            TransactionType::PropertyUpdate => &["name", "category"],
+            _ => &[],
+        }
+    }
+
+    fn is_property_operation(transaction_type: &TransactionType) -> bool {
+        !matches!(transaction_type, TransactionType::NetworkSync | TransactionType::PeerDiscovery)
+    }
+}
+
+impl TransactionRule for SchemaRule {
+    fn name(&self) -> &'static str {
+        "schema"
+    }
+
+    fn check(&self, transaction: &Transaction, _context: &SecurityContext) -> Result<(), String> {
+        let data = &transaction.data;
+        let expected = Self::operation(&transaction.transaction_type);
+        if data.operation != expected {
+            return Err(format!(
+                "Operation {:?} does not match {:?}, expected {:?}",
+                data.operation, transaction.transaction_type, expected
+            ));
+        }
+        if Self::is_property_operation(&transaction.transaction_type) && data.property_id.trim().is_empty() {
+            return Err("Property id cannot be empty".to_string());
+        }
+
+        let metadata = match &data.metadata {
+            Value::Object(metadata) => metadata,
+            Value::Null if Self::required_fields(&transaction.transaction_type).is_empty() => return Ok(()),
+            _ => return Err("Metadata must be a JSON object".to_string()),
+        };
+        for field in Self::required_fields(&transaction.transaction_type) {
+            match metadata.get(*field) {
+                Some(Value::String(value)) if !value.trim().is_empty() => {}
+                _ => return Err(format!("Metadata field {:?} is required", field)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Bounds the serialized size of a single transaction. Blocks are also
+/// checked as a whole against the same limit.
+pub struct SizeRule {
+    max_size: usize,
+}
+
+impl SizeRule {
+    pub fn new(max_size: usize) -> Self {
+        Self { max_size }
+    }
+
+    fn size(transaction: &Transaction) -> Result<usize, String> {
+        serde_json::to_vec(transaction)
+            .map(|bytes| bytes.len())
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl TransactionRule for SizeRule {
+    fn name(&self) -> &'static str {
+        "size"
+    }
+
+    fn check(&self, transaction: &Transaction, _context: &SecurityContext) -> Result<(), String> {
+        let size = Self::size(transaction)?;
+        if size > self.max_size {
+            return Err(format!("Transaction is {} bytes, limit is {}", size, self.max_size));
+        }
+        Ok(())
+    }
+}
+
+/// Rejects a transaction id (its nonce) seen within the replay window, and
+/// transactions timestamped outside the window since those can no longer
+/// be told apart from replays.
+///
+/// The id is recorded by `check`, so this rule runs last.
+pub struct ReplayRule {
+    window: Duration,
+    seen: Mutex<HashMap<Uuid, DateTime<Utc>>>,
+}
+
+impl ReplayRule {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn check_at(&self, transaction: &Transaction, now: DateTime<Utc>) -> Result<(), String> {
+        let mut seen = self.seen.lock();
+        seen.retain(|_, timestamp| *timestamp >= now - self.window);
+
+        if transaction.timestamp < now - self.window {
+            return Err("Transaction is older than the replay window".to_string());
+        }
+        if transaction.timestamp > now + Duration::seconds(MAX_CLOCK_SKEW_SECONDS) {
+            return Err("Transaction is timestamped in the future".to_string());
+        }
+        if seen.contains_key(&transaction.id) {
+            return Err(format!("Transaction {} was already submitted", transaction.id));
+        }
+        seen.insert(transaction.id, transaction.timestamp);
+        Ok(())
+    }
+}
+
+impl TransactionRule for ReplayRule {
+    fn name(&self) -> &'static str {
+        "replay"
+    }
+
+    fn check(&self, transaction: &Transaction, _context: &SecurityContext) -> Result<(), String> {
+        self.check_at(transaction, Utc::now())
+    }
+}
+
+/// Validates transactions against the signature, classification, schema,
+/// size and replay rules.
 pub struct ValidationEngineImpl {
-    // Implementation details...
+    signature: SignatureRule,
+    classification: ClassificationRule,
+    schema: SchemaRule,
+    size: SizeRule,
+    replay: ReplayRule,
+    max_block_size: usize,
+}
+
+impl ValidationEngineImpl {
+    pub fn new(config: &ConsensusConfig) -> Self {
+        Self {
+            signature: SignatureRule::default(),
+            classification: ClassificationRule,
+            schema: SchemaRule,
+            size: SizeRule::new(config.max_block_size),
+            replay: ReplayRule::new(Duration::minutes(DEFAULT_REPLAY_WINDOW_MINUTES)),
+            max_block_size: config.max_block_size,
+        }
+    }
+
+    pub fn with_replay_window(mut self, window: Duration) -> Self {
+        self.replay = ReplayRule::new(window);
+        self
+    }
+
+    /// Registers the key `user_id` signs transactions with.
+    pub fn register_signer_key(&self, user_id: i32, key: SignerKey) {
+        self.signature.register_key(user_id, key);
+    }
+
+    /// Rules that do not depend on what has been seen before.
+    fn stateless_rules(&self) -> [&dyn TransactionRule; 4] {
+        [&self.size, &self.schema, &self.classification, &self.signature]
+    }
+
+    fn check_rules<'a>(
+        rules: impl IntoIterator<Item = &'a dyn TransactionRule>,
+        transaction: &Transaction,
+        context: &SecurityContext,
+    ) -> Result<(), String> {
+        for rule in rules {
+            rule.check(transaction, context)
+                .map_err(|reason| format!("{}: {}", rule.name(), reason))?;
+        }
+        Ok(())
+    }
+
+    fn check_block(&self, block: &Block, context: &SecurityContext) -> Result<(), String> {
+        if block.classification > context.classification {
+            return Err(format!(
+                "classification: Block is {} but validator is cleared for {}",
+                block.classification, context.classification
+            ));
+        }
+
+        let mut ids = HashSet::new();
+        let mut total_size = 0;
+        for transaction in &block.transactions {
+            if !ids.insert(transaction.id) {
+                return Err(format!("replay: Transaction {} appears twice in the block", transaction.id));
+            }
+            if transaction.classification > block.classification {
+                return Err(format!(
+                    "classification: Transaction {} is classified above its block",
+                    transaction.id
+                ));
+            }
+            Self::check_rules(self.stateless_rules(), transaction, context)?;
+            total_size += SizeRule::size(transaction)?;
+        }
+
+        if total_size > self.max_block_size {
+            return Err(format!("size: Block is {} bytes, limit is {}", total_size, self.max_block_size));
+        }
+        Ok(())
+    }
+
+    fn result(outcome: Result<(), String>, classification: SecurityClassification) -> ValidationResult {
+        ValidationResult {
+            is_valid: outcome.is_ok(),
+            reason: outcome.err(),
+            classification,
+        }
+    }
 }
 
 #[async_trait]
 impl ValidationEngine for ValidationEngineImpl {
+    /// Checks every transaction with the stateless rules, that no
+    /// transaction appears twice or is classified above the block, and that
+    /// the block fits in `max_block_size`. Replay across blocks is left to
+    /// transaction admission.
     async fn validate_block(
         &self,
         block: &Block,
         context: &SecurityContext,
     ) -> Result<ValidationResult, BlockchainError> {
-        // Implementation here
-        Ok(ValidationResult::Valid)
+        let outcome = self.check_block(block, context);
+
+        Ok(Self::result(outcome, block.classification))
     }
 
     async fn validate_transaction(
@@ -45,7 +417,270 @@ impl ValidationEngine for ValidationEngineImpl {
         transaction: &Transaction,
         context: &SecurityContext,
     ) -> Result<ValidationResult, BlockchainError> {
-        // Implementation here
-        Ok(ValidationResult::Valid)
+        let rules = self
+            .stateless_rules()
+            .into_iter()
+            .chain(std::iter::once(&self.replay as &dyn TransactionRule));
+        let outcome = Self::check_rules(rules, transaction, context);
+
+        Ok(Self::result(outcome, transaction.classification))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::blockchain::types::{BlockHeader, TransactionData};
+    use ed25519_dalek::{Signer, SigningKey};
+    use serde_json::json;
+
+    fn key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    fn context(classification: SecurityClassification) -> SecurityContext {
+        let mut context = SecurityContext::new(1);
+        context.classification = classification;
+        context
+    }
+
+    fn transaction() -> Transaction {
+        Transaction {
+            id: Uuid::new_v4(),
+            transaction_type: TransactionType::PropertyTransfer,
+            data: TransactionData {
+                property_id: "property-1".to_string(),
+                metadata: json!({ "to_custodian": "custodian-2" }),
+                operation: "transfer".to_string(),
+            },
+            timestamp: Utc::now(),
+            signer: 1,
+            signature: None,
+            classification: SecurityClassification::Unclassified,
+        }
+    }
+
+    fn sign(mut transaction: Transaction) -> Transaction {
+        transaction.signature = Some(hex::encode(key().sign(&transaction_digest(&transaction)).to_bytes()));
+        transaction
+    }
+
+    fn engine() -> ValidationEngineImpl {
+        let engine = ValidationEngineImpl::new(&ConsensusConfig::default());
+        engine.register_signer_key(
+            1,
+            SignerKey {
+                algorithm: SignatureAlgorithm::Ed25519,
+                public_key: hex::encode(key().verifying_key().to_bytes()),
+            },
+        );
+        engine
+    }
+
+    fn block(transactions: Vec<Transaction>) -> Block {
+        Block {
+            id: Uuid::new_v4(),
+            header: BlockHeader {
+                version: 1,
+                previous_hash: String::new(),
+                merkle_root: String::new(),
+                timestamp: Utc::now(),
+                difficulty: 1,
+                nonce: 0,
+                wait_certificate: None,
+            },
+            total_transactions: transactions.len() as u64,
+            transactions,
+            block_height: 1,
+            created_at: Utc::now(),
+            confirmed_at: None,
+            classification: SecurityClassification::Unclassified,
+        }
+    }
+
+    #[test]
+    fn test_signature_rule() {
+        let rule = SignatureRule::default();
+        let context = context(SecurityClassification::Unclassified);
+        let signed = sign(transaction());
+        assert!(rule.check(&signed, &context).is_err(), "no key registered");
+
+        rule.register_key(
+            1,
+            SignerKey {
+                algorithm: SignatureAlgorithm::Ed25519,
+                public_key: hex::encode(key().verifying_key().to_bytes()),
+            },
+        );
+        assert!(rule.check(&signed, &context).is_ok());
+        assert!(rule.check(&transaction(), &context).is_err(), "unsigned");
+
+        let mut tampered = signed.clone();
+        tampered.data.metadata = json!({ "to_custodian": "custodian-3" });
+        assert!(rule.check(&tampered, &context).is_err());
+
+        // The signer's key decides, not who relays the transaction
+        let relayed_by_other = SecurityContext::new(2);
+        assert!(rule.check(&signed, &relayed_by_other).is_ok());
+
+        let other = SigningKey::from_bytes(&[8; 32]);
+        rule.register_key(
+            2,
+            SignerKey {
+                algorithm: SignatureAlgorithm::Ed25519,
+                public_key: hex::encode(other.verifying_key().to_bytes()),
+            },
+        );
+        let mut claimed = signed.clone();
+        claimed.signer = 2;
+        assert!(rule.check(&claimed, &context).is_err(), "signature re-attributed to another signer");
+
+        rule.register_key(
+            1,
+            SignerKey {
+                algorithm: SignatureAlgorithm::RSA,
+                public_key: String::new(),
+            },
+        );
+        assert!(rule.check(&signed, &context).is_err(), "unsupported algorithm");
+    }
+
+    #[test]
+    fn test_digest_ignores_metadata_key_order() {
+        let mut first = transaction();
+        first.data.metadata = json!({ "a": 1, "b": { "c": 2, "d": 3 } });
+        let mut second = first.clone();
+        second.data.metadata = json!({ "b": { "d": 3, "c": 2 }, "a": 1 });
+        assert_eq!(transaction_digest(&first), transaction_digest(&second));
+    }
+
+    #[test]
+    fn test_classification_rule() {
+        let mut secret = transaction();
+        secret.classification = SecurityClassification::Secret;
+
+        assert!(ClassificationRule.check(&secret, &context(SecurityClassification::Secret)).is_ok());
+        assert!(ClassificationRule.check(&secret, &context(SecurityClassification::TopSecret)).is_ok());
+        assert!(ClassificationRule
+            .check(&secret, &context(SecurityClassification::Confidential))
+            .is_err());
+    }
+
+    #[test]
+    fn test_schema_rule() {
+        let context = context(SecurityClassification::Unclassified);
+        assert!(SchemaRule.check(&transaction(), &context).is_ok());
+
+        let mut wrong_operation = transaction();
+        wrong_operation.data.operation = "delete".to_string();
+        assert!(SchemaRule.check(&wrong_operation, &context).is_err());
+
+        let mut no_property = transaction();
+        no_property.data.property_id = " ".to_string();
+        assert!(SchemaRule.check(&no_property, &context).is_err());
+
+        let mut missing_field = transaction();
+        missing_field.data.metadata = json!({ "to_custodian": "" });
+        assert!(SchemaRule.check(&missing_field, &context).is_err());
+
+        let mut not_object = transaction();
+        not_object.data.metadata = json!(["custodian-2"]);
+        assert!(SchemaRule.check(&not_object, &context).is_err());
+
+        let mut sync = transaction();
+        sync.transaction_type = TransactionType::NetworkSync;
+        sync.data = TransactionData {
+            property_id: String::new(),
+            metadata: Value::Null,
+            operation: "sync".to_string(),
+        };
+        assert!(SchemaRule.check(&sync, &context).is_ok());
+    }
+
+    #[test]
+    fn test_size_rule() {
+        let context = context(SecurityClassification::Unclassified);
+        let transaction = transaction();
+        let size = SizeRule::size(&transaction).unwrap();
+
+        assert!(SizeRule::new(size).check(&transaction, &context).is_ok());
+        assert!(SizeRule::new(size - 1).check(&transaction, &context).is_err());
+    }
+
+    #[test]
+    fn test_replay_rule() {
+        let rule = ReplayRule::new(Duration::minutes(10));
+        let now = Utc::now();
+        let transaction = transaction();
+
+        assert!(rule.check_at(&transaction, now).is_ok());
+        assert!(rule.check_at(&transaction, now).is_err(), "duplicate nonce");
+
+        let mut stale = self::transaction();
+        stale.timestamp = now - Duration::minutes(11);
+        assert!(rule.check_at(&stale, now).is_err());
+
+        let mut future = self::transaction();
+        future.timestamp = now + Duration::minutes(10);
+        assert!(rule.check_at(&future, now).is_err());
+
+        // Forgotten ids are pruned once they leave the window
+        assert!(rule.check_at(&self::transaction(), now + Duration::minutes(11)).is_err());
+        assert!(rule.seen.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_validate_transaction_applies_all_rules() {
+        let engine = engine();
+        let context = context(SecurityClassification::Unclassified);
+        let signed = sign(transaction());
+
+        let result = engine.validate_transaction(&signed, &context).await.unwrap();
+        assert!(result.is_valid, "{:?}", result.reason);
+
+        let replayed = engine.validate_transaction(&signed, &context).await.unwrap();
+        assert!(!replayed.is_valid);
+        assert!(replayed.reason.unwrap().starts_with("replay:"));
+
+        let unsigned = engine.validate_transaction(&transaction(), &context).await.unwrap();
+        assert!(unsigned.reason.unwrap().starts_with("signature:"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_block() {
+        let engine = engine();
+        let context = context(SecurityClassification::Unclassified);
+        let first = sign(transaction());
+        let second = sign(transaction());
+
+        let result = engine
+            .validate_block(&block(vec![first.clone(), second.clone()]), &context)
+            .await
+            .unwrap();
+        assert!(result.is_valid, "{:?}", result.reason);
+
+        // Block validation does not consume nonces
+        assert!(engine.validate_transaction(&first, &context).await.unwrap().is_valid);
+
+        let duplicate = engine
+            .validate_block(&block(vec![first.clone(), first.clone()]), &context)
+            .await
+            .unwrap();
+        assert!(duplicate.reason.unwrap().starts_with("replay:"));
+
+        let config = ConsensusConfig {
+            max_block_size: SizeRule::size(&first).unwrap() + 1,
+            ..ConsensusConfig::default()
+        };
+        let small = ValidationEngineImpl::new(&config);
+        small.register_signer_key(
+            1,
+            SignerKey {
+                algorithm: SignatureAlgorithm::Ed25519,
+                public_key: hex::encode(key().verifying_key().to_bytes()),
+            },
+        );
+        let too_big = small.validate_block(&block(vec![first, second]), &context).await.unwrap();
+        assert!(too_big.reason.unwrap().starts_with("size:"));
     }
 }
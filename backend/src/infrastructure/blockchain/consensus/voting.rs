@@ -0,0 +1,178 @@
+use crate::error::blockchain::BlockchainError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// PBFT tolerates `f` faulty members out of `3f + 1`.
+pub const MIN_PBFT_MEMBERS: usize = 4;
+
+/// Voting consensus for a small unit network of known validators.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PbftConfig {
+    /// Hex compressed secp256k1 public keys of the validators.
+    pub members: Vec<String>,
+    pub block_publishing_delay: Duration,
+    pub idle_timeout: Duration,
+    pub commit_timeout: Duration,
+}
+
+impl PbftConfig {
+    pub fn new(members: Vec<String>) -> Self {
+        Self {
+            members,
+            block_publishing_delay: Duration::from_secs(1),
+            idle_timeout: Duration::from_secs(30),
+            commit_timeout: Duration::from_secs(10),
+        }
+    }
+
+    pub fn settings(&self) -> Result<Vec<(String, String)>, BlockchainError> {
+        if self.members.len() < MIN_PBFT_MEMBERS {
+            return Err(BlockchainError::ValidationError(format!(
+                "PBFT needs at least {} members, got {}",
+                MIN_PBFT_MEMBERS,
+                self.members.len()
+            )));
+        }
+        check_validator_keys(&self.members)?;
+        if self.commit_timeout >= self.idle_timeout {
+            return Err(BlockchainError::ValidationError(
+                "PBFT commit timeout must be shorter than the idle timeout".to_string(),
+            ));
+        }
+
+        let members = serde_json::to_string(&self.members)
+            .map_err(|e| BlockchainError::SerializationError(e.to_string()))?;
+        Ok(vec![
+            ("sawtooth.consensus.pbft.members".to_string(), members),
+            millis("sawtooth.consensus.pbft.block_publishing_delay", self.block_publishing_delay),
+            millis("sawtooth.consensus.pbft.idle_timeout", self.idle_timeout),
+            millis("sawtooth.consensus.pbft.commit_timeout", self.commit_timeout),
+        ])
+    }
+}
+
+/// Leader-based consensus for unit networks that only need crash fault
+/// tolerance.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RaftConfig {
+    /// Hex compressed secp256k1 public keys of the validators.
+    pub peers: Vec<String>,
+    /// Time between blocks published by the leader.
+    pub period: Duration,
+    pub heartbeat_tick: u32,
+    pub election_tick: u32,
+}
+
+impl RaftConfig {
+    pub fn new(peers: Vec<String>) -> Self {
+        Self {
+            peers,
+            period: Duration::from_secs(3),
+            heartbeat_tick: 2,
+            election_tick: 20,
+        }
+    }
+
+    pub fn settings(&self) -> Result<Vec<(String, String)>, BlockchainError> {
+        if self.peers.is_empty() {
+            return Err(BlockchainError::ValidationError(
+                "Raft needs at least one peer".to_string(),
+            ));
+        }
+        check_validator_keys(&self.peers)?;
+        if self.election_tick <= self.heartbeat_tick {
+            return Err(BlockchainError::ValidationError(
+                "Raft election tick must exceed the heartbeat tick".to_string(),
+            ));
+        }
+
+        let peers = serde_json::to_string(&self.peers)
+            .map_err(|e| BlockchainError::SerializationError(e.to_string()))?;
+        Ok(vec![
+            ("sawtooth.consensus.raft.peers".to_string(), peers),
+            millis("sawtooth.consensus.raft.period", self.period),
+            (
+                "sawtooth.consensus.raft.heartbeat_tick".to_string(),
+                self.heartbeat_tick.to_string(),
+            ),
+            (
+                "sawtooth.consensus.raft.election_tick".to_string(),
+                self.election_tick.to_string(),
+            ),
+        ])
+    }
+}
+
+fn check_validator_keys(keys: &[String]) -> Result<(), BlockchainError> {
+    let mut seen = HashSet::new();
+    for key in keys {
+        let valid = key.len() == 66
+            && (key.starts_with("02") || key.starts_with("03"))
+            && key.chars().all(|c| c.is_ascii_hexdigit());
+        if !valid {
+            return Err(BlockchainError::ValidationError(format!(
+                "Invalid validator public key: {}",
+                key
+            )));
+        }
+        if !seen.insert(key.to_ascii_lowercase()) {
+            return Err(BlockchainError::ValidationError(format!(
+                "Duplicate validator public key: {}",
+                key
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn millis(key: &str, duration: Duration) -> (String, String) {
+    (key.to_string(), duration.as_millis().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(n: u8) -> String {
+        format!("02{}", hex::encode([n; 32]))
+    }
+
+    #[test]
+    fn test_pbft_settings() {
+        let config = PbftConfig::new((1..=4).map(member).collect());
+        let settings = config.settings().unwrap();
+        assert_eq!(settings[0].0, "sawtooth.consensus.pbft.members");
+        assert!(settings[0].1.contains(&member(4)));
+        assert!(settings
+            .iter()
+            .any(|(key, value)| key == "sawtooth.consensus.pbft.idle_timeout" && value == "30000"));
+    }
+
+    #[test]
+    fn test_pbft_rejects_small_or_duplicate_membership() {
+        assert!(PbftConfig::new((1..=3).map(member).collect()).settings().is_err());
+        assert!(PbftConfig::new(vec![member(1), member(2), member(3), member(1)])
+            .settings()
+            .is_err());
+        assert!(PbftConfig::new(vec![member(1), member(2), member(3), "02zz".to_string()])
+            .settings()
+            .is_err());
+    }
+
+    #[test]
+    fn test_raft_settings() {
+        assert!(RaftConfig::new(Vec::new()).settings().is_err());
+
+        let settings = RaftConfig::new(vec![member(1)]).settings().unwrap();
+        assert!(settings
+            .iter()
+            .any(|(key, value)| key == "sawtooth.consensus.raft.period" && value == "3000"));
+
+        let config = RaftConfig {
+            election_tick: 2,
+            ..RaftConfig::new(vec![member(1)])
+        };
+        assert!(config.settings().is_err());
+    }
+}
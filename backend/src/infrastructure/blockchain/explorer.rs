@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use serde::{Deserialize, Serialize};
+
+use crate::error::blockchain::BlockchainError;
+use super::sawtooth::{
+    state::{PropertyState, TransferRecord},
+    HandReceiptPayload, SawtoothClient, FAMILY_NAME,
+};
+
+/// Blocks per page when the caller does not ask for a size.
+pub const DEFAULT_BLOCK_PAGE_SIZE: usize = 20;
+/// Largest page the explorer will request from the REST API.
+pub const MAX_BLOCK_PAGE_SIZE: usize = 100;
+
+/// A block with its handreceipt transactions decoded.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplorerBlock {
+    pub block_id: String,
+    pub block_num: u64,
+    pub previous_block_id: String,
+    pub state_root_hash: String,
+    pub signer_public_key: String,
+    pub batch_ids: Vec<String>,
+    /// Transactions of every family in the block.
+    pub transaction_count: usize,
+    /// Only transactions in the handreceipt namespace.
+    pub transactions: Vec<ExplorerTransaction>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplorerTransaction {
+    pub transaction_id: String,
+    /// Known when the transaction was read as part of a block.
+    pub batch_id: Option<String>,
+    pub block_id: Option<String>,
+    pub family_name: String,
+    pub family_version: String,
+    pub signer_public_key: String,
+    pub dependencies: Vec<String>,
+    /// `None` if the payload is not a handreceipt payload or failed to decode.
+    pub payload: Option<HandReceiptPayload>,
+    pub decode_error: Option<String>,
+    /// Custody record a transfer produced, looked up in current state.
+    pub transfer: Option<TransferRecord>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockPage {
+    pub blocks: Vec<ExplorerBlock>,
+    pub head: Option<String>,
+    /// Block id to pass as `start` for the next (older) page.
+    pub next: Option<String>,
+    pub limit: usize,
+}
+
+// Sawtooth REST API shapes
+
+#[derive(Debug, Deserialize)]
+struct RestTransactionHeader {
+    family_name: String,
+    family_version: String,
+    signer_public_key: String,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestTransaction {
+    header: RestTransactionHeader,
+    header_signature: String,
+    payload: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestBatch {
+    header_signature: String,
+    #[serde(default)]
+    transactions: Vec<RestTransaction>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestBlockHeader {
+    /// uint64 fields are rendered as strings
+    block_num: String,
+    previous_block_id: String,
+    state_root_hash: String,
+    #[serde(default)]
+    signer_public_key: String,
+    #[serde(default)]
+    batch_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestBlock {
+    header: RestBlockHeader,
+    header_signature: String,
+    #[serde(default)]
+    batches: Vec<RestBatch>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RestPaging {
+    #[serde(default)]
+    next_position: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestBlockPage {
+    data: Vec<RestBlock>,
+    #[serde(default)]
+    head: Option<String>,
+    #[serde(default)]
+    paging: RestPaging,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestData<T> {
+    data: T,
+}
+
+fn parse<T: serde::de::DeserializeOwned>(value: serde_json::Value) -> Result<T, BlockchainError> {
+    serde_json::from_value(value).map_err(|e| BlockchainError::SerializationError(e.to_string()))
+}
+
+/// Read-only view of the ledger for auditors, decoding Sawtooth REST data
+/// into handreceipt types.
+pub struct LedgerExplorer {
+    client: Arc<SawtoothClient>,
+}
+
+impl LedgerExplorer {
+    pub fn new(client: Arc<SawtoothClient>) -> Self {
+        Self { client }
+    }
+
+    /// Newest-first page of blocks starting at `start`, or at the head.
+    /// Returns `None` if `start` is not a known block.
+    pub async fn list_blocks(&self, start: Option<&str>, limit: usize) -> Result<Option<BlockPage>, BlockchainError> {
+        let limit = limit.clamp(1, MAX_BLOCK_PAGE_SIZE);
+        let page: RestBlockPage = match self.client.get_blocks(start, limit).await? {
+            Some(body) => parse(body)?,
+            None => return Ok(None),
+        };
+
+        let mut blocks = page
+            .data
+            .into_iter()
+            .map(decode_block)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.attach_transfers(blocks.iter_mut().flat_map(|block| block.transactions.iter_mut()))
+            .await?;
+        Ok(Some(BlockPage {
+            blocks,
+            head: page.head,
+            next: page.paging.next_position,
+            limit,
+        }))
+    }
+
+    pub async fn get_block(&self, block_id: &str) -> Result<Option<ExplorerBlock>, BlockchainError> {
+        match self.client.get_block(block_id).await? {
+            Some(body) => {
+                let block: RestData<RestBlock> = parse(body)?;
+                let mut block = decode_block(block.data)?;
+                self.attach_transfers(block.transactions.iter_mut()).await?;
+                Ok(Some(block))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_transaction(&self, transaction_id: &str) -> Result<Option<ExplorerTransaction>, BlockchainError> {
+        match self.client.get_transaction(transaction_id).await? {
+            Some(body) => {
+                let transaction: RestData<RestTransaction> = parse(body)?;
+                let mut transaction = decode_transaction(transaction.data, None, None);
+                self.attach_transfers(std::iter::once(&mut transaction)).await?;
+                Ok(Some(transaction))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_property_state(&self, property_id: &str) -> Result<Option<PropertyState>, BlockchainError> {
+        self.client.get_property_state(property_id).await
+    }
+
+    /// Fills in the custody record of each transfer, reading every
+    /// property's state once however many of its transfers are listed.
+    async fn attach_transfers<'a>(
+        &self,
+        transactions: impl Iterator<Item = &'a mut ExplorerTransaction>,
+    ) -> Result<(), BlockchainError> {
+        let mut records: HashMap<String, HashMap<uuid::Uuid, TransferRecord>> = HashMap::new();
+        for transaction in transactions {
+            let (property_id, transfer_id) = match &transaction.payload {
+                Some(HandReceiptPayload::Transfer { property_id, transfer_id, .. }) => {
+                    match uuid::Uuid::parse_str(transfer_id) {
+                        Ok(transfer_id) => (property_id, transfer_id),
+                        Err(_) => continue,
+                    }
+                }
+                _ => continue,
+            };
+
+            if !records.contains_key(property_id) {
+                let history = self
+                    .client
+                    .get_property_state(property_id)
+                    .await?
+                    .map(|state| {
+                        state
+                            .transfer_history
+                            .into_iter()
+                            .map(|record| (record.transfer_id, record))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                records.insert(property_id.clone(), history);
+            }
+            transaction.transfer = records[property_id].get(&transfer_id).cloned();
+        }
+        Ok(())
+    }
+}
+
+/// Decodes a block's handreceipt transactions, leaving their custody
+/// records to `attach_transfers`.
+fn decode_block(block: RestBlock) -> Result<ExplorerBlock, BlockchainError> {
+    let block_num = block.header.block_num.parse().map_err(|_| {
+        BlockchainError::SerializationError(format!("Invalid block number {}", block.header.block_num))
+    })?;
+
+    let mut transaction_count = 0;
+    let mut transactions = Vec::new();
+    for batch in block.batches {
+        transaction_count += batch.transactions.len();
+        for transaction in batch.transactions {
+            if transaction.header.family_name != FAMILY_NAME {
+                continue;
+            }
+            transactions.push(decode_transaction(
+                transaction,
+                Some(batch.header_signature.clone()),
+                Some(block.header_signature.clone()),
+            ));
+        }
+    }
+
+    Ok(ExplorerBlock {
+        block_id: block.header_signature,
+        block_num,
+        previous_block_id: block.header.previous_block_id,
+        state_root_hash: block.header.state_root_hash,
+        signer_public_key: block.header.signer_public_key,
+        batch_ids: block.header.batch_ids,
+        transaction_count,
+        transactions,
+    })
+}
+
+fn decode_transaction(
+    transaction: RestTransaction,
+    batch_id: Option<String>,
+    block_id: Option<String>,
+) -> ExplorerTransaction {
+    let (payload, decode_error) = if transaction.header.family_name == FAMILY_NAME {
+        let decoded = BASE64.decode(&transaction.payload)
+            .map_err(|e| BlockchainError::SerializationError(e.to_string()))
+            .and_then(|bytes| HandReceiptPayload::decode(&transaction.header.family_version, &bytes));
+        match decoded {
+            Ok(payload) => (Some(payload), None),
+            Err(e) => (None, Some(e.to_string())),
+        }
+    } else {
+        (None, None)
+    };
+
+    ExplorerTransaction {
+        transaction_id: transaction.header_signature,
+        batch_id,
+        block_id,
+        family_name: transaction.header.family_name,
+        family_version: transaction.header.family_version,
+        signer_public_key: transaction.header.signer_public_key,
+        dependencies: transaction.header.dependencies,
+        payload,
+        decode_error,
+        transfer: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::blockchain::{
+        sawtooth::{client::BATCH_STATUS_TIMEOUT, state::PropertyMetadata, EmbeddedLedgerService},
+        BlockchainConfig,
+    };
+    use ed25519_dalek::SigningKey;
+    use handreceipt_protocol::signing::{transfer_digest, TransferRole, TransferSignature};
+
+    async fn ledger() -> (EmbeddedLedgerService, LedgerExplorer) {
+        let service = EmbeddedLedgerService::start(BlockchainConfig::default()).await.unwrap();
+        let explorer = LedgerExplorer::new(service.sawtooth().client());
+        (service, explorer)
+    }
+
+    fn metadata() -> PropertyMetadata {
+        PropertyMetadata {
+            name: "M4 Carbine".to_string(),
+            description: "Rifle".to_string(),
+            category: "Weapon".to_string(),
+            serial_number: Some("W123".to_string()),
+            is_sensitive_item: true,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pages_through_blocks() {
+        let (service, explorer) = ledger().await;
+        let client = service.sawtooth().client();
+        for n in 0..3 {
+            let batch_id = client
+                .create_property(format!("property-{}", n), "custodian-1".to_string(), metadata())
+                .await
+                .unwrap();
+            client.wait_for_batch(&batch_id, BATCH_STATUS_TIMEOUT).await.unwrap();
+        }
+
+        let first = explorer.list_blocks(None, 2).await.unwrap().unwrap();
+        assert_eq!(first.blocks.len(), 2);
+        assert_eq!(first.blocks[0].block_num, 2);
+        assert_eq!(first.head.as_deref(), Some(first.blocks[0].block_id.as_str()));
+        assert!(matches!(
+            first.blocks[0].transactions[0].payload,
+            Some(HandReceiptPayload::Create { .. })
+        ));
+
+        let second = explorer.list_blocks(first.next.as_deref(), 2).await.unwrap().unwrap();
+        assert_eq!(second.blocks.len(), 1);
+        assert_eq!(second.blocks[0].block_num, 0);
+        assert!(second.next.is_none());
+
+        let block = explorer.get_block(&second.blocks[0].block_id).await.unwrap().unwrap();
+        assert_eq!(block.transaction_count, 1);
+        assert!(explorer.get_block("missing").await.unwrap().is_none());
+        assert!(explorer.list_blocks(Some("missing"), 2).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_decodes_transfer_into_custody_record() {
+        let (service, explorer) = ledger().await;
+        let client = service.sawtooth().client();
+        let releasing = SigningKey::from_bytes(&[1; 32]);
+        let receiving = SigningKey::from_bytes(&[2; 32]);
+
+        let receipt = client
+            .submit_batch(vec![HandReceiptPayload::Create {
+                property_id: "property-1".to_string(),
+                initial_custodian: "custodian-1".to_string(),
+                metadata: PropertyMetadata {
+                    is_sensitive_item: false,
+                    ..metadata()
+                },
+                custodian_key: Some(hex::encode(releasing.verifying_key().to_bytes())),
+            }])
+            .await
+            .unwrap();
+        client.wait_for_batch(&receipt.batch_id, BATCH_STATUS_TIMEOUT).await.unwrap();
+
+        let transfer_id = uuid::Uuid::new_v4();
+        let digest = transfer_digest("property-1", "custodian-1", "custodian-2", &transfer_id.to_string(), "");
+        let receipt = client
+            .submit_batch(vec![HandReceiptPayload::Transfer {
+                property_id: "property-1".to_string(),
+                to_custodian: "custodian-2".to_string(),
+                transfer_id: transfer_id.to_string(),
+                signatures: vec![
+                    TransferSignature::sign(TransferRole::Releasing, &releasing, &digest),
+                    TransferSignature::sign(TransferRole::Receiving, &receiving, &digest),
+                ],
+                timestamp: chrono::Utc::now().timestamp_millis(),
+                annex_digest: String::new(),
+            }])
+            .await
+            .unwrap();
+        client.wait_for_batch(&receipt.batch_id, BATCH_STATUS_TIMEOUT).await.unwrap();
+
+        let transaction = explorer
+            .get_transaction(&receipt.transaction_ids[0])
+            .await
+            .unwrap()
+            .unwrap();
+        let record = transaction.transfer.unwrap();
+        assert_eq!(record.transfer_id, transfer_id);
+        assert_eq!(record.from_custodian, "custodian-1");
+        assert_eq!(record.to_custodian, "custodian-2");
+
+        let state = explorer.get_property_state("property-1").await.unwrap().unwrap();
+        assert_eq!(state.custodian, "custodian-2");
+        assert!(explorer.get_transaction("missing").await.unwrap().is_none());
+    }
+}
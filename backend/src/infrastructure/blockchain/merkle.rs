@@ -1,17 +1,12 @@
+use handreceipt_protocol::merkle;
 use sha2::{Sha256, Digest};
-use std::collections::HashMap;
-use uuid::Uuid;
 
 use crate::{
     error::blockchain::BlockchainError,
     types::blockchain::BlockchainTransaction,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum Side {
-    Left,
-    Right,
-}
+pub use handreceipt_protocol::merkle::{MerkleProof, Side};
 
 /// Represents a node in the Merkle tree
 #[derive(Debug, Clone)]
@@ -57,14 +52,6 @@ pub struct MerkleTree {
     root: Option<MerkleNode>,
 }
 
-/// Represents a proof of inclusion in the Merkle tree
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct MerkleProof {
-    pub leaf_hash: String,
-    pub sibling_hashes: Vec<(String, Side)>,
-    pub root_hash: String,
-}
-
 impl MerkleTree {
     /// Creates a new Merkle tree from a list of transactions
     pub fn new(transactions: &[BlockchainTransaction]) -> Result<Self, BlockchainError> {
@@ -88,49 +75,50 @@ impl MerkleTree {
         self.root.as_ref().map(|node| node.hash.as_str())
     }
 
+    /// Builds a tree over the transaction ids of a Sawtooth batch, in batch order.
+    pub fn from_transaction_ids(transaction_ids: &[String]) -> Self {
+        let leaves: Vec<String> = transaction_ids
+            .iter()
+            .map(|id| merkle::hash_leaf(id.as_bytes()))
+            .collect();
+
+        let tree_levels = merkle::tree_levels(leaves.clone());
+        let root = tree_levels
+            .last()
+            .and_then(|level| level.first())
+            .cloned()
+            .map(MerkleNode::new);
+
+        Self {
+            transactions: Vec::new(),
+            cached_hashes: leaves,
+            tree_levels,
+            root,
+        }
+    }
+
     /// Generates a proof of inclusion for a transaction
     pub fn generate_proof(&self, transaction: &BlockchainTransaction) -> Result<MerkleProof, BlockchainError> {
-        let tx_hash = Self::hash_transaction(transaction)?;
-        let mut current_hash = tx_hash.clone();
-        let mut sibling_hashes = Vec::new();
-        
-        for level in &self.tree_levels {
-            let pos = level.iter().position(|h| h == &current_hash)
-                .ok_or_else(|| BlockchainError::ValidationError("Transaction not found in tree".into()))?;
-            
-            if pos % 2 == 0 {
-                if let Some(sibling) = level.get(pos + 1) {
-                    sibling_hashes.push((sibling.clone(), Side::Right));
-                    current_hash = Self::hash_pair(&current_hash, sibling);
-                } else {
-                    current_hash = Self::hash_pair(&current_hash, &current_hash);
-                }
-            } else {
-                let sibling = &level[pos - 1];
-                sibling_hashes.push((sibling.clone(), Side::Left));
-                current_hash = Self::hash_pair(sibling, &current_hash);
-            }
-        }
-        
-        Ok(MerkleProof {
-            leaf_hash: tx_hash,
-            sibling_hashes,
-            root_hash: self.get_root().unwrap_or_default().to_string(),
-        })
+        self.generate_proof_for_leaf(&Self::hash_transaction(transaction)?)
+    }
+
+    /// Generates a proof of inclusion for a transaction id added with
+    /// `from_transaction_ids`
+    pub fn generate_proof_for_id(&self, transaction_id: &str) -> Result<MerkleProof, BlockchainError> {
+        self.generate_proof_for_leaf(&merkle::hash_leaf(transaction_id.as_bytes()))
+    }
+
+    fn generate_proof_for_leaf(&self, leaf_hash: &str) -> Result<MerkleProof, BlockchainError> {
+        let index = self.cached_hashes.iter().position(|h| h == leaf_hash)
+            .ok_or_else(|| BlockchainError::ValidationError("Transaction not found in tree".into()))?;
+
+        merkle::prove(&self.tree_levels, index)
+            .ok_or_else(|| BlockchainError::ValidationError("Transaction not found in tree".into()))
     }
 
-    /// Verifies a Merkle proof
+    /// Verifies a Merkle proof against this tree's root
     pub fn verify_proof(&self, proof: &MerkleProof) -> Result<bool, BlockchainError> {
-        let mut current_hash = proof.leaf_hash.clone();
-        
-        for (sibling_hash, side) in &proof.sibling_hashes {
-            current_hash = match side {
-                Side::Left => Self::hash_pair(sibling_hash, &current_hash),
-                Side::Right => Self::hash_pair(&current_hash, sibling_hash),
-            };
-        }
-        
-        Ok(current_hash == proof.root_hash)
+        Ok(proof.verify() && self.get_root() == Some(proof.root_hash.as_str()))
     }
 
     /// Hashes a transaction
@@ -145,10 +133,7 @@ impl MerkleTree {
 
     /// Hashes two child hashes together
     fn hash_pair(left: &str, right: &str) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(left.as_bytes());
-        hasher.update(right.as_bytes());
-        format!("{:x}", hasher.finalize())
+        merkle::hash_pair(left, right)
     }
 
     fn build(&mut self) -> Result<(), BlockchainError> {
@@ -196,6 +181,8 @@ impl MerkleTree {
 mod tests {
     use super::*;
     use chrono::Utc;
+    use std::collections::HashMap;
+    use uuid::Uuid;
     use crate::types::blockchain::{TransactionType, TransactionData, TransactionMetadata, TransactionStatus};
 
     fn create_test_transaction(id: u32) -> BlockchainTransaction {
@@ -266,7 +253,7 @@ mod tests {
 
         let tree = MerkleTree::new(&transactions).unwrap();
         let mut proof = tree.generate_proof(&transactions[0]).unwrap();
-        proof.sibling_hashes.reverse();
+        proof.sibling_hashes[0].1 = Side::Left;
         
         assert!(!tree.verify_proof(&proof).unwrap());
     }
@@ -288,4 +275,28 @@ mod tests {
         let proof = tree.generate_proof(&transactions[0]).unwrap();
         assert!(tree.verify_proof(&proof).unwrap());
     }
+
+    #[test]
+    fn test_last_transaction_of_odd_tree() {
+        let transactions = vec![
+            create_test_transaction(1),
+            create_test_transaction(2),
+            create_test_transaction(3),
+        ];
+
+        let tree = MerkleTree::new(&transactions).unwrap();
+        let proof = tree.generate_proof(&transactions[2]).unwrap();
+        assert!(tree.verify_proof(&proof).unwrap());
+    }
+
+    #[test]
+    fn test_batch_transaction_ids() {
+        let ids: Vec<String> = (0..5).map(|i| format!("txn-{}", i)).collect();
+        let tree = MerkleTree::from_transaction_ids(&ids);
+
+        let proof = tree.generate_proof_for_id("txn-4").unwrap();
+        assert!(tree.verify_proof(&proof).unwrap());
+        assert!(proof.verify_leaf(b"txn-4"));
+        assert!(tree.generate_proof_for_id("txn-9").is_err());
+    }
 }
@@ -1,10 +1,16 @@
 pub mod authority;
+pub mod certificate;
+pub mod certificate_authority;
 pub mod verification;
 pub mod merkle;
 pub mod consensus;
+pub mod explorer;
 pub mod types;
 pub mod sawtooth;
-pub mod poet;
+pub mod outbox;
+pub mod proof;
+pub mod reconciliation;
+pub mod signatures;
 
 pub use authority::{AuthorityNode, MilitaryCertificate, PropertyTransfer, TransferSignature, SignerRole};
 pub use verification::{BlockchainVerification, TransferVerification, VerificationResult, TransactionBatch};
@@ -18,8 +24,7 @@ pub use sawtooth::{
 
 use crate::error::blockchain::BlockchainError;
 use crate::types::security::SecurityContext;
-use crate::infrastructure::blockchain::consensus::poet::{PoETConfig, PoETConsensus};
-use std::time::Duration;
+use ed25519_dalek::SigningKey;
 
 /// Trait for blockchain operations
 #[async_trait::async_trait]
@@ -55,16 +60,33 @@ pub struct BlockchainConfig {
     pub validator_url: String,
     pub validator_private_key: String,
     pub consensus: ConsensusEngine,
+    /// Hex Ed25519 seed signing PoET wait certificates.
+    pub consensus_key: String,
+}
+
+impl BlockchainConfig {
+    /// Parses `node_id`, the validator id consensus certificates carry.
+    pub fn validator_id(&self) -> Result<uuid::Uuid, BlockchainError> {
+        uuid::Uuid::parse_str(&self.node_id).map_err(|e| {
+            BlockchainError::ValidationError(format!("Invalid node id {}: {}", self.node_id, e))
+        })
+    }
+
+    /// Parses `consensus_key`, or `None` if it is unset.
+    pub fn consensus_signing_key(&self) -> Result<Option<SigningKey>, BlockchainError> {
+        if self.consensus_key.is_empty() {
+            return Ok(None);
+        }
+        let seed: [u8; 32] = hex::decode(&self.consensus_key)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| BlockchainError::ValidationError("Invalid consensus key".to_string()))?;
+        Ok(Some(SigningKey::from_bytes(&seed)))
+    }
 }
 
 impl Default for BlockchainConfig {
     fn default() -> Self {
-        let poet_config = PoETConfig {
-            initial_wait_time: Duration::from_secs(10),
-            target_wait_time: Duration::from_secs(20),
-            wait_time_fluctuation: 0.1, // 10% fluctuation
-        };
-
         Self {
             node_id: uuid::Uuid::new_v4().to_string(),
             is_authority: false,
@@ -74,44 +96,28 @@ impl Default for BlockchainConfig {
             max_validators: 10,
             validator_url: "tcp://localhost:4004".to_string(), // Default Sawtooth URL
             validator_private_key: String::new(), // Should be provided
-            consensus: ConsensusEngine::PoET(poet_config),
+            consensus: ConsensusEngine::Sawtooth,
+            consensus_key: String::new(),
         }
     }
 }
 
-/// Creates a new blockchain service instance
+/// Creates a new blockchain service instance for the configured
+/// consensus engine
 pub fn create_blockchain_service(
     config: BlockchainConfig,
 ) -> Result<Box<dyn BlockchainService>, BlockchainError> {
     use sawtooth::service::SawtoothService;
 
-    match config.consensus {
-        ConsensusEngine::Sawtooth => {
-            let validator_url = config.validator_url.clone();
-            let validator_key = config.validator_private_key.clone();
-
-            let service = SawtoothService::new(
-                validator_url,
-                validator_key,
-                config.clone(), // Pass a clone of the config
-            )?;
-
-            Ok(Box::new(service))
-        },
-        ConsensusEngine::PoET(poet_config) => {
-            // Placeholder for PoET service.  For now, return Sawtooth.
-            let validator_url = config.validator_url.clone();
-            let validator_key = config.validator_private_key.clone();
-
-            let service = SawtoothService::new(
-                validator_url,
-                validator_key,
-                config.clone(), // Pass a clone of the config
-            )?;
-
-            Ok(Box::new(service))
-        }
-    }
+    // Reject unusable engine configurations before connecting
+    config.consensus.settings()?;
+
+    let service = SawtoothService::new(
+        config.validator_url.clone(),
+        config.validator_private_key.clone(),
+        config,
+    )?;
+    Ok(Box::new(service))
 }
 
 #[cfg(test)]
@@ -125,11 +131,13 @@ mod tests {
         let tree = MerkleTree::new(&[]).unwrap();
         assert!(tree.get_root().is_none());
 
+        // Nothing proves against an empty tree
         let proof = MerkleProof {
-            root: "test".to_string(),
-            proof: vec!["test".to_string()],
+            leaf_hash: "test".to_string(),
+            sibling_hashes: Vec::new(),
+            root_hash: "test".to_string(),
         };
-        assert!(tree.verify_proof(&proof).unwrap());
+        assert!(!tree.verify_proof(&proof).unwrap());
     }
 
     #[test]
@@ -137,6 +145,35 @@ mod tests {
         let config = BlockchainConfig::default();
         assert!(!config.is_authority);
         assert_eq!(config.batch_size, 100);
+        assert!(config.validator_id().is_ok());
+
+        let config = BlockchainConfig {
+            node_id: "node-1".to_string(),
+            ..BlockchainConfig::default()
+        };
+        assert!(config.validator_id().is_err());
         assert_eq!(config.batch_timeout, std::time::Duration::from_secs(30));
+        assert!(matches!(config.consensus, ConsensusEngine::Sawtooth));
+    }
+
+    #[test]
+    fn test_consensus_signing_key() {
+        let mut config = BlockchainConfig::default();
+        assert!(config.consensus_signing_key().unwrap().is_none());
+
+        config.consensus_key = hex::encode([3u8; 32]);
+        assert!(config.consensus_signing_key().unwrap().is_some());
+
+        config.consensus_key = "abc".to_string();
+        assert!(config.consensus_signing_key().is_err());
+    }
+
+    #[test]
+    fn test_property_address_matches_mobile() {
+        // Same golden vector the mobile processor is pinned to
+        assert_eq!(
+            sawtooth::PropertyState::get_address("property-1"),
+            "a0779e00e0ddb57859914a15b77c4e69b6a6ce7ae62ca16a140d1b7924d80edcd85713"
+        );
     }
 }
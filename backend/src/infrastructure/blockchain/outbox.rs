@@ -0,0 +1,410 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use handreceipt_protocol::signing::TransferSignature;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        outbox::{entity::OutboxEntry, repository::OutboxRepository},
+        property::{entity::Property, repository::PropertyRepository},
+        transfer::entity::Transfer,
+    },
+    error::{blockchain::BlockchainError, CoreError, RepositoryError},
+    infrastructure::blockchain::sawtooth::{
+        batcher::{QueuedReceipt, TransactionBatcher},
+        client::BatchStatus,
+        handler::PROPERTY_NOT_FOUND,
+        service::sync_status,
+        state::PropertyMetadata,
+        HandReceiptPayload, SawtoothClient,
+    },
+};
+
+pub const AGGREGATE_PROPERTY: &str = "property";
+pub const AGGREGATE_TRANSFER: &str = "transfer";
+
+/// Ledger metadata for a property row.
+pub fn ledger_metadata(property: &Property) -> PropertyMetadata {
+    PropertyMetadata {
+        name: property.name.clone(),
+        description: property.description.clone(),
+        category: property.category.to_string(),
+        serial_number: property.serial_number.clone(),
+        is_sensitive_item: property.is_sensitive,
+        created_at: property.created_at,
+    }
+}
+
+/// Nonce of a property's Create entry. There is only ever one, so a
+/// resubmission cannot queue a second Create beside a pending one.
+pub fn create_nonce(property_id: i32) -> String {
+    format!("property-{}-create", property_id)
+}
+
+/// `custodian_key` is the holder's registered key. Without one custody
+/// starts unbound and the first transfer needs an approver.
+pub fn create_payload(property: &Property, custodian_key: Option<String>) -> HandReceiptPayload {
+    HandReceiptPayload::Create {
+        property_id: property.id.to_string(),
+        initial_custodian: property.current_holder_id.to_string(),
+        metadata: ledger_metadata(property),
+        custodian_key,
+    }
+}
+
+pub fn update_payload(property: &Property) -> HandReceiptPayload {
+    HandReceiptPayload::Update {
+        property_id: property.id.to_string(),
+        metadata: ledger_metadata(property),
+        updated_at: property.updated_at.timestamp_millis(),
+        status: property.status.to_string(),
+    }
+}
+
+pub fn delete_payload(property_id: i32) -> HandReceiptPayload {
+    HandReceiptPayload::Delete {
+        property_id: property_id.to_string(),
+    }
+}
+
+/// Ledger transfer id for a transfer row. It is derived from the row id so
+/// the processor can recognise a transfer it has already applied.
+pub fn ledger_transfer_id(transfer_id: i32) -> Uuid {
+    Uuid::from_u128(transfer_id as u32 as u128)
+}
+
+/// Transfer row id for a ledger transfer id, if it was derived from one.
+pub fn transfer_row_id(ledger_id: &Uuid) -> Option<i32> {
+    u32::try_from(ledger_id.as_u128()).ok().map(|id| id as i32)
+}
+
+pub fn transfer_payload(transfer: &Transfer, signatures: Vec<TransferSignature>) -> HandReceiptPayload {
+    HandReceiptPayload::Transfer {
+        property_id: transfer.property_id.to_string(),
+        to_custodian: transfer.to_holder_id.to_string(),
+        transfer_id: ledger_transfer_id(transfer.id).to_string(),
+        signatures,
+        timestamp: transfer.updated_at.timestamp_millis(),
+        annex_digest: transfer.annex_digest(),
+    }
+}
+
+/// Digest the parties to `transfer` sign. The ledger custodian is the holder
+/// id, so the releasing custodian is the transfer's `from_holder_id`. The
+/// shortage annex digest is the one [`transfer_payload`] carries.
+pub fn transfer_digest(transfer: &Transfer) -> [u8; 32] {
+    handreceipt_protocol::signing::transfer_digest(
+        &transfer.property_id.to_string(),
+        &transfer.from_holder_id.to_string(),
+        &transfer.to_holder_id.to_string(),
+        &ledger_transfer_id(transfer.id).to_string(),
+        &transfer.annex_digest(),
+    )
+}
+
+#[derive(Debug, Clone)]
+pub struct OutboxRelayConfig {
+    /// Entries claimed per run.
+    pub batch_limit: i64,
+    /// Transactions packed into one batch.
+    pub batch_size: usize,
+    /// How long a claimed entry is hidden from other relays.
+    pub lease: Duration,
+    /// How long to wait for a submitted batch to commit.
+    pub commit_timeout: Duration,
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Attempts before an entry is marked failed.
+    pub max_attempts: i32,
+}
+
+impl Default for OutboxRelayConfig {
+    fn default() -> Self {
+        Self {
+            batch_limit: 50,
+            batch_size: 50,
+            lease: Duration::from_secs(120),
+            commit_timeout: Duration::from_secs(30),
+            base_backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(300),
+            max_attempts: 10,
+        }
+    }
+}
+
+impl OutboxRelayConfig {
+    /// Delay before retrying an entry that has been attempted `attempts` times.
+    pub fn backoff(&self, attempts: i32) -> Duration {
+        let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+        self.base_backoff
+            .saturating_mul(2u32.pow(exponent))
+            .min(self.max_backoff)
+    }
+}
+
+/// Submits outbox entries to the ledger until they commit.
+///
+/// The entries claimed in one run go out together through a
+/// `TransactionBatcher`, `batch_size` to a batch. Every entry is signed with
+/// its own nonce, so a retry after a timeout or crash resubmits the
+/// identical transaction instead of a second one. The outcome of each
+/// property entry is recorded as that property's sync status.
+pub struct OutboxRelay {
+    client: Arc<SawtoothClient>,
+    batcher: TransactionBatcher,
+    outbox: Arc<dyn OutboxRepository>,
+    properties: Arc<dyn PropertyRepository>,
+    config: OutboxRelayConfig,
+}
+
+impl OutboxRelay {
+    pub fn new(
+        client: Arc<SawtoothClient>,
+        outbox: Arc<dyn OutboxRepository>,
+        properties: Arc<dyn PropertyRepository>,
+        config: OutboxRelayConfig,
+    ) -> Self {
+        // Each run flushes the batcher itself, so nothing waits out a batch timeout
+        let batcher = TransactionBatcher::new(client.clone(), config.batch_size, Duration::ZERO)
+            .with_commit_timeout(config.commit_timeout);
+        Self { client, batcher, outbox, properties, config }
+    }
+
+    /// Runs the relay every `interval` until the handle is aborted.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.run_once().await {
+                    error!("Outbox relay run failed: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Delivers every due entry once and returns how many were claimed.
+    pub async fn run_once(&self) -> Result<usize, CoreError> {
+        let entries = self
+            .outbox
+            .claim_due(self.config.batch_limit, self.config.lease)
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+        // Claims hand out one entry per aggregate, so sending them together
+        // cannot reorder an aggregate's transactions
+        let mut queued = Vec::with_capacity(entries.len());
+        for entry in &entries {
+            queued.push(self.queue(entry).await);
+        }
+        self.batcher.flush().await;
+
+        for (entry, queued) in entries.iter().zip(queued) {
+            let (batch_id, outcome) = match queued {
+                Ok(receipt) => self.confirm(entry, receipt).await,
+                Err(settled) => settled,
+            };
+            if entry.aggregate_type == AGGREGATE_PROPERTY {
+                self.record_sync(entry, batch_id, &outcome).await;
+            }
+            self.settle(entry, outcome)
+                .await
+                .map_err(|e| CoreError::Repository(e.to_string()))?;
+        }
+
+        Ok(entries.len())
+    }
+
+    /// Queues an entry with the batcher. An entry that needs no submission
+    /// comes back as its batch id, if one was ever assigned, and outcome.
+    async fn queue(&self, entry: &OutboxEntry) -> Result<QueuedReceipt, (Option<String>, Result<(), BlockchainError>)> {
+        // An earlier attempt may have committed after we stopped waiting
+        if let Some(batch_id) = &entry.batch_id {
+            if let Ok(status) = self.client.get_batch_status(batch_id, None).await {
+                if status.status == BatchStatus::Committed {
+                    return Err((Some(batch_id.clone()), Ok(())));
+                }
+            }
+        }
+
+        let payload: HandReceiptPayload = serde_json::from_value(entry.payload.clone())
+            .map_err(|e| (entry.batch_id.clone(), Err(BlockchainError::SerializationError(e.to_string()))))?;
+        Ok(self.batcher.queue(payload, entry.nonce.clone()).await)
+    }
+
+    /// Records the batch a queued entry went out in and waits for it.
+    /// Returns the batch id, if one was ever assigned, alongside the outcome.
+    async fn confirm(
+        &self,
+        entry: &OutboxEntry,
+        mut receipt: QueuedReceipt,
+    ) -> (Option<String>, Result<(), BlockchainError>) {
+        let batch_id = match receipt.accepted().await {
+            Some(accepted) => {
+                if entry.batch_id.as_deref() != Some(accepted.batch_id.as_str()) {
+                    let transaction_id = accepted.transaction_ids.first().map(String::as_str).unwrap_or_default();
+                    if let Err(e) = self.outbox.record_batch(entry.id, &accepted.batch_id, transaction_id).await {
+                        warn!("Failed to record batch for outbox entry {}: {}", entry.id, e);
+                    }
+                }
+                Some(accepted.batch_id)
+            }
+            None => entry.batch_id.clone(),
+        };
+
+        (batch_id, receipt.committed().await.map(|_| ()))
+    }
+
+    async fn record_sync(
+        &self,
+        entry: &OutboxEntry,
+        batch_id: Option<String>,
+        outcome: &Result<(), BlockchainError>,
+    ) {
+        let status = sync_status(batch_id, outcome);
+        if let Err(e) = self.properties.update_sync_status(entry.aggregate_id, &status).await {
+            warn!("Failed to record sync status for property {}: {}", entry.aggregate_id, e);
+        }
+    }
+
+    async fn settle(
+        &self,
+        entry: &OutboxEntry,
+        outcome: Result<(), BlockchainError>,
+    ) -> Result<(), RepositoryError> {
+        match outcome {
+            Ok(()) => {
+                debug!("Outbox entry {} committed", entry.id);
+                self.outbox.mark_committed(entry.id).await
+            }
+            // The validator rejects the same transaction the same way every
+            // time, unless it names a property whose create has not committed
+            Err(e @ BlockchainError::InvalidTransaction { .. }) if !awaits_property(&e) => {
+                error!("Outbox entry {} rejected: {}", entry.id, e);
+                self.outbox.mark_failed(entry.id, &e.to_string()).await
+            }
+            Err(e @ BlockchainError::SerializationError(_)) => {
+                error!("Outbox entry {} rejected: {}", entry.id, e);
+                self.outbox.mark_failed(entry.id, &e.to_string()).await
+            }
+            Err(e) if entry.attempts >= self.config.max_attempts => {
+                error!("Outbox entry {} gave up after {} attempts: {}", entry.id, entry.attempts, e);
+                self.outbox.mark_failed(entry.id, &e.to_string()).await
+            }
+            Err(e) => {
+                let delay = self.config.backoff(entry.attempts);
+                warn!("Outbox entry {} failed, retrying in {:?}: {}", entry.id, delay, e);
+                let next_attempt_at = Utc::now()
+                    + chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::seconds(60));
+                self.outbox
+                    .schedule_retry(entry.id, &e.to_string(), next_attempt_at)
+                    .await
+            }
+        }
+    }
+}
+
+/// Returns true if `err` only means the property's create is not on the
+/// ledger yet, which a later attempt can get past.
+fn awaits_property(err: &BlockchainError) -> bool {
+    matches!(err, BlockchainError::InvalidTransaction { message, .. } if message.contains(PROPERTY_NOT_FOUND))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let config = OutboxRelayConfig::default();
+        assert_eq!(config.backoff(1), Duration::from_secs(2));
+        assert_eq!(config.backoff(2), Duration::from_secs(4));
+        assert_eq!(config.backoff(5), Duration::from_secs(32));
+        assert_eq!(config.backoff(20), config.max_backoff);
+    }
+
+    #[test]
+    fn test_sync_status_records_batch_id() {
+        use crate::domain::property::service::SyncState;
+
+        let committed = sync_status(Some("batch-1".to_string()), &Ok(()));
+        assert_eq!(committed.state, SyncState::Committed);
+        assert_eq!(committed.blockchain_hash.as_deref(), Some("batch-1"));
+
+        let rejected = sync_status(
+            Some("batch-1".to_string()),
+            &Err(BlockchainError::InvalidTransaction {
+                transaction_id: "tx".to_string(),
+                message: "Invalid signature".to_string(),
+            }),
+        );
+        assert_eq!(rejected.state, SyncState::Invalid);
+        assert_eq!(rejected.blockchain_hash.as_deref(), Some("batch-1"));
+
+        let timed_out = sync_status(None, &Err(BlockchainError::BatchTimeout("batch-1".to_string())));
+        assert_eq!(timed_out.state, SyncState::Pending);
+    }
+
+    #[test]
+    fn test_missing_property_is_retried() {
+        let rejected = |message: &str| BlockchainError::InvalidTransaction {
+            transaction_id: "tx".to_string(),
+            message: message.to_string(),
+        };
+        assert!(awaits_property(&rejected(PROPERTY_NOT_FOUND)));
+        assert!(!awaits_property(&rejected("Invalid signature")));
+        assert!(!awaits_property(&BlockchainError::BatchTimeout("batch-1".to_string())));
+    }
+
+    #[test]
+    fn test_ledger_transfer_id_is_stable() {
+        assert_eq!(ledger_transfer_id(42), ledger_transfer_id(42));
+        assert_ne!(ledger_transfer_id(42), ledger_transfer_id(43));
+        assert_eq!(
+            ledger_transfer_id(42).to_string(),
+            "00000000-0000-0000-0000-00000000002a"
+        );
+        assert_eq!(transfer_row_id(&ledger_transfer_id(42)), Some(42));
+        assert_eq!(transfer_row_id(&Uuid::new_v4()), None);
+    }
+
+    #[test]
+    fn test_annex_is_signed_and_carried() {
+        use crate::domain::{
+            component::entity::{ComponentKind, PropertyComponent, ShortageAnnex},
+            models::location::Location,
+        };
+
+        let mut transfer = Transfer::new(7, 1, 2, Location::default(), None);
+        transfer.id = 42;
+        let whole = transfer_digest(&transfer);
+        assert!(transfer.annex_digest().is_empty());
+
+        let mut cable = PropertyComponent::new(7, "Cable, slave".to_string(), ComponentKind::BasicIssueItem, 1);
+        cable.on_hand_quantity = 0;
+        let annex = ShortageAnnex::new(7, &[cable]);
+        transfer.set_shortage_annex(&annex);
+        assert_eq!(transfer.annex_digest(), annex.digest);
+        assert_ne!(transfer_digest(&transfer), whole);
+
+        match transfer_payload(&transfer, Vec::new()) {
+            HandReceiptPayload::Transfer { property_id, to_custodian, transfer_id, annex_digest, .. } => {
+                assert_eq!(annex_digest, annex.digest);
+                // The processor recomputes the digest from the payload
+                assert_eq!(
+                    handreceipt_protocol::signing::transfer_digest(&property_id, "1", &to_custodian, &transfer_id, &annex_digest),
+                    transfer_digest(&transfer)
+                );
+            }
+            payload => panic!("unexpected payload {:?}", payload),
+        }
+
+        // The digest follows the lines, not a stored digest that disagrees
+        transfer.metadata["shortage_annex"]["digest"] = serde_json::json!("00");
+        assert_eq!(transfer.annex_digest(), annex.digest);
+    }
+}
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use handreceipt_protocol::merkle::BatchInclusionProof;
+
+use crate::{
+    domain::outbox::{entity::OutboxStatus, repository::OutboxRepository},
+    error::blockchain::BlockchainError,
+    infrastructure::blockchain::{
+        merkle::MerkleTree,
+        outbox::AGGREGATE_TRANSFER,
+        sawtooth::SawtoothClient,
+    },
+};
+
+/// Builds inclusion proofs for transfers committed through the outbox.
+pub struct TransferProofService {
+    client: Arc<SawtoothClient>,
+    outbox: Arc<dyn OutboxRepository>,
+}
+
+impl TransferProofService {
+    pub fn new(client: Arc<SawtoothClient>, outbox: Arc<dyn OutboxRepository>) -> Self {
+        Self { client, outbox }
+    }
+
+    /// Proves that the transaction completing `transfer_id` is part of the
+    /// batch that carried it, together with the signed header that anchors
+    /// the proof's root. Returns `None` until that batch has committed.
+    pub async fn prove_transfer(&self, transfer_id: i32) -> Result<Option<BatchInclusionProof>, BlockchainError> {
+        let entries = self
+            .outbox
+            .list_by_aggregate(AGGREGATE_TRANSFER, transfer_id)
+            .await
+            .map_err(|e| BlockchainError::StateError(e.to_string()))?;
+
+        let committed = entries.into_iter().rev().find_map(|entry| {
+            match (entry.status, entry.batch_id, entry.transaction_id) {
+                (OutboxStatus::Committed, Some(batch_id), Some(transaction_id)) => Some((batch_id, transaction_id)),
+                _ => None,
+            }
+        });
+        let (batch_id, transaction_id) = match committed {
+            Some(ids) => ids,
+            None => return Ok(None),
+        };
+
+        let header = match self.client.get_batch_header(&batch_id).await? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+
+        let proof = MerkleTree::from_transaction_ids(&header.transaction_ids).generate_proof_for_id(&transaction_id)?;
+        Ok(Some(BatchInclusionProof {
+            batch_id,
+            transaction_id,
+            batcher_public_key: header.signer_public_key,
+            transaction_ids: header.transaction_ids,
+            proof,
+        }))
+    }
+}
@@ -0,0 +1,257 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::{
+    domain::{
+        property::{entity::Property, repository::PropertyRepository},
+        reconciliation::{
+            entity::{DriftType, LedgerDrift, ReconciliationReport},
+            repository::ReconciliationRepository,
+        },
+    },
+    error::CoreError,
+    infrastructure::blockchain::sawtooth::{state::PropertyState, SawtoothClient},
+};
+
+/// Database status a ledger status stands for. States created before the
+/// processor recorded `available` still say `active`.
+fn database_status(ledger_status: &str) -> &str {
+    match ledger_status {
+        "active" => "available",
+        status => status,
+    }
+}
+
+/// Compares a property row with its on-chain state.
+pub fn diff_property(property: &Property, state: Option<&PropertyState>) -> Vec<LedgerDrift> {
+    let state = match state {
+        Some(state) => state,
+        None => {
+            return vec![LedgerDrift::new(
+                property.id,
+                DriftType::MissingOnLedger,
+                Some(property.id.to_string()),
+                None,
+            )]
+        }
+    };
+
+    let mut drift = Vec::new();
+
+    let custodian = property.current_holder_id.to_string();
+    if state.custodian != custodian {
+        drift.push(LedgerDrift::new(
+            property.id,
+            DriftType::CustodianMismatch,
+            Some(custodian),
+            Some(state.custodian.clone()),
+        ));
+    }
+
+    let status = property.status.to_string();
+    if database_status(&state.status) != status {
+        drift.push(LedgerDrift::new(
+            property.id,
+            DriftType::StatusMismatch,
+            Some(status),
+            Some(state.status.clone()),
+        ));
+    }
+
+    drift
+}
+
+/// Periodically diffs `properties` against on-chain `PropertyState`.
+///
+/// With `resubmit` enabled, properties missing from the ledger are queued in
+/// the outbox for the relay to create. Custodian drift is only reported,
+/// since correcting it needs a transfer signed by the holders.
+pub struct ReconciliationJob {
+    client: Arc<SawtoothClient>,
+    properties: Arc<dyn PropertyRepository>,
+    reconciliation: Arc<dyn ReconciliationRepository>,
+    resubmit: bool,
+}
+
+impl ReconciliationJob {
+    pub fn new(
+        client: Arc<SawtoothClient>,
+        properties: Arc<dyn PropertyRepository>,
+        reconciliation: Arc<dyn ReconciliationRepository>,
+        resubmit: bool,
+    ) -> Self {
+        Self {
+            client,
+            properties,
+            reconciliation,
+            resubmit,
+        }
+    }
+
+    /// Runs the job every `interval` until the handle is aborted.
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.run().await {
+                    error!("Reconciliation run failed: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Performs one reconciliation run and returns its report.
+    pub async fn run(&self) -> Result<ReconciliationReport, CoreError> {
+        let run = self
+            .reconciliation
+            .start_run()
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+        let outcome = self.check_all(run.id).await;
+        let (checked, error) = match &outcome {
+            Ok(checked) => (*checked, None),
+            Err(e) => (0, Some(e.to_string())),
+        };
+
+        self.reconciliation
+            .complete_run(run.id, checked, error.as_deref())
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))?;
+        outcome?;
+
+        let report = self
+            .reconciliation
+            .get_report(run.id)
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))?
+            .ok_or_else(|| CoreError::NotFound(format!("Reconciliation run {}", run.id)))?;
+
+        info!(
+            "Reconciliation run {} checked {} properties, found {} drifted",
+            report.run.id, report.run.properties_checked, report.run.drift_count
+        );
+        Ok(report)
+    }
+
+    async fn check_all(&self, run_id: i32) -> Result<i32, CoreError> {
+        let properties = self
+            .properties
+            .list_properties()
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+        let mut checked = 0;
+        for property in &properties {
+            // One unreadable state should not hide drift in the rest
+            let state = match self.client.get_property_state(&property.id.to_string()).await {
+                Ok(state) => state,
+                Err(e) => {
+                    warn!("Skipping property {}: failed to read ledger state: {}", property.id, e);
+                    continue;
+                }
+            };
+            checked += 1;
+
+            for mut drift in diff_property(property, state.as_ref()) {
+                if self.resubmit {
+                    drift.resubmitted = self.resubmit(property, &drift).await;
+                }
+                self.reconciliation
+                    .record_drift(run_id, &drift)
+                    .await
+                    .map_err(|e| CoreError::Repository(e.to_string()))?;
+            }
+        }
+
+        if checked < properties.len() {
+            warn!(
+                "Reconciliation run {} skipped {} of {} properties",
+                run_id,
+                properties.len() - checked,
+                properties.len()
+            );
+        }
+        Ok(checked as i32)
+    }
+
+    async fn resubmit(&self, property: &Property, drift: &LedgerDrift) -> bool {
+        let result = match drift.drift_type {
+            DriftType::MissingOnLedger => self.reconciliation.resubmit_property(property).await,
+            // Custody only moves with the parties' signatures, which the
+            // job cannot produce; status goes out with the next update
+            DriftType::CustodianMismatch | DriftType::StatusMismatch => return false,
+        };
+
+        match result {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("Failed to resubmit property {}: {}", property.id, e);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::location::Location;
+    use crate::domain::property::entity::{PropertyCategory, PropertyStatus};
+
+    fn property() -> Property {
+        let mut property = Property::new(
+            "M4 Carbine".to_string(),
+            "Rifle".to_string(),
+            PropertyCategory::Weapon,
+            7,
+            Location::default(),
+        );
+        property.id = 42;
+        property
+    }
+
+    fn state(custodian: &str, status: &str) -> PropertyState {
+        PropertyState::new(
+            "42".to_string(),
+            custodian.to_string(),
+            custodian.to_string(),
+            status.to_string(),
+            chrono::Utc::now(),
+        )
+    }
+
+    #[test]
+    fn test_no_drift() {
+        assert!(diff_property(&property(), Some(&state("7", "available"))).is_empty());
+    }
+
+    #[test]
+    fn test_active_ledger_status_is_available() {
+        assert!(diff_property(&property(), Some(&state("7", "active"))).is_empty());
+    }
+
+    #[test]
+    fn test_missing_on_ledger() {
+        let drift = diff_property(&property(), None);
+        assert_eq!(drift.len(), 1);
+        assert_eq!(drift[0].drift_type, DriftType::MissingOnLedger);
+    }
+
+    #[test]
+    fn test_custodian_and_status_drift() {
+        let mut property = property();
+        property.status = PropertyStatus::Maintenance;
+
+        let drift = diff_property(&property, Some(&state("9", "available")));
+        assert_eq!(drift.len(), 2);
+        assert_eq!(drift[0].drift_type, DriftType::CustodianMismatch);
+        assert_eq!(drift[0].database_value.as_deref(), Some("7"));
+        assert_eq!(drift[0].ledger_value.as_deref(), Some("9"));
+        assert_eq!(drift[1].drift_type, DriftType::StatusMismatch);
+    }
+}
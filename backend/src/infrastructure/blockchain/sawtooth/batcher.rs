@@ -0,0 +1,503 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{oneshot, Mutex, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+use super::client::{BatchReceipt, SawtoothClient, BATCH_STATUS_TIMEOUT};
+use super::transaction::HandReceiptPayload;
+use crate::error::blockchain::BlockchainError;
+
+type Reply = oneshot::Sender<Result<BatchReceipt, BlockchainError>>;
+
+struct QueuedTransaction {
+    payload: HandReceiptPayload,
+    nonce: String,
+    enqueued_at: Instant,
+    /// Told the batch and transaction id once the batch is accepted.
+    accepted: oneshot::Sender<BatchReceipt>,
+    reply: Reply,
+}
+
+/// A transaction queued with [`TransactionBatcher::queue`].
+pub struct QueuedReceipt {
+    accepted: oneshot::Receiver<BatchReceipt>,
+    committed: oneshot::Receiver<Result<BatchReceipt, BlockchainError>>,
+}
+
+impl QueuedReceipt {
+    /// The batch id and this transaction's id, once the REST API has
+    /// accepted its batch; `None` if the batch was never accepted.
+    pub async fn accepted(&mut self) -> Option<BatchReceipt> {
+        (&mut self.accepted).await.ok()
+    }
+
+    /// Waits for the batch to commit and returns its receipt.
+    pub async fn committed(self) -> Result<BatchReceipt, BlockchainError> {
+        self.committed
+            .await
+            .map_err(|_| BlockchainError::ServiceError("Transaction batcher dropped request".to_string()))?
+    }
+}
+
+/// A batch accepted by the REST API whose outcome is not known yet.
+struct InFlightBatch {
+    receipt: BatchReceipt,
+    replies: Vec<Reply>,
+    /// In-flight batches whose transactions this batch depends on.
+    depends_on: HashSet<String>,
+}
+
+/// Dependency bookkeeping for batches that have not committed yet.
+#[derive(Default)]
+struct InFlight {
+    /// Maps a state address to the last in-flight transaction writing it.
+    last_writers: HashMap<String, String>,
+    /// Maps an in-flight transaction id to its batch id.
+    batch_of: HashMap<String, String>,
+    batches: HashMap<String, InFlightBatch>,
+}
+
+impl InFlight {
+    /// Tracks an accepted batch. `before` is `last_writers` as it was when
+    /// the batch was built; every writer it replaced is a dependency.
+    fn track(&mut self, receipt: BatchReceipt, replies: Vec<Reply>, before: &HashMap<String, String>) {
+        let depends_on = before
+            .iter()
+            .filter(|(address, writer)| self.last_writers.get(*address) != Some(*writer))
+            .filter_map(|(_, writer)| self.batch_of.get(writer).cloned())
+            .collect();
+
+        for transaction_id in &receipt.transaction_ids {
+            self.batch_of.insert(transaction_id.clone(), receipt.batch_id.clone());
+        }
+        self.batches.insert(
+            receipt.batch_id.clone(),
+            InFlightBatch {
+                receipt,
+                replies,
+                depends_on,
+            },
+        );
+    }
+
+    /// Resolves a batch and forgets its transactions, so later payloads no
+    /// longer depend on them. An invalid batch also fails every in-flight
+    /// batch depending on it, since those can never commit.
+    fn settle(&mut self, batch_id: &str, outcome: Result<(), BlockchainError>) {
+        let mut settling = vec![(batch_id.to_string(), outcome)];
+
+        while let Some((batch_id, outcome)) = settling.pop() {
+            let batch = match self.batches.remove(&batch_id) {
+                Some(batch) => batch,
+                None => continue,
+            };
+
+            let transaction_ids: HashSet<&String> = batch.receipt.transaction_ids.iter().collect();
+            self.last_writers.retain(|_, writer| !transaction_ids.contains(writer));
+            for transaction_id in &transaction_ids {
+                self.batch_of.remove(*transaction_id);
+            }
+
+            if let Err(BlockchainError::InvalidTransaction { transaction_id: rejected, .. }) = &outcome {
+                for (dependent, pending) in &self.batches {
+                    if pending.depends_on.contains(&batch_id) {
+                        warn!("Batch {} depends on invalid batch {}", dependent, batch_id);
+                        settling.push((
+                            dependent.clone(),
+                            Err(BlockchainError::InvalidTransaction {
+                                transaction_id: rejected.clone(),
+                                message: format!("Depends on invalid batch {}", batch_id),
+                            }),
+                        ));
+                    }
+                }
+            }
+
+            for (reply, transaction_id) in batch.replies.into_iter().zip(&batch.receipt.transaction_ids) {
+                let _ = reply.send(match &outcome {
+                    Ok(()) => Ok(batch.receipt.clone()),
+                    Err(err) => Err(copy_error(err, transaction_id)),
+                });
+            }
+        }
+    }
+}
+
+/// Copies a batch outcome for the submitter of `transaction_id`. Only the
+/// rejected transaction is invalid; the others in the batch failed through
+/// no fault of their own and can be submitted again.
+fn copy_error(err: &BlockchainError, transaction_id: &str) -> BlockchainError {
+    match err {
+        BlockchainError::InvalidTransaction { transaction_id: rejected, message } if rejected == transaction_id => {
+            BlockchainError::InvalidTransaction {
+                transaction_id: rejected.clone(),
+                message: message.clone(),
+            }
+        }
+        BlockchainError::InvalidTransaction { transaction_id: rejected, message } => BlockchainError::ServiceError(
+            format!("Batch was rejected for transaction {}: {}", rejected, message),
+        ),
+        BlockchainError::BatchTimeout(batch_id) => BlockchainError::BatchTimeout(batch_id.clone()),
+        err => BlockchainError::ServiceError(err.to_string()),
+    }
+}
+
+/// Collects payloads into multi-transaction batches.
+///
+/// A batch is flushed once `batch_size` payloads are queued or the oldest
+/// queued payload has waited `batch_timeout`, whichever comes first.
+/// Transactions depend on the last in-flight transaction writing the same
+/// address; dependencies are dropped once that batch commits or fails.
+/// A batch still pending after `commit_timeout` fails with `BatchTimeout`.
+pub struct TransactionBatcher {
+    client: Arc<SawtoothClient>,
+    batch_size: usize,
+    batch_timeout: Duration,
+    commit_timeout: Duration,
+    queue: Mutex<Vec<QueuedTransaction>>,
+    in_flight: Arc<Mutex<InFlight>>,
+    notify: Notify,
+    started: AtomicBool,
+    stopped: AtomicBool,
+}
+
+impl TransactionBatcher {
+    pub fn new(client: Arc<SawtoothClient>, batch_size: usize, batch_timeout: Duration) -> Self {
+        Self {
+            client,
+            batch_size: batch_size.max(1),
+            batch_timeout,
+            commit_timeout: BATCH_STATUS_TIMEOUT,
+            queue: Mutex::new(Vec::new()),
+            in_flight: Arc::new(Mutex::new(InFlight::default())),
+            notify: Notify::new(),
+            started: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    pub fn with_commit_timeout(mut self, commit_timeout: Duration) -> Self {
+        self.commit_timeout = commit_timeout;
+        self
+    }
+
+    /// Queues a payload and waits for the batch containing it to commit.
+    ///
+    /// Fails with `InvalidTransaction` if the payload is rejected, and with
+    /// `BatchTimeout` if its batch is still pending after `commit_timeout`.
+    /// A payload whose batch failed because of another transaction fails
+    /// with `ServiceError` and can be submitted again.
+    pub async fn submit(&self, payload: HandReceiptPayload) -> Result<BatchReceipt, BlockchainError> {
+        if !self.started.load(Ordering::SeqCst) {
+            return Err(BlockchainError::ServiceError("Transaction batcher is not running".to_string()));
+        }
+        if self.stopped.load(Ordering::SeqCst) {
+            return Err(BlockchainError::ServiceError("Transaction batcher is stopped".to_string()));
+        }
+
+        let receipt = self.queue(payload, Uuid::new_v4().to_string()).await;
+        self.notify.notify_one();
+        receipt.committed().await
+    }
+
+    /// Queues a payload under a caller-chosen transaction nonce without
+    /// waiting for it. Unless the flusher is running, the caller sends the
+    /// queue with [`flush`](Self::flush).
+    pub async fn queue(&self, payload: HandReceiptPayload, nonce: String) -> QueuedReceipt {
+        let (accepted, accepted_rx) = oneshot::channel();
+        let (reply, committed) = oneshot::channel();
+        self.queue.lock().await.push(QueuedTransaction {
+            payload,
+            nonce,
+            enqueued_at: Instant::now(),
+            accepted,
+            reply,
+        });
+        QueuedReceipt {
+            accepted: accepted_rx,
+            committed,
+        }
+    }
+
+    /// Spawns the background flusher.
+    pub fn start(self: Arc<Self>) -> JoinHandle<()> {
+        self.started.store(true, Ordering::SeqCst);
+        tokio::spawn(async move {
+            while !self.stopped.load(Ordering::SeqCst) {
+                let deadline = self
+                    .queue
+                    .lock()
+                    .await
+                    .first()
+                    .map(|txn| txn.enqueued_at + self.batch_timeout);
+
+                match deadline {
+                    Some(deadline) => {
+                        tokio::select! {
+                            _ = self.notify.notified() => {}
+                            _ = tokio::time::sleep_until(deadline) => {}
+                        }
+                    }
+                    None => self.notify.notified().await,
+                }
+
+                self.flush_ready().await;
+            }
+            self.flush().await;
+        })
+    }
+
+    /// Stops the background flusher after draining the queue.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.notify.notify_one();
+    }
+
+    /// Flushes every queued payload regardless of size or age.
+    pub async fn flush(&self) {
+        loop {
+            let batch = self.take(true).await;
+            if batch.is_empty() {
+                break;
+            }
+            self.send(batch).await;
+        }
+    }
+
+    async fn flush_ready(&self) {
+        loop {
+            let batch = self.take(false).await;
+            if batch.is_empty() {
+                break;
+            }
+            self.send(batch).await;
+        }
+    }
+
+    /// Takes up to `batch_size` payloads if the batch is full, expired or forced.
+    async fn take(&self, force: bool) -> Vec<QueuedTransaction> {
+        let mut queue = self.queue.lock().await;
+        let expired = queue
+            .first()
+            .map(|txn| txn.enqueued_at.elapsed() >= self.batch_timeout)
+            .unwrap_or(false);
+
+        if queue.is_empty() || !(force || expired || queue.len() >= self.batch_size) {
+            return Vec::new();
+        }
+
+        let count = queue.len().min(self.batch_size);
+        queue.drain(..count).collect()
+    }
+
+    async fn send(&self, batch: Vec<QueuedTransaction>) {
+        let mut payloads = Vec::with_capacity(batch.len());
+        let mut accepted = Vec::with_capacity(batch.len());
+        let mut replies = Vec::with_capacity(batch.len());
+        for txn in batch {
+            payloads.push((txn.payload, txn.nonce));
+            accepted.push(txn.accepted);
+            replies.push(txn.reply);
+        }
+        debug!("Flushing batch of {} transactions", payloads.len());
+
+        let mut in_flight = self.in_flight.lock().await;
+        let before = in_flight.last_writers.clone();
+        let result = self.client.submit_with_nonces_after(&payloads, &mut in_flight.last_writers).await;
+
+        match result {
+            Ok(receipt) => {
+                for (accepted, transaction_id) in accepted.into_iter().zip(&receipt.transaction_ids) {
+                    let _ = accepted.send(BatchReceipt {
+                        batch_id: receipt.batch_id.clone(),
+                        transaction_ids: vec![transaction_id.clone()],
+                    });
+                }
+                let batch_id = receipt.batch_id.clone();
+                in_flight.track(receipt, replies, &before);
+                drop(in_flight);
+                self.watch(batch_id);
+            }
+            Err(err) => {
+                error!("Failed to submit batch: {}", err);
+                let message = err.to_string();
+                for reply in replies {
+                    let _ = reply.send(Err(BlockchainError::ServiceError(message.clone())));
+                }
+            }
+        }
+    }
+
+    /// Settles an accepted batch once the validator commits or rejects it.
+    fn watch(&self, batch_id: String) {
+        let client = self.client.clone();
+        let in_flight = self.in_flight.clone();
+        let commit_timeout = self.commit_timeout;
+        tokio::spawn(async move {
+            let outcome = client.wait_for_batch(&batch_id, commit_timeout).await;
+            in_flight.lock().await.settle(&batch_id, outcome);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+    const TEST_KEY: &str = "2f1e7b7a130d7ba9da0068b3bb0ba1d79e7e77110302c9f746c3c2a63fe40088";
+
+    fn transfer(property_id: &str) -> HandReceiptPayload {
+        HandReceiptPayload::Transfer {
+            property_id: property_id.to_string(),
+            to_custodian: "custodian".to_string(),
+            transfer_id: Uuid::new_v4().to_string(),
+            signatures: Vec::new(),
+            timestamp: 1_700_000_000_000,
+            annex_digest: String::new(),
+        }
+    }
+
+    /// Answers every status request with `status`.
+    async fn mock_statuses(server: &MockServer, status: &'static str) {
+        Mock::given(method("GET"))
+            .and(path("/batch_statuses"))
+            .respond_with(move |request: &Request| {
+                let id = request
+                    .url
+                    .query_pairs()
+                    .find(|(key, _)| key == "id")
+                    .map(|(_, id)| id.into_owned())
+                    .unwrap_or_default();
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "data": [{ "id": id, "status": status, "invalid_transactions": [] }]
+                }))
+            })
+            .mount(server)
+            .await;
+    }
+
+    async fn setup(batch_size: usize, batch_timeout: Duration) -> (MockServer, Arc<TransactionBatcher>) {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/batches"))
+            .respond_with(ResponseTemplate::new(202))
+            .mount(&server)
+            .await;
+        mock_statuses(&server, "COMMITTED").await;
+
+        let client = Arc::new(SawtoothClient::new(server.uri(), TEST_KEY.to_string()).unwrap());
+        let batcher = Arc::new(TransactionBatcher::new(client, batch_size, batch_timeout));
+        batcher.clone().start();
+        (server, batcher)
+    }
+
+    fn posted_batches(requests: &[Request]) -> usize {
+        requests.iter().filter(|request| request.method == wiremock::http::Method::Post).count()
+    }
+
+    #[tokio::test]
+    async fn test_flushes_full_batch() {
+        let (server, batcher) = setup(2, Duration::from_secs(60)).await;
+
+        let (first, second) = tokio::join!(
+            batcher.submit(transfer("property-1")),
+            batcher.submit(transfer("property-2")),
+        );
+        let (first, second) = (first.unwrap(), second.unwrap());
+
+        assert_eq!(first.batch_id, second.batch_id);
+        assert_eq!(first.transaction_ids.len(), 2);
+        assert_eq!(posted_batches(&server.received_requests().await.unwrap()), 1);
+    }
+
+    #[tokio::test]
+    async fn test_flushes_after_timeout() {
+        let (server, batcher) = setup(10, Duration::from_millis(50)).await;
+
+        let receipt = batcher.submit(transfer("property-1")).await.unwrap();
+
+        assert_eq!(receipt.transaction_ids.len(), 1);
+        assert_eq!(posted_batches(&server.received_requests().await.unwrap()), 1);
+        // Committed writers are no longer depended on
+        assert!(batcher.in_flight.lock().await.last_writers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_queued_transactions_keep_their_nonce() {
+        let (server, batcher) = setup(10, Duration::from_secs(60)).await;
+        let payload = transfer("property-1");
+
+        let mut first = batcher.queue(payload.clone(), "property-1-create".to_string()).await;
+        let mut second = batcher.queue(transfer("property-2"), "property-2-create".to_string()).await;
+        batcher.flush().await;
+
+        let first_accepted = first.accepted().await.unwrap();
+        let second_accepted = second.accepted().await.unwrap();
+        assert_eq!(first_accepted.batch_id, second_accepted.batch_id);
+        assert_ne!(first_accepted.transaction_ids, second_accepted.transaction_ids);
+        assert_eq!(first.committed().await.unwrap().transaction_ids.len(), 2);
+        assert_eq!(posted_batches(&server.received_requests().await.unwrap()), 1);
+
+        // The same payload and nonce sign to the same transaction
+        let (_server, again) = setup(10, Duration::from_secs(60)).await;
+        let mut resent = again.queue(payload, "property-1-create".to_string()).await;
+        again.flush().await;
+        assert_eq!(resent.accepted().await.unwrap().transaction_ids, first_accepted.transaction_ids);
+    }
+
+    #[tokio::test]
+    async fn test_submit_requires_start() {
+        let client = Arc::new(SawtoothClient::new("http://unused".to_string(), TEST_KEY.to_string()).unwrap());
+        let batcher = TransactionBatcher::new(client, 1, Duration::from_secs(1));
+        assert!(batcher.submit(transfer("property-1")).await.is_err());
+    }
+
+    #[test]
+    fn test_invalid_batch_fails_dependents() {
+        let receipt = |batch: &str, txn: &str| BatchReceipt {
+            batch_id: batch.to_string(),
+            transaction_ids: vec![txn.to_string()],
+        };
+        let mut in_flight = InFlight::default();
+
+        in_flight.last_writers.insert("address".to_string(), "txn-1".to_string());
+        let (first, mut first_reply) = oneshot::channel();
+        in_flight.track(receipt("batch-1", "txn-1"), vec![first], &HashMap::new());
+
+        let before = in_flight.last_writers.clone();
+        in_flight.last_writers.insert("address".to_string(), "txn-2".to_string());
+        let (second, mut second_reply) = oneshot::channel();
+        in_flight.track(receipt("batch-2", "txn-2"), vec![second], &before);
+        assert!(in_flight.batches["batch-2"].depends_on.contains("batch-1"));
+
+        in_flight.settle(
+            "batch-1",
+            Err(BlockchainError::InvalidTransaction {
+                transaction_id: "txn-1".to_string(),
+                message: "rejected".to_string(),
+            }),
+        );
+
+        assert!(matches!(
+            first_reply.try_recv().unwrap(),
+            Err(BlockchainError::InvalidTransaction { .. })
+        ));
+        // The dependent transaction itself was fine and can be resubmitted
+        assert!(matches!(
+            second_reply.try_recv().unwrap(),
+            Err(BlockchainError::ServiceError(_))
+        ));
+        assert!(in_flight.batches.is_empty());
+        assert!(in_flight.last_writers.is_empty());
+        assert!(in_flight.batch_of.is_empty());
+    }
+}
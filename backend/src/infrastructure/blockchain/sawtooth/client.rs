@@ -1,13 +1,16 @@
+use std::collections::HashMap;
 use std::time::Duration;
 use std::sync::Arc;
 use std::ops::Deref;
 use parking_lot::Mutex;
 use uuid::Uuid;
+use chrono::{DateTime, Utc};
 use sawtooth_sdk::signing::secp256k1::{Secp256k1Context, Secp256k1PrivateKey};
 
 use sawtooth_sdk::{
     messages::{
         batch::{Batch, BatchHeader, BatchList},
+        setting::Setting,
         transaction::{Transaction, TransactionHeader},
     },
     signing::{create_context, Context, PrivateKey, Signer},
@@ -18,12 +21,75 @@ use serde::{Deserialize, Serialize};
 use hex;
 use openssl;
 
-use super::{FAMILY_NAME, FAMILY_VERSION};
-use super::state::PropertyMetadata;
+use handreceipt_protocol::merkle::BatchHeader as BatchHeaderMessage;
+use handreceipt_protocol::messages::PayloadEncoding;
+use handreceipt_protocol::signing::TransferSignature;
+
+use super::FAMILY_NAME;
+use super::state::{PropertyMetadata, PropertyState};
 use super::transaction::HandReceiptPayload;
 use crate::error::blockchain::BlockchainError;
 
-const BATCH_STATUS_TIMEOUT: Duration = Duration::from_secs(300);
+pub const BATCH_STATUS_TIMEOUT: Duration = Duration::from_secs(300);
+/// Longest `wait` the REST API will hold a batch status request open.
+const BATCH_STATUS_MAX_WAIT: Duration = Duration::from_secs(60);
+const BATCH_STATUS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const PAYLOAD_ENCODING: PayloadEncoding = PayloadEncoding::Protobuf;
+
+/// Commit status of a batch as reported by `/batch_statuses`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BatchStatus {
+    Committed,
+    Invalid,
+    Pending,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvalidTransaction {
+    pub id: String,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub extended_data: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchStatusEntry {
+    pub id: String,
+    pub status: BatchStatus,
+    #[serde(default)]
+    pub invalid_transactions: Vec<InvalidTransaction>,
+}
+
+impl BatchStatusEntry {
+    /// Converts an INVALID batch into the error for its first rejected transaction.
+    pub fn invalid_error(&self) -> BlockchainError {
+        match self.invalid_transactions.first() {
+            Some(txn) => BlockchainError::InvalidTransaction {
+                transaction_id: txn.id.clone(),
+                message: txn.message.clone(),
+            },
+            None => BlockchainError::InvalidTransaction {
+                transaction_id: self.id.clone(),
+                message: "Batch rejected by validator".to_string(),
+            },
+        }
+    }
+}
+
+/// Ids assigned to a submitted batch and its transactions, in payload order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchReceipt {
+    pub batch_id: String,
+    pub transaction_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchStatusResponse {
+    data: Vec<BatchStatusEntry>,
+}
 
 pub struct SawtoothClient {
     url: String,
@@ -62,6 +128,12 @@ impl SawtoothClient {
         Ok((context, private_key))
     }
 
+    /// Hex public key this client signs transactions and batches with.
+    pub fn public_key(&self) -> Result<String, BlockchainError> {
+        let (context, private_key) = self.create_signer_owned()?;
+        Ok(context.get_public_key(&*private_key)?.as_hex())
+    }
+
     pub async fn create_property(
         &self,
         property_id: String,
@@ -72,100 +144,218 @@ impl SawtoothClient {
             property_id,
             initial_custodian,
             metadata,
+            custodian_key: None,
         };
 
         self.submit_transaction(payload).await
     }
 
+    /// Submits a transfer. `signatures` must be over the transfer digest for
+    /// `transfer_id` and `annex_digest`, or the processor rejects it.
     pub async fn transfer_property(
         &self,
         property_id: String,
         to_custodian: String,
+        transfer_id: String,
+        signatures: Vec<TransferSignature>,
+        timestamp: DateTime<Utc>,
+        annex_digest: String,
     ) -> Result<String, BlockchainError> {
-        let transfer_id = Uuid::new_v4().to_string();
-        
         let payload = HandReceiptPayload::Transfer {
             property_id,
             to_custodian,
             transfer_id: transfer_id.clone(),
+            signatures,
+            timestamp: timestamp.timestamp_millis(),
+            annex_digest,
         };
 
         self.submit_transaction(payload).await?;
         Ok(transfer_id)
     }
 
+    /// Anchors the digest of an issuer's signed revocation list on-chain.
+    pub async fn anchor_revocations(
+        &self,
+        issuer: String,
+        digest: String,
+        revoked_count: u32,
+        updated_at: i64,
+    ) -> Result<String, BlockchainError> {
+        let payload = HandReceiptPayload::AnchorRevocations {
+            issuer,
+            digest,
+            revoked_count,
+            updated_at,
+        };
+
+        self.submit_transaction(payload).await
+    }
+
     async fn submit_transaction(
         &self,
         payload: HandReceiptPayload,
     ) -> Result<String, BlockchainError> {
-        // Serialize the payload
-        let payload_bytes = serde_json::to_vec(&payload)
-            .map_err(|err| BlockchainError::SerializationError(format!("Failed to serialize payload: {}", err)))?;
-
-        // Create all transaction data synchronously before any async operations
-        let batch_list = {
-            // Create owned signer components
-            let (context, private_key) = self.create_signer_owned()?;
-            let signer = Signer::new(&*context, &*private_key);
-            
-            // Create transaction header
-            let mut txn_header = TransactionHeader::new();
-            txn_header.set_family_name(FAMILY_NAME.to_string());
-            txn_header.set_family_version(FAMILY_VERSION.to_string());
-            txn_header.set_nonce(Uuid::new_v4().to_string());
-            txn_header.set_signer_public_key(signer.get_public_key()?.as_hex());
-            txn_header.set_batcher_public_key(signer.get_public_key()?.as_hex());
-            txn_header.set_payload_sha512(hex::encode(openssl::sha::sha512(&payload_bytes)));
+        Ok(self.submit_batch(vec![payload]).await?.batch_id)
+    }
 
-            // Create Transaction
-            let mut txn = Transaction::new();
-            txn.set_header(txn_header.write_to_bytes().map_err(|e| e.to_string())?);
-            txn.set_header_signature(signer.sign(&txn.get_header()).map_err(|e| e.to_string())?);
-            txn.set_payload(payload_bytes);
+    /// Submits payloads as a single atomic batch. Transactions touching the
+    /// same property depend on the previous transaction for that property.
+    pub async fn submit_batch(
+        &self,
+        payloads: Vec<HandReceiptPayload>,
+    ) -> Result<BatchReceipt, BlockchainError> {
+        let mut last_writers = HashMap::new();
+        self.submit_batch_after(payloads, &mut last_writers).await
+    }
 
-            // Create batch header
-            let mut batch_header = BatchHeader::new();
-            batch_header.set_signer_public_key(signer.get_public_key()?.as_hex());
-            batch_header.set_transaction_ids(protobuf::RepeatedField::from_vec(vec![txn.get_header_signature().to_string()]));
-
-            // Create batch
-            let mut batch = Batch::new();
-            batch.set_header(batch_header.write_to_bytes().map_err(|e| e.to_string())?);
-            batch.set_header_signature(signer.sign(&batch.get_header()).map_err(|e| e.to_string())?);
-            batch.set_transactions(protobuf::RepeatedField::from_vec(vec![txn]));
-
-            // Create batch list
-            let mut batch_list = BatchList::new();
-            batch_list.set_batches(protobuf::RepeatedField::from_vec(vec![batch]));
-            
-            // Convert to bytes before leaving the synchronous block
-            batch_list.write_to_bytes().map_err(|e| e.to_string())?
-        };
+    /// Like `submit_batch`, but also orders transactions after earlier ones.
+    ///
+    /// `last_writers` maps a state address to the id of the last transaction
+    /// that wrote it; it is only updated once the batch has been accepted.
+    pub async fn submit_batch_after(
+        &self,
+        payloads: Vec<HandReceiptPayload>,
+        last_writers: &mut HashMap<String, String>,
+    ) -> Result<BatchReceipt, BlockchainError> {
+        let mut writers = last_writers.clone();
+        let (batch, receipt) = self.build_batch(&payloads, &mut writers)?;
+        self.post_batch(batch).await?;
+
+        *last_writers = writers;
+        Ok(receipt)
+    }
+
+    /// Submits payloads as one batch, each with a caller-chosen transaction
+    /// nonce, ordered after earlier transactions as in `submit_batch_after`.
+    ///
+    /// Signing is deterministic, so resubmitting the same payload with the
+    /// same nonce and dependencies produces the same transaction id and the
+    /// validator applies it at most once.
+    pub async fn submit_with_nonces_after(
+        &self,
+        payloads: &[(HandReceiptPayload, String)],
+        last_writers: &mut HashMap<String, String>,
+    ) -> Result<BatchReceipt, BlockchainError> {
+        let transactions: Vec<_> = payloads
+            .iter()
+            .map(|(payload, nonce)| (payload, nonce.clone()))
+            .collect();
+        let mut writers = last_writers.clone();
+        let (batch, receipt) = self.sign_batch(&transactions, &mut writers)?;
+        self.post_batch(batch).await?;
+
+        *last_writers = writers;
+        Ok(receipt)
+    }
+
+    async fn post_batch(&self, batch: Batch) -> Result<(), BlockchainError> {
+        // Create batch list
+        let mut batch_list = BatchList::new();
+        batch_list.set_batches(protobuf::RepeatedField::from_vec(vec![batch]));
+        let batch_list = batch_list.write_to_bytes().map_err(|e| e.to_string())?;
 
-        // Now do the async network call with the prepared batch list
         let response = self.client
             .post(&format!("{}/batches", self.url))
             .header("Content-Type", "application/octet-stream")
             .body(batch_list)
             .send()
             .await
-            .map_err(|e| BlockchainError::ServiceError(format!("Failed to submit batch: {}", e.to_string())))?;
+            .map_err(|e| BlockchainError::ServiceError(format!("Failed to submit batch: {}", e)))?;
 
         if !response.status().is_success() {
             return Err(BlockchainError::ServiceError(format!("Failed to submit batch: {}", response.status())));
         }
 
-        let batch_id = response
-            .headers()
-            .get("Location")
-            .and_then(|h| h.to_str().ok())
-            .ok_or_else(|| BlockchainError::ServiceError("No batch ID returned".to_string()))?
-            .split("/")
-            .last()
-            .ok_or_else(|| BlockchainError::ServiceError("Invalid batch ID format".to_string()))?
-            .to_string();
+        Ok(())
+    }
 
-        Ok(batch_id)
+    /// Builds and signs a batch. The batch id is its header signature.
+    pub fn build_batch(
+        &self,
+        payloads: &[HandReceiptPayload],
+        last_writers: &mut HashMap<String, String>,
+    ) -> Result<(Batch, BatchReceipt), BlockchainError> {
+        let transactions: Vec<_> = payloads
+            .iter()
+            .map(|payload| (payload, Uuid::new_v4().to_string()))
+            .collect();
+        self.sign_batch(&transactions, last_writers)
+    }
+
+    fn sign_batch(
+        &self,
+        payloads: &[(&HandReceiptPayload, String)],
+        last_writers: &mut HashMap<String, String>,
+    ) -> Result<(Batch, BatchReceipt), BlockchainError> {
+        if payloads.is_empty() {
+            return Err(BlockchainError::ValidationError("Cannot submit an empty batch".to_string()));
+        }
+
+        // Create owned signer components
+        let (context, private_key) = self.create_signer_owned()?;
+        let signer = Signer::new(&*context, &*private_key);
+        let public_key = signer.get_public_key()?.as_hex();
+
+        let mut transactions = Vec::with_capacity(payloads.len());
+        for (payload, nonce) in payloads {
+            let payload_bytes = payload.encode(PAYLOAD_ENCODING)?;
+            let inputs = payload.inputs();
+            let outputs = payload.outputs();
+
+            let mut dependencies: Vec<String> = outputs
+                .iter()
+                .filter_map(|address| last_writers.get(address).cloned())
+                .collect();
+            dependencies.sort();
+            dependencies.dedup();
+
+            // Create transaction header
+            let mut txn_header = TransactionHeader::new();
+            txn_header.set_family_name(FAMILY_NAME.to_string());
+            txn_header.set_family_version(PAYLOAD_ENCODING.family_version().to_string());
+            txn_header.set_nonce(nonce.clone());
+            txn_header.set_signer_public_key(public_key.clone());
+            txn_header.set_batcher_public_key(public_key.clone());
+            txn_header.set_payload_sha512(hex::encode(openssl::sha::sha512(&payload_bytes)));
+            txn_header.set_inputs(protobuf::RepeatedField::from_vec(inputs));
+            txn_header.set_outputs(protobuf::RepeatedField::from_vec(outputs.clone()));
+            txn_header.set_dependencies(protobuf::RepeatedField::from_vec(dependencies));
+
+            // Create Transaction
+            let mut txn = Transaction::new();
+            txn.set_header(txn_header.write_to_bytes().map_err(|e| e.to_string())?);
+            txn.set_header_signature(signer.sign(&txn.get_header()).map_err(|e| e.to_string())?);
+            txn.set_payload(payload_bytes);
+
+            for address in outputs {
+                last_writers.insert(address, txn.get_header_signature().to_string());
+            }
+            transactions.push(txn);
+        }
+
+        let transaction_ids: Vec<String> = transactions
+            .iter()
+            .map(|txn| txn.get_header_signature().to_string())
+            .collect();
+
+        // Create batch header
+        let mut batch_header = BatchHeader::new();
+        batch_header.set_signer_public_key(public_key);
+        batch_header.set_transaction_ids(protobuf::RepeatedField::from_vec(transaction_ids.clone()));
+
+        // Create batch
+        let mut batch = Batch::new();
+        batch.set_header(batch_header.write_to_bytes().map_err(|e| e.to_string())?);
+        batch.set_header_signature(signer.sign(&batch.get_header()).map_err(|e| e.to_string())?);
+        batch.set_transactions(protobuf::RepeatedField::from_vec(transactions));
+
+        let receipt = BatchReceipt {
+            batch_id: batch.get_header_signature().to_string(),
+            transaction_ids,
+        };
+
+        Ok((batch, receipt))
     }
 
     pub async fn get_transaction_status(&self, transaction_id: &str) -> Result<String, BlockchainError> {
@@ -189,4 +379,333 @@ impl SawtoothClient {
             )))
         }
     }
-} 
\ No newline at end of file
+
+    /// Reads a raw state entry, returning `None` if the address is unset.
+    pub async fn get_state(&self, address: &str) -> Result<Option<Vec<u8>>, BlockchainError> {
+        let response = self.client
+            .get(&format!("{}/state/{}", self.url, address))
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(BlockchainError::NetworkError(format!(
+                "Failed to get state: {}",
+                response.status()
+            )));
+        }
+
+        let body = response.json::<serde_json::Value>().await?;
+        let data = body["data"]
+            .as_str()
+            .ok_or_else(|| BlockchainError::StateError(format!("No data at address {}", address)))?;
+
+        base64::decode(data)
+            .map(Some)
+            .map_err(|e| BlockchainError::SerializationError(e.to_string()))
+    }
+
+    /// Reads an on-chain `sawtooth_settings` value, returning `None` if the
+    /// setting is unset.
+    pub async fn get_setting(&self, key: &str) -> Result<Option<String>, BlockchainError> {
+        let data = match self.get_state(&handreceipt_protocol::addressing::setting_address(key)).await? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let setting: Setting = protobuf::parse_from_bytes(&data)
+            .map_err(|e| BlockchainError::SerializationError(e.to_string()))?;
+        Ok(setting
+            .get_entries()
+            .iter()
+            .find(|entry| entry.get_key() == key)
+            .map(|entry| entry.get_value().to_string()))
+    }
+
+    /// Reads the header of a batch, returning `None` if the validator does
+    /// not know the batch.
+    pub async fn get_batch_header(&self, batch_id: &str) -> Result<Option<BatchHeaderMessage>, BlockchainError> {
+        let body = match self.get_json(&format!("{}/batches/{}", self.url, batch_id), "batch").await? {
+            Some(body) => body,
+            None => return Ok(None),
+        };
+
+        let header = &body["data"]["header"];
+        let missing = || BlockchainError::StateError(format!("No header for batch {}", batch_id));
+        let signer_public_key = header["signer_public_key"].as_str().ok_or_else(missing)?;
+        let ids = header["transaction_ids"].as_array().ok_or_else(missing)?;
+
+        Ok(Some(BatchHeaderMessage {
+            signer_public_key: signer_public_key.to_string(),
+            transaction_ids: ids.iter()
+                .filter_map(|id| id.as_str().map(str::to_string))
+                .collect(),
+        }))
+    }
+
+    /// Fetches a page of blocks, newest first, as the REST API returns it.
+    /// `start` is the block id to begin from; `None` starts at the head.
+    /// Returns `None` if the validator does not know `start`.
+    pub async fn get_blocks(&self, start: Option<&str>, limit: usize) -> Result<Option<serde_json::Value>, BlockchainError> {
+        let mut url = format!("{}/blocks?limit={}", self.url, limit);
+        if let Some(start) = start {
+            url.push_str(&format!("&start={}", start));
+        }
+        self.get_json(&url, "blocks").await
+    }
+
+    /// Fetches a block, returning `None` if the validator does not know it.
+    pub async fn get_block(&self, block_id: &str) -> Result<Option<serde_json::Value>, BlockchainError> {
+        self.get_json(&format!("{}/blocks/{}", self.url, block_id), "block").await
+    }
+
+    /// Fetches a committed transaction, returning `None` if there is none.
+    pub async fn get_transaction(&self, transaction_id: &str) -> Result<Option<serde_json::Value>, BlockchainError> {
+        self.get_json(&format!("{}/transactions/{}", self.url, transaction_id), "transaction").await
+    }
+
+    async fn get_json(&self, url: &str, resource: &str) -> Result<Option<serde_json::Value>, BlockchainError> {
+        let response = self.client.get(url).send().await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(BlockchainError::NetworkError(format!(
+                "Failed to get {}: {}",
+                resource,
+                response.status()
+            )));
+        }
+        Ok(Some(response.json::<serde_json::Value>().await?))
+    }
+
+    /// Reads a property's on-chain state.
+    pub async fn get_property_state(&self, property_id: &str) -> Result<Option<PropertyState>, BlockchainError> {
+        match self.get_state(&PropertyState::get_address(property_id)).await? {
+            Some(bytes) => PropertyState::deserialize(&bytes)
+                .map(Some)
+                .map_err(|e| BlockchainError::SerializationError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Fetches the status of a batch, optionally letting the REST API hold the
+    /// request open for up to `wait` until the batch is committed.
+    pub async fn get_batch_status(
+        &self,
+        batch_id: &str,
+        wait: Option<Duration>,
+    ) -> Result<BatchStatusEntry, BlockchainError> {
+        let mut url = format!("{}/batch_statuses?id={}", self.url, batch_id);
+        if let Some(wait) = wait {
+            url.push_str(&format!("&wait={}", wait.as_secs().max(1)));
+        }
+
+        let response = self.client.get(&url).send().await?;
+        if !response.status().is_success() {
+            return Err(BlockchainError::NetworkError(format!(
+                "Failed to get batch status: {}",
+                response.status()
+            )));
+        }
+
+        response
+            .json::<BatchStatusResponse>()
+            .await?
+            .data
+            .into_iter()
+            .find(|entry| entry.id == batch_id)
+            .ok_or_else(|| BlockchainError::StateError(format!("No status returned for batch {}", batch_id)))
+    }
+
+    /// Waits until a batch is committed.
+    ///
+    /// INVALID batches fail with `BlockchainError::InvalidTransaction`. Batches
+    /// still PENDING when `timeout` elapses fail with `BatchTimeout`, and batches
+    /// the validator never saw fail with `StateError`.
+    pub async fn wait_for_batch(&self, batch_id: &str, timeout: Duration) -> Result<(), BlockchainError> {
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            let entry = self
+                .get_batch_status(batch_id, Some(remaining.min(BATCH_STATUS_MAX_WAIT)))
+                .await?;
+
+            match entry.status {
+                BatchStatus::Committed => return Ok(()),
+                BatchStatus::Invalid => return Err(entry.invalid_error()),
+                BatchStatus::Pending | BatchStatus::Unknown => {
+                    if tokio::time::Instant::now() >= deadline {
+                        return Err(match entry.status {
+                            BatchStatus::Pending => BlockchainError::BatchTimeout(batch_id.to_string()),
+                            _ => BlockchainError::StateError(format!("Batch {} is unknown to the validator", batch_id)),
+                        });
+                    }
+                    // UNKNOWN is answered immediately, so back off before asking again
+                    tokio::time::sleep(BATCH_STATUS_POLL_INTERVAL.min(remaining)).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const TEST_KEY: &str = "2f1e7b7a130d7ba9da0068b3bb0ba1d79e7e77110302c9f746c3c2a63fe40088";
+
+    async fn mock_status(server: &MockServer, body: serde_json::Value) {
+        Mock::given(method("GET"))
+            .and(path("/batch_statuses"))
+            .and(query_param("id", "batch-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(server)
+            .await;
+    }
+
+    fn transfer(property_id: &str) -> HandReceiptPayload {
+        HandReceiptPayload::Transfer {
+            property_id: property_id.to_string(),
+            to_custodian: "custodian".to_string(),
+            transfer_id: Uuid::new_v4().to_string(),
+            signatures: Vec::new(),
+            timestamp: 1_700_000_000_000,
+            annex_digest: String::new(),
+        }
+    }
+
+    fn header(txn: &Transaction) -> TransactionHeader {
+        protobuf::parse_from_bytes(txn.get_header()).unwrap()
+    }
+
+    #[test]
+    fn test_build_batch_dependencies() {
+        let client = SawtoothClient::new("http://localhost:8008".to_string(), TEST_KEY.to_string()).unwrap();
+        let payloads = vec![transfer("property-1"), transfer("property-2"), transfer("property-1")];
+
+        let mut last_writers = HashMap::new();
+        let (batch, receipt) = client.build_batch(&payloads, &mut last_writers).unwrap();
+        let txns = batch.get_transactions();
+
+        assert_eq!(receipt.batch_id, batch.get_header_signature());
+        assert_eq!(receipt.transaction_ids.len(), 3);
+        assert!(header(&txns[0]).get_dependencies().is_empty());
+        assert!(header(&txns[1]).get_dependencies().is_empty());
+        assert_eq!(header(&txns[2]).get_dependencies(), &[receipt.transaction_ids[0].clone()]);
+        assert_eq!(header(&txns[2]).get_outputs(), &[PropertyState::get_address("property-1")]);
+        assert_eq!(
+            header(&txns[2]).get_inputs(),
+            &[
+                PropertyState::get_address("property-1"),
+                handreceipt_protocol::addressing::setting_address(handreceipt_protocol::signing::APPROVERS_SETTING),
+            ]
+        );
+        assert_eq!(
+            last_writers.get(&PropertyState::get_address("property-1")),
+            Some(&receipt.transaction_ids[2])
+        );
+    }
+
+    #[test]
+    fn test_build_batch_rejects_empty() {
+        let client = SawtoothClient::new("http://localhost:8008".to_string(), TEST_KEY.to_string()).unwrap();
+        assert!(client.build_batch(&[], &mut HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_same_nonce_signs_same_batch() {
+        let client = SawtoothClient::new("http://localhost:8008".to_string(), TEST_KEY.to_string()).unwrap();
+        let payload = transfer("property-1");
+
+        let (_, first) = client
+            .sign_batch(&[(&payload, "outbox-1".to_string())], &mut HashMap::new())
+            .unwrap();
+        let (_, second) = client
+            .sign_batch(&[(&payload, "outbox-1".to_string())], &mut HashMap::new())
+            .unwrap();
+        let (_, other) = client
+            .sign_batch(&[(&payload, "outbox-2".to_string())], &mut HashMap::new())
+            .unwrap();
+
+        assert_eq!(first.batch_id, second.batch_id);
+        assert_eq!(first.transaction_ids, second.transaction_ids);
+        assert_ne!(first.transaction_ids, other.transaction_ids);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_committed_batch() {
+        let server = MockServer::start().await;
+        mock_status(&server, serde_json::json!({
+            "data": [{ "id": "batch-1", "status": "COMMITTED", "invalid_transactions": [] }]
+        })).await;
+
+        let client = SawtoothClient::new(server.uri(), TEST_KEY.to_string()).unwrap();
+        assert!(client.wait_for_batch("batch-1", Duration::from_secs(1)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_invalid_batch() {
+        let server = MockServer::start().await;
+        mock_status(&server, serde_json::json!({
+            "data": [{
+                "id": "batch-1",
+                "status": "INVALID",
+                "invalid_transactions": [{ "id": "txn-1", "message": "Property does not exist" }]
+            }]
+        })).await;
+
+        let client = SawtoothClient::new(server.uri(), TEST_KEY.to_string()).unwrap();
+        match client.wait_for_batch("batch-1", Duration::from_secs(1)).await {
+            Err(BlockchainError::InvalidTransaction { transaction_id, message }) => {
+                assert_eq!(transaction_id, "txn-1");
+                assert_eq!(message, "Property does not exist");
+            }
+            other => panic!("expected invalid transaction, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_pending_batch_times_out() {
+        let server = MockServer::start().await;
+        mock_status(&server, serde_json::json!({
+            "data": [{ "id": "batch-1", "status": "PENDING", "invalid_transactions": [] }]
+        })).await;
+
+        let client = SawtoothClient::new(server.uri(), TEST_KEY.to_string()).unwrap();
+        assert!(matches!(
+            client.wait_for_batch("batch-1", Duration::from_millis(200)).await,
+            Err(BlockchainError::BatchTimeout(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_setting() {
+        use sawtooth_sdk::messages::setting::Setting_Entry;
+
+        let key = "sawtooth.consensus.algorithm.name";
+        let mut entry = Setting_Entry::new();
+        entry.set_key(key.to_string());
+        entry.set_value("pbft".to_string());
+        let mut setting = Setting::new();
+        setting.set_entries(protobuf::RepeatedField::from_vec(vec![entry]));
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/state/{}", handreceipt_protocol::addressing::setting_address(key))))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": base64::encode(setting.write_to_bytes().unwrap())
+            })))
+            .mount(&server)
+            .await;
+
+        let client = SawtoothClient::new(server.uri(), TEST_KEY.to_string()).unwrap();
+        assert_eq!(client.get_setting(key).await.unwrap().as_deref(), Some("pbft"));
+        assert_eq!(client.get_setting("sawtooth.consensus.pbft.members").await.unwrap(), None);
+    }
+}
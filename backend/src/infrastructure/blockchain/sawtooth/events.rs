@@ -0,0 +1,629 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use protobuf::Message as _;
+use sawtooth_sdk::messages::client_event::{
+    ClientEventsSubscribeRequest, ClientEventsSubscribeResponse,
+    ClientEventsSubscribeResponse_Status,
+};
+use sawtooth_sdk::messages::events::{
+    Event, EventFilter, EventFilter_FilterType, EventList, EventSubscription,
+};
+use sawtooth_sdk::messages::transaction_receipt::{StateChangeList, StateChange_Type};
+use sawtooth_sdk::messages::validator::Message_MessageType;
+use sawtooth_sdk::messaging::stream::{MessageConnection, MessageSender};
+use sawtooth_sdk::messaging::zmq_stream::ZmqMessageConnection;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use super::addressing::{address_space, AddressSpace};
+use super::namespace_prefix;
+use super::state::PropertyState;
+use crate::{
+    domain::{
+        ledger::{
+            entity::{BlockCheckpoint, CompletedTransfer},
+            repository::LedgerCheckpointRepository,
+        },
+        property::{
+            repository::PropertyRepository,
+            service::{SyncState, SyncStatus},
+        },
+        transfer::{entity::TransferStatus, repository::TransferRepository},
+    },
+    error::blockchain::BlockchainError,
+    infrastructure::blockchain::outbox::transfer_row_id,
+};
+
+pub const BLOCK_COMMIT_EVENT: &str = "sawtooth/block-commit";
+pub const STATE_DELTA_EVENT: &str = "sawtooth/state-delta";
+
+/// Checkpoints offered to the validator when resubscribing. It resumes from
+/// the newest one still on its chain, which replays anything after a fork.
+const RESUME_DEPTH: i64 = 50;
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const SUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A `sawtooth/block-commit` event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockCommit {
+    pub block_id: String,
+    pub block_num: i64,
+    pub state_root_hash: String,
+    pub previous_block_id: String,
+}
+
+/// A single state change within the handreceipt namespace.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateDelta {
+    Set { address: String, value: Vec<u8> },
+    Delete { address: String },
+}
+
+/// The events the validator publishes for one committed block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockEvents {
+    pub block: BlockCommit,
+    pub deltas: Vec<StateDelta>,
+}
+
+impl BlockEvents {
+    /// Parses an event list. Lists without a block-commit event are ignored.
+    pub fn from_event_list(list: &EventList) -> Result<Option<Self>, BlockchainError> {
+        let block = match list
+            .get_events()
+            .iter()
+            .find(|event| event.get_event_type() == BLOCK_COMMIT_EVENT)
+        {
+            Some(event) => parse_block_commit(event)?,
+            None => return Ok(None),
+        };
+
+        let mut deltas = Vec::new();
+        for event in list
+            .get_events()
+            .iter()
+            .filter(|event| event.get_event_type() == STATE_DELTA_EVENT)
+        {
+            let changes = StateChangeList::parse_from_bytes(event.get_data())
+                .map_err(|e| BlockchainError::SerializationError(format!("Invalid state delta: {}", e)))?;
+
+            for change in changes.get_state_changes() {
+                if address_space(change.get_address()).is_none() {
+                    continue;
+                }
+                deltas.push(match change.get_field_type() {
+                    StateChange_Type::SET => StateDelta::Set {
+                        address: change.get_address().to_string(),
+                        value: change.get_value().to_vec(),
+                    },
+                    StateChange_Type::DELETE => StateDelta::Delete {
+                        address: change.get_address().to_string(),
+                    },
+                    _ => continue,
+                });
+            }
+        }
+
+        Ok(Some(Self { block, deltas }))
+    }
+}
+
+fn parse_block_commit(event: &Event) -> Result<BlockCommit, BlockchainError> {
+    let attribute = |key: &str| {
+        event
+            .get_attributes()
+            .iter()
+            .find(|attr| attr.get_key() == key)
+            .map(|attr| attr.get_value().to_string())
+            .ok_or_else(|| BlockchainError::SerializationError(format!("Block commit missing {}", key)))
+    };
+
+    Ok(BlockCommit {
+        block_id: attribute("block_id")?,
+        block_num: attribute("block_num")?
+            .parse()
+            .map_err(|_| BlockchainError::SerializationError("Invalid block_num".to_string()))?,
+        state_root_hash: attribute("state_root_hash")?,
+        previous_block_id: attribute("previous_block_id")?,
+    })
+}
+
+/// Returns true if `block` does not extend the last applied block.
+pub fn is_fork(last: Option<&BlockCheckpoint>, block: &BlockCommit) -> bool {
+    match last {
+        Some(last) => block.block_num <= last.block_num || block.previous_block_id != last.block_id,
+        None => false,
+    }
+}
+
+/// Event subscriptions for the handreceipt namespace.
+pub fn subscriptions() -> Vec<EventSubscription> {
+    let mut block_commit = EventSubscription::new();
+    block_commit.set_event_type(BLOCK_COMMIT_EVENT.to_string());
+
+    let mut filter = EventFilter::new();
+    filter.set_key("address".to_string());
+    filter.set_match_string(format!("^{}.*", namespace_prefix()));
+    filter.set_filter_type(EventFilter_FilterType::REGEX_ANY);
+
+    let mut state_delta = EventSubscription::new();
+    state_delta.set_event_type(STATE_DELTA_EVENT.to_string());
+    state_delta.set_filters(protobuf::RepeatedField::from_vec(vec![filter]));
+
+    vec![block_commit, state_delta]
+}
+
+/// Streams handreceipt state deltas from a validator into Postgres.
+///
+/// Transfer records written by the outbox relay complete the matching
+/// transfer row. A property whose state changes is marked committed if the
+/// relay left it pending, which happens when its batch committed after the
+/// relay stopped waiting; the relay's batch id is kept. A checkpoint is
+/// stored after each block with the transfers it completed, so applying a
+/// block twice is harmless, a restart resumes from the last known block and
+/// a fork restores what the orphaned blocks changed.
+pub struct LedgerEventSubscriber {
+    validator_url: String,
+    properties: Arc<dyn PropertyRepository>,
+    transfers: Arc<dyn TransferRepository>,
+    checkpoints: Arc<dyn LedgerCheckpointRepository>,
+}
+
+impl LedgerEventSubscriber {
+    pub fn new(
+        validator_url: String,
+        properties: Arc<dyn PropertyRepository>,
+        transfers: Arc<dyn TransferRepository>,
+        checkpoints: Arc<dyn LedgerCheckpointRepository>,
+    ) -> Self {
+        Self {
+            validator_url,
+            properties,
+            transfers,
+            checkpoints,
+        }
+    }
+
+    /// Subscribes until the handle is aborted, reconnecting on failure.
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.run().await {
+                    error!("Ledger event subscription failed: {}", e);
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        })
+    }
+
+    /// Subscribes once and applies events until the stream ends.
+    pub async fn run(&self) -> Result<(), BlockchainError> {
+        let known_blocks: Vec<String> = self
+            .checkpoints
+            .recent(RESUME_DEPTH)
+            .await
+            .map_err(|e| BlockchainError::StateError(e.to_string()))?
+            .into_iter()
+            .map(|checkpoint| checkpoint.block_id)
+            .collect();
+
+        let (tx, mut rx) = mpsc::channel::<EventList>(64);
+        let url = self.validator_url.clone();
+        let stream = tokio::task::spawn_blocking(move || stream_events(&url, known_blocks, tx));
+
+        while let Some(list) = rx.recv().await {
+            match BlockEvents::from_event_list(&list) {
+                Ok(Some(events)) => self.apply_block(events).await?,
+                Ok(None) => {}
+                Err(e) => warn!("Skipping malformed event list: {}", e),
+            }
+        }
+
+        stream
+            .await
+            .map_err(|e| BlockchainError::ServiceError(e.to_string()))?
+    }
+
+    /// Applies one block's deltas and records its checkpoint.
+    pub async fn apply_block(&self, events: BlockEvents) -> Result<(), BlockchainError> {
+        let last = self
+            .checkpoints
+            .recent(1)
+            .await
+            .map_err(|e| BlockchainError::StateError(e.to_string()))?
+            .into_iter()
+            .next();
+
+        if is_fork(last.as_ref(), &events.block) {
+            warn!(
+                "Block {} ({}) does not extend {:?}, rewinding",
+                events.block.block_num, events.block.block_id,
+                last.as_ref().map(|c| &c.block_id)
+            );
+            let orphaned = self
+                .checkpoints
+                .rewind_to(events.block.block_num)
+                .await
+                .map_err(|e| BlockchainError::StateError(e.to_string()))?;
+            for checkpoint in &orphaned {
+                self.revert(checkpoint).await?;
+            }
+        }
+
+        let mut completed_transfers = Vec::new();
+        for delta in &events.deltas {
+            if let StateDelta::Set { address, value } = delta {
+                if address_space(address) == Some(AddressSpace::Property) {
+                    completed_transfers.extend(self.apply_property_state(value).await?);
+                }
+            } else {
+                debug!("Ignoring state delete in block {}", events.block.block_id);
+            }
+        }
+
+        let block = events.block;
+        let mut checkpoint = BlockCheckpoint::new(
+            block.block_id,
+            block.block_num,
+            block.state_root_hash,
+            block.previous_block_id,
+        );
+        checkpoint.completed_transfers = completed_transfers;
+        self.checkpoints
+            .save(&checkpoint)
+            .await
+            .map_err(|e| BlockchainError::StateError(e.to_string()))
+    }
+
+    /// Confirms the property's sync status, completes the transfer rows
+    /// recorded in its history and returns the ones this block changed.
+    async fn apply_property_state(&self, value: &[u8]) -> Result<Vec<CompletedTransfer>, BlockchainError> {
+        let state = PropertyState::deserialize(value)
+            .map_err(|e| BlockchainError::SerializationError(e.to_string()))?;
+
+        // Only properties the database knows about carry numeric ids
+        let property_id = match state.id.parse::<i32>() {
+            Ok(id) => id,
+            Err(_) => return Ok(Vec::new()),
+        };
+        self.confirm_sync(property_id).await?;
+
+        let transfer_ids: Vec<i32> = state
+            .transfer_history
+            .iter()
+            .filter_map(|record| transfer_row_id(&record.transfer_id))
+            .collect();
+
+        let completed = self
+            .transfers
+            .complete_transfers(&transfer_ids)
+            .await
+            .map_err(|e| BlockchainError::StateError(e.to_string()))?;
+
+        Ok(completed
+            .into_iter()
+            .map(|(transfer_id, previous_status)| {
+                info!("Transfer {} completed on ledger", transfer_id);
+                CompletedTransfer {
+                    transfer_id,
+                    previous_status,
+                }
+            })
+            .collect())
+    }
+
+    /// Marks a pending property committed, keeping the relay's batch id.
+    /// Unsynced and invalid statuses are the relay's to resolve.
+    async fn confirm_sync(&self, property_id: i32) -> Result<(), BlockchainError> {
+        let status = self
+            .properties
+            .get_sync_status(property_id)
+            .await
+            .map_err(|e| BlockchainError::StateError(e.to_string()))?;
+
+        if let Some(status) = status.filter(|status| status.state == SyncState::Pending) {
+            self.properties
+                .update_sync_status(
+                    property_id,
+                    &SyncStatus::new(SyncState::Committed, status.blockchain_hash, None),
+                )
+                .await
+                .map_err(|e| BlockchainError::StateError(e.to_string()))?;
+            debug!("Property {} committed on ledger", property_id);
+        }
+        Ok(())
+    }
+
+    /// Restores the transfers an orphaned block completed. Blocks on the new
+    /// chain complete them again as they are replayed.
+    async fn revert(&self, checkpoint: &BlockCheckpoint) -> Result<(), BlockchainError> {
+        for completed in &checkpoint.completed_transfers {
+            let transfer = self
+                .transfers
+                .get_transfer(completed.transfer_id)
+                .await
+                .map_err(|e| BlockchainError::StateError(e.to_string()))?;
+
+            if let Some(mut transfer) = transfer {
+                if transfer.status == TransferStatus::Completed {
+                    transfer.status = completed.previous_status;
+                    self.transfers
+                        .update_transfer(&transfer)
+                        .await
+                        .map_err(|e| BlockchainError::StateError(e.to_string()))?;
+                    warn!(
+                        "Transfer {} reverted to {} with orphaned block {}",
+                        transfer.id, transfer.status, checkpoint.block_id
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs on a blocking thread: subscribes and forwards event lists until the
+/// connection drops or the receiver goes away.
+fn stream_events(
+    url: &str,
+    last_known_block_ids: Vec<String>,
+    events: mpsc::Sender<EventList>,
+) -> Result<(), BlockchainError> {
+    let connection = ZmqMessageConnection::new(url);
+    let (mut sender, receiver) = connection.create();
+
+    let mut request = ClientEventsSubscribeRequest::new();
+    request.set_subscriptions(protobuf::RepeatedField::from_vec(subscriptions()));
+    request.set_last_known_block_ids(protobuf::RepeatedField::from_vec(last_known_block_ids));
+    let request = request
+        .write_to_bytes()
+        .map_err(|e| BlockchainError::SerializationError(e.to_string()))?;
+
+    let mut future = sender
+        .send(
+            Message_MessageType::CLIENT_EVENTS_SUBSCRIBE_REQUEST,
+            &Uuid::new_v4().to_string(),
+            &request,
+        )
+        .map_err(|e| BlockchainError::NetworkError(format!("{:?}", e)))?;
+    let reply = future
+        .get_timeout(SUBSCRIBE_TIMEOUT)
+        .map_err(|e| BlockchainError::NetworkError(format!("{:?}", e)))?;
+
+    let response = ClientEventsSubscribeResponse::parse_from_bytes(reply.get_content())
+        .map_err(|e| BlockchainError::SerializationError(e.to_string()))?;
+    match response.get_status() {
+        ClientEventsSubscribeResponse_Status::OK => {}
+        ClientEventsSubscribeResponse_Status::UNKNOWN_BLOCK => {
+            return Err(BlockchainError::StateError(
+                "Validator knows none of the checkpointed blocks".to_string(),
+            ))
+        }
+        status => {
+            return Err(BlockchainError::NetworkError(format!(
+                "Event subscription rejected: {:?} {}",
+                status,
+                response.get_response_message()
+            )))
+        }
+    }
+    info!("Subscribed to ledger events at {}", url);
+
+    loop {
+        let message = match receiver.recv() {
+            Ok(Ok(message)) => message,
+            Ok(Err(e)) => return Err(BlockchainError::NetworkError(format!("{:?}", e))),
+            Err(_) => return Err(BlockchainError::NetworkError("Event stream closed".to_string())),
+        };
+        if message.get_message_type() != Message_MessageType::CLIENT_EVENTS {
+            continue;
+        }
+
+        let list = EventList::parse_from_bytes(message.get_content())
+            .map_err(|e| BlockchainError::SerializationError(e.to_string()))?;
+        if events.blocking_send(list).is_err() {
+            sender.close();
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use handreceipt_protocol::addressing::property_address;
+    use sawtooth_sdk::messages::events::Event_Attribute;
+    use sawtooth_sdk::messages::transaction_receipt::StateChange;
+
+    fn attribute(key: &str, value: &str) -> Event_Attribute {
+        let mut attr = Event_Attribute::new();
+        attr.set_key(key.to_string());
+        attr.set_value(value.to_string());
+        attr
+    }
+
+    fn block_commit(block_id: &str, block_num: i64, previous: &str) -> Event {
+        let mut event = Event::new();
+        event.set_event_type(BLOCK_COMMIT_EVENT.to_string());
+        event.set_attributes(protobuf::RepeatedField::from_vec(vec![
+            attribute("block_id", block_id),
+            attribute("block_num", &block_num.to_string()),
+            attribute("state_root_hash", "root"),
+            attribute("previous_block_id", previous),
+        ]));
+        event
+    }
+
+    fn state_delta(changes: Vec<(String, StateChange_Type)>) -> Event {
+        let mut list = StateChangeList::new();
+        list.set_state_changes(protobuf::RepeatedField::from_vec(
+            changes
+                .into_iter()
+                .map(|(address, kind)| {
+                    let mut change = StateChange::new();
+                    change.set_address(address);
+                    change.set_value(vec![1, 2, 3]);
+                    change.set_field_type(kind);
+                    change
+                })
+                .collect(),
+        ));
+
+        let mut event = Event::new();
+        event.set_event_type(STATE_DELTA_EVENT.to_string());
+        event.set_data(list.write_to_bytes().unwrap());
+        event
+    }
+
+    fn checkpoint(block_id: &str, block_num: i64) -> BlockCheckpoint {
+        BlockCheckpoint::new(block_id.to_string(), block_num, "root".to_string(), String::new())
+    }
+
+    #[test]
+    fn test_parses_block_events() {
+        let property = property_address("1");
+        let foreign = "1cf126".to_string() + &"0".repeat(64);
+
+        let mut list = EventList::new();
+        list.set_events(protobuf::RepeatedField::from_vec(vec![
+            block_commit("block-2", 2, "block-1"),
+            state_delta(vec![
+                (property.clone(), StateChange_Type::SET),
+                (foreign, StateChange_Type::SET),
+            ]),
+        ]));
+
+        let events = BlockEvents::from_event_list(&list).unwrap().unwrap();
+        assert_eq!(events.block.block_id, "block-2");
+        assert_eq!(events.block.block_num, 2);
+        assert_eq!(events.block.previous_block_id, "block-1");
+        assert_eq!(
+            events.deltas,
+            vec![StateDelta::Set { address: property, value: vec![1, 2, 3] }]
+        );
+    }
+
+    #[test]
+    fn test_ignores_list_without_block_commit() {
+        let mut list = EventList::new();
+        list.set_events(protobuf::RepeatedField::from_vec(vec![state_delta(vec![])]));
+        assert!(BlockEvents::from_event_list(&list).unwrap().is_none());
+    }
+
+    /// Checkpoints kept in a list, like `ledger_checkpoints`.
+    #[derive(Default)]
+    struct MockCheckpoints(parking_lot::Mutex<Vec<BlockCheckpoint>>);
+
+    #[async_trait::async_trait]
+    impl LedgerCheckpointRepository for MockCheckpoints {
+        async fn recent(&self, limit: i64) -> Result<Vec<BlockCheckpoint>, crate::error::RepositoryError> {
+            let mut checkpoints = self.0.lock().clone();
+            checkpoints.sort_by_key(|checkpoint| std::cmp::Reverse(checkpoint.block_num));
+            checkpoints.truncate(limit as usize);
+            Ok(checkpoints)
+        }
+
+        async fn save(&self, checkpoint: &BlockCheckpoint) -> Result<(), crate::error::RepositoryError> {
+            self.0.lock().push(checkpoint.clone());
+            Ok(())
+        }
+
+        async fn rewind_to(&self, block_num: i64) -> Result<Vec<BlockCheckpoint>, crate::error::RepositoryError> {
+            let mut checkpoints = self.0.lock();
+            let (orphaned, kept) = checkpoints.drain(..).partition(|checkpoint| checkpoint.block_num >= block_num);
+            *checkpoints = kept;
+            Ok(orphaned)
+        }
+    }
+
+    fn property_delta(transfer_ids: &[i32]) -> StateDelta {
+        let mut state = PropertyState::new(
+            "1".to_string(),
+            "2".to_string(),
+            "2".to_string(),
+            "available".to_string(),
+            chrono::Utc::now(),
+        );
+        for id in transfer_ids {
+            state.transfer_history.push(super::super::state::TransferRecord {
+                transfer_id: crate::infrastructure::blockchain::outbox::ledger_transfer_id(*id),
+                from_custodian: "1".to_string(),
+                to_custodian: "2".to_string(),
+                timestamp: chrono::Utc::now(),
+                status: crate::domain::models::transfer::TransferStatus::Completed,
+                signatures: Vec::new(),
+                transfer_signatures: Vec::new(),
+                annex_digest: String::new(),
+            });
+        }
+        StateDelta::Set {
+            address: property_address("1"),
+            value: state.serialize().unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_applies_blocks_and_reverts_forks() {
+        use crate::domain::{models::location::Location, transfer::entity::Transfer};
+        use crate::test_support::{MockProperties, MockTransfers};
+
+        let mut transfer = Transfer::new(1, 1, 2, Location::default(), None);
+        transfer.id = 1;
+        transfer.approve(3);
+        let properties = Arc::new(MockProperties::default());
+        properties
+            .update_sync_status(1, &SyncStatus::new(SyncState::Pending, Some("batch-1".to_string()), None))
+            .await
+            .unwrap();
+        let transfers = Arc::new(MockTransfers::new(vec![transfer]));
+        let subscriber = LedgerEventSubscriber::new(
+            "tcp://unused:4004".to_string(),
+            properties.clone(),
+            transfers.clone(),
+            Arc::new(MockCheckpoints::default()),
+        );
+        let block = |block_id: &str, block_num: i64, previous: &str, deltas| BlockEvents {
+            block: BlockCommit {
+                block_id: block_id.to_string(),
+                block_num,
+                state_root_hash: "root".to_string(),
+                previous_block_id: previous.to_string(),
+            },
+            deltas,
+        };
+
+        subscriber.apply_block(block("block-1", 1, "block-0", vec![property_delta(&[1])])).await.unwrap();
+        let status = |transfers: &MockTransfers| transfers.transfers.lock()[0].status;
+        assert_eq!(status(&transfers), TransferStatus::Completed);
+        let sync = properties.get_sync_status(1).await.unwrap().unwrap();
+        assert_eq!(sync.state, SyncState::Committed);
+        assert_eq!(sync.blockchain_hash.as_deref(), Some("batch-1"));
+
+        // A sibling of block 1 without the transfer orphans it
+        subscriber.apply_block(block("block-1b", 1, "block-0", vec![property_delta(&[])])).await.unwrap();
+        assert_eq!(status(&transfers), TransferStatus::Approved);
+
+        // And the transfer completes again once a block carries it
+        subscriber.apply_block(block("block-2", 2, "block-1b", vec![property_delta(&[1])])).await.unwrap();
+        assert_eq!(status(&transfers), TransferStatus::Completed);
+    }
+
+    #[test]
+    fn test_fork_detection() {
+        let next = BlockCommit {
+            block_id: "block-3".to_string(),
+            block_num: 3,
+            state_root_hash: "root".to_string(),
+            previous_block_id: "block-2".to_string(),
+        };
+        assert!(!is_fork(None, &next));
+        assert!(!is_fork(Some(&checkpoint("block-2", 2)), &next));
+        // Sibling of the last applied block
+        assert!(is_fork(Some(&checkpoint("block-2b", 2)), &next));
+        // Replayed from an earlier block
+        assert!(is_fork(Some(&checkpoint("block-5", 5)), &next));
+    }
+}
@@ -1,10 +1,22 @@
 use sawtooth_sdk::messages::processor::TpProcessRequest;
 use sawtooth_sdk::processor::handler::{ApplyError, TransactionContext, TransactionHandler};
-use protobuf::Message;
+use sawtooth_sdk::messages::setting::Setting;
+use chrono::{DateTime, Utc};
+use handreceipt_protocol::addressing::{revocation_anchor_address, setting_address};
+use handreceipt_protocol::messages::{supported_family_versions, RevocationAnchor, REVOCATION_AUTHORITY_SETTING};
+use handreceipt_protocol::signing::{parse_approvers, transfer_digest, ApprovalPolicy, APPROVERS_SETTING};
+use tracing::{debug, info};
 
-const FAMILY_NAME: &str = "handreceipt";
-const FAMILY_VERSION: &str = "1.0";
-const NAMESPACE: &str = "handreceipt";
+use super::state::{from_millis, PropertyMetadata, PropertyState, TransferRecord};
+use super::transaction::HandReceiptPayload;
+use super::{namespace_prefix, FAMILY_NAME};
+use crate::domain::models::transfer::TransferStatus;
+
+/// Rejection for payloads naming a property the ledger has not created.
+pub const PROPERTY_NOT_FOUND: &str = "Property does not exist";
+
+/// Matches `PropertyStatus::Available` in the database.
+const STATUS_AVAILABLE: &str = "available";
 
 pub struct HandReceiptTransactionHandler {
     family_name: String,
@@ -16,9 +28,253 @@ impl HandReceiptTransactionHandler {
     pub fn new() -> Self {
         HandReceiptTransactionHandler {
             family_name: FAMILY_NAME.to_string(),
-            family_versions: vec![FAMILY_VERSION.to_string()],
-            namespaces: vec![NAMESPACE.to_string()],
+            family_versions: supported_family_versions(),
+            namespaces: vec![namespace_prefix()],
+        }
+    }
+
+    fn get_state(
+        &self,
+        context: &mut dyn TransactionContext,
+        property_id: &str,
+    ) -> Result<Option<PropertyState>, ApplyError> {
+        let address = PropertyState::get_address(property_id);
+        match context.get_state_entry(&address)? {
+            Some(data) => PropertyState::deserialize(&data)
+                .map(Some)
+                .map_err(|err| ApplyError::InvalidTransaction(format!("Failed to deserialize state: {}", err))),
+            None => Ok(None),
+        }
+    }
+
+    fn set_state(
+        &self,
+        context: &mut dyn TransactionContext,
+        state: &PropertyState,
+    ) -> Result<(), ApplyError> {
+        let data = state
+            .serialize()
+            .map_err(|err| ApplyError::InvalidTransaction(format!("Failed to serialize state: {}", err)))?;
+        context.set_state_entry(PropertyState::get_address(&state.id), data)?;
+        Ok(())
+    }
+
+    /// Value of the `sawtooth_settings` entry for `key`, if it is set.
+    fn get_setting(&self, context: &mut dyn TransactionContext, key: &str) -> Result<Option<String>, ApplyError> {
+        let data = match context.get_state_entry(&setting_address(key))? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let setting: Setting = protobuf::parse_from_bytes(&data)
+            .map_err(|err| ApplyError::InvalidTransaction(format!("Failed to parse setting: {}", err)))?;
+
+        Ok(setting
+            .get_entries()
+            .iter()
+            .find(|entry| entry.get_key() == key)
+            .map(|entry| entry.get_value().to_string()))
+    }
+
+    /// Keys allowed to approve transfers, from the entry for
+    /// `APPROVERS_SETTING`. No entry means no approvers.
+    fn get_approvers(&self, context: &mut dyn TransactionContext) -> Result<Vec<String>, ApplyError> {
+        Ok(self
+            .get_setting(context, APPROVERS_SETTING)?
+            .map(|value| parse_approvers(&value))
+            .unwrap_or_default())
+    }
+
+    /// Checks that `signer` may change or delete `state`: it must be the
+    /// bound custodian key or listed in `APPROVERS_SETTING`. Returns whether
+    /// the signer is an approver.
+    fn authorize_owner(
+        &self,
+        context: &mut dyn TransactionContext,
+        state: &PropertyState,
+        signer: &str,
+    ) -> Result<bool, ApplyError> {
+        let is_approver = self.get_approvers(context)?.iter().any(|key| key.eq_ignore_ascii_case(signer));
+        let is_custodian = state.custodian_key.as_deref().is_some_and(|key| key.eq_ignore_ascii_case(signer));
+        if !is_approver && !is_custodian {
+            return Err(ApplyError::InvalidTransaction(format!(
+                "Signer {} is neither the custodian of property {} nor an approver",
+                signer, state.id
+            )));
+        }
+        Ok(is_approver)
+    }
+
+    /// Time carried by a payload. The processor never reads its own clock,
+    /// since every validator has to write the same state.
+    fn payload_time(&self, millis: i64) -> Result<DateTime<Utc>, ApplyError> {
+        if millis <= 0 {
+            return Err(ApplyError::InvalidTransaction("Payload timestamp is missing".into()));
+        }
+        from_millis(millis).map_err(ApplyError::InvalidTransaction)
+    }
+
+    fn validate_metadata(&self, metadata: &PropertyMetadata) -> Result<(), ApplyError> {
+        if metadata.name.is_empty() {
+            return Err(ApplyError::InvalidTransaction("Property name cannot be empty".into()));
+        }
+        if metadata.category.is_empty() {
+            return Err(ApplyError::InvalidTransaction("Property category cannot be empty".into()));
+        }
+        Ok(())
+    }
+
+    fn apply_payload(
+        &self,
+        payload: HandReceiptPayload,
+        signer_public_key: &str,
+        context: &mut dyn TransactionContext,
+    ) -> Result<(), ApplyError> {
+        match payload {
+            HandReceiptPayload::Create { property_id, initial_custodian, metadata, custodian_key } => {
+                self.validate_metadata(&metadata)?;
+                if self.get_state(context, &property_id)?.is_some() {
+                    return Err(ApplyError::InvalidTransaction("Property already exists".into()));
+                }
+
+                let mut state = PropertyState::new(
+                    property_id,
+                    initial_custodian.clone(),
+                    initial_custodian,
+                    STATUS_AVAILABLE.to_string(),
+                    metadata.created_at,
+                );
+                state.custodian_key = custodian_key.map(|key| key.to_ascii_lowercase());
+                state.requires_approval = metadata.is_sensitive_item;
+                state.metadata = Some(metadata);
+                self.set_state(context, &state)?;
+                info!("Created property {}", state.id);
+            }
+
+            HandReceiptPayload::Transfer { property_id, to_custodian, transfer_id, signatures, timestamp, annex_digest } => {
+                if to_custodian.is_empty() {
+                    return Err(ApplyError::InvalidTransaction("Receiving custodian cannot be empty".into()));
+                }
+                let timestamp = self.payload_time(timestamp)?;
+                let record_id = uuid::Uuid::parse_str(&transfer_id)
+                    .map_err(|_| ApplyError::InvalidTransaction("Invalid transfer ID".into()))?;
+                let mut state = self
+                    .get_state(context, &property_id)?
+                    .ok_or_else(|| ApplyError::InvalidTransaction(PROPERTY_NOT_FOUND.into()))?;
+
+                // A resubmitted transfer must not move custody twice
+                if state.transfer_history.iter().any(|record| record.transfer_id == record_id) {
+                    debug!("Transfer {} already applied to property {}", transfer_id, property_id);
+                    return Ok(());
+                }
+
+                // Custody only moves once the required parties have signed
+                let digest = transfer_digest(&property_id, &state.custodian, &to_custodian, &transfer_id, &annex_digest);
+                let policy = ApprovalPolicy {
+                    custodian_key: state.custodian_key.clone(),
+                    requires_approval: state.requires_approval,
+                    approvers: self.get_approvers(context)?,
+                };
+                let receiving_key = policy
+                    .check(&digest, &signatures)
+                    .map_err(|err| {
+                        ApplyError::InvalidTransaction(format!("Transfer {} is not authorized: {}", transfer_id, err))
+                    })?
+                    .to_ascii_lowercase();
+
+                state.transfer_history.push(TransferRecord {
+                    transfer_id: record_id,
+                    from_custodian: state.custodian.clone(),
+                    to_custodian: to_custodian.clone(),
+                    timestamp,
+                    status: TransferStatus::Completed,
+                    signatures: vec![signer_public_key.to_string()],
+                    transfer_signatures: signatures,
+                    annex_digest,
+                });
+                state.custodian = to_custodian;
+                state.custodian_key = Some(receiving_key);
+                state.last_updated = timestamp;
+                self.set_state(context, &state)?;
+                info!("Transferred property {} ({})", property_id, transfer_id);
+            }
+
+            HandReceiptPayload::Update { property_id, metadata, updated_at, status } => {
+                self.validate_metadata(&metadata)?;
+                let updated_at = self.payload_time(updated_at)?;
+                let mut state = self
+                    .get_state(context, &property_id)?
+                    .ok_or_else(|| ApplyError::InvalidTransaction(PROPERTY_NOT_FOUND.into()))?;
+
+                // Only an approver may drop the approver requirement
+                let is_approver = self.authorize_owner(context, &state, signer_public_key)?;
+                if state.requires_approval && !metadata.is_sensitive_item && !is_approver {
+                    return Err(ApplyError::InvalidTransaction(format!(
+                        "Only an approver may clear the approval requirement of property {}",
+                        property_id
+                    )));
+                }
+
+                state.requires_approval = metadata.is_sensitive_item;
+                state.metadata = Some(metadata);
+                if !status.is_empty() {
+                    state.status = status;
+                }
+                state.last_updated = updated_at;
+                self.set_state(context, &state)?;
+            }
+
+            HandReceiptPayload::Delete { property_id } => {
+                let state = self
+                    .get_state(context, &property_id)?
+                    .ok_or_else(|| ApplyError::InvalidTransaction(PROPERTY_NOT_FOUND.into()))?;
+                // Deleting and re-creating would otherwise rebind custody
+                self.authorize_owner(context, &state, signer_public_key)?;
+                context.delete_state_entry(&PropertyState::get_address(&property_id))?;
+            }
+
+            HandReceiptPayload::AnchorRevocations { issuer, digest, revoked_count, updated_at } => {
+                if issuer.is_empty() {
+                    return Err(ApplyError::InvalidTransaction("Revocation issuer cannot be empty".into()));
+                }
+                if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(ApplyError::InvalidTransaction("Invalid revocation list digest".into()));
+                }
+
+                // Only the certificate authority's signer may anchor lists
+                let authority = self.get_setting(context, REVOCATION_AUTHORITY_SETTING)?;
+                if !authority.is_some_and(|key| key.trim().eq_ignore_ascii_case(signer_public_key)) {
+                    return Err(ApplyError::InvalidTransaction(format!(
+                        "Signer {} is not the revocation authority",
+                        signer_public_key
+                    )));
+                }
+
+                // A delayed anchor must not roll back a newer revocation list
+                let address = revocation_anchor_address(&issuer);
+                if let Some(data) = context.get_state_entry(&address)? {
+                    let current = RevocationAnchor::from_bytes(&data).map_err(|err| {
+                        ApplyError::InvalidTransaction(format!("Failed to deserialize anchor: {}", err))
+                    })?;
+                    if current.updated_at > updated_at {
+                        return Err(ApplyError::InvalidTransaction(format!(
+                            "Revocation list for {} is older than the anchored one",
+                            issuer
+                        )));
+                    }
+                }
+
+                let anchor = RevocationAnchor {
+                    issuer,
+                    digest: digest.to_ascii_lowercase(),
+                    revoked_count,
+                    updated_at,
+                };
+                context.set_state_entry(address, anchor.to_bytes())?;
+                info!("Anchored revocation list of {} ({} revoked)", anchor.issuer, revoked_count);
+            }
         }
+
+        Ok(())
     }
 }
 
@@ -40,16 +296,519 @@ impl TransactionHandler for HandReceiptTransactionHandler {
         request: &TpProcessRequest,
         context: &mut dyn TransactionContext,
     ) -> Result<(), ApplyError> {
-        let payload = request.get_payload();
         let header = request.get_header();
+        let family_version = header.get_family_version();
+        debug!("Processing {} transaction from {}", family_version, header.get_signer_public_key());
 
-        // TODO: Implement the actual transaction processing logic here
-        // This should include:
-        // 1. Deserialize the payload
-        // 2. Validate the transaction
-        // 3. Update the state
-        // 4. Return success or error
+        // Family version 1.0 is legacy JSON, 2.0 is protobuf
+        let payload = HandReceiptPayload::decode(family_version, request.get_payload())
+            .map_err(|err| ApplyError::InvalidTransaction(format!("Failed to decode payload: {}", err)))?;
 
-        Ok(())
+        self.apply_payload(payload, header.get_signer_public_key(), context)
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::SigningKey;
+    use handreceipt_protocol::messages::PayloadEncoding;
+    use handreceipt_protocol::signing::{TransferRole, TransferSignature};
+    use protobuf::Message;
+    use sawtooth_sdk::messages::setting::Setting_Entry;
+    use sawtooth_sdk::messages::transaction::TransactionHeader;
+    use sawtooth_sdk::processor::handler::ContextError;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    struct MockContext {
+        state: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    impl MockContext {
+        fn new() -> Self {
+            Self {
+                state: Mutex::new(HashMap::new()),
+            }
+        }
+    }
+
+    impl TransactionContext for MockContext {
+        fn get_state_entries(&self, addresses: &[String]) -> Result<Vec<(String, Vec<u8>)>, ContextError> {
+            let state = self.state.lock().unwrap();
+            Ok(addresses
+                .iter()
+                .filter_map(|addr| state.get(addr).map(|data| (addr.clone(), data.clone())))
+                .collect())
+        }
+
+        fn set_state_entries(&self, entries: Vec<(String, Vec<u8>)>) -> Result<(), ContextError> {
+            let mut state = self.state.lock().unwrap();
+            for (addr, data) in entries {
+                state.insert(addr, data);
+            }
+            Ok(())
+        }
+
+        fn delete_state_entries(&self, addresses: &[String]) -> Result<Vec<String>, ContextError> {
+            let mut state = self.state.lock().unwrap();
+            Ok(addresses
+                .iter()
+                .filter(|addr| state.remove(*addr).is_some())
+                .cloned()
+                .collect())
+        }
+
+        fn add_receipt_data(&self, _data: &[u8]) -> Result<(), ContextError> {
+            Ok(())
+        }
+
+        fn add_event(
+            &self,
+            _event_type: String,
+            _attributes: Vec<(String, String)>,
+            _data: &[u8],
+        ) -> Result<(), ContextError> {
+            Ok(())
+        }
+    }
+
+    fn request(payload: &HandReceiptPayload, encoding: PayloadEncoding) -> TpProcessRequest {
+        request_from(payload, encoding, "signer")
+    }
+
+    fn request_from(payload: &HandReceiptPayload, encoding: PayloadEncoding, signer: &str) -> TpProcessRequest {
+        let mut header = TransactionHeader::new();
+        header.set_family_name(FAMILY_NAME.to_string());
+        header.set_family_version(encoding.family_version().to_string());
+        header.set_signer_public_key(signer.to_string());
+
+        let mut request = TpProcessRequest::new();
+        request.set_header(header);
+        request.set_payload(payload.encode(encoding).unwrap());
+        request
+    }
+
+    fn create_payload() -> HandReceiptPayload {
+        HandReceiptPayload::Create {
+            property_id: "property-1".to_string(),
+            initial_custodian: "custodian-1".to_string(),
+            metadata: PropertyMetadata {
+                name: "M4 Carbine".to_string(),
+                description: "Rifle".to_string(),
+                category: "Weapon".to_string(),
+                serial_number: Some("W123".to_string()),
+                is_sensitive_item: true,
+                created_at: Utc::now(),
+            },
+            custodian_key: None,
+        }
+    }
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn public_key(seed: u8) -> String {
+        hex::encode(key(seed).verifying_key().to_bytes())
+    }
+
+    const APPROVER: u8 = 9;
+    const TRANSFERRED_AT: i64 = 1_700_000_000_000;
+
+    fn set_setting(context: &mut MockContext, key: &str, value: String) {
+        let mut entry = Setting_Entry::new();
+        entry.set_key(key.to_string());
+        entry.set_value(value);
+        let mut setting = Setting::new();
+        setting.set_entries(protobuf::RepeatedField::from_vec(vec![entry]));
+        context
+            .set_state_entry(setting_address(key), setting.write_to_bytes().unwrap())
+            .unwrap();
+    }
+
+    fn set_approvers(context: &mut MockContext, seeds: &[u8]) {
+        set_setting(
+            context,
+            APPROVERS_SETTING,
+            seeds.iter().map(|seed| public_key(*seed)).collect::<Vec<_>>().join(","),
+        );
+    }
+
+    /// Transfer of property-1 from `from_custodian`, signed by each
+    /// (role, key seed) pair.
+    fn signed_transfer(
+        from_custodian: &str,
+        to_custodian: &str,
+        transfer_id: &str,
+        signers: &[(TransferRole, u8)],
+    ) -> HandReceiptPayload {
+        let digest = transfer_digest("property-1", from_custodian, to_custodian, transfer_id, "");
+        HandReceiptPayload::Transfer {
+            property_id: "property-1".to_string(),
+            to_custodian: to_custodian.to_string(),
+            transfer_id: transfer_id.to_string(),
+            signatures: signers
+                .iter()
+                .map(|(role, seed)| TransferSignature::sign(*role, &key(*seed), &digest))
+                .collect(),
+            timestamp: TRANSFERRED_AT,
+            annex_digest: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_legacy_json_and_protobuf_payloads() {
+        let handler = HandReceiptTransactionHandler::new();
+        let mut context = MockContext::new();
+        set_approvers(&mut context, &[APPROVER]);
+        assert_eq!(handler.family_versions(), vec!["1.0", "2.0"]);
+
+        // Create with a legacy JSON payload
+        handler
+            .apply(&request(&create_payload(), PayloadEncoding::Json), &mut context)
+            .unwrap();
+
+        // Transfer with a protobuf payload
+        let transfer = signed_transfer(
+            "custodian-1",
+            "custodian-2",
+            &uuid::Uuid::new_v4().to_string(),
+            &[(TransferRole::Releasing, 1), (TransferRole::Receiving, 2), (TransferRole::Approver, APPROVER)],
+        );
+        handler
+            .apply(&request(&transfer, PayloadEncoding::Protobuf), &mut context)
+            .unwrap();
+
+        let state = handler.get_state(&mut context, "property-1").unwrap().unwrap();
+        assert_eq!(state.custodian, "custodian-2");
+        assert_eq!(state.custodian_key, Some(public_key(2)));
+        assert_eq!(state.last_updated.timestamp_millis(), TRANSFERRED_AT);
+        assert_eq!(state.transfer_history.len(), 1);
+        assert_eq!(state.transfer_history[0].timestamp.timestamp_millis(), TRANSFERRED_AT);
+        assert_eq!(state.transfer_history[0].from_custodian, "custodian-1");
+        assert_eq!(state.transfer_history[0].transfer_signatures.len(), 3);
+    }
+
+    #[test]
+    fn test_repeated_transfer_is_applied_once() {
+        let handler = HandReceiptTransactionHandler::new();
+        let mut context = MockContext::new();
+        set_approvers(&mut context, &[APPROVER]);
+        handler
+            .apply(&request(&create_payload(), PayloadEncoding::Protobuf), &mut context)
+            .unwrap();
+
+        let transfer_id = uuid::Uuid::new_v4().to_string();
+        for custodian in ["custodian-2", "custodian-3"] {
+            let transfer = signed_transfer(
+                "custodian-1",
+                custodian,
+                &transfer_id,
+                &[(TransferRole::Releasing, 1), (TransferRole::Receiving, 2), (TransferRole::Approver, APPROVER)],
+            );
+            handler
+                .apply(&request(&transfer, PayloadEncoding::Protobuf), &mut context)
+                .unwrap();
+        }
+
+        let state = handler.get_state(&mut context, "property-1").unwrap().unwrap();
+        assert_eq!(state.custodian, "custodian-2");
+        assert_eq!(state.transfer_history.len(), 1);
+    }
+
+    #[test]
+    fn test_custody_moves_only_with_required_signers() {
+        let handler = HandReceiptTransactionHandler::new();
+        let mut context = MockContext::new();
+        set_approvers(&mut context, &[APPROVER]);
+        handler
+            .apply(&request(&create_payload(), PayloadEncoding::Protobuf), &mut context)
+            .unwrap();
+
+        let rejected = [
+            // Unsigned
+            signed_transfer("custodian-1", "custodian-2", "00000000-0000-0000-0000-000000000001", &[]),
+            // Sensitive item without an approver
+            signed_transfer(
+                "custodian-1",
+                "custodian-2",
+                "00000000-0000-0000-0000-000000000001",
+                &[(TransferRole::Releasing, 1), (TransferRole::Receiving, 2)],
+            ),
+            // Approver not in the setting
+            signed_transfer(
+                "custodian-1",
+                "custodian-2",
+                "00000000-0000-0000-0000-000000000001",
+                &[(TransferRole::Releasing, 1), (TransferRole::Receiving, 2), (TransferRole::Approver, 3)],
+            ),
+            // Signed over a different releasing custodian
+            signed_transfer(
+                "custodian-9",
+                "custodian-2",
+                "00000000-0000-0000-0000-000000000001",
+                &[(TransferRole::Releasing, 1), (TransferRole::Receiving, 2), (TransferRole::Approver, APPROVER)],
+            ),
+        ];
+        for transfer in &rejected {
+            assert!(handler.apply(&request(transfer, PayloadEncoding::Protobuf), &mut context).is_err());
+        }
+        let state = handler.get_state(&mut context, "property-1").unwrap().unwrap();
+        assert_eq!(state.custodian, "custodian-1");
+        assert!(state.transfer_history.is_empty());
+
+        let transfer = signed_transfer(
+            "custodian-1",
+            "custodian-2",
+            "00000000-0000-0000-0000-000000000001",
+            &[(TransferRole::Releasing, 1), (TransferRole::Receiving, 2), (TransferRole::Approver, APPROVER)],
+        );
+        handler
+            .apply(&request(&transfer, PayloadEncoding::Protobuf), &mut context)
+            .unwrap();
+
+        // Custody is now bound to key 2, so key 1 can no longer release
+        let transfer = signed_transfer(
+            "custodian-2",
+            "custodian-3",
+            "00000000-0000-0000-0000-000000000002",
+            &[(TransferRole::Releasing, 1), (TransferRole::Receiving, 3), (TransferRole::Approver, APPROVER)],
+        );
+        assert!(handler.apply(&request(&transfer, PayloadEncoding::Protobuf), &mut context).is_err());
+
+        let transfer = signed_transfer(
+            "custodian-2",
+            "custodian-3",
+            "00000000-0000-0000-0000-000000000002",
+            &[(TransferRole::Releasing, 2), (TransferRole::Receiving, 3), (TransferRole::Approver, APPROVER)],
+        );
+        handler
+            .apply(&request(&transfer, PayloadEncoding::Protobuf), &mut context)
+            .unwrap();
+
+        let state = handler.get_state(&mut context, "property-1").unwrap().unwrap();
+        assert_eq!(state.custodian, "custodian-3");
+        assert_eq!(state.custodian_key, Some(public_key(3)));
+    }
+
+    #[test]
+    fn test_bound_routine_item_needs_only_holders() {
+        let handler = HandReceiptTransactionHandler::new();
+        let mut context = MockContext::new();
+
+        let mut create = create_payload();
+        if let HandReceiptPayload::Create { metadata, custodian_key, .. } = &mut create {
+            metadata.is_sensitive_item = false;
+            *custodian_key = Some(public_key(1));
+        }
+        handler
+            .apply(&request(&create, PayloadEncoding::Protobuf), &mut context)
+            .unwrap();
+
+        let transfer = signed_transfer(
+            "custodian-1",
+            "custodian-2",
+            "00000000-0000-0000-0000-000000000001",
+            &[(TransferRole::Releasing, 1), (TransferRole::Receiving, 2)],
+        );
+        handler
+            .apply(&request(&transfer, PayloadEncoding::Protobuf), &mut context)
+            .unwrap();
+
+        let state = handler.get_state(&mut context, "property-1").unwrap().unwrap();
+        assert_eq!(state.custodian, "custodian-2");
+    }
+
+    #[test]
+    fn test_update_keeps_metadata_and_payload_time() {
+        let handler = HandReceiptTransactionHandler::new();
+        let mut context = MockContext::new();
+        set_setting(&mut context, APPROVERS_SETTING, "signer".to_string());
+        handler
+            .apply(&request(&create_payload(), PayloadEncoding::Protobuf), &mut context)
+            .unwrap();
+
+        let mut metadata = match create_payload() {
+            HandReceiptPayload::Create { metadata, .. } => metadata,
+            _ => unreachable!(),
+        };
+        metadata.description = "Rifle, 5.56mm".to_string();
+        metadata.is_sensitive_item = false;
+        // State stores times in milliseconds
+        metadata.created_at = from_millis(metadata.created_at.timestamp_millis()).unwrap();
+        let update = |updated_at| HandReceiptPayload::Update {
+            property_id: "property-1".to_string(),
+            metadata: metadata.clone(),
+            updated_at,
+            status: "maintenance".to_string(),
+        };
+
+        // Without a payload time there is nothing every validator agrees on
+        assert!(handler.apply(&request(&update(0), PayloadEncoding::Protobuf), &mut context).is_err());
+        assert!(handler
+            .apply(&request(&update(i64::MAX), PayloadEncoding::Protobuf), &mut context)
+            .is_err());
+
+        handler
+            .apply(&request(&update(TRANSFERRED_AT), PayloadEncoding::Protobuf), &mut context)
+            .unwrap();
+        let state = handler.get_state(&mut context, "property-1").unwrap().unwrap();
+        assert_eq!(state.metadata.as_ref(), Some(&metadata));
+        assert_eq!(state.status, "maintenance");
+        assert!(!state.requires_approval);
+        assert_eq!(state.last_updated.timestamp_millis(), TRANSFERRED_AT);
+    }
+
+    #[test]
+    fn test_update_and_delete_need_custodian_or_approver() {
+        let handler = HandReceiptTransactionHandler::new();
+        let mut context = MockContext::new();
+        set_approvers(&mut context, &[APPROVER]);
+
+        let mut create = create_payload();
+        let mut metadata = match &mut create {
+            HandReceiptPayload::Create { metadata, custodian_key, .. } => {
+                *custodian_key = Some(public_key(1));
+                metadata.clone()
+            }
+            _ => unreachable!(),
+        };
+        handler
+            .apply(&request(&create, PayloadEncoding::Protobuf), &mut context)
+            .unwrap();
+
+        metadata.description = "Rifle, 5.56mm".to_string();
+        let update = |metadata: &PropertyMetadata| HandReceiptPayload::Update {
+            property_id: "property-1".to_string(),
+            metadata: metadata.clone(),
+            updated_at: TRANSFERRED_AT,
+            status: String::new(),
+        };
+        let delete = HandReceiptPayload::Delete {
+            property_id: "property-1".to_string(),
+        };
+
+        // Anyone else can neither change nor delete the item
+        for payload in [update(&metadata), delete.clone()] {
+            assert!(handler
+                .apply(&request_from(&payload, PayloadEncoding::Protobuf, &public_key(2)), &mut context)
+                .is_err());
+        }
+
+        // The custodian can update it but not drop the approver requirement
+        handler
+            .apply(&request_from(&update(&metadata), PayloadEncoding::Protobuf, &public_key(1)), &mut context)
+            .unwrap();
+        metadata.is_sensitive_item = false;
+        assert!(handler
+            .apply(&request_from(&update(&metadata), PayloadEncoding::Protobuf, &public_key(1)), &mut context)
+            .is_err());
+        assert!(handler.get_state(&mut context, "property-1").unwrap().unwrap().requires_approval);
+
+        handler
+            .apply(&request_from(&update(&metadata), PayloadEncoding::Protobuf, &public_key(APPROVER)), &mut context)
+            .unwrap();
+        assert!(!handler.get_state(&mut context, "property-1").unwrap().unwrap().requires_approval);
+
+        handler
+            .apply(&request_from(&delete, PayloadEncoding::Protobuf, &public_key(1)), &mut context)
+            .unwrap();
+        assert!(handler.get_state(&mut context, "property-1").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_revocation_anchor_rejects_stale_list() {
+        let handler = HandReceiptTransactionHandler::new();
+        let mut context = MockContext::new();
+        set_setting(&mut context, REVOCATION_AUTHORITY_SETTING, "signer".to_string());
+
+        let anchor = |digest: &str, updated_at: i64| HandReceiptPayload::AnchorRevocations {
+            issuer: "brigade-ca".to_string(),
+            digest: digest.to_string(),
+            revoked_count: 1,
+            updated_at,
+        };
+        handler
+            .apply(&request(&anchor(&"ab".repeat(32), 2_000), PayloadEncoding::Protobuf), &mut context)
+            .unwrap();
+        assert!(handler
+            .apply(&request(&anchor(&"cd".repeat(32), 1_000), PayloadEncoding::Protobuf), &mut context)
+            .is_err());
+        assert!(handler
+            .apply(&request(&anchor("not-a-digest", 3_000), PayloadEncoding::Protobuf), &mut context)
+            .is_err());
+
+        let data = context
+            .get_state_entry(&revocation_anchor_address("brigade-ca"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(RevocationAnchor::from_bytes(&data).unwrap().digest, "ab".repeat(32));
+    }
+
+    #[test]
+    fn test_revocation_anchor_requires_authority() {
+        let handler = HandReceiptTransactionHandler::new();
+        let mut context = MockContext::new();
+        let anchor = HandReceiptPayload::AnchorRevocations {
+            issuer: "brigade-ca".to_string(),
+            digest: "ab".repeat(32),
+            revoked_count: 1,
+            updated_at: 1_000,
+        };
+
+        assert!(handler
+            .apply(&request(&anchor, PayloadEncoding::Protobuf), &mut context)
+            .is_err());
+
+        set_setting(&mut context, REVOCATION_AUTHORITY_SETTING, "another-signer".to_string());
+        assert!(handler
+            .apply(&request(&anchor, PayloadEncoding::Protobuf), &mut context)
+            .is_err());
+        assert!(context
+            .get_state_entry(&revocation_anchor_address("brigade-ca"))
+            .unwrap()
+            .is_none());
+
+        set_setting(&mut context, REVOCATION_AUTHORITY_SETTING, "signer".to_string());
+        handler
+            .apply(&request(&anchor, PayloadEncoding::Protobuf), &mut context)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_reads_legacy_json_state() {
+        let handler = HandReceiptTransactionHandler::new();
+        let mut context = MockContext::new();
+
+        let legacy = PropertyState::new(
+            "property-1".to_string(),
+            "custodian-1".to_string(),
+            "custodian-1".to_string(),
+            STATUS_AVAILABLE.to_string(),
+            Utc::now(),
+        );
+        context
+            .set_state_entry(
+                PropertyState::get_address("property-1"),
+                serde_json::to_vec(&legacy).unwrap(),
+            )
+            .unwrap();
+
+        let state = handler.get_state(&mut context, "property-1").unwrap().unwrap();
+        assert_eq!(state.custodian, "custodian-1");
+    }
+
+    #[test]
+    fn test_rejects_unknown_family_version() {
+        let handler = HandReceiptTransactionHandler::new();
+        let mut context = MockContext::new();
+
+        let mut request = request(&create_payload(), PayloadEncoding::Protobuf);
+        let mut header = request.get_header().clone();
+        header.set_family_version("3.0".to_string());
+        request.set_header(header);
+
+        assert!(handler.apply(&request, &mut context).is_err());
+    }
+}
@@ -0,0 +1,933 @@
+//! Single-node ledger that runs in-process for development and tests.
+//!
+//! `EmbeddedLedger` validates and executes batches with
+//! `HandReceiptTransactionHandler` against in-memory state, sealing one block
+//! per committed batch. `EmbeddedLedgerService` serves it on a loopback port
+//! with the same REST shapes as the Sawtooth REST API, so `SawtoothClient`
+//! and everything built on it work unchanged without a validator.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+use actix_web::{dev::ServerHandle, web, App, HttpResponse, HttpServer};
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use handreceipt_protocol::merkle::{hash_leaf, tree_levels};
+use handreceipt_protocol::signing::APPROVERS_SETTING;
+use parking_lot::RwLock;
+use protobuf::Message;
+use sawtooth_sdk::messages::batch::{Batch, BatchHeader, BatchList};
+use sawtooth_sdk::messages::processor::TpProcessRequest;
+use sawtooth_sdk::messages::setting::{Setting, Setting_Entry};
+use sawtooth_sdk::messages::transaction::{Transaction, TransactionHeader};
+use sawtooth_sdk::processor::handler::{ApplyError, ContextError, TransactionContext, TransactionHandler};
+use sawtooth_sdk::signing::secp256k1::{Secp256k1Context, Secp256k1PublicKey};
+use sawtooth_sdk::signing::Context;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    error::blockchain::BlockchainError,
+    infrastructure::blockchain::{
+        consensus::{ConsensusEngine, ALGORITHM_NAME_SETTING, ALGORITHM_VERSION_SETTING},
+        merkle::{MerkleProof, MerkleTree},
+        types::ChainStatus,
+        verification::TransferVerification,
+        BlockchainConfig, BlockchainService,
+    },
+};
+
+use super::client::{BatchStatus, BatchStatusEntry, InvalidTransaction};
+use super::handler::HandReceiptTransactionHandler;
+use super::service::SawtoothService;
+
+/// Previous block id of the genesis block, as Sawtooth reports it.
+pub const NULL_BLOCK_ID: &str = "0000000000000000";
+
+/// Page size for `/blocks` when the request does not give one.
+const DEFAULT_PAGE_LIMIT: usize = 100;
+const MAX_PAGE_LIMIT: usize = 1000;
+
+/// Header of a block sealed by the embedded ledger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedgerBlock {
+    pub block_id: String,
+    pub block_num: u64,
+    pub previous_block_id: String,
+    pub state_root_hash: String,
+    pub batch_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct BatchRecord {
+    batch: Batch,
+    status: BatchStatus,
+    invalid_transactions: Vec<InvalidTransaction>,
+}
+
+#[derive(Default)]
+struct LedgerState {
+    state: BTreeMap<String, Vec<u8>>,
+    blocks: Vec<LedgerBlock>,
+    batches: HashMap<String, BatchRecord>,
+    /// Committed transactions and the block that holds them.
+    transactions: HashMap<String, (Transaction, String)>,
+}
+
+/// In-memory single-node ledger.
+pub struct EmbeddedLedger {
+    handler: HandReceiptTransactionHandler,
+    inner: RwLock<LedgerState>,
+}
+
+impl Default for EmbeddedLedger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EmbeddedLedger {
+    pub fn new() -> Self {
+        Self {
+            handler: HandReceiptTransactionHandler::new(),
+            inner: RwLock::new(LedgerState::default()),
+        }
+    }
+
+    /// Writes a `sawtooth_settings` entry directly into state, as a genesis
+    /// batch would.
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<(), BlockchainError> {
+        let mut entry = Setting_Entry::new();
+        entry.set_key(key.to_string());
+        entry.set_value(value.to_string());
+        let mut setting = Setting::new();
+        setting.set_entries(protobuf::RepeatedField::from_vec(vec![entry]));
+        let bytes = setting
+            .write_to_bytes()
+            .map_err(|e| BlockchainError::SerializationError(e.to_string()))?;
+
+        self.inner
+            .write()
+            .state
+            .insert(handreceipt_protocol::addressing::setting_address(key), bytes);
+        Ok(())
+    }
+
+    /// Validates and executes every batch in a serialized `BatchList`,
+    /// returning the batch ids in order.
+    ///
+    /// Malformed or badly signed batches reject the whole list, as the REST
+    /// API does. A batch whose transactions fail is recorded as INVALID and
+    /// leaves state untouched. Resubmitting a known batch is a no-op.
+    pub fn submit(&self, batch_list: &[u8]) -> Result<Vec<String>, BlockchainError> {
+        let batch_list = BatchList::parse_from_bytes(batch_list)
+            .map_err(|e| BlockchainError::SerializationError(e.to_string()))?;
+        let batches = batch_list.get_batches();
+        if batches.is_empty() {
+            return Err(BlockchainError::ValidationError("No batches submitted".to_string()));
+        }
+        for batch in batches {
+            verify_batch_signatures(batch)?;
+        }
+
+        let mut inner = self.inner.write();
+        let mut ids = Vec::with_capacity(batches.len());
+        for batch in batches {
+            let batch_id = batch.get_header_signature().to_string();
+            if !inner.batches.contains_key(&batch_id) {
+                let record = match self.execute(&inner, batch) {
+                    Ok(state) => {
+                        inner.state = state;
+                        self.seal_block(&mut inner, batch);
+                        BatchRecord {
+                            batch: batch.clone(),
+                            status: BatchStatus::Committed,
+                            invalid_transactions: Vec::new(),
+                        }
+                    }
+                    Err(invalid) => BatchRecord {
+                        batch: batch.clone(),
+                        status: BatchStatus::Invalid,
+                        invalid_transactions: vec![invalid],
+                    },
+                };
+                inner.batches.insert(batch_id.clone(), record);
+            }
+            ids.push(batch_id);
+        }
+        Ok(ids)
+    }
+
+    /// Runs a batch's transactions in order against a copy of state.
+    fn execute(
+        &self,
+        inner: &LedgerState,
+        batch: &Batch,
+    ) -> Result<BTreeMap<String, Vec<u8>>, InvalidTransaction> {
+        let mut state = inner.state.clone();
+        let mut applied = HashSet::new();
+
+        for transaction in batch.get_transactions() {
+            let id = transaction.get_header_signature().to_string();
+            let invalid = |message: String| InvalidTransaction {
+                id: id.clone(),
+                message,
+                extended_data: None,
+            };
+
+            let header = TransactionHeader::parse_from_bytes(transaction.get_header())
+                .map_err(|e| invalid(format!("Malformed transaction header: {}", e)))?;
+            if header.get_family_name() != self.handler.family_name()
+                || !self
+                    .handler
+                    .family_versions()
+                    .iter()
+                    .any(|version| version == header.get_family_version())
+            {
+                return Err(invalid(format!(
+                    "No transaction processor for {} {}",
+                    header.get_family_name(),
+                    header.get_family_version()
+                )));
+            }
+            if header.get_payload_sha512() != hex::encode(openssl::sha::sha512(transaction.get_payload())) {
+                return Err(invalid("Payload does not match its hash".to_string()));
+            }
+            if let Some(missing) = header
+                .get_dependencies()
+                .iter()
+                .find(|dependency| !inner.transactions.contains_key(*dependency) && !applied.contains(*dependency))
+            {
+                return Err(invalid(format!("Dependency {} has not been committed", missing)));
+            }
+            if inner.transactions.contains_key(&id) {
+                return Err(invalid("Transaction has already been committed".to_string()));
+            }
+
+            let mut request = TpProcessRequest::new();
+            request.set_header(header.clone());
+            request.set_payload(transaction.get_payload().to_vec());
+            request.set_signature(id.clone());
+
+            let mut context = LedgerContext::new(state, &header);
+            match self.handler.apply(&request, &mut context) {
+                Ok(()) => state = context.into_state(),
+                Err(ApplyError::InvalidTransaction(message)) | Err(ApplyError::InternalError(message)) => {
+                    return Err(invalid(message));
+                }
+            }
+            applied.insert(id);
+        }
+        Ok(state)
+    }
+
+    fn seal_block(&self, inner: &mut LedgerState, batch: &Batch) {
+        let previous_block_id = inner
+            .blocks
+            .last()
+            .map(|block| block.block_id.clone())
+            .unwrap_or_else(|| NULL_BLOCK_ID.to_string());
+        let block_num = inner.blocks.len() as u64;
+        let state_root_hash = state_root(&inner.state);
+        let batch_ids = vec![batch.get_header_signature().to_string()];
+
+        let mut preimage = Vec::new();
+        preimage.extend_from_slice(&block_num.to_be_bytes());
+        for field in [&previous_block_id, &state_root_hash, &batch_ids[0]] {
+            preimage.extend_from_slice(field.as_bytes());
+        }
+        let block_id = hex::encode(openssl::sha::sha512(&preimage));
+
+        for transaction in batch.get_transactions() {
+            inner.transactions.insert(
+                transaction.get_header_signature().to_string(),
+                (transaction.clone(), block_id.clone()),
+            );
+        }
+        inner.blocks.push(LedgerBlock {
+            block_id,
+            block_num,
+            previous_block_id,
+            state_root_hash,
+            batch_ids,
+        });
+    }
+
+    pub fn head(&self) -> Option<LedgerBlock> {
+        self.inner.read().blocks.last().cloned()
+    }
+
+    pub fn get_state(&self, address: &str) -> Option<Vec<u8>> {
+        self.inner.read().state.get(address).cloned()
+    }
+
+    /// Status of each batch id, UNKNOWN for batches never submitted.
+    pub fn batch_statuses(&self, ids: &[String]) -> Vec<BatchStatusEntry> {
+        let inner = self.inner.read();
+        ids.iter()
+            .map(|id| match inner.batches.get(id) {
+                Some(record) => BatchStatusEntry {
+                    id: id.clone(),
+                    status: record.status,
+                    invalid_transactions: record.invalid_transactions.clone(),
+                },
+                None => BatchStatusEntry {
+                    id: id.clone(),
+                    status: BatchStatus::Unknown,
+                    invalid_transactions: Vec::new(),
+                },
+            })
+            .collect()
+    }
+
+    // REST representations, matching the Sawtooth REST API
+
+    /// `/state/{address}` body, or `None` if the address is unset.
+    pub fn state_json(&self, address: &str) -> Option<Value> {
+        let inner = self.inner.read();
+        let data = inner.state.get(address)?;
+        Some(json!({
+            "data": BASE64.encode(data),
+            "head": inner.blocks.last().map(|block| block.block_id.clone()),
+        }))
+    }
+
+    pub fn batch_json(&self, batch_id: &str) -> Option<Value> {
+        let inner = self.inner.read();
+        let record = inner.batches.get(batch_id)?;
+        Some(json!({ "data": batch_to_json(&record.batch) }))
+    }
+
+    /// Committed transactions only, as the validator only serves those.
+    pub fn transaction_json(&self, transaction_id: &str) -> Option<Value> {
+        let inner = self.inner.read();
+        let (transaction, _) = inner.transactions.get(transaction_id)?;
+        Some(json!({ "data": transaction_to_json(transaction) }))
+    }
+
+    pub fn block_json(&self, block_id: &str) -> Option<Value> {
+        let inner = self.inner.read();
+        let block = inner.blocks.iter().find(|block| block.block_id == block_id)?;
+        Some(json!({ "data": block_to_json(&inner, block) }))
+    }
+
+    /// Newest-first page of blocks starting at block `start` (a block id),
+    /// or the head when `start` is `None`.
+    pub fn blocks_json(&self, start: Option<&str>, limit: Option<usize>) -> Option<Value> {
+        let inner = self.inner.read();
+        let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT);
+        let end = match start {
+            Some(start) => inner.blocks.iter().position(|block| block.block_id == start)? + 1,
+            None => inner.blocks.len(),
+        };
+        let begin = end.saturating_sub(limit);
+
+        let data: Vec<Value> = inner.blocks[begin..end]
+            .iter()
+            .rev()
+            .map(|block| block_to_json(&inner, block))
+            .collect();
+        let mut paging = json!({ "limit": limit });
+        if let Some(start) = start {
+            paging["start"] = json!(start);
+        }
+        if begin > 0 {
+            paging["next_position"] = json!(inner.blocks[begin - 1].block_id);
+        }
+
+        Some(json!({
+            "data": data,
+            "head": inner.blocks.last().map(|block| block.block_id.clone()),
+            "paging": paging,
+        }))
+    }
+}
+
+/// Sawtooth validators reject batches whose header or transaction signatures
+/// do not verify, or whose header does not list exactly its transactions.
+fn verify_batch_signatures(batch: &Batch) -> Result<(), BlockchainError> {
+    let context = Secp256k1Context::new();
+    let invalid = |message: String| BlockchainError::ValidationError(message);
+
+    let header = BatchHeader::parse_from_bytes(batch.get_header())
+        .map_err(|e| invalid(format!("Malformed batch header: {}", e)))?;
+    let signer = Secp256k1PublicKey::from_hex(header.get_signer_public_key())
+        .map_err(|e| invalid(format!("Invalid batch signer: {}", e)))?;
+    if !context
+        .verify(batch.get_header_signature(), batch.get_header(), &signer)
+        .unwrap_or(false)
+    {
+        return Err(invalid(format!("Invalid signature on batch {}", batch.get_header_signature())));
+    }
+
+    let transaction_ids: Vec<&str> = batch
+        .get_transactions()
+        .iter()
+        .map(|transaction| transaction.get_header_signature())
+        .collect();
+    if header.get_transaction_ids().iter().map(String::as_str).ne(transaction_ids.iter().copied()) {
+        return Err(invalid("Batch header does not match its transactions".to_string()));
+    }
+
+    for transaction in batch.get_transactions() {
+        let transaction_header = TransactionHeader::parse_from_bytes(transaction.get_header())
+            .map_err(|e| invalid(format!("Malformed transaction header: {}", e)))?;
+        if transaction_header.get_batcher_public_key() != header.get_signer_public_key() {
+            return Err(invalid("Transaction was not batched by its batch signer".to_string()));
+        }
+        let signer = Secp256k1PublicKey::from_hex(transaction_header.get_signer_public_key())
+            .map_err(|e| invalid(format!("Invalid transaction signer: {}", e)))?;
+        if !context
+            .verify(transaction.get_header_signature(), transaction.get_header(), &signer)
+            .unwrap_or(false)
+        {
+            return Err(invalid(format!(
+                "Invalid signature on transaction {}",
+                transaction.get_header_signature()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Merkle root over the sorted state entries, empty for empty state.
+pub fn state_root(state: &BTreeMap<String, Vec<u8>>) -> String {
+    let leaves = state
+        .iter()
+        .map(|(address, data)| {
+            let mut leaf = address.as_bytes().to_vec();
+            leaf.extend_from_slice(data);
+            hash_leaf(&leaf)
+        })
+        .collect();
+    tree_levels(leaves)
+        .last()
+        .and_then(|root| root.first().cloned())
+        .unwrap_or_default()
+}
+
+fn transaction_to_json(transaction: &Transaction) -> Value {
+    let header = TransactionHeader::parse_from_bytes(transaction.get_header()).unwrap_or_default();
+    json!({
+        "header": {
+            "batcher_public_key": header.get_batcher_public_key(),
+            "dependencies": header.get_dependencies(),
+            "family_name": header.get_family_name(),
+            "family_version": header.get_family_version(),
+            "inputs": header.get_inputs(),
+            "nonce": header.get_nonce(),
+            "outputs": header.get_outputs(),
+            "payload_sha512": header.get_payload_sha512(),
+            "signer_public_key": header.get_signer_public_key(),
+        },
+        "header_signature": transaction.get_header_signature(),
+        "payload": BASE64.encode(transaction.get_payload()),
+    })
+}
+
+fn batch_to_json(batch: &Batch) -> Value {
+    let header = BatchHeader::parse_from_bytes(batch.get_header()).unwrap_or_default();
+    json!({
+        "header": {
+            "signer_public_key": header.get_signer_public_key(),
+            "transaction_ids": header.get_transaction_ids(),
+        },
+        "header_signature": batch.get_header_signature(),
+        "trace": batch.get_trace(),
+        "transactions": batch
+            .get_transactions()
+            .iter()
+            .map(transaction_to_json)
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn block_to_json(inner: &LedgerState, block: &LedgerBlock) -> Value {
+    let batches: Vec<Value> = block
+        .batch_ids
+        .iter()
+        .filter_map(|id| inner.batches.get(id))
+        .map(|record| batch_to_json(&record.batch))
+        .collect();
+    json!({
+        "header": {
+            "batch_ids": block.batch_ids,
+            // The REST API renders uint64 fields as strings
+            "block_num": block.block_num.to_string(),
+            "consensus": BASE64.encode(b"Devmode"),
+            "previous_block_id": block.previous_block_id,
+            "signer_public_key": "",
+            "state_root_hash": block.state_root_hash,
+        },
+        "header_signature": block.block_id,
+        "batches": batches,
+    })
+}
+
+/// Transaction context confined to the transaction's declared inputs and
+/// outputs, as the validator enforces.
+struct LedgerContext {
+    state: Mutex<BTreeMap<String, Vec<u8>>>,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+}
+
+impl LedgerContext {
+    fn new(state: BTreeMap<String, Vec<u8>>, header: &TransactionHeader) -> Self {
+        Self {
+            state: Mutex::new(state),
+            inputs: header.get_inputs().to_vec(),
+            outputs: header.get_outputs().to_vec(),
+        }
+    }
+
+    fn into_state(self) -> BTreeMap<String, Vec<u8>> {
+        self.state.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn check(allowed: &[String], address: &str, access: &str) -> Result<(), ContextError> {
+        if allowed.iter().any(|prefix| address.starts_with(prefix.as_str())) {
+            Ok(())
+        } else {
+            Err(ContextError::AuthorizationError(format!(
+                "Tried to {} unauthorized address {}",
+                access, address
+            )))
+        }
+    }
+}
+
+impl TransactionContext for LedgerContext {
+    fn get_state_entries(&self, addresses: &[String]) -> Result<Vec<(String, Vec<u8>)>, ContextError> {
+        for address in addresses {
+            Self::check(&self.inputs, address, "get")?;
+        }
+        let state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Ok(addresses
+            .iter()
+            .filter_map(|address| state.get(address).map(|data| (address.clone(), data.clone())))
+            .collect())
+    }
+
+    fn set_state_entries(&self, entries: Vec<(String, Vec<u8>)>) -> Result<(), ContextError> {
+        for (address, _) in &entries {
+            Self::check(&self.outputs, address, "set")?;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.extend(entries);
+        Ok(())
+    }
+
+    fn delete_state_entries(&self, addresses: &[String]) -> Result<Vec<String>, ContextError> {
+        for address in addresses {
+            Self::check(&self.outputs, address, "delete")?;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Ok(addresses
+            .iter()
+            .filter(|address| state.remove(*address).is_some())
+            .cloned()
+            .collect())
+    }
+
+    fn add_receipt_data(&self, _data: &[u8]) -> Result<(), ContextError> {
+        Ok(())
+    }
+
+    fn add_event(
+        &self,
+        _event_type: String,
+        _attributes: Vec<(String, String)>,
+        _data: &[u8],
+    ) -> Result<(), ContextError> {
+        Ok(())
+    }
+}
+
+// REST API
+
+fn rest_error(status: actix_web::http::StatusCode, code: u32, title: &str, message: String) -> HttpResponse {
+    HttpResponse::build(status).json(json!({
+        "error": { "code": code, "title": title, "message": message }
+    }))
+}
+
+async fn post_batches(ledger: web::Data<Arc<EmbeddedLedger>>, body: web::Bytes) -> HttpResponse {
+    match ledger.submit(&body) {
+        Ok(ids) => HttpResponse::Accepted().json(json!({
+            "link": format!("/batch_statuses?id={}", ids.join(",")),
+        })),
+        Err(e) => rest_error(
+            actix_web::http::StatusCode::BAD_REQUEST,
+            30,
+            "Submitted Batches Invalid",
+            e.to_string(),
+        ),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchStatusQuery {
+    id: String,
+}
+
+async fn get_batch_statuses(
+    ledger: web::Data<Arc<EmbeddedLedger>>,
+    query: web::Query<BatchStatusQuery>,
+) -> HttpResponse {
+    // Batches execute on submission, so there is never anything to wait for
+    let ids: Vec<String> = query.id.split(',').map(str::to_string).collect();
+    HttpResponse::Ok().json(json!({ "data": ledger.batch_statuses(&ids) }))
+}
+
+async fn get_state(ledger: web::Data<Arc<EmbeddedLedger>>, address: web::Path<String>) -> HttpResponse {
+    match ledger.state_json(&address) {
+        Some(body) => HttpResponse::Ok().json(body),
+        None => rest_error(
+            actix_web::http::StatusCode::NOT_FOUND,
+            75,
+            "State Not Found",
+            format!("There is no state data at address {}", address),
+        ),
+    }
+}
+
+async fn get_batch(ledger: web::Data<Arc<EmbeddedLedger>>, batch_id: web::Path<String>) -> HttpResponse {
+    match ledger.batch_json(&batch_id) {
+        Some(body) => HttpResponse::Ok().json(body),
+        None => rest_error(
+            actix_web::http::StatusCode::NOT_FOUND,
+            71,
+            "Batch Not Found",
+            format!("There is no batch with the id {}", batch_id),
+        ),
+    }
+}
+
+async fn get_transaction(
+    ledger: web::Data<Arc<EmbeddedLedger>>,
+    transaction_id: web::Path<String>,
+) -> HttpResponse {
+    match ledger.transaction_json(&transaction_id) {
+        Some(body) => HttpResponse::Ok().json(body),
+        None => rest_error(
+            actix_web::http::StatusCode::NOT_FOUND,
+            72,
+            "Transaction Not Found",
+            format!("There is no transaction with the id {}", transaction_id),
+        ),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PagingQuery {
+    start: Option<String>,
+    limit: Option<usize>,
+}
+
+async fn get_blocks(ledger: web::Data<Arc<EmbeddedLedger>>, query: web::Query<PagingQuery>) -> HttpResponse {
+    match ledger.blocks_json(query.start.as_deref(), query.limit) {
+        Some(body) => HttpResponse::Ok().json(body),
+        None => rest_error(
+            actix_web::http::StatusCode::NOT_FOUND,
+            70,
+            "Block Not Found",
+            "The paging start block was not found".to_string(),
+        ),
+    }
+}
+
+async fn get_block(ledger: web::Data<Arc<EmbeddedLedger>>, block_id: web::Path<String>) -> HttpResponse {
+    match ledger.block_json(&block_id) {
+        Some(body) => HttpResponse::Ok().json(body),
+        None => rest_error(
+            actix_web::http::StatusCode::NOT_FOUND,
+            70,
+            "Block Not Found",
+            format!("There is no block with the id {}", block_id),
+        ),
+    }
+}
+
+/// Registers the Sawtooth REST API routes backed by `ledger`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/batches", web::post().to(post_batches))
+        .route("/batches/{batch_id}", web::get().to(get_batch))
+        .route("/batch_statuses", web::get().to(get_batch_statuses))
+        .route("/state/{address}", web::get().to(get_state))
+        .route("/transactions/{transaction_id}", web::get().to(get_transaction))
+        .route("/blocks", web::get().to(get_blocks))
+        .route("/blocks/{block_id}", web::get().to(get_block));
+}
+
+/// `BlockchainService` backed by an `EmbeddedLedger` served on loopback.
+///
+/// Behaves like `SawtoothService` against a dev-mode network, so code and
+/// tests written for a validator run without one.
+pub struct EmbeddedLedgerService {
+    ledger: Arc<EmbeddedLedger>,
+    service: SawtoothService,
+    server: ServerHandle,
+    url: String,
+}
+
+impl EmbeddedLedgerService {
+    /// Starts the ledger's REST API on an ephemeral loopback port. Must be
+    /// called from within a Tokio runtime.
+    ///
+    /// `config.validator_url` is ignored. An empty
+    /// `config.validator_private_key` gets a fresh random key, and that key
+    /// is listed as an approver so the service can update and delete the
+    /// property it creates.
+    pub async fn start(mut config: BlockchainConfig) -> Result<Self, BlockchainError> {
+        let ledger = Arc::new(EmbeddedLedger::new());
+        if let Some((name, version)) = config.consensus.algorithm() {
+            ledger.set_setting(ALGORITHM_NAME_SETTING, name)?;
+            ledger.set_setting(ALGORITHM_VERSION_SETTING, version)?;
+        } else {
+            // The embedded ledger seals blocks itself, like dev mode
+            config.consensus = ConsensusEngine::DevMode;
+            ledger.set_setting(ALGORITHM_NAME_SETTING, "Devmode")?;
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .map_err(|e| BlockchainError::ServiceError(format!("Failed to bind embedded ledger: {}", e)))?;
+        let url = format!(
+            "http://{}",
+            listener
+                .local_addr()
+                .map_err(|e| BlockchainError::ServiceError(e.to_string()))?
+        );
+
+        let data = web::Data::new(ledger.clone());
+        let server = HttpServer::new(move || App::new().app_data(data.clone()).configure(configure))
+            .workers(1)
+            .disable_signals()
+            .listen(listener)
+            .map_err(|e| BlockchainError::ServiceError(format!("Failed to start embedded ledger: {}", e)))?
+            .run();
+        let handle = server.handle();
+        tokio::spawn(server);
+
+        if config.validator_private_key.is_empty() {
+            config.validator_private_key = Secp256k1Context::new()
+                .new_random_private_key()
+                .map_err(|e| BlockchainError::ServiceError(e.to_string()))?
+                .as_hex();
+        }
+        config.validator_url = url.clone();
+        let service = SawtoothService::new(url.clone(), config.validator_private_key.clone(), config)?;
+        ledger.set_setting(APPROVERS_SETTING, &service.client().public_key()?)?;
+
+        Ok(Self {
+            ledger,
+            service,
+            server: handle,
+            url,
+        })
+    }
+
+    pub fn ledger(&self) -> Arc<EmbeddedLedger> {
+        self.ledger.clone()
+    }
+
+    /// Base URL of the ledger's REST API.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn sawtooth(&self) -> &SawtoothService {
+        &self.service
+    }
+}
+
+#[async_trait]
+impl BlockchainService for EmbeddedLedgerService {
+    async fn initialize(&self) -> Result<(), BlockchainError> {
+        self.service.initialize().await
+    }
+
+    async fn shutdown(&self) -> Result<(), BlockchainError> {
+        self.service.shutdown().await?;
+        self.server.stop(true).await;
+        Ok(())
+    }
+
+    async fn get_status(&self) -> Result<ChainStatus, BlockchainError> {
+        self.service.get_status().await
+    }
+
+    fn verification_service(&self) -> &dyn TransferVerification {
+        self.service.verification_service()
+    }
+
+    async fn get_current_batch_merkle_tree(&self) -> Result<Option<MerkleTree>, BlockchainError> {
+        self.service.get_current_batch_merkle_tree().await
+    }
+
+    async fn get_transaction_proof(&self, transaction_id: &str) -> Result<Option<MerkleProof>, BlockchainError> {
+        self.service.get_transaction_proof(transaction_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use handreceipt_protocol::merkle::BatchInclusionProof;
+    use crate::infrastructure::blockchain::merkle::MerkleTree;
+    use crate::infrastructure::blockchain::sawtooth::{
+        client::{SawtoothClient, BATCH_STATUS_TIMEOUT},
+        state::PropertyMetadata,
+        HandReceiptPayload,
+    };
+
+    const TEST_KEY: &str = "2f1e7b7a130d7ba9da0068b3bb0ba1d79e7e77110302c9f746c3c2a63fe40088";
+
+    fn metadata() -> PropertyMetadata {
+        PropertyMetadata {
+            name: "M4 Carbine".to_string(),
+            description: "Rifle".to_string(),
+            category: "Weapon".to_string(),
+            serial_number: Some("W123".to_string()),
+            is_sensitive_item: true,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    fn create(property_id: &str) -> HandReceiptPayload {
+        HandReceiptPayload::Create {
+            property_id: property_id.to_string(),
+            initial_custodian: "custodian-1".to_string(),
+            metadata: metadata(),
+            custodian_key: None,
+        }
+    }
+
+    fn update(property_id: &str) -> HandReceiptPayload {
+        HandReceiptPayload::Update {
+            property_id: property_id.to_string(),
+            metadata: metadata(),
+            updated_at: chrono::Utc::now().timestamp_millis(),
+            status: "in_use".to_string(),
+        }
+    }
+
+    fn batch_list(client: &SawtoothClient, payloads: &[HandReceiptPayload]) -> (Vec<u8>, String) {
+        let (batch, receipt) = client.build_batch(payloads, &mut HashMap::new()).unwrap();
+        let mut list = BatchList::new();
+        list.set_batches(protobuf::RepeatedField::from_vec(vec![batch]));
+        (list.write_to_bytes().unwrap(), receipt.batch_id)
+    }
+
+    #[test]
+    fn test_commits_batches_into_blocks() {
+        let ledger = EmbeddedLedger::new();
+        let client = SawtoothClient::new("http://unused".to_string(), TEST_KEY.to_string()).unwrap();
+        ledger.set_setting(APPROVERS_SETTING, &client.public_key().unwrap()).unwrap();
+
+        let (bytes, batch_id) = batch_list(&client, &[create("property-1"), update("property-1")]);
+        assert_eq!(ledger.submit(&bytes).unwrap(), vec![batch_id.clone()]);
+        assert_eq!(ledger.batch_statuses(&[batch_id.clone()])[0].status, BatchStatus::Committed);
+
+        let genesis = ledger.head().unwrap();
+        assert_eq!(genesis.block_num, 0);
+        assert_eq!(genesis.previous_block_id, NULL_BLOCK_ID);
+        assert_eq!(genesis.batch_ids, vec![batch_id.clone()]);
+        assert!(ledger.get_state(&super::super::PropertyState::get_address("property-1")).is_some());
+
+        // Resubmitting is idempotent
+        ledger.submit(&bytes).unwrap();
+        assert_eq!(ledger.head().unwrap().block_num, 0);
+    }
+
+    #[test]
+    fn test_invalid_batch_leaves_state_untouched() {
+        let ledger = EmbeddedLedger::new();
+        let client = SawtoothClient::new("http://unused".to_string(), TEST_KEY.to_string()).unwrap();
+
+        // The create succeeds but the batch fails as a whole
+        let (bytes, batch_id) = batch_list(&client, &[create("property-1"), update("property-2")]);
+        ledger.submit(&bytes).unwrap();
+
+        let status = &ledger.batch_statuses(&[batch_id])[0];
+        assert_eq!(status.status, BatchStatus::Invalid);
+        assert_eq!(status.invalid_transactions.len(), 1);
+        assert!(ledger.head().is_none());
+        assert!(ledger.get_state(&super::super::PropertyState::get_address("property-1")).is_none());
+        assert_eq!(ledger.batch_statuses(&["unknown".to_string()])[0].status, BatchStatus::Unknown);
+    }
+
+    #[test]
+    fn test_rejects_tampered_batch() {
+        let ledger = EmbeddedLedger::new();
+        let client = SawtoothClient::new("http://unused".to_string(), TEST_KEY.to_string()).unwrap();
+
+        let (mut batch, _) = client.build_batch(&[create("property-1")], &mut HashMap::new()).unwrap();
+        batch.mut_transactions()[0].set_payload(b"tampered".to_vec());
+        let mut list = BatchList::new();
+        list.set_batches(protobuf::RepeatedField::from_vec(vec![batch]));
+        let batch_id = list.get_batches()[0].get_header_signature().to_string();
+
+        // Signatures cover headers, so the payload hash check catches this
+        ledger.submit(&list.write_to_bytes().unwrap()).unwrap();
+        assert_eq!(ledger.batch_statuses(&[batch_id])[0].status, BatchStatus::Invalid);
+
+        assert!(ledger.submit(b"not a batch list").is_err());
+    }
+
+    #[test]
+    fn test_context_enforces_declared_addresses() {
+        let mut header = TransactionHeader::new();
+        header.set_inputs(protobuf::RepeatedField::from_vec(vec!["a0".to_string()]));
+        header.set_outputs(protobuf::RepeatedField::from_vec(vec!["a0ff".to_string()]));
+        let context = LedgerContext::new(BTreeMap::new(), &header);
+
+        assert!(context.get_state_entries(&["a0ff01".to_string()]).is_ok());
+        assert!(context.get_state_entries(&["b0".to_string()]).is_err());
+        assert!(context.set_state_entries(vec![("a0ff01".to_string(), vec![1])]).is_ok());
+        assert!(context.set_state_entries(vec![("a001".to_string(), vec![1])]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_client_round_trip() {
+        let service = EmbeddedLedgerService::start(BlockchainConfig {
+            validator_private_key: TEST_KEY.to_string(),
+            ..BlockchainConfig::default()
+        })
+        .await
+        .unwrap();
+        service.initialize().await.unwrap();
+        let client = service.sawtooth().client();
+
+        let receipt = client
+            .create_property("property-1".to_string(), "custodian-1".to_string(), metadata())
+            .await
+            .unwrap();
+        client.wait_for_batch(&receipt, BATCH_STATUS_TIMEOUT).await.unwrap();
+
+        let state = client.get_property_state("property-1").await.unwrap().unwrap();
+        assert_eq!(state.custodian, "custodian-1");
+        assert_eq!(
+            client.get_setting(ALGORITHM_NAME_SETTING).await.unwrap().as_deref(),
+            Some("Devmode")
+        );
+
+        let head = service.ledger().head().unwrap();
+        let header = client.get_batch_header(&head.batch_ids[0]).await.unwrap().unwrap();
+        assert_eq!(header.transaction_ids.len(), 1);
+        let inclusion = BatchInclusionProof {
+            batch_id: head.batch_ids[0].clone(),
+            transaction_id: header.transaction_ids[0].clone(),
+            batcher_public_key: header.signer_public_key.clone(),
+            proof: MerkleTree::from_transaction_ids(&header.transaction_ids)
+                .generate_proof_for_id(&header.transaction_ids[0])
+                .unwrap(),
+            transaction_ids: header.transaction_ids,
+        };
+        assert!(inclusion.verify(&client.public_key().unwrap()));
+        assert!(matches!(service.get_status().await.unwrap(), ChainStatus::Active));
+
+        service.shutdown().await.unwrap();
+    }
+}
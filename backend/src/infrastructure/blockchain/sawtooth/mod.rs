@@ -1,5 +1,8 @@
+pub mod batcher;
 pub mod client;
+pub mod events;
 pub mod handler;
+pub mod ledger;
 pub mod service;
 pub mod state;
 pub mod transaction;
@@ -11,13 +14,16 @@ use sawtooth_sdk::processor::handler::ApplyError;
 use sawtooth_sdk::processor::handler::TransactionContext;
 use sawtooth_sdk::processor::handler::TransactionHandler;
 
-pub const FAMILY_NAME: &str = "handreceipt";
-pub const FAMILY_VERSION: &str = "1.0";
-pub const NAMESPACE: &str = "handreceipt";
+pub use handreceipt_protocol::addressing::{
+    self, namespace_prefix, AddressSpace, FAMILY_NAME, FAMILY_VERSION,
+};
 
 // Re-export main components
+pub use batcher::TransactionBatcher;
 pub use client::SawtoothClient;
+pub use events::LedgerEventSubscriber;
 pub use handler::HandReceiptTransactionHandler;
+pub use ledger::{EmbeddedLedger, EmbeddedLedgerService};
 pub use service::SawtoothService;
 pub use state::PropertyState;
 pub use transaction::HandReceiptPayload;
@@ -1,8 +1,13 @@
 use std::sync::Arc;
 use async_trait::async_trait;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use tokio::task::JoinHandle;
 
 use crate::{
+    domain::property::{
+        repository::PropertyRepository,
+        service::{SyncState, SyncStatus},
+    },
     error::blockchain::BlockchainError,
     infrastructure::blockchain::{
         BlockchainService,
@@ -10,24 +15,46 @@ use crate::{
         merkle::{MerkleTree, MerkleProof},
         types::ChainStatus,
         verification::{TransferVerification, VerificationService},
+        consensus::{ConsensusProtocol, ValidatorStatus, ALGORITHM_NAME_SETTING},
         ConsensusEngine,
     },
-    consensus::poet::PoETConsensus,
 };
 
 use super::{
-    client::SawtoothClient,
+    batcher::TransactionBatcher,
+    client::{SawtoothClient, BATCH_STATUS_TIMEOUT},
     handler::HandReceiptTransactionHandler,
     verification::SawtoothVerification,
 };
 
+/// Sync status for a property whose latest batch ended with `outcome`.
+///
+/// `blockchain_hash` is always the id of that batch, so a property can be
+/// traced to the exact submission that last synced it.
+pub fn sync_status(batch_id: Option<String>, outcome: &Result<(), BlockchainError>) -> SyncStatus {
+    match outcome {
+        Ok(()) => SyncStatus::new(SyncState::Committed, batch_id, None),
+        Err(err @ BlockchainError::InvalidTransaction { .. }) => {
+            SyncStatus::new(SyncState::Invalid, batch_id, Some(err.to_string()))
+        }
+        Err(err @ BlockchainError::BatchTimeout(_)) => {
+            SyncStatus::new(SyncState::Pending, batch_id, Some(err.to_string()))
+        }
+        Err(err) => SyncStatus::new(SyncState::Unsynced, batch_id, Some(err.to_string())),
+    }
+}
+
 pub struct SawtoothService {
     client: Arc<SawtoothClient>,
     config: BlockchainConfig,
     handler: Arc<HandReceiptTransactionHandler>,
     current_batch: Arc<RwLock<Option<MerkleTree>>>,
     verification_service: Arc<VerificationService>,
-    poet_consensus: Option<PoETConsensus>,
+    /// Engine for consensus this node runs itself (dev mode, PoET);
+    /// `None` when the validator network runs it.
+    consensus: Option<Arc<dyn ConsensusProtocol>>,
+    batcher: Arc<TransactionBatcher>,
+    flusher: Mutex<Option<JoinHandle<()>>>,
 }
 
 impl SawtoothService {
@@ -41,23 +68,89 @@ impl SawtoothService {
         let current_batch = Arc::new(RwLock::new(None));
         let verification = Arc::new(SawtoothVerification::new(client.clone()));
         let verification_service = Arc::new(VerificationService::new(verification));
+        let batcher = Arc::new(TransactionBatcher::new(
+            client.clone(),
+            config.batch_size,
+            config.batch_timeout,
+        ));
 
-        let poet_consensus = match &config.consensus {
-            ConsensusEngine::PoET(poet_config) => Some(PoETConsensus::new(poet_config.clone())),
+        let consensus = match &config.consensus {
+            ConsensusEngine::DevMode | ConsensusEngine::PoET(_) => {
+                Some(config.consensus.create_protocol(config.validator_id()?, config.consensus_signing_key()?)?)
+            }
             _ => None,
         };
 
-
         Ok(Self {
             client,
             config,
             handler,
             current_batch,
             verification_service,
-            poet_consensus,
+            consensus,
+            batcher,
+            flusher: Mutex::new(None),
         })
     }
 
+    pub fn client(&self) -> Arc<SawtoothClient> {
+        self.client.clone()
+    }
+
+    pub fn consensus(&self) -> Option<Arc<dyn ConsensusProtocol>> {
+        self.consensus.clone()
+    }
+
+    /// Fails if the validator network runs a different consensus engine
+    /// than configured. An unset setting or unreachable validator only
+    /// warns, since the network may not be bootstrapped yet.
+    async fn check_network_consensus(&self) -> Result<(), BlockchainError> {
+        let expected = match self.config.consensus.algorithm() {
+            Some((name, _)) => name,
+            None => return Ok(()),
+        };
+
+        match self.client.get_setting(ALGORITHM_NAME_SETTING).await {
+            Ok(Some(actual)) if actual.eq_ignore_ascii_case(expected) => Ok(()),
+            Ok(Some(actual)) => Err(BlockchainError::ValidationError(format!(
+                "Configured for {} consensus but the network runs {}",
+                expected, actual
+            ))),
+            Ok(None) => {
+                tracing::warn!("{} is unset; expected {}", ALGORITHM_NAME_SETTING, expected);
+                Ok(())
+            }
+            Err(e) => {
+                tracing::warn!("Could not read the network consensus setting: {}", e);
+                Ok(())
+            }
+        }
+    }
+
+    /// Batcher that packs payloads into batches of `config.batch_size`.
+    pub fn batcher(&self) -> Arc<TransactionBatcher> {
+        self.batcher.clone()
+    }
+
+    /// Waits for a property's batch to commit and records the outcome as the
+    /// property's sync status.
+    pub async fn confirm_property_sync(
+        &self,
+        property_id: i32,
+        batch_id: &str,
+        repository: &dyn PropertyRepository,
+    ) -> Result<SyncStatus, BlockchainError> {
+        let outcome = self.client.wait_for_batch(batch_id, BATCH_STATUS_TIMEOUT).await;
+        let status = sync_status(Some(batch_id.to_string()), &outcome);
+
+        repository
+            .update_sync_status(property_id, &status)
+            .await
+            .map_err(|e| BlockchainError::StateError(e.to_string()))?;
+
+        Ok(status)
+    }
+
     async fn update_batch_tree(&self, transactions: &[crate::types::blockchain::BlockchainTransaction]) -> Result<(), BlockchainError> {
         let new_tree = MerkleTree::new(transactions)
             .map_err(|e| BlockchainError::ValidationError(e.to_string()))?;
@@ -70,18 +163,41 @@ impl SawtoothService {
 #[async_trait]
 impl BlockchainService for SawtoothService {
     async fn initialize(&self) -> Result<(), BlockchainError> {
-        // TODO: Implement initialization logic
+        self.check_network_consensus().await?;
+
+        let mut flusher = self.flusher.lock();
+        if flusher.is_none() {
+            *flusher = Some(self.batcher.clone().start());
+        }
         Ok(())
     }
 
     async fn shutdown(&self) -> Result<(), BlockchainError> {
-        // TODO: Implement shutdown logic
+        self.batcher.stop();
+        let flusher = self.flusher.lock().take();
+        if let Some(flusher) = flusher {
+            flusher
+                .await
+                .map_err(|e| BlockchainError::ServiceError(format!("Batch flusher failed: {}", e)))?;
+        }
         Ok(())
     }
 
+    /// Active unless this node runs consensus itself and its validator
+    /// has been suspended or deactivated.
     async fn get_status(&self) -> Result<ChainStatus, BlockchainError> {
-        // TODO: Implement status check
-        Ok(ChainStatus::Active)
+        let consensus = match &self.consensus {
+            Some(consensus) => consensus,
+            None => return Ok(ChainStatus::Active),
+        };
+        let validator = consensus
+            .get_validator_info()
+            .await
+            .map_err(|e| BlockchainError::StateError(e.to_string()))?;
+        Ok(match validator.status {
+            ValidatorStatus::Active => ChainStatus::Active,
+            ValidatorStatus::Inactive | ValidatorStatus::Suspended => ChainStatus::Halted,
+        })
     }
 
     fn verification_service(&self) -> &dyn TransferVerification {
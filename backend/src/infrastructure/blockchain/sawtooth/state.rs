@@ -1,14 +1,14 @@
 use sawtooth_sdk::processor::handler::{ApplyError, TransactionContext};
 use serde::{Deserialize, Serialize};
-use sha2::{Sha512, Digest};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeZone, Utc};
+use handreceipt_protocol::messages::{self, SCHEMA_VERSION};
+use handreceipt_protocol::signing::TransferSignature;
 use uuid::Uuid;
 
 use crate::domain::models::transfer::TransferStatus;
+use super::addressing;
 
-const NAMESPACE: &str = "handreceipt";
-
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PropertyState {
     pub id: String,
     pub owner: String,
@@ -17,6 +17,15 @@ pub struct PropertyState {
     pub timestamp: i64,
     pub transfer_history: Vec<TransferRecord>,
     pub last_updated: DateTime<Utc>,
+    /// Hex Ed25519 key of the current custodian, once custody is bound to one.
+    #[serde(default)]
+    pub custodian_key: Option<String>,
+    /// Whether every transfer needs an approver signature.
+    #[serde(default)]
+    pub requires_approval: bool,
+    /// Metadata of the latest create or update.
+    #[serde(default)]
+    pub metadata: Option<PropertyMetadata>,
 }
 
 impl PropertyState {
@@ -25,43 +34,91 @@ impl PropertyState {
         owner: String,
         custodian: String,
         status: String,
-        timestamp: i64,
+        created_at: DateTime<Utc>,
     ) -> Self {
         Self {
             id,
             owner,
             custodian,
             status,
-            timestamp,
+            timestamp: created_at.timestamp_millis(),
+            transfer_history: Vec::new(),
+            last_updated: created_at,
+            custodian_key: None,
+            requires_approval: false,
+            metadata: None,
         }
     }
 
+    /// State address of a property, shared with the mobile processor.
     pub fn get_address(property_id: &str) -> String {
-        let mut hasher = Sha512::new();
-        hasher.update(property_id.as_bytes());
-        let hash = hasher.finalize();
-        
-        // Sawtooth requires 70-char hex addresses
-        // First 6 chars for namespace prefix
-        let namespace = &hash[..3];
-        let property_hash = &hash[3..35];
-        
-        format!(
-            "{}{}",
-            hex::encode(namespace),
-            hex::encode(property_hash)
-        )
+        addressing::property_address(property_id)
     }
 
+    /// Serializes state as protobuf. State is always written in the current
+    /// schema, regardless of the family version of the transaction.
     pub fn serialize(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-        serde_json::to_vec(self).map_err(|e| e.into())
+        Ok(self.to_proto().to_bytes())
     }
 
+    /// Deserializes protobuf state, falling back to legacy JSON state written
+    /// by family version 1.0.
     pub fn deserialize(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
-        serde_json::from_slice(bytes).map_err(|e| e.into())
+        if messages::is_legacy_json(bytes) {
+            return serde_json::from_slice(bytes).map_err(|e| e.into());
+        }
+
+        let state = messages::PropertyState::from_bytes(bytes)?;
+        Self::from_proto(state)
+    }
+
+    pub fn to_proto(&self) -> messages::PropertyState {
+        messages::PropertyState {
+            schema_version: SCHEMA_VERSION,
+            id: self.id.clone(),
+            owner: self.owner.clone(),
+            custodian: self.custodian.clone(),
+            status: self.status.clone(),
+            timestamp: self.timestamp,
+            transfer_history: self.transfer_history.iter().map(TransferRecord::to_proto).collect(),
+            last_updated: self.last_updated.timestamp_millis(),
+            custodian_key: self.custodian_key.clone().unwrap_or_default(),
+            requires_approval: self.requires_approval,
+            metadata: self.metadata.as_ref().map(PropertyMetadata::to_proto),
+        }
+    }
+
+    pub fn from_proto(state: messages::PropertyState) -> Result<Self, Box<dyn std::error::Error>> {
+        if state.schema_version > SCHEMA_VERSION {
+            return Err(format!("Unsupported state schema version: {}", state.schema_version).into());
+        }
+
+        Ok(Self {
+            id: state.id,
+            owner: state.owner,
+            custodian: state.custodian,
+            status: state.status,
+            timestamp: state.timestamp,
+            transfer_history: state
+                .transfer_history
+                .into_iter()
+                .map(TransferRecord::from_proto)
+                .collect::<Result<_, _>>()?,
+            last_updated: from_millis(state.last_updated)?,
+            custodian_key: Some(state.custodian_key).filter(|key| !key.is_empty()),
+            requires_approval: state.requires_approval,
+            metadata: state.metadata.map(PropertyMetadata::from_proto).transpose()?,
+        })
     }
 }
 
+/// Parses a Unix timestamp in milliseconds.
+pub fn from_millis(millis: i64) -> Result<DateTime<Utc>, String> {
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .ok_or_else(|| format!("Invalid timestamp: {}", millis))
+}
+
 pub trait StateReader {
     fn get_state_entry(&self, address: &str) -> Result<Option<Vec<u8>>, ApplyError>;
 }
@@ -104,10 +161,49 @@ pub struct TransferRecord {
     pub to_custodian: String,
     pub timestamp: DateTime<Utc>,
     pub status: TransferStatus,
+    /// Public keys of the transaction signers.
     pub signatures: Vec<String>,
+    /// Party signatures over the transfer digest.
+    #[serde(default)]
+    pub transfer_signatures: Vec<TransferSignature>,
+    /// Digest of the shortage annex signed for, empty if there was none.
+    #[serde(default)]
+    pub annex_digest: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl TransferRecord {
+    pub fn to_proto(&self) -> messages::TransferRecord {
+        messages::TransferRecord {
+            transfer_id: self.transfer_id.to_string(),
+            from_custodian: self.from_custodian.clone(),
+            to_custodian: self.to_custodian.clone(),
+            timestamp: self.timestamp.timestamp_millis(),
+            status: self.status.to_string(),
+            signatures: self.signatures.clone(),
+            transfer_signatures: self.transfer_signatures.iter().map(TransferSignature::to_proto).collect(),
+            annex_digest: self.annex_digest.clone(),
+        }
+    }
+
+    pub fn from_proto(record: messages::TransferRecord) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            transfer_id: Uuid::parse_str(&record.transfer_id)?,
+            from_custodian: record.from_custodian,
+            to_custodian: record.to_custodian,
+            timestamp: from_millis(record.timestamp)?,
+            status: record.status.parse()?,
+            signatures: record.signatures,
+            transfer_signatures: record
+                .transfer_signatures
+                .into_iter()
+                .map(TransferSignature::from_proto)
+                .collect::<Result<_, _>>()?,
+            annex_digest: record.annex_digest,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct PropertyMetadata {
     pub name: String,
     pub description: String,
@@ -116,3 +212,27 @@ pub struct PropertyMetadata {
     pub is_sensitive_item: bool,
     pub created_at: DateTime<Utc>,
 } 
+
+impl PropertyMetadata {
+    pub fn to_proto(&self) -> messages::PropertyMetadata {
+        messages::PropertyMetadata {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            category: self.category.clone(),
+            serial_number: self.serial_number.clone(),
+            is_sensitive_item: self.is_sensitive_item,
+            created_at: self.created_at.timestamp_millis(),
+        }
+    }
+
+    pub fn from_proto(metadata: messages::PropertyMetadata) -> Result<Self, String> {
+        Ok(Self {
+            name: metadata.name,
+            description: metadata.description,
+            category: metadata.category,
+            serial_number: metadata.serial_number,
+            is_sensitive_item: metadata.is_sensitive_item,
+            created_at: from_millis(metadata.created_at)?,
+        })
+    }
+}
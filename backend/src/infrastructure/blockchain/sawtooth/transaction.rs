@@ -1,23 +1,212 @@
 use serde::{Deserialize, Serialize};
-use crate::infrastructure::blockchain::sawtooth::state::PropertyMetadata;
+use handreceipt_protocol::addressing::{revocation_anchor_address, setting_address};
+use handreceipt_protocol::messages::{self, hand_receipt_payload::Action, PayloadEncoding, REVOCATION_AUTHORITY_SETTING, SCHEMA_VERSION};
+use handreceipt_protocol::signing::{TransferSignature, APPROVERS_SETTING};
+use crate::error::blockchain::BlockchainError;
+use crate::infrastructure::blockchain::sawtooth::state::{PropertyMetadata, PropertyState};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum HandReceiptPayload {
     Create {
         property_id: String,
         initial_custodian: String,
         metadata: PropertyMetadata,
+        /// Hex Ed25519 key of the initial custodian, if already known.
+        #[serde(default)]
+        custodian_key: Option<String>,
     },
     Transfer {
         property_id: String,
         to_custodian: String,
         transfer_id: String,
+        /// Party signatures over `transfer_digest`.
+        #[serde(default)]
+        signatures: Vec<TransferSignature>,
+        /// Unix timestamp in milliseconds at which the transfer was made.
+        #[serde(default)]
+        timestamp: i64,
+        /// Hex SHA-256 of the shortage annex signed for with the item, empty
+        /// if it is short nothing.
+        #[serde(default)]
+        annex_digest: String,
     },
     Update {
         property_id: String,
         metadata: PropertyMetadata,
+        /// Unix timestamp in milliseconds.
+        #[serde(default)]
+        updated_at: i64,
+        /// Database status of the property; empty leaves the ledger's as is.
+        #[serde(default)]
+        status: String,
     },
     Delete {
         property_id: String,
     },
-} 
\ No newline at end of file
+    /// Publishes the digest of a certificate authority's revocation list.
+    AnchorRevocations {
+        issuer: String,
+        digest: String,
+        revoked_count: u32,
+        /// Unix timestamp in milliseconds.
+        updated_at: i64,
+    },
+}
+
+impl HandReceiptPayload {
+    /// Property the payload acts on, if any.
+    pub fn property_id(&self) -> Option<&str> {
+        match self {
+            HandReceiptPayload::Create { property_id, .. }
+            | HandReceiptPayload::Transfer { property_id, .. }
+            | HandReceiptPayload::Update { property_id, .. }
+            | HandReceiptPayload::Delete { property_id } => Some(property_id),
+            HandReceiptPayload::AnchorRevocations { .. } => None,
+        }
+    }
+
+    /// State addresses read by this payload. Transfers, updates and deletes
+    /// also read the approver setting and revocation anchors the authority
+    /// setting.
+    pub fn inputs(&self) -> Vec<String> {
+        let mut inputs = self.outputs();
+        match self {
+            HandReceiptPayload::Transfer { .. }
+            | HandReceiptPayload::Update { .. }
+            | HandReceiptPayload::Delete { .. } => inputs.push(setting_address(APPROVERS_SETTING)),
+            HandReceiptPayload::AnchorRevocations { .. } => inputs.push(setting_address(REVOCATION_AUTHORITY_SETTING)),
+            _ => {}
+        }
+        inputs
+    }
+
+    /// State addresses written by this payload.
+    pub fn outputs(&self) -> Vec<String> {
+        match self {
+            HandReceiptPayload::AnchorRevocations { issuer, .. } => vec![revocation_anchor_address(issuer)],
+            _ => self.property_id().map(PropertyState::get_address).into_iter().collect(),
+        }
+    }
+
+    /// Encodes the payload for the family version matching `encoding`.
+    pub fn encode(&self, encoding: PayloadEncoding) -> Result<Vec<u8>, BlockchainError> {
+        match encoding {
+            PayloadEncoding::Json => Ok(serde_json::to_vec(self)?),
+            PayloadEncoding::Protobuf => Ok(self.to_proto().to_bytes()),
+        }
+    }
+
+    /// Decodes a payload submitted under `family_version`.
+    pub fn decode(family_version: &str, bytes: &[u8]) -> Result<Self, BlockchainError> {
+        let encoding = PayloadEncoding::from_family_version(family_version).ok_or_else(|| {
+            BlockchainError::ValidationError(format!("Unsupported family version: {}", family_version))
+        })?;
+
+        match encoding {
+            PayloadEncoding::Json => Ok(serde_json::from_slice(bytes)?),
+            PayloadEncoding::Protobuf => {
+                let payload = messages::HandReceiptPayload::from_bytes(bytes)
+                    .map_err(|e| BlockchainError::SerializationError(e.to_string()))?;
+                Self::from_proto(payload)
+            }
+        }
+    }
+
+    pub fn to_proto(&self) -> messages::HandReceiptPayload {
+        let action = match self {
+            HandReceiptPayload::Create { property_id, initial_custodian, metadata, custodian_key } => {
+                Action::Create(messages::CreateProperty {
+                    property_id: property_id.clone(),
+                    initial_custodian: initial_custodian.clone(),
+                    metadata: Some(metadata.to_proto()),
+                    custodian_key: custodian_key.clone().unwrap_or_default(),
+                })
+            }
+            HandReceiptPayload::Transfer { property_id, to_custodian, transfer_id, signatures, timestamp, annex_digest } => {
+                Action::Transfer(messages::TransferProperty {
+                    property_id: property_id.clone(),
+                    to_custodian: to_custodian.clone(),
+                    transfer_id: transfer_id.clone(),
+                    signatures: signatures.iter().map(TransferSignature::to_proto).collect(),
+                    timestamp: *timestamp,
+                    annex_digest: annex_digest.clone(),
+                })
+            }
+            HandReceiptPayload::Update { property_id, metadata, updated_at, status } => {
+                Action::Update(messages::UpdateProperty {
+                    property_id: property_id.clone(),
+                    metadata: Some(metadata.to_proto()),
+                    updated_at: *updated_at,
+                    status: status.clone(),
+                })
+            }
+            HandReceiptPayload::Delete { property_id } => {
+                Action::Delete(messages::DeleteProperty {
+                    property_id: property_id.clone(),
+                })
+            }
+            HandReceiptPayload::AnchorRevocations { issuer, digest, revoked_count, updated_at } => {
+                Action::AnchorRevocations(messages::RevocationAnchor {
+                    issuer: issuer.clone(),
+                    digest: digest.clone(),
+                    revoked_count: *revoked_count,
+                    updated_at: *updated_at,
+                })
+            }
+        };
+
+        messages::HandReceiptPayload::new(action)
+    }
+
+    pub fn from_proto(payload: messages::HandReceiptPayload) -> Result<Self, BlockchainError> {
+        if payload.schema_version > SCHEMA_VERSION {
+            return Err(BlockchainError::ValidationError(format!(
+                "Unsupported payload schema version: {}",
+                payload.schema_version
+            )));
+        }
+
+        let missing_metadata = || BlockchainError::ValidationError("Payload is missing metadata".to_string());
+        let metadata = |metadata: Option<messages::PropertyMetadata>| {
+            PropertyMetadata::from_proto(metadata.ok_or_else(missing_metadata)?).map_err(BlockchainError::ValidationError)
+        };
+
+        match payload.action {
+            Some(Action::Create(create)) => Ok(HandReceiptPayload::Create {
+                property_id: create.property_id,
+                initial_custodian: create.initial_custodian,
+                metadata: metadata(create.metadata)?,
+                custodian_key: Some(create.custodian_key).filter(|key| !key.is_empty()),
+            }),
+            Some(Action::Transfer(transfer)) => Ok(HandReceiptPayload::Transfer {
+                property_id: transfer.property_id,
+                to_custodian: transfer.to_custodian,
+                transfer_id: transfer.transfer_id,
+                signatures: transfer
+                    .signatures
+                    .into_iter()
+                    .map(TransferSignature::from_proto)
+                    .collect::<Result<_, _>>()
+                    .map_err(BlockchainError::ValidationError)?,
+                timestamp: transfer.timestamp,
+                annex_digest: transfer.annex_digest,
+            }),
+            Some(Action::Update(update)) => Ok(HandReceiptPayload::Update {
+                property_id: update.property_id,
+                metadata: metadata(update.metadata)?,
+                updated_at: update.updated_at,
+                status: update.status,
+            }),
+            Some(Action::Delete(delete)) => Ok(HandReceiptPayload::Delete {
+                property_id: delete.property_id,
+            }),
+            Some(Action::AnchorRevocations(anchor)) => Ok(HandReceiptPayload::AnchorRevocations {
+                issuer: anchor.issuer,
+                digest: anchor.digest,
+                revoked_count: anchor.revoked_count,
+                updated_at: anchor.updated_at,
+            }),
+            None => Err(BlockchainError::ValidationError("Payload has no action".to_string())),
+        }
+    }
+}
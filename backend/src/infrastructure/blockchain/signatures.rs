@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use handreceipt_protocol::signing::{TransferRole, TransferSignature};
+
+use crate::{
+    domain::{
+        certificate::CertificateRepository,
+        transfer::{
+            entity::{Transfer, TransferStatus},
+            repository::TransferRepository,
+        },
+    },
+    error::{blockchain::BlockchainError, RepositoryError},
+    infrastructure::blockchain::outbox,
+    types::security::SecurityContext,
+};
+
+/// Collects party signatures for transfers.
+///
+/// Signatures are checked against the transfer digest when they arrive so
+/// a bad one is refused here rather than failing the ledger transaction.
+/// Each role is signed once, by the user who plays it, with a key from one
+/// of their active certificates. The approval policy itself is enforced by
+/// the transaction processor.
+pub struct TransferSignatureService {
+    transfers: Arc<dyn TransferRepository>,
+    certificates: Arc<dyn CertificateRepository>,
+}
+
+impl TransferSignatureService {
+    pub fn new(transfers: Arc<dyn TransferRepository>, certificates: Arc<dyn CertificateRepository>) -> Self {
+        Self { transfers, certificates }
+    }
+
+    async fn transfer(&self, transfer_id: i32) -> Result<Option<Transfer>, BlockchainError> {
+        self.transfers
+            .get_transfer(transfer_id)
+            .await
+            .map_err(|e| BlockchainError::StateError(e.to_string()))
+    }
+
+    /// Digest the parties to `transfer_id` sign, or `None` if there is no
+    /// such transfer.
+    pub async fn digest(&self, transfer_id: i32) -> Result<Option<[u8; 32]>, BlockchainError> {
+        Ok(self.transfer(transfer_id).await?.as_ref().map(outbox::transfer_digest))
+    }
+
+    /// Verifies and stores `signature` on behalf of `signer`, returning every
+    /// signature collected so far. Returns `None` if there is no such
+    /// transfer.
+    pub async fn add_signature(
+        &self,
+        transfer_id: i32,
+        signer: &SecurityContext,
+        signature: TransferSignature,
+    ) -> Result<Option<Vec<TransferSignature>>, BlockchainError> {
+        let transfer = match self.transfer(transfer_id).await? {
+            Some(transfer) => transfer,
+            None => return Ok(None),
+        };
+
+        if matches!(transfer.status, TransferStatus::Rejected | TransferStatus::Cancelled) {
+            return Err(BlockchainError::ValidationError(format!(
+                "Transfer {} is {} and can no longer be signed",
+                transfer_id, transfer.status
+            )));
+        }
+
+        let plays_role = match signature.role {
+            TransferRole::Releasing => signer.user_id == transfer.from_holder_id,
+            TransferRole::Receiving => signer.user_id == transfer.to_holder_id,
+            TransferRole::Approver => signer.can_approve_transfers(),
+        };
+        if !plays_role {
+            return Err(BlockchainError::ValidationError(format!(
+                "User {} cannot sign transfer {} as {}",
+                signer.user_id, transfer_id, signature.role
+            )));
+        }
+
+        let now = Utc::now();
+        let registered = self
+            .certificates
+            .list_by_user(signer.user_id)
+            .await
+            .map_err(|e| BlockchainError::StateError(e.to_string()))?
+            .iter()
+            .filter(|record| record.is_active(now))
+            .any(|record| record.certificate.public_key.eq_ignore_ascii_case(&signature.public_key));
+        if !registered {
+            return Err(BlockchainError::ValidationError(format!(
+                "Key {} is not registered to user {}",
+                signature.public_key, signer.user_id
+            )));
+        }
+
+        signature
+            .verify(&outbox::transfer_digest(&transfer))
+            .map_err(|e| BlockchainError::ValidationError(e.to_string()))?;
+
+        self.transfers
+            .add_signature(transfer_id, &signature)
+            .await
+            .map_err(|e| match e {
+                RepositoryError::Validation(message) => BlockchainError::ValidationError(message),
+                other => BlockchainError::StateError(other.to_string()),
+            })?;
+
+        self.transfers
+            .list_signatures(transfer_id)
+            .await
+            .map(Some)
+            .map_err(|e| BlockchainError::StateError(e.to_string()))
+    }
+}
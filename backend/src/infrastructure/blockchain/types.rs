@@ -1,5 +1,6 @@
 use crate::{
     error::CoreError,
+    infrastructure::blockchain::consensus::poet::WaitCertificate,
     types::security::{SecurityContext, SecurityClassification},
 };
 use chrono::{DateTime, Utc};
@@ -26,6 +27,9 @@ pub struct BlockHeader {
     pub timestamp: DateTime<Utc>,
     pub difficulty: u32,
     pub nonce: u64,
+    /// Present on blocks sealed by PoET.
+    #[serde(default)]
+    pub wait_certificate: Option<WaitCertificate>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +38,8 @@ pub struct Transaction {
     pub transaction_type: TransactionType,
     pub data: TransactionData,
     pub timestamp: DateTime<Utc>,
+    /// User whose registered key made `signature`.
+    pub signer: i32,
     pub signature: Option<String>,
     pub classification: SecurityClassification,
 }
@@ -11,6 +11,7 @@ use uuid::Uuid;
 use parking_lot::RwLock as ParkingRwLock;
 
 use crate::{
+    domain::component::entity::ShortageAnnex,
     domain::models::transfer::{PropertyTransferRecord, TransferStatus},
     error::CoreError,
     types::{
@@ -34,6 +35,7 @@ use super::{
         AuthorityService,
         MilitaryCertificate,
     },
+    outbox,
 };
 
 const BATCH_SIZE: usize = 100;
@@ -131,15 +133,18 @@ impl BlockchainVerification {
     }
 
     /// Converts domain transfer to blockchain transfer
-    fn to_blockchain_transfer(&self, transfer: &PropertyTransferRecord) -> PropertyTransfer {
+    fn to_blockchain_transfer(&self, transfer: &PropertyTransferRecord, context: &SecurityContext) -> PropertyTransfer {
         PropertyTransfer {
+            transfer_id: outbox::ledger_transfer_id(transfer.id),
             property_id: Uuid::new_v4(),
+            unit_id: context.unit_code.clone(),
             from_custodian: Some(transfer.from_node.clone()),
             to_custodian: transfer.to_node.clone(),
             initiated_by: transfer.id.to_string(),
             requires_approval: transfer.status == TransferStatus::Pending,
             timestamp: transfer.timestamp,
             signatures: Vec::new(),
+            annex_digest: ShortageAnnex::signed_digest(&transfer.metadata),
         }
     }
 
@@ -209,7 +214,7 @@ impl TransferVerification for BlockchainVerification {
         context: &SecurityContext,
     ) -> Result<VerificationResult, CoreError> {
         // Convert to blockchain transfer
-        let mut blockchain_transfer = self.to_blockchain_transfer(transfer);
+        let mut blockchain_transfer = self.to_blockchain_transfer(transfer, context);
 
         // Sign transfer with current authority
         let role = self.get_signer_role(context);
@@ -283,7 +288,7 @@ impl TransferVerification for BlockchainVerification {
         transfer: &PropertyTransferRecord,
         context: &SecurityContext,
     ) -> Result<String, CoreError> {
-        let blockchain_transfer = self.to_blockchain_transfer(transfer);
+        let blockchain_transfer = self.to_blockchain_transfer(transfer, context);
         
         // Record transfer on authority node
         self.authority_node
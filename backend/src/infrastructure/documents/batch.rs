@@ -0,0 +1,387 @@
+//! Bulk QR generation for a whole property book.
+//!
+//! A job is recorded, then runs in the background: it issues a label QR code
+//! for every property matching its filter and writes them out as a zip of
+//! PNG or SVG files, or as one PDF of label sheets. Progress is saved as it
+//! goes and the finished file is stored with the job for later download.
+
+use std::io::{Cursor, Write};
+use std::sync::Arc;
+
+use tracing::{error, info};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    domain::{
+        models::qr::{render_png, render_svg, QRCodeService, QRRenderOptions},
+        property::{entity::Property, filter::PropertyFilter, service::PropertyService},
+        qr_batch::{
+            entity::{QRBatchFormat, QRBatchJob, QRBatchStatus},
+            repository::QRBatchRepository,
+        },
+    },
+    error::CoreError,
+    types::security::SecurityContext,
+};
+use super::labels::{is_labelable, property_label, render_label_sheets, sort_by_hand_receipt, LabelTemplate, PropertyLabel};
+
+/// Label template used when a label sheet job names none.
+pub const DEFAULT_LABEL_TEMPLATE: &str = "avery-5160";
+
+/// How many properties are processed between progress updates.
+const PROGRESS_INTERVAL: usize = 25;
+
+/// Error recorded on jobs that were still unfinished when the server
+/// stopped. Their requester's context is gone, so they are not restarted.
+pub const INTERRUPTED_JOB_ERROR: &str = "Interrupted by a server restart; submit the batch again";
+
+pub struct QRBatchService {
+    jobs: Arc<dyn QRBatchRepository>,
+    properties: Arc<dyn PropertyService>,
+    qr_service: Arc<dyn QRCodeService>,
+    render: QRRenderOptions,
+}
+
+impl QRBatchService {
+    pub fn new(
+        jobs: Arc<dyn QRBatchRepository>,
+        properties: Arc<dyn PropertyService>,
+        qr_service: Arc<dyn QRCodeService>,
+    ) -> Self {
+        Self {
+            jobs,
+            properties,
+            qr_service,
+            render: QRRenderOptions::default(),
+        }
+    }
+
+    /// Image size and quiet zone for the PNG and SVG formats.
+    pub fn with_render_options(mut self, render: QRRenderOptions) -> Self {
+        self.render = render;
+        self
+    }
+
+    /// Records a job and starts it in the background.
+    pub async fn submit(
+        self: &Arc<Self>,
+        filter: PropertyFilter,
+        format: QRBatchFormat,
+        template: Option<String>,
+        context: &SecurityContext,
+    ) -> Result<QRBatchJob, CoreError> {
+        let template = match format {
+            QRBatchFormat::LabelSheet => {
+                let name = template.unwrap_or_else(|| DEFAULT_LABEL_TEMPLATE.to_string());
+                if LabelTemplate::by_name(&name).is_none() {
+                    return Err(CoreError::Validation(format!("Unknown label template {}", name)));
+                }
+                Some(name)
+            }
+            _ => None,
+        };
+
+        let job = self.jobs
+            .create_job(&QRBatchJob::new(context.user_id, filter, format, template))
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+        let service = Arc::clone(self);
+        let (queued, context) = (job.clone(), context.clone());
+        tokio::spawn(async move {
+            service.run(queued, &context).await;
+        });
+        Ok(job)
+    }
+
+    /// Runs `job` to completion, recording success or failure on it.
+    pub async fn run(&self, job: QRBatchJob, context: &SecurityContext) {
+        let outcome = match self.generate(&job, context).await {
+            Ok(output) => self.jobs.complete_job(job.id, &output).await,
+            Err(e) => {
+                error!("QR batch job {} failed: {}", job.id, e);
+                self.jobs.fail_job(job.id, &e.to_string()).await
+            }
+        };
+        match outcome {
+            Ok(()) => info!("QR batch job {} finished", job.id),
+            Err(e) => error!("Could not record outcome of QR batch job {}: {}", job.id, e),
+        }
+    }
+
+    async fn generate(&self, job: &QRBatchJob, context: &SecurityContext) -> Result<Vec<u8>, CoreError> {
+        let mut properties = self
+            .properties
+            .list_properties(context)
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))?;
+        properties.retain(|property| job.filter.matches(property) && is_labelable(property));
+        sort_by_hand_receipt(&mut properties);
+
+        self.jobs
+            .start_job(job.id, properties.len() as i32)
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+        let mut labels = Vec::with_capacity(properties.len());
+        for (index, property) in properties.iter().enumerate() {
+            labels.push(property_label(self.qr_service.as_ref(), property, context).await?);
+            let processed = index + 1;
+            if processed % PROGRESS_INTERVAL == 0 || processed == properties.len() {
+                self.jobs
+                    .update_progress(job.id, processed as i32)
+                    .await
+                    .map_err(|e| CoreError::Repository(e.to_string()))?;
+            }
+        }
+
+        match job.format {
+            QRBatchFormat::PngZip | QRBatchFormat::SvgZip => self.zip(job.format, &properties, &labels),
+            QRBatchFormat::LabelSheet => {
+                let name = job.template.as_deref().unwrap_or(DEFAULT_LABEL_TEMPLATE);
+                let template = LabelTemplate::by_name(name)
+                    .ok_or_else(|| CoreError::Validation(format!("Unknown label template {}", name)))?;
+                render_label_sheets(&template, &labels)
+            }
+        }
+    }
+
+    fn zip(&self, format: QRBatchFormat, properties: &[Property], labels: &[PropertyLabel]) -> Result<Vec<u8>, CoreError> {
+        let zip_error = |e: zip::result::ZipError| CoreError::InternalError(format!("Could not write zip: {}", e));
+        let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+        for (property, label) in properties.iter().zip(labels) {
+            // PNGs are already compressed
+            let (extension, bytes, method) = match format {
+                QRBatchFormat::SvgZip => ("svg", render_svg(&label.payload, &self.render)?.into_bytes(), CompressionMethod::Deflated),
+                _ => ("png", render_png(&label.payload, &self.render)?, CompressionMethod::Stored),
+            };
+            archive
+                .start_file(entry_name(property, extension), FileOptions::default().compression_method(method))
+                .map_err(zip_error)?;
+            archive.write_all(&bytes)?;
+        }
+        Ok(archive.finish().map_err(zip_error)?.into_inner())
+    }
+
+    /// A job, visible to whoever requested it and to officers.
+    pub async fn get_job(&self, id: i32, context: &SecurityContext) -> Result<QRBatchJob, CoreError> {
+        let job = self.jobs
+            .get_job(id)
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))?
+            .ok_or_else(|| CoreError::NotFound(format!("QR batch job {} not found", id)))?;
+        if job.requested_by != context.user_id && !context.is_officer() {
+            return Err(CoreError::Authorization("Not permitted to view this QR batch job".to_string()));
+        }
+        Ok(job)
+    }
+
+    pub async fn list_jobs(&self, limit: i64, context: &SecurityContext) -> Result<Vec<QRBatchJob>, CoreError> {
+        self.jobs
+            .list_jobs(context.user_id, limit)
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))
+    }
+
+    /// The finished file of a completed job.
+    pub async fn download(&self, id: i32, context: &SecurityContext) -> Result<(QRBatchJob, Vec<u8>), CoreError> {
+        let job = self.get_job(id, context).await?;
+        if job.status != QRBatchStatus::Completed {
+            return Err(CoreError::Validation(format!(
+                "QR batch job {} is {}, not completed",
+                id,
+                job.status.as_str()
+            )));
+        }
+        let output = self.jobs
+            .get_output(id)
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))?
+            .ok_or_else(|| CoreError::NotFound(format!("Output of QR batch job {} not found", id)))?;
+        Ok((job, output))
+    }
+}
+
+/// File name inside the zip: the property id, then its serial number or
+/// name reduced to characters safe in any file system.
+fn entry_name(property: &Property, extension: &str) -> String {
+    let label = property.serial_number.as_deref().unwrap_or(&property.name);
+    let slug: String = label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    format!("{}-{}.{}", property.id, slug, extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use parking_lot::Mutex;
+    use std::collections::HashMap;
+    use crate::{
+        domain::{
+            models::{location::Location, qr_token::QRUsage},
+            property::entity::PropertyCategory,
+        },
+        error::RepositoryError,
+        test_support::{MockProperties, StubQRService},
+    };
+
+    #[derive(Default)]
+    struct MockJobs {
+        jobs: Mutex<HashMap<i32, QRBatchJob>>,
+        outputs: Mutex<HashMap<i32, Vec<u8>>>,
+        progress: Mutex<Vec<i32>>,
+    }
+
+    #[async_trait]
+    impl QRBatchRepository for MockJobs {
+        async fn create_job(&self, job: &QRBatchJob) -> Result<QRBatchJob, RepositoryError> {
+            let mut jobs = self.jobs.lock();
+            let job = QRBatchJob { id: jobs.len() as i32 + 1, ..job.clone() };
+            jobs.insert(job.id, job.clone());
+            Ok(job)
+        }
+
+        async fn get_job(&self, id: i32) -> Result<Option<QRBatchJob>, RepositoryError> {
+            Ok(self.jobs.lock().get(&id).cloned())
+        }
+
+        async fn list_jobs(&self, user_id: i32, _limit: i64) -> Result<Vec<QRBatchJob>, RepositoryError> {
+            Ok(self.jobs.lock().values().filter(|job| job.requested_by == user_id).cloned().collect())
+        }
+
+        async fn start_job(&self, id: i32, total: i32) -> Result<(), RepositoryError> {
+            let mut jobs = self.jobs.lock();
+            let job = jobs.get_mut(&id).unwrap();
+            job.status = QRBatchStatus::Running;
+            job.total = total;
+            Ok(())
+        }
+
+        async fn update_progress(&self, id: i32, processed: i32) -> Result<(), RepositoryError> {
+            self.jobs.lock().get_mut(&id).unwrap().processed = processed;
+            self.progress.lock().push(processed);
+            Ok(())
+        }
+
+        async fn complete_job(&self, id: i32, output: &[u8]) -> Result<(), RepositoryError> {
+            let mut jobs = self.jobs.lock();
+            let job = jobs.get_mut(&id).unwrap();
+            job.status = QRBatchStatus::Completed;
+            job.output_size = Some(output.len() as i64);
+            self.outputs.lock().insert(id, output.to_vec());
+            Ok(())
+        }
+
+        async fn fail_job(&self, id: i32, error: &str) -> Result<(), RepositoryError> {
+            let mut jobs = self.jobs.lock();
+            let job = jobs.get_mut(&id).unwrap();
+            job.status = QRBatchStatus::Failed;
+            job.error = Some(error.to_string());
+            Ok(())
+        }
+
+        async fn fail_unfinished_jobs(&self, error: &str) -> Result<u64, RepositoryError> {
+            let mut failed = 0;
+            for job in self.jobs.lock().values_mut() {
+                if matches!(job.status, QRBatchStatus::Queued | QRBatchStatus::Running) {
+                    job.status = QRBatchStatus::Failed;
+                    job.error = Some(error.to_string());
+                    failed += 1;
+                }
+            }
+            Ok(failed)
+        }
+
+        async fn get_output(&self, id: i32) -> Result<Option<Vec<u8>>, RepositoryError> {
+            Ok(self.outputs.lock().get(&id).cloned())
+        }
+    }
+
+    fn property(id: i32, category: PropertyCategory, unit: &str) -> Property {
+        let mut property = Property::new(
+            format!("Item {}", id),
+            String::new(),
+            category,
+            1,
+            Location::default(),
+        );
+        property.id = id;
+        property.serial_number = Some(format!("SN/{:03}", id));
+        property.hand_receipt_number = Some("HR-A".to_string());
+        property.metadata = serde_json::json!({ "unit": unit });
+        property
+    }
+
+    fn service(properties: Vec<Property>) -> (Arc<MockJobs>, Arc<StubQRService>, QRBatchService) {
+        let jobs = Arc::new(MockJobs::default());
+        let qr_service = Arc::new(StubQRService::default());
+        let service = QRBatchService::new(jobs.clone(), Arc::new(MockProperties::new(properties)), qr_service.clone());
+        (jobs, qr_service, service)
+    }
+
+    #[test]
+    fn test_filter() {
+        let rifle = property(1, PropertyCategory::Weapon, "A-1-1");
+        let any = PropertyFilter::default();
+        assert!(any.matches(&rifle));
+
+        let weapons = PropertyFilter {
+            unit: Some("A-1-1".to_string()),
+            category: Some(PropertyCategory::Weapon),
+            hand_receipt_number: Some("HR-A".to_string()),
+            ..Default::default()
+        };
+        assert!(weapons.matches(&rifle));
+        assert!(!weapons.matches(&property(2, PropertyCategory::Vehicle, "A-1-1")));
+        assert!(!weapons.matches(&property(3, PropertyCategory::Weapon, "B-1-1")));
+    }
+
+    #[tokio::test]
+    async fn test_png_zip_job() {
+        let mut properties: Vec<Property> = (1..=30).map(|id| property(id, PropertyCategory::Weapon, "A-1-1")).collect();
+        properties.push(property(31, PropertyCategory::Vehicle, "A-1-1"));
+        // Sensitive items get no label codes
+        let mut sensitive = property(32, PropertyCategory::Weapon, "A-1-1");
+        sensitive.is_sensitive = true;
+        properties.push(sensitive);
+        let (jobs, qr_service, service) = service(properties);
+        let context = SecurityContext::new(7);
+
+        let filter = PropertyFilter { category: Some(PropertyCategory::Weapon), ..Default::default() };
+        let job = jobs.create_job(&QRBatchJob::new(7, filter, QRBatchFormat::PngZip, None)).await.unwrap();
+        service.run(job.clone(), &context).await;
+
+        let (job, output) = service.download(job.id, &context).await.unwrap();
+        assert_eq!((job.status, job.total, job.processed, job.progress()), (QRBatchStatus::Completed, 30, 30, 100));
+        assert_eq!(*jobs.progress.lock(), vec![25, 30]);
+        assert_eq!(*qr_service.issued.lock(), vec![QRUsage::Label; 30]);
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(output)).unwrap();
+        assert_eq!(archive.len(), 30);
+        let first = archive.by_name("1-SN_001.png").unwrap();
+        assert!(first.size() > 0);
+
+        // Only the requester and officers may fetch it
+        assert!(matches!(service.get_job(job.id, &SecurityContext::new(8)).await, Err(CoreError::Authorization(_))));
+    }
+
+    #[tokio::test]
+    async fn test_label_sheet_job_and_unfinished_download() {
+        let (jobs, _, service) = service(vec![property(1, PropertyCategory::Weapon, "A-1-1")]);
+        let context = SecurityContext::new(7);
+
+        let job = jobs
+            .create_job(&QRBatchJob::new(7, PropertyFilter::default(), QRBatchFormat::LabelSheet, Some("5163".to_string())))
+            .await
+            .unwrap();
+        assert!(matches!(service.download(job.id, &context).await, Err(CoreError::Validation(_))));
+
+        service.run(job.clone(), &context).await;
+        let (job, output) = service.download(job.id, &context).await.unwrap();
+        assert_eq!(job.filename(), "qr-batch-1.pdf");
+        assert!(output.starts_with(b"%PDF-1.4"));
+    }
+}
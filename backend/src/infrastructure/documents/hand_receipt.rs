@@ -0,0 +1,763 @@
+//! DA Form 2062 hand receipts.
+//!
+//! Lists the property signed to a holder or carried on a hand receipt
+//! number the way the paper form does: one line per stock number and
+//! description with its unit of issue, quantity and serial numbers. The
+//! signature block below the items is filled from the party signatures of
+//! the completed transfers that put each item in the holder's hands, and a
+//! shortage annex lists the components missing from end items. Every
+//! document handed out is hashed and the hash recorded in the audit log, so
+//! a printed copy can later be matched to what the system issued.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use handreceipt_protocol::signing::TransferSignature;
+use serde::Serialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    domain::{
+        audit_log::{entity::AuditLogEntry, repository::AuditLogRepository},
+        component::{
+            entity::{ShortageAnnex, ShortageLine},
+            repository::ComponentRepository,
+        },
+        property::{entity::Property, filter::PropertyFilter, service::PropertyService},
+        transfer::{
+            entity::{Transfer, TransferStatus},
+            repository::TransferRepository,
+        },
+    },
+    error::CoreError,
+    types::security::SecurityContext,
+};
+use super::pdf::{fit_text, text_width, PdfDocument, PdfFont, PdfPage, LETTER};
+
+/// Audit log event recorded for every generated hand receipt.
+pub const HAND_RECEIPT_EVENT: &str = "hand_receipt_generated";
+
+const MARGIN: f32 = 36.0;
+const ROW_HEIGHT: f32 = 14.0;
+const TEXT_SIZE: f32 = 8.0;
+const LABEL_SIZE: f32 = 6.0;
+/// Where the item table starts and ends on every page.
+const TABLE_TOP: f32 = 104.0;
+const TABLE_BOTTOM: f32 = LETTER.1 - 48.0;
+/// Column headings and widths: stock number, item description, unit of
+/// issue, quantity.
+const COLUMNS: [(&str, f32); 4] = [
+    ("a. STOCK NUMBER", 110.0),
+    ("b. ITEM DESCRIPTION", 340.0),
+    ("c. UI", 40.0),
+    ("d. QTY", 50.0),
+];
+
+/// One line of the form. Items sharing a stock number, description and
+/// unit of issue are listed together.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HandReceiptLine {
+    pub stock_number: Option<String>,
+    pub description: String,
+    pub unit_of_issue: String,
+    pub quantity: i32,
+    /// Serial numbers of the items on the line, omitted for sensitive items
+    /// the caller is not cleared to see.
+    pub serial_numbers: Vec<String>,
+}
+
+/// Signatures of one completed transfer onto the hand receipt.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HandReceiptSignatureBlock {
+    pub transfer_id: i32,
+    pub from_holder_id: i32,
+    pub to_holder_id: i32,
+    pub completed_at: DateTime<Utc>,
+    pub signatures: Vec<TransferSignature>,
+    /// The end item's shortage annex the receiver signed for, if it has a
+    /// component listing.
+    pub shortage_annex: Option<ShortageAnnex>,
+}
+
+/// Components an end item on the hand receipt is short.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EndItemShortages {
+    pub property_id: i32,
+    pub stock_number: Option<String>,
+    pub description: String,
+    pub serial_number: Option<String>,
+    pub shortages: Vec<ShortageLine>,
+}
+
+/// Everything printed on a DA 2062.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HandReceipt {
+    /// Issuing unit.
+    pub from: String,
+    pub holder_id: Option<i32>,
+    pub hand_receipt_number: Option<String>,
+    pub lines: Vec<HandReceiptLine>,
+    /// Shortage annex: end items missing components, in property id order.
+    pub shortages: Vec<EndItemShortages>,
+    pub signatures: Vec<HandReceiptSignatureBlock>,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl HandReceipt {
+    /// Groups `properties` into lines, ordered by stock number then
+    /// description, with items lacking a stock number last.
+    pub fn lines(properties: &[Property]) -> Vec<HandReceiptLine> {
+        let mut lines: BTreeMap<(bool, Option<String>, String, String), HandReceiptLine> = BTreeMap::new();
+        for property in properties {
+            let key = (
+                property.nsn.is_none(),
+                property.nsn.clone(),
+                property.name.clone(),
+                property.unit_of_issue().to_string(),
+            );
+            let line = lines.entry(key).or_insert_with(|| HandReceiptLine {
+                stock_number: property.nsn.clone(),
+                description: property.name.clone(),
+                unit_of_issue: property.unit_of_issue().to_string(),
+                quantity: 0,
+                serial_numbers: Vec::new(),
+            });
+            line.quantity += property.quantity;
+            line.serial_numbers.extend(property.serial_number.clone());
+        }
+
+        let mut lines: Vec<HandReceiptLine> = lines.into_values().collect();
+        for line in &mut lines {
+            line.serial_numbers.sort();
+        }
+        lines
+    }
+
+    /// What goes in the TO box.
+    fn to(&self) -> String {
+        match self.holder_id {
+            Some(holder_id) => format!("Holder #{}", holder_id),
+            None => "Multiple holders".to_string(),
+        }
+    }
+}
+
+/// A table row: one string per column.
+struct Row {
+    font: PdfFont,
+    cells: [String; 4],
+}
+
+impl Row {
+    fn new(font: PdfFont, cells: [String; 4]) -> Self {
+        Self { font, cells }
+    }
+
+    /// Text spanning the description column only.
+    fn description(font: PdfFont, text: String) -> Self {
+        Self::new(font, [String::new(), text, String::new(), String::new()])
+    }
+}
+
+/// Joins `values` after `prefix`, wrapping onto indented continuation
+/// runs that fit `width`.
+fn wrap_list(prefix: &str, values: &[String], width: f32) -> Vec<String> {
+    let indent = " ".repeat(prefix.len());
+    let mut runs: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for value in values {
+        let lead = if runs.is_empty() { prefix } else { indent.as_str() };
+        let candidate = if current.is_empty() {
+            value.clone()
+        } else {
+            format!("{}, {}", current, value)
+        };
+        if !current.is_empty() && text_width(&format!("{}{}", lead, candidate), TEXT_SIZE, PdfFont::Regular) > width {
+            runs.push(format!("{}{},", lead, current));
+            current = value.clone();
+        } else {
+            current = candidate;
+        }
+    }
+    if !current.is_empty() {
+        let lead = if runs.is_empty() { prefix } else { indent.as_str() };
+        runs.push(format!("{}{}", lead, current));
+    }
+    runs
+}
+
+/// Abbreviates a hex key or signature for print.
+fn short_hex(hex: &str) -> String {
+    if hex.len() > 16 {
+        format!("{}...", &hex[..16])
+    } else {
+        hex.to_string()
+    }
+}
+
+fn rows(receipt: &HandReceipt) -> Vec<Row> {
+    let description_width = COLUMNS[1].1 - 8.0;
+    let mut rows = Vec::new();
+    for line in &receipt.lines {
+        rows.push(Row::new(
+            PdfFont::Regular,
+            [
+                line.stock_number.clone().unwrap_or_default(),
+                line.description.clone(),
+                line.unit_of_issue.clone(),
+                line.quantity.to_string(),
+            ],
+        ));
+        for run in wrap_list("SN: ", &line.serial_numbers, description_width) {
+            rows.push(Row::description(PdfFont::Regular, run));
+        }
+    }
+
+    if !receipt.shortages.is_empty() {
+        rows.push(Row::description(PdfFont::Regular, String::new()));
+        rows.push(Row::description(PdfFont::Bold, "SHORTAGE ANNEX".to_string()));
+    }
+    for end_item in &receipt.shortages {
+        let description = match &end_item.serial_number {
+            Some(serial) => format!("{} SN {}", end_item.description, serial),
+            None => end_item.description.clone(),
+        };
+        rows.push(Row::new(
+            PdfFont::Bold,
+            [end_item.stock_number.clone().unwrap_or_default(), description, String::new(), String::new()],
+        ));
+        for line in &end_item.shortages {
+            rows.push(Row::new(
+                PdfFont::Regular,
+                [
+                    line.nsn.clone().unwrap_or_default(),
+                    format!("  {} (short {} of {})", line.name, line.shortage, line.required_quantity),
+                    line.unit_of_issue.clone(),
+                    line.shortage.to_string(),
+                ],
+            ));
+        }
+    }
+
+    rows.push(Row::description(PdfFont::Regular, String::new()));
+    rows.push(Row::description(PdfFont::Bold, "SIGNATURES OF COMPLETED TRANSFERS".to_string()));
+    if receipt.signatures.is_empty() {
+        rows.push(Row::description(PdfFont::Regular, "No signed transfers on record".to_string()));
+    }
+    for block in &receipt.signatures {
+        rows.push(Row::new(
+            PdfFont::Bold,
+            [
+                block.completed_at.format("%d %b %Y").to_string().to_uppercase(),
+                format!(
+                    "Transfer #{}: Holder #{} to Holder #{}",
+                    block.transfer_id, block.from_holder_id, block.to_holder_id
+                ),
+                String::new(),
+                String::new(),
+            ],
+        ));
+        for signature in &block.signatures {
+            rows.push(Row::description(
+                PdfFont::Regular,
+                format!(
+                    "{}: key {} signed {}",
+                    signature.role.as_str().to_uppercase(),
+                    short_hex(&signature.public_key),
+                    short_hex(&signature.signature)
+                ),
+            ));
+        }
+        if let Some(annex) = &block.shortage_annex {
+            let text = if annex.is_empty() {
+                "Signed for complete, no shortages".to_string()
+            } else {
+                format!(
+                    "Signed for with {} shortage line(s), annex {}",
+                    annex.lines.len(),
+                    short_hex(&annex.digest)
+                )
+            };
+            rows.push(Row::description(PdfFont::Regular, text));
+        }
+    }
+    rows
+}
+
+/// Draws a labelled header box.
+fn header_box(page: &mut PdfPage, x: f32, width: f32, label: &str, value: &str) {
+    let (y, height) = (56.0, 40.0);
+    page.stroke_rect(x, y, width, height, 0.75);
+    page.text(x + 3.0, y + 8.0, LABEL_SIZE, PdfFont::Regular, label);
+    page.text(
+        x + 3.0,
+        y + 28.0,
+        10.0,
+        PdfFont::Bold,
+        &fit_text(value, width - 6.0, 10.0, PdfFont::Bold),
+    );
+}
+
+fn draw_page(page: &mut PdfPage, receipt: &HandReceipt, rows: &[Row], number: usize, count: usize) {
+    page.text(MARGIN, 48.0, 12.0, PdfFont::Bold, "HAND RECEIPT/ANNEX NUMBER");
+    page.text(LETTER.0 - MARGIN - 150.0, 48.0, LABEL_SIZE, PdfFont::Regular, "For use of this form, see DA PAM 710-2-1");
+
+    let hand_receipt_number = receipt.hand_receipt_number.clone().unwrap_or_default();
+    header_box(page, MARGIN, 180.0, "1. FROM:", &receipt.from);
+    header_box(page, MARGIN + 180.0, 180.0, "2. TO:", &receipt.to());
+    header_box(page, MARGIN + 360.0, 120.0, "3. HAND RECEIPT NUMBER", &hand_receipt_number);
+    header_box(page, MARGIN + 480.0, 60.0, "PAGE", &format!("{} OF {}", number, count));
+
+    // Column headings
+    let mut x = MARGIN;
+    for (heading, width) in COLUMNS {
+        page.stroke_rect(x, TABLE_TOP, width, ROW_HEIGHT + 4.0, 0.75);
+        page.text(x + 3.0, TABLE_TOP + 12.0, TEXT_SIZE - 1.0, PdfFont::Bold, heading);
+        x += width;
+    }
+
+    // Item rows, with column rules down to the bottom of the table
+    let body_top = TABLE_TOP + ROW_HEIGHT + 4.0;
+    for (index, row) in rows.iter().enumerate() {
+        let baseline = body_top + (index + 1) as f32 * ROW_HEIGHT - 4.0;
+        let mut x = MARGIN;
+        for (cell, (_, width)) in row.cells.iter().zip(COLUMNS) {
+            if !cell.is_empty() {
+                let text = fit_text(cell, width - 6.0, TEXT_SIZE, row.font);
+                page.text(x + 3.0, baseline, TEXT_SIZE, row.font, &text);
+            }
+            x += width;
+        }
+    }
+    let mut x = MARGIN;
+    for (_, width) in COLUMNS {
+        page.stroke_rect(x, body_top, width, TABLE_BOTTOM - body_top, 0.5);
+        x += width;
+    }
+
+    page.text(MARGIN, LETTER.1 - 30.0, TEXT_SIZE, PdfFont::Bold, "DA FORM 2062");
+    page.text(
+        LETTER.0 - MARGIN - 160.0,
+        LETTER.1 - 30.0,
+        TEXT_SIZE - 1.0,
+        PdfFont::Regular,
+        &format!("Generated {}", receipt.generated_at.format("%Y-%m-%d %H:%M UTC")),
+    );
+}
+
+/// Lays `receipt` out on as many pages as its items and signatures need
+/// and returns the PDF.
+pub fn render_hand_receipt(receipt: &HandReceipt) -> Vec<u8> {
+    let rows = rows(receipt);
+    let per_page = ((TABLE_BOTTOM - TABLE_TOP - ROW_HEIGHT - 4.0) / ROW_HEIGHT) as usize;
+    let pages: Vec<&[Row]> = rows.chunks(per_page.max(1)).collect();
+
+    let mut document = PdfDocument::new(LETTER);
+    for (index, rows) in pages.iter().enumerate() {
+        draw_page(document.add_page(), receipt, rows, index + 1, pages.len());
+    }
+    document.to_bytes()
+}
+
+/// A rendered hand receipt and the hash recorded for it.
+#[derive(Debug, Clone)]
+pub struct GeneratedHandReceipt {
+    pub receipt: HandReceipt,
+    pub pdf: Vec<u8>,
+    /// Hex SHA-256 of `pdf`.
+    pub sha256: String,
+    pub audit_entry_id: i32,
+}
+
+impl GeneratedHandReceipt {
+    pub fn filename(&self) -> String {
+        let subject = match (&self.receipt.hand_receipt_number, self.receipt.holder_id) {
+            (Some(number), _) => number.clone(),
+            (None, Some(holder_id)) => format!("holder-{}", holder_id),
+            (None, None) => "property".to_string(),
+        };
+        let subject: String = subject
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+            .collect();
+        format!("da2062-{}.pdf", subject)
+    }
+}
+
+/// Builds DA 2062s from the property book and records each one issued.
+pub struct HandReceiptService {
+    properties: Arc<dyn PropertyService>,
+    transfers: Arc<dyn TransferRepository>,
+    components: Arc<dyn ComponentRepository>,
+    audit_log: Arc<dyn AuditLogRepository>,
+}
+
+impl HandReceiptService {
+    pub fn new(
+        properties: Arc<dyn PropertyService>,
+        transfers: Arc<dyn TransferRepository>,
+        components: Arc<dyn ComponentRepository>,
+        audit_log: Arc<dyn AuditLogRepository>,
+    ) -> Self {
+        Self {
+            properties,
+            transfers,
+            components,
+            audit_log,
+        }
+    }
+
+    /// Hand receipt for the property signed to `holder_id` and/or carried
+    /// on `hand_receipt_number`, as visible to `context`.
+    pub async fn generate(
+        &self,
+        holder_id: Option<i32>,
+        hand_receipt_number: Option<String>,
+        context: &SecurityContext,
+    ) -> Result<GeneratedHandReceipt, CoreError> {
+        if holder_id.is_none() && hand_receipt_number.is_none() {
+            return Err(CoreError::Validation(
+                "A holder or hand receipt number is required".to_string(),
+            ));
+        }
+        let filter = PropertyFilter {
+            holder_id,
+            hand_receipt_number: hand_receipt_number.clone(),
+            ..Default::default()
+        };
+
+        let mut properties = self
+            .properties
+            .list_properties(context)
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))?;
+        properties.retain(|property| filter.matches(property));
+        if properties.is_empty() {
+            return Err(CoreError::NotFound(match (&hand_receipt_number, holder_id) {
+                (Some(number), _) => format!("No property on hand receipt {}", number),
+                _ => format!("No property signed to holder {}", holder_id.unwrap_or_default()),
+            }));
+        }
+        properties.sort_by_key(|property| property.id);
+
+        let signatures = self.signature_blocks(&properties).await?;
+        if !context.can_handle_sensitive_items() {
+            for property in properties.iter_mut().filter(|property| property.is_sensitive) {
+                property.redact();
+            }
+        }
+        let shortages = self.shortages(&properties).await?;
+
+        let receipt = HandReceipt {
+            from: properties
+                .iter()
+                .find_map(|property| property.unit())
+                .unwrap_or(&context.unit)
+                .to_string(),
+            holder_id: holder_id.or_else(|| {
+                let first = properties[0].current_holder_id;
+                properties
+                    .iter()
+                    .all(|property| property.current_holder_id == first)
+                    .then_some(first)
+            }),
+            hand_receipt_number,
+            lines: HandReceipt::lines(&properties),
+            shortages,
+            signatures,
+            generated_at: Utc::now(),
+        };
+        let pdf = render_hand_receipt(&receipt);
+        let sha256 = hex::encode(Sha256::digest(&pdf));
+
+        // The document is only handed out once its hash is on record
+        let entry = self
+            .audit_log
+            .record(&AuditLogEntry::new(
+                HAND_RECEIPT_EVENT,
+                format!("Generated DA Form 2062 for {}", receipt.hand_receipt_number.clone().unwrap_or_else(|| receipt.to())),
+                Some(context.user_id),
+                json!({
+                    "sha256": sha256,
+                    "holder_id": receipt.holder_id,
+                    "hand_receipt_number": receipt.hand_receipt_number,
+                    "property_ids": properties.iter().map(|property| property.id).collect::<Vec<_>>(),
+                    "transfer_ids": receipt.signatures.iter().map(|block| block.transfer_id).collect::<Vec<_>>(),
+                    "shortage_property_ids": receipt.shortages.iter().map(|end_item| end_item.property_id).collect::<Vec<_>>(),
+                    "generated_at": receipt.generated_at,
+                    "size": pdf.len(),
+                }),
+            ))
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+        Ok(GeneratedHandReceipt {
+            receipt,
+            pdf,
+            sha256,
+            audit_entry_id: entry.id,
+        })
+    }
+
+    /// Current shortages of the end items among `properties`.
+    async fn shortages(&self, properties: &[Property]) -> Result<Vec<EndItemShortages>, CoreError> {
+        let mut shortages = Vec::new();
+        for property in properties {
+            let components = self
+                .components
+                .list_components(property.id)
+                .await
+                .map_err(|e| CoreError::Repository(e.to_string()))?;
+            let annex = ShortageAnnex::new(property.id, &components);
+            if !annex.is_empty() {
+                shortages.push(EndItemShortages {
+                    property_id: property.id,
+                    stock_number: property.nsn.clone(),
+                    description: property.name.clone(),
+                    serial_number: property.serial_number.clone(),
+                    shortages: annex.lines,
+                });
+            }
+        }
+        Ok(shortages)
+    }
+
+    /// Signatures of the completed transfer that most recently handed each
+    /// of `properties` to its current holder, oldest first.
+    async fn signature_blocks(&self, properties: &[Property]) -> Result<Vec<HandReceiptSignatureBlock>, CoreError> {
+        let mut seen = HashSet::new();
+        let mut blocks = Vec::new();
+        for property in properties {
+            let transfers = self
+                .transfers
+                .list_by_property(property.id)
+                .await
+                .map_err(|e| CoreError::Repository(e.to_string()))?;
+            let latest = transfers
+                .into_iter()
+                .filter(|transfer| {
+                    transfer.status == TransferStatus::Completed
+                        && transfer.to_holder_id == property.current_holder_id
+                })
+                .max_by_key(|transfer| (transfer.updated_at, transfer.id));
+            let Some(transfer) = latest else { continue };
+            if !seen.insert(transfer.id) {
+                continue;
+            }
+
+            let signatures = self
+                .transfers
+                .list_signatures(transfer.id)
+                .await
+                .map_err(|e| CoreError::Repository(e.to_string()))?;
+            if !signatures.is_empty() {
+                blocks.push(signature_block(&transfer, signatures));
+            }
+        }
+        blocks.sort_by_key(|block| (block.completed_at, block.transfer_id));
+        Ok(blocks)
+    }
+}
+
+fn signature_block(transfer: &Transfer, signatures: Vec<TransferSignature>) -> HandReceiptSignatureBlock {
+    HandReceiptSignatureBlock {
+        transfer_id: transfer.id,
+        from_holder_id: transfer.from_holder_id,
+        to_holder_id: transfer.to_holder_id,
+        completed_at: transfer.updated_at,
+        signatures,
+        shortage_annex: transfer.shortage_annex(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use handreceipt_protocol::signing::TransferRole;
+    use parking_lot::Mutex;
+    use crate::{
+        domain::{
+            component::entity::{ComponentKind, PropertyComponent},
+            models::location::Location,
+            property::entity::PropertyCategory,
+        },
+        error::RepositoryError,
+        test_support::{MockComponents, MockProperties, MockTransfers},
+    };
+
+    #[derive(Default)]
+    struct MockAuditLog(Mutex<Vec<AuditLogEntry>>);
+
+    #[async_trait]
+    impl AuditLogRepository for MockAuditLog {
+        async fn record(&self, entry: &AuditLogEntry) -> Result<AuditLogEntry, RepositoryError> {
+            let mut entries = self.0.lock();
+            let entry = AuditLogEntry { id: entries.len() as i32 + 1, ..entry.clone() };
+            entries.push(entry.clone());
+            Ok(entry)
+        }
+    }
+
+    fn rifle(id: i32, holder: i32) -> Property {
+        let mut property = Property::new(
+            "Rifle, 5.56mm, M4".to_string(),
+            String::new(),
+            PropertyCategory::Weapon,
+            holder,
+            Location::default(),
+        );
+        property.id = id;
+        property.nsn = Some("1005-01-231-0973".to_string());
+        property.serial_number = Some(format!("W{:06}", id));
+        property.hand_receipt_number = Some("HR-A-0001".to_string());
+        property.is_sensitive = true;
+        property.metadata = json!({ "unit": "A-1-1" });
+        property
+    }
+
+    fn cots(id: i32, holder: i32) -> Property {
+        let mut property = Property::new(
+            "Cot, folding".to_string(),
+            String::new(),
+            PropertyCategory::Supply,
+            holder,
+            Location::default(),
+        );
+        property.id = id;
+        property.quantity = 12;
+        property.hand_receipt_number = Some("HR-A-0001".to_string());
+        property.metadata = json!({ "unit": "A-1-1", "unit_of_issue": "PR" });
+        property
+    }
+
+    fn completed(id: i32, property_id: i32, from: i32, to: i32) -> Transfer {
+        let mut transfer = Transfer::new(property_id, from, to, Location::default(), None);
+        transfer.id = id;
+        transfer.complete();
+        transfer
+    }
+
+    /// Both parties' signatures on each completed transfer, with keys
+    /// derived from the transfer id.
+    async fn signed(transfers: Vec<Transfer>) -> MockTransfers {
+        let repository = MockTransfers::new(transfers.clone());
+        for transfer in transfers.iter().filter(|transfer| transfer.status == TransferStatus::Completed) {
+            for role in [TransferRole::Releasing, TransferRole::Receiving] {
+                let signature = TransferSignature {
+                    role,
+                    public_key: format!("{:02x}", transfer.id).repeat(32),
+                    signature: "ab".repeat(64),
+                };
+                repository.add_signature(transfer.id, &signature).await.unwrap();
+            }
+        }
+        repository
+    }
+
+    #[test]
+    fn test_lines_group_items() {
+        let properties: Vec<Property> = (1..=3).map(|id| rifle(id, 7)).chain([cots(4, 7)]).collect();
+        let lines = HandReceipt::lines(&properties);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].description, "Rifle, 5.56mm, M4");
+        assert_eq!(lines[0].quantity, 3);
+        assert_eq!(lines[0].serial_numbers, vec!["W000001", "W000002", "W000003"]);
+        assert_eq!(lines[1].stock_number, None);
+        assert_eq!((lines[1].unit_of_issue.as_str(), lines[1].quantity), ("PR", 12));
+
+        let runs = wrap_list("SN: ", &lines[0].serial_numbers, 60.0);
+        assert!(runs.len() > 1);
+        assert!(runs[0].starts_with("SN: W000001"));
+    }
+
+    #[test]
+    fn test_render_paginates() {
+        let properties: Vec<Property> = (1..=60)
+            .map(|id| {
+                let mut property = cots(id, 7);
+                property.name = format!("Item {:02}", id);
+                property
+            })
+            .collect();
+        let receipt = HandReceipt {
+            from: "A-1-1".to_string(),
+            holder_id: Some(7),
+            hand_receipt_number: Some("HR-A-0001".to_string()),
+            lines: HandReceipt::lines(&properties),
+            shortages: Vec::new(),
+            signatures: Vec::new(),
+            generated_at: Utc::now(),
+        };
+        let text = String::from_utf8_lossy(&render_hand_receipt(&receipt)).to_string();
+        assert!(text.contains("/Count 2"));
+        assert!(text.contains("(HAND RECEIPT/ANNEX NUMBER) Tj"));
+        assert!(text.contains("(2 OF 2) Tj"));
+        assert!(text.contains("(Item 60) Tj"));
+        assert!(text.contains("(No signed transfers on record) Tj"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_signs_and_records_hash() {
+        let properties = vec![rifle(1, 7), rifle(2, 7), cots(3, 7), cots(4, 8)];
+        // Rifle 1 is missing its sling, and was signed for that way
+        let mut sling = PropertyComponent::new(1, "Sling, multipoint".to_string(), ComponentKind::BasicIssueItem, 1);
+        sling.id = 1;
+        sling.on_hand_quantity = 0;
+        let mut signed_short = completed(11, 1, 5, 7);
+        signed_short.set_shortage_annex(&ShortageAnnex::new(1, &[sling.clone()]));
+        let transfers = vec![
+            completed(10, 1, 3, 5),
+            signed_short,
+            completed(12, 2, 3, 7),
+            // Still pending, so not part of the receipt
+            Transfer::new(3, 3, 7, Location::default(), None),
+        ];
+        let audit_log = Arc::new(MockAuditLog::default());
+        let service = HandReceiptService::new(
+            Arc::new(MockProperties::new(properties)),
+            Arc::new(signed(transfers).await),
+            Arc::new(MockComponents::new(vec![sling])),
+            audit_log.clone(),
+        );
+
+        let generated = service.generate(Some(7), None, &SecurityContext::new(9)).await.unwrap();
+        assert_eq!(generated.receipt.from, "A-1-1");
+        assert_eq!(generated.receipt.lines.len(), 2);
+        // Not cleared for sensitive items, so the rifles' serials are withheld
+        assert_eq!(generated.receipt.lines[0].quantity, 2);
+        assert!(generated.receipt.lines[0].serial_numbers.is_empty());
+        let transfer_ids: Vec<i32> = generated.receipt.signatures.iter().map(|block| block.transfer_id).collect();
+        assert_eq!(transfer_ids, vec![11, 12]);
+        assert_eq!(generated.filename(), "da2062-holder-7.pdf");
+
+        let text = String::from_utf8_lossy(&generated.pdf).to_string();
+        assert!(text.contains("(Transfer #11: Holder #5 to Holder #7) Tj"));
+        assert!(text.contains("(RECEIVING: key 0b0b0b0b0b0b0b0b... signed abababababababab...) Tj"));
+        assert!(text.contains("(SHORTAGE ANNEX) Tj"));
+        assert!(text.contains("(  Sling, multipoint \\(short 1 of 1\\)) Tj"));
+        assert!(text.contains("(Signed for with 1 shortage line\\(s\\), annex "));
+        assert_eq!(generated.receipt.shortages.len(), 1);
+        assert_eq!(generated.receipt.shortages[0].serial_number, None);
+
+        let entries = audit_log.0.lock().clone();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].event_type, HAND_RECEIPT_EVENT);
+        assert_eq!(entries[0].user_id, Some(9));
+        assert_eq!(entries[0].metadata["sha256"], hex::encode(Sha256::digest(&generated.pdf)));
+        assert_eq!(entries[0].metadata["property_ids"], json!([1, 2, 3]));
+        assert_eq!(entries[0].metadata["shortage_property_ids"], json!([1]));
+        assert_eq!(generated.audit_entry_id, entries[0].id);
+
+        assert!(matches!(
+            service.generate(None, None, &SecurityContext::new(9)).await,
+            Err(CoreError::Validation(_))
+        ));
+        assert!(matches!(
+            service.generate(None, Some("HR-Z".to_string()), &SecurityContext::new(9)).await,
+            Err(CoreError::NotFound(_))
+        ));
+    }
+}
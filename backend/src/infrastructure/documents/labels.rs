@@ -0,0 +1,290 @@
+//! Printable QR label sheets.
+//!
+//! Lays property labels out on standard Avery-style sheets: the QR code on
+//! the left, then name, NSN, serial number and hand receipt number. Each
+//! label's QR payload is issued as a long-lived label token. Completing a
+//! transfer revokes every code for the property (see
+//! `application::transfer::commands`), so old labels stop scanning once the
+//! item changes hands and a fresh sheet has to be printed. Sensitive items
+//! are never labeled; they are handed over with short-lived transfer codes.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain::{
+        models::{
+            qr::{qr_modules, QRCodeService, QRData},
+            qr_token::QRUsage,
+        },
+        property::{entity::Property, service::PropertyService},
+    },
+    error::CoreError,
+    types::security::SecurityContext,
+};
+use super::pdf::{fit_text, PdfDocument, PdfFont, PdfPage, LETTER, POINTS_PER_INCH};
+
+/// Light modules kept around each printed symbol.
+const LABEL_QUIET_ZONE: usize = 2;
+
+/// Geometry of a label sheet, in points.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LabelTemplate {
+    pub page: (f32, f32),
+    pub columns: usize,
+    pub rows: usize,
+    pub label_width: f32,
+    pub label_height: f32,
+    pub left_margin: f32,
+    pub top_margin: f32,
+    /// Distance between the left edges of neighbouring labels.
+    pub horizontal_pitch: f32,
+    /// Distance between the top edges of neighbouring labels.
+    pub vertical_pitch: f32,
+}
+
+impl LabelTemplate {
+    /// Avery 5160: 30 address labels, 2 5/8" x 1".
+    pub fn avery_5160() -> Self {
+        Self::inches(3, 10, (2.625, 1.0), (0.1875, 0.5), (2.75, 1.0))
+    }
+
+    /// Avery 5163: 10 shipping labels, 4" x 2".
+    pub fn avery_5163() -> Self {
+        Self::inches(2, 5, (4.0, 2.0), (0.15625, 0.5), (4.1875, 2.0))
+    }
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "avery-5160" | "5160" => Some(Self::avery_5160()),
+            "avery-5163" | "5163" => Some(Self::avery_5163()),
+            _ => None,
+        }
+    }
+
+    fn inches(
+        columns: usize,
+        rows: usize,
+        label: (f32, f32),
+        margins: (f32, f32),
+        pitch: (f32, f32),
+    ) -> Self {
+        Self {
+            page: LETTER,
+            columns,
+            rows,
+            label_width: label.0 * POINTS_PER_INCH,
+            label_height: label.1 * POINTS_PER_INCH,
+            left_margin: margins.0 * POINTS_PER_INCH,
+            top_margin: margins.1 * POINTS_PER_INCH,
+            horizontal_pitch: pitch.0 * POINTS_PER_INCH,
+            vertical_pitch: pitch.1 * POINTS_PER_INCH,
+        }
+    }
+
+    pub fn labels_per_page(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    /// Top-left corner of the label in `slot`, counting across then down.
+    fn origin(&self, slot: usize) -> (f32, f32) {
+        let (row, column) = (slot / self.columns, slot % self.columns);
+        (
+            self.left_margin + column as f32 * self.horizontal_pitch,
+            self.top_margin + row as f32 * self.vertical_pitch,
+        )
+    }
+}
+
+/// What gets printed on one label.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertyLabel {
+    /// Signed QR payload, see [`QRCodeService::issue_qr`].
+    pub payload: String,
+    pub name: String,
+    pub nsn: Option<String>,
+    pub serial_number: Option<String>,
+    pub hand_receipt_number: Option<String>,
+}
+
+/// Renders `labels` onto as many sheets as needed and returns the PDF.
+pub fn render_label_sheets(template: &LabelTemplate, labels: &[PropertyLabel]) -> Result<Vec<u8>, CoreError> {
+    let mut document = PdfDocument::new(template.page);
+    for sheet in labels.chunks(template.labels_per_page().max(1)) {
+        let page = document.add_page();
+        for (slot, label) in sheet.iter().enumerate() {
+            draw_label(page, template, template.origin(slot), label)?;
+        }
+    }
+    if document.page_count() == 0 {
+        document.add_page();
+    }
+    Ok(document.to_bytes())
+}
+
+fn draw_label(
+    page: &mut PdfPage,
+    template: &LabelTemplate,
+    (x, y): (f32, f32),
+    label: &PropertyLabel,
+) -> Result<(), CoreError> {
+    let padding = (template.label_height * 0.06).max(2.0);
+
+    // QR code, square, filling the label height
+    let (width, modules) = qr_modules(&label.payload)?;
+    let side = template.label_height - 2.0 * padding;
+    let module = side / (width + 2 * LABEL_QUIET_ZONE) as f32;
+    let (qr_x, qr_y) = (x + padding + module * LABEL_QUIET_ZONE as f32, y + padding + module * LABEL_QUIET_ZONE as f32);
+    for (index, _) in modules.iter().enumerate().filter(|(_, dark)| **dark) {
+        let (column, row) = (index % width, index / width);
+        page.fill_rect(qr_x + column as f32 * module, qr_y + row as f32 * module, module, module);
+    }
+
+    // Text block to the right of the code
+    let text_x = x + padding + side + padding;
+    let text_width = template.label_width - (text_x - x) - padding;
+    let lines: Vec<(PdfFont, String)> = [
+        Some((PdfFont::Bold, label.name.clone())),
+        label.nsn.as_ref().map(|nsn| (PdfFont::Regular, format!("NSN {}", nsn))),
+        label.serial_number.as_ref().map(|serial| (PdfFont::Regular, format!("SN {}", serial))),
+        label.hand_receipt_number.as_ref().map(|number| (PdfFont::Regular, format!("HR {}", number))),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let size = (template.label_height / 6.0).clamp(5.0, 11.0);
+    let leading = size * 1.2;
+    let fitting = (((template.label_height - 2.0 * padding) / leading) as usize).max(1);
+    for (line, (font, text)) in lines.into_iter().take(fitting).enumerate() {
+        let baseline = y + padding + size + line as f32 * leading;
+        page.text(text_x, baseline, size, font, &fit_text(&text, text_width, size, font));
+    }
+    Ok(())
+}
+
+/// Issues label QR codes for a unit's property and lays them out.
+pub struct LabelSheetService {
+    properties: Arc<dyn PropertyService>,
+    qr_service: Arc<dyn QRCodeService>,
+}
+
+impl LabelSheetService {
+    pub fn new(properties: Arc<dyn PropertyService>, qr_service: Arc<dyn QRCodeService>) -> Self {
+        Self { properties, qr_service }
+    }
+
+    /// Label sheet for the property visible to `context`, optionally limited
+    /// to `property_ids`, in hand receipt number order.
+    pub async fn unit_sheets(
+        &self,
+        template: &LabelTemplate,
+        property_ids: Option<&[i32]>,
+        context: &SecurityContext,
+    ) -> Result<Vec<u8>, CoreError> {
+        let mut properties = self
+            .properties
+            .list_properties(context)
+            .await
+            .map_err(|e| CoreError::Repository(e.to_string()))?;
+        if let Some(ids) = property_ids {
+            properties.retain(|property| ids.contains(&property.id));
+        }
+        properties.retain(is_labelable);
+        sort_by_hand_receipt(&mut properties);
+
+        let mut labels = Vec::with_capacity(properties.len());
+        for property in &properties {
+            labels.push(property_label(self.qr_service.as_ref(), property, context).await?);
+        }
+        render_label_sheets(template, &labels)
+    }
+}
+
+/// Print order: by hand receipt number, then id.
+pub fn sort_by_hand_receipt(properties: &mut [Property]) {
+    properties.sort_by(|a, b| {
+        a.hand_receipt_number
+            .cmp(&b.hand_receipt_number)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+/// Whether `property` may carry a printed label.
+pub fn is_labelable(property: &Property) -> bool {
+    !property.is_sensitive
+}
+
+/// Issues a label QR code for `property` and collects what is printed
+/// beside it.
+pub async fn property_label(
+    qr_service: &dyn QRCodeService,
+    property: &Property,
+    context: &SecurityContext,
+) -> Result<PropertyLabel, CoreError> {
+    if !is_labelable(property) {
+        return Err(CoreError::Validation(format!(
+            "Property {} is a sensitive item and cannot be labeled",
+            property.id
+        )));
+    }
+
+    // Everything worth reading is printed beside the code, so the payload
+    // carries no metadata and stays small enough for tiny labels
+    let data = QRData::new(
+        property.id,
+        property.current_holder_id.to_string(),
+        serde_json::json!({}),
+    )
+    .with_category(property.category);
+    let payload = qr_service.issue_qr(&data, QRUsage::Label, context).await?;
+
+    Ok(PropertyLabel {
+        payload,
+        name: property.name.clone(),
+        nsn: property.nsn.clone(),
+        serial_number: property.serial_number.clone(),
+        hand_receipt_number: property.hand_receipt_number.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(n: usize) -> PropertyLabel {
+        PropertyLabel {
+            payload: format!("HR1:LABEL{}", n),
+            name: format!("Rifle, 5.56mm, M4 #{}", n),
+            nsn: Some("1005-01-231-0973".to_string()),
+            serial_number: Some(format!("W{:06}", n)),
+            hand_receipt_number: Some("HR-A-0001".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_templates_fit_the_page() {
+        for template in [LabelTemplate::avery_5160(), LabelTemplate::avery_5163()] {
+            let (right, bottom) = template.origin(template.labels_per_page() - 1);
+            assert!(right + template.label_width <= template.page.0 + 0.01);
+            assert!(bottom + template.label_height <= template.page.1 + 0.01);
+        }
+        assert_eq!(LabelTemplate::by_name("5160"), Some(LabelTemplate::avery_5160()));
+        assert_eq!(LabelTemplate::by_name("avery-9999"), None);
+    }
+
+    #[test]
+    fn test_sheets_paginate() {
+        let template = LabelTemplate::avery_5163();
+        let labels: Vec<PropertyLabel> = (0..23).map(label).collect();
+        let pdf = render_label_sheets(&template, &labels).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("/Count 3"));
+        assert!(text.contains("(NSN 1005-01-231-0973) Tj"));
+        assert!(text.contains("(SN W000022) Tj"));
+
+        let empty = render_label_sheets(&template, &[]).unwrap();
+        assert!(String::from_utf8_lossy(&empty).contains("/Count 1"));
+    }
+}
@@ -0,0 +1,11 @@
+pub mod batch;
+pub mod hand_receipt;
+pub mod labels;
+pub mod pdf;
+
+pub use batch::{QRBatchService, DEFAULT_LABEL_TEMPLATE, INTERRUPTED_JOB_ERROR};
+pub use hand_receipt::{render_hand_receipt, GeneratedHandReceipt, HandReceipt, HandReceiptService};
+pub use labels::{
+    is_labelable, property_label, render_label_sheets, sort_by_hand_receipt, LabelSheetService, LabelTemplate, PropertyLabel,
+};
+pub use pdf::{PdfDocument, PdfFont, PdfPage};
@@ -0,0 +1,261 @@
+//! Minimal PDF writer for printable documents.
+//!
+//! Only what our forms and labels need: filled and stroked rectangles,
+//! lines, and text in the standard Helvetica faces, which every PDF reader
+//! ships so nothing has to be embedded. Coordinates are in points (1/72 in)
+//! measured from the top-left corner of the page; the writer flips them into
+//! PDF's bottom-left space.
+
+use std::fmt::Write as _;
+
+pub const POINTS_PER_INCH: f32 = 72.0;
+
+/// US Letter in points.
+pub const LETTER: (f32, f32) = (8.5 * POINTS_PER_INCH, 11.0 * POINTS_PER_INCH);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfFont {
+    Regular,
+    Bold,
+}
+
+impl PdfFont {
+    fn resource(&self) -> &'static str {
+        match self {
+            PdfFont::Regular => "F1",
+            PdfFont::Bold => "F2",
+        }
+    }
+}
+
+/// Approximate advance width of `text` in Helvetica at `size`. Good enough
+/// to truncate text to a box; Bold runs slightly wider.
+pub fn text_width(text: &str, size: f32, font: PdfFont) -> f32 {
+    let em = match font {
+        PdfFont::Regular => 0.52,
+        PdfFont::Bold => 0.56,
+    };
+    text.chars().count() as f32 * size * em
+}
+
+/// Shortens `text` with an ellipsis so it fits `width` points.
+pub fn fit_text(text: &str, width: f32, size: f32, font: PdfFont) -> String {
+    if text_width(text, size, font) <= width {
+        return text.to_string();
+    }
+    let mut fitted: String = text.to_string();
+    while !fitted.is_empty() && text_width(&fitted, size, font) + text_width("...", size, font) > width {
+        fitted.pop();
+    }
+    format!("{}...", fitted.trim_end())
+}
+
+/// Drawing operations for one page.
+#[derive(Debug, Clone)]
+pub struct PdfPage {
+    height: f32,
+    content: String,
+}
+
+impl PdfPage {
+    fn new(height: f32) -> Self {
+        Self {
+            height,
+            content: String::new(),
+        }
+    }
+
+    pub fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        let _ = writeln!(self.content, "{} {} {} {} re f", n(x), n(self.height - y - height), n(width), n(height));
+    }
+
+    pub fn stroke_rect(&mut self, x: f32, y: f32, width: f32, height: f32, line_width: f32) {
+        let _ = writeln!(
+            self.content,
+            "{} w {} {} {} {} re S",
+            n(line_width),
+            n(x),
+            n(self.height - y - height),
+            n(width),
+            n(height)
+        );
+    }
+
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32), line_width: f32) {
+        let _ = writeln!(
+            self.content,
+            "{} w {} {} m {} {} l S",
+            n(line_width),
+            n(from.0),
+            n(self.height - from.1),
+            n(to.0),
+            n(self.height - to.1)
+        );
+    }
+
+    /// Draws `text` with its baseline at `y`.
+    pub fn text(&mut self, x: f32, y: f32, size: f32, font: PdfFont, text: &str) {
+        let _ = writeln!(
+            self.content,
+            "BT /{} {} Tf {} {} Td ({}) Tj ET",
+            font.resource(),
+            n(size),
+            n(x),
+            n(self.height - y),
+            escape(text)
+        );
+    }
+}
+
+/// A document of same-sized pages.
+#[derive(Debug, Clone)]
+pub struct PdfDocument {
+    width: f32,
+    height: f32,
+    pages: Vec<PdfPage>,
+}
+
+impl PdfDocument {
+    pub fn new(size: (f32, f32)) -> Self {
+        Self {
+            width: size.0,
+            height: size.1,
+            pages: Vec::new(),
+        }
+    }
+
+    /// Starts a new page and returns it for drawing.
+    pub fn add_page(&mut self) -> &mut PdfPage {
+        self.pages.push(PdfPage::new(self.height));
+        self.pages.last_mut().expect("page was just pushed")
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Serializes the document. Objects are numbered catalog, page tree,
+    /// the two fonts, then a page and content stream pair per page.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut objects: Vec<Vec<u8>> = Vec::new();
+        let first_page = 5;
+        let kids: Vec<String> = (0..self.pages.len())
+            .map(|index| format!("{} 0 R", first_page + 2 * index))
+            .collect();
+
+        objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+        objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), self.pages.len()).into_bytes());
+        objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec());
+        objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec());
+        for (index, page) in self.pages.iter().enumerate() {
+            let content_id = first_page + 2 * index + 1;
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                    n(self.width),
+                    n(self.height),
+                    content_id
+                )
+                .into_bytes(),
+            );
+            let stream = latin1(&page.content);
+            let mut object = format!("<< /Length {} >>\nstream\n", stream.len()).into_bytes();
+            object.extend_from_slice(&stream);
+            object.extend_from_slice(b"\nendstream");
+            objects.push(object);
+        }
+
+        let mut out = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+            out.extend_from_slice(object);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref = out.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(table, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            table,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        );
+        out.extend_from_slice(table.as_bytes());
+        out
+    }
+}
+
+/// Formats a coordinate without trailing zeros.
+fn n(value: f32) -> String {
+    let formatted = format!("{:.2}", value);
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Escapes a PDF literal string.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Encodes content for the WinAnsi fonts. Latin-1 maps directly; anything
+/// else becomes `?`.
+fn latin1(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| u8::try_from(u32::from(c)).unwrap_or(b'?'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_structure() {
+        let mut document = PdfDocument::new(LETTER);
+        let page = document.add_page();
+        page.fill_rect(10.0, 20.0, 5.0, 5.0);
+        page.text(72.0, 72.0, 12.0, PdfFont::Bold, "M4 (Carbine) \\ 1");
+        document.add_page();
+
+        let bytes = document.to_bytes();
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.trim_end().ends_with("%%EOF"));
+        assert!(text.contains("/Count 2"));
+        assert!(text.contains("/Kids [5 0 R 7 0 R]"));
+        assert!(text.contains("10 767 5 5 re f"));
+        assert!(text.contains("(M4 \\(Carbine\\) \\\\ 1) Tj"));
+
+        // Every xref entry points at its object header
+        let xref = text.find("\nxref\n").unwrap() + 1;
+        let entries: Vec<&str> = text[xref..].lines().skip(3).take(8).collect();
+        assert_eq!(entries.len(), 8);
+        for (index, entry) in entries.iter().enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(bytes[offset..].starts_with(format!("{} 0 obj", index + 1).as_bytes()));
+        }
+    }
+
+    #[test]
+    fn test_fit_text() {
+        assert_eq!(fit_text("Rifle", 100.0, 10.0, PdfFont::Regular), "Rifle");
+        let fitted = fit_text("Night vision device, monocular, AN/PVS-14", 60.0, 10.0, PdfFont::Regular);
+        assert!(fitted.ends_with("..."));
+        assert!(text_width(&fitted, 10.0, PdfFont::Regular) <= 60.0);
+    }
+}
@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, Int32Array, RecordBatch, StringArray,
+    TimestampMicrosecondArray,
+};
+use arrow_schema::SchemaRef;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt, TryStreamExt,
+};
+use parquet::arrow::ArrowWriter;
+use serde::{Deserialize, Serialize};
+
+use crate::error::CoreError;
+
+/// Rows encoded together. Each batch becomes one chunk of the response and,
+/// for Parquet, one row group.
+pub const EXPORT_BATCH_ROWS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Jsonl => "application/x-ndjson",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// A flat row that can be written in every export format. Serialized
+/// fields must follow the order of [`ExportRecord::schema`].
+pub trait ExportRecord: Serialize + Send + 'static {
+    fn schema() -> SchemaRef;
+
+    /// One array per schema field, holding that column of `rows`.
+    fn columns(rows: &[Self]) -> Vec<ArrayRef>
+    where
+        Self: Sized;
+}
+
+pub(crate) fn int32_column<T>(rows: &[T], value: impl Fn(&T) -> Option<i32>) -> ArrayRef {
+    Arc::new(rows.iter().map(value).collect::<Int32Array>())
+}
+
+pub(crate) fn float64_column<T>(rows: &[T], value: impl Fn(&T) -> Option<f64>) -> ArrayRef {
+    Arc::new(rows.iter().map(value).collect::<Float64Array>())
+}
+
+pub(crate) fn boolean_column<T>(rows: &[T], value: impl Fn(&T) -> bool) -> ArrayRef {
+    Arc::new(rows.iter().map(|row| Some(value(row))).collect::<BooleanArray>())
+}
+
+pub(crate) fn string_column<T>(rows: &[T], value: impl Fn(&T) -> Option<&str>) -> ArrayRef {
+    Arc::new(rows.iter().map(value).collect::<StringArray>())
+}
+
+pub(crate) fn timestamp_column<T>(rows: &[T], value: impl Fn(&T) -> Option<DateTime<Utc>>) -> ArrayRef {
+    Arc::new(
+        rows.iter()
+            .map(|row| value(row).map(|at| at.timestamp_micros()))
+            .collect::<TimestampMicrosecondArray>()
+            .with_timezone("UTC"),
+    )
+}
+
+fn export_error(e: impl std::fmt::Display) -> CoreError {
+    CoreError::InternalError(format!("Export failed: {}", e))
+}
+
+enum Encoder {
+    Csv { header_written: bool },
+    Jsonl,
+    Parquet(Box<ArrowWriter<Vec<u8>>>),
+}
+
+impl Encoder {
+    fn new<T: ExportRecord>(format: ExportFormat) -> Result<Self, CoreError> {
+        Ok(match format {
+            ExportFormat::Csv => Encoder::Csv { header_written: false },
+            ExportFormat::Jsonl => Encoder::Jsonl,
+            ExportFormat::Parquet => {
+                Encoder::Parquet(Box::new(ArrowWriter::try_new(Vec::new(), T::schema(), None).map_err(export_error)?))
+            }
+        })
+    }
+
+    /// Encodes `rows`, returning the bytes that are complete so far.
+    fn write<T: ExportRecord>(&mut self, rows: &[T]) -> Result<Vec<u8>, CoreError> {
+        match self {
+            Encoder::Csv { header_written } => {
+                let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+                if !*header_written {
+                    writer
+                        .write_record(T::schema().fields().iter().map(|field| field.name()))
+                        .map_err(export_error)?;
+                    *header_written = true;
+                }
+                for row in rows {
+                    writer.serialize(row).map_err(export_error)?;
+                }
+                writer.into_inner().map_err(export_error)
+            }
+            Encoder::Jsonl => {
+                let mut out = Vec::new();
+                for row in rows {
+                    serde_json::to_writer(&mut out, row)?;
+                    out.push(b'\n');
+                }
+                Ok(out)
+            }
+            Encoder::Parquet(writer) => {
+                if !rows.is_empty() {
+                    let batch = RecordBatch::try_new(T::schema(), T::columns(rows)).map_err(export_error)?;
+                    writer.write(&batch).map_err(export_error)?;
+                    // Close the row group so its pages reach the buffer
+                    writer.flush().map_err(export_error)?;
+                }
+                // The writer tracks offsets itself, so written bytes can be
+                // handed out as they are produced
+                Ok(std::mem::take(writer.inner_mut()))
+            }
+        }
+    }
+
+    /// Whatever remains, e.g. a CSV header for an empty export or the
+    /// Parquet footer.
+    fn finish<T: ExportRecord>(mut self) -> Result<Vec<u8>, CoreError> {
+        match self {
+            Encoder::Csv { header_written: false } => self.write::<T>(&[]),
+            Encoder::Csv { .. } | Encoder::Jsonl => Ok(Vec::new()),
+            Encoder::Parquet(writer) => writer.into_inner().map_err(export_error),
+        }
+    }
+}
+
+/// Encodes `rows` as they arrive, [`EXPORT_BATCH_ROWS`] at a time. The
+/// stream ends after the first error.
+pub fn encode<T: ExportRecord>(
+    format: ExportFormat,
+    rows: BoxStream<'static, Result<T, CoreError>>,
+) -> BoxStream<'static, Result<Bytes, CoreError>> {
+    let encoder = match Encoder::new::<T>(format) {
+        Ok(encoder) => encoder,
+        Err(e) => return stream::once(async move { Err(e) }).boxed(),
+    };
+
+    stream::unfold(
+        (rows.try_chunks(EXPORT_BATCH_ROWS), Some(encoder)),
+        |(mut batches, mut encoder)| async move {
+            let current = encoder.as_mut()?;
+            let chunk = match batches.next().await {
+                Some(Ok(batch)) => current.write(&batch),
+                Some(Err(e)) => Err(e.1),
+                // Nothing follows the final chunk
+                None => encoder.take()?.finish::<T>(),
+            };
+            if chunk.is_err() {
+                encoder = None;
+            }
+            Some((chunk.map(Bytes::from), (batches, encoder)))
+        },
+    )
+    .try_filter(|chunk| futures::future::ready(!chunk.is_empty()))
+    .boxed()
+}
@@ -0,0 +1,487 @@
+//! Property book and transfer exports.
+//!
+//! Records are read from the repositories a page at a time, already
+//! filtered, and encoded as they arrive, so a unit's whole book never sits
+//! in memory. Sensitive items
+//! are redacted for callers not cleared to handle them, exactly as they
+//! would be anywhere else.
+
+pub mod encoder;
+
+use std::sync::{Arc, OnceLock};
+
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt, TryStreamExt,
+};
+use serde::Serialize;
+
+use crate::{
+    domain::{
+        property::{entity::Property, filter::PropertyFilter, repository::PropertyRepository},
+        transfer::{entity::Transfer, filter::TransferFilter, repository::TransferRepository},
+    },
+    error::CoreError,
+    types::{permissions::Permission, security::SecurityContext},
+};
+use self::encoder::{
+    boolean_column, encode, float64_column, int32_column, string_column, timestamp_column, ExportRecord,
+};
+pub use self::encoder::ExportFormat;
+
+/// Records read from a repository per query.
+const PAGE_SIZE: i64 = 500;
+
+fn timestamp_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+}
+
+/// One exported property.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PropertyRecord {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub category: String,
+    pub status: String,
+    pub current_holder_id: i32,
+    pub unit: Option<String>,
+    pub quantity: i32,
+    pub nsn: Option<String>,
+    pub serial_number: Option<String>,
+    pub hand_receipt_number: Option<String>,
+    pub is_sensitive: bool,
+    pub requires_approval: bool,
+    pub building: Option<String>,
+    pub room: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Set when sensitive details were withheld from the caller.
+    pub redacted: bool,
+}
+
+impl PropertyRecord {
+    pub fn new(mut property: Property, redact: bool) -> Self {
+        if redact {
+            property.redact();
+        }
+        let unit = property.unit().map(str::to_string);
+        Self {
+            id: property.id,
+            name: property.name,
+            description: property.description,
+            category: property.category.to_string(),
+            status: property.status.to_string(),
+            current_holder_id: property.current_holder_id,
+            unit,
+            quantity: property.quantity,
+            nsn: property.nsn,
+            serial_number: property.serial_number,
+            hand_receipt_number: property.hand_receipt_number,
+            is_sensitive: property.is_sensitive,
+            requires_approval: property.requires_approval,
+            building: property.location.building,
+            room: property.location.room,
+            latitude: (!redact).then_some(property.location.latitude),
+            longitude: (!redact).then_some(property.location.longitude),
+            notes: property.notes,
+            created_at: property.created_at,
+            updated_at: property.updated_at,
+            redacted: redact,
+        }
+    }
+}
+
+impl ExportRecord for PropertyRecord {
+    fn schema() -> SchemaRef {
+        static SCHEMA: OnceLock<SchemaRef> = OnceLock::new();
+        SCHEMA
+            .get_or_init(|| {
+                Arc::new(Schema::new(vec![
+                    Field::new("id", DataType::Int32, false),
+                    Field::new("name", DataType::Utf8, false),
+                    Field::new("description", DataType::Utf8, false),
+                    Field::new("category", DataType::Utf8, false),
+                    Field::new("status", DataType::Utf8, false),
+                    Field::new("current_holder_id", DataType::Int32, false),
+                    Field::new("unit", DataType::Utf8, true),
+                    Field::new("quantity", DataType::Int32, false),
+                    Field::new("nsn", DataType::Utf8, true),
+                    Field::new("serial_number", DataType::Utf8, true),
+                    Field::new("hand_receipt_number", DataType::Utf8, true),
+                    Field::new("is_sensitive", DataType::Boolean, false),
+                    Field::new("requires_approval", DataType::Boolean, false),
+                    Field::new("building", DataType::Utf8, true),
+                    Field::new("room", DataType::Utf8, true),
+                    Field::new("latitude", DataType::Float64, true),
+                    Field::new("longitude", DataType::Float64, true),
+                    Field::new("notes", DataType::Utf8, true),
+                    Field::new("created_at", timestamp_type(), false),
+                    Field::new("updated_at", timestamp_type(), false),
+                    Field::new("redacted", DataType::Boolean, false),
+                ]))
+            })
+            .clone()
+    }
+
+    fn columns(rows: &[Self]) -> Vec<arrow_array::ArrayRef> {
+        vec![
+            int32_column(rows, |row| Some(row.id)),
+            string_column(rows, |row| Some(&row.name)),
+            string_column(rows, |row| Some(&row.description)),
+            string_column(rows, |row| Some(&row.category)),
+            string_column(rows, |row| Some(&row.status)),
+            int32_column(rows, |row| Some(row.current_holder_id)),
+            string_column(rows, |row| row.unit.as_deref()),
+            int32_column(rows, |row| Some(row.quantity)),
+            string_column(rows, |row| row.nsn.as_deref()),
+            string_column(rows, |row| row.serial_number.as_deref()),
+            string_column(rows, |row| row.hand_receipt_number.as_deref()),
+            boolean_column(rows, |row| row.is_sensitive),
+            boolean_column(rows, |row| row.requires_approval),
+            string_column(rows, |row| row.building.as_deref()),
+            string_column(rows, |row| row.room.as_deref()),
+            float64_column(rows, |row| row.latitude),
+            float64_column(rows, |row| row.longitude),
+            string_column(rows, |row| row.notes.as_deref()),
+            timestamp_column(rows, |row| Some(row.created_at)),
+            timestamp_column(rows, |row| Some(row.updated_at)),
+            boolean_column(rows, |row| row.redacted),
+        ]
+    }
+}
+
+/// One exported transfer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TransferRecord {
+    pub id: i32,
+    pub property_id: i32,
+    pub from_holder_id: i32,
+    pub to_holder_id: i32,
+    pub status: String,
+    pub building: Option<String>,
+    pub room: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub approved_at: Option<DateTime<Utc>>,
+    pub approved_by_id: Option<i32>,
+    /// Set when the transfer moved a sensitive item the caller may not see.
+    pub redacted: bool,
+}
+
+impl TransferRecord {
+    pub fn new(mut transfer: Transfer, redact: bool) -> Self {
+        if redact {
+            transfer.redact();
+        }
+        Self {
+            id: transfer.id,
+            property_id: transfer.property_id,
+            from_holder_id: transfer.from_holder_id,
+            to_holder_id: transfer.to_holder_id,
+            status: transfer.status.to_string(),
+            building: transfer.location.building,
+            room: transfer.location.room,
+            latitude: (!redact).then_some(transfer.location.latitude),
+            longitude: (!redact).then_some(transfer.location.longitude),
+            notes: transfer.notes,
+            created_at: transfer.created_at,
+            updated_at: transfer.updated_at,
+            approved_at: transfer.approved_at,
+            approved_by_id: transfer.approved_by_id,
+            redacted: redact,
+        }
+    }
+}
+
+impl ExportRecord for TransferRecord {
+    fn schema() -> SchemaRef {
+        static SCHEMA: OnceLock<SchemaRef> = OnceLock::new();
+        SCHEMA
+            .get_or_init(|| {
+                Arc::new(Schema::new(vec![
+                    Field::new("id", DataType::Int32, false),
+                    Field::new("property_id", DataType::Int32, false),
+                    Field::new("from_holder_id", DataType::Int32, false),
+                    Field::new("to_holder_id", DataType::Int32, false),
+                    Field::new("status", DataType::Utf8, false),
+                    Field::new("building", DataType::Utf8, true),
+                    Field::new("room", DataType::Utf8, true),
+                    Field::new("latitude", DataType::Float64, true),
+                    Field::new("longitude", DataType::Float64, true),
+                    Field::new("notes", DataType::Utf8, true),
+                    Field::new("created_at", timestamp_type(), false),
+                    Field::new("updated_at", timestamp_type(), false),
+                    Field::new("approved_at", timestamp_type(), true),
+                    Field::new("approved_by_id", DataType::Int32, true),
+                    Field::new("redacted", DataType::Boolean, false),
+                ]))
+            })
+            .clone()
+    }
+
+    fn columns(rows: &[Self]) -> Vec<arrow_array::ArrayRef> {
+        vec![
+            int32_column(rows, |row| Some(row.id)),
+            int32_column(rows, |row| Some(row.property_id)),
+            int32_column(rows, |row| Some(row.from_holder_id)),
+            int32_column(rows, |row| Some(row.to_holder_id)),
+            string_column(rows, |row| Some(&row.status)),
+            string_column(rows, |row| row.building.as_deref()),
+            string_column(rows, |row| row.room.as_deref()),
+            float64_column(rows, |row| row.latitude),
+            float64_column(rows, |row| row.longitude),
+            string_column(rows, |row| row.notes.as_deref()),
+            timestamp_column(rows, |row| Some(row.created_at)),
+            timestamp_column(rows, |row| Some(row.updated_at)),
+            timestamp_column(rows, |row| row.approved_at),
+            int32_column(rows, |row| row.approved_by_id),
+            boolean_column(rows, |row| row.redacted),
+        ]
+    }
+}
+
+/// Every property matching `filter`, paged by id.
+fn all_properties(
+    properties: Arc<dyn PropertyRepository>,
+    filter: PropertyFilter,
+) -> BoxStream<'static, Result<Property, CoreError>> {
+    stream::try_unfold(Some(0), move |after_id| {
+        let (properties, filter) = (properties.clone(), filter.clone());
+        async move {
+            let Some(after_id) = after_id else { return Ok::<_, CoreError>(None) };
+            let page = properties
+                .list_properties_after(&filter, after_id, PAGE_SIZE)
+                .await
+                .map_err(|e| CoreError::Repository(e.to_string()))?;
+            // A short page is the last one
+            let next = match page.last() {
+                Some(last) if page.len() as i64 == PAGE_SIZE => Some(last.id),
+                _ => None,
+            };
+            Ok(Some((stream::iter(page.into_iter().map(Ok::<_, CoreError>)), next)))
+        }
+    })
+    .try_flatten()
+    .boxed()
+}
+
+/// Every transfer matching `filter` with whether its property is
+/// sensitive, paged by id.
+fn all_transfers(
+    transfers: Arc<dyn TransferRepository>,
+    filter: TransferFilter,
+) -> BoxStream<'static, Result<(Transfer, bool), CoreError>> {
+    stream::try_unfold(Some(0), move |after_id| {
+        let (transfers, filter) = (transfers.clone(), filter.clone());
+        async move {
+            let Some(after_id) = after_id else { return Ok::<_, CoreError>(None) };
+            let page = transfers
+                .list_transfers_after(&filter, after_id, PAGE_SIZE)
+                .await
+                .map_err(|e| CoreError::Repository(e.to_string()))?;
+            let next = match page.last() {
+                Some((last, _)) if page.len() as i64 == PAGE_SIZE => Some(last.id),
+                _ => None,
+            };
+            Ok(Some((stream::iter(page.into_iter().map(Ok::<_, CoreError>)), next)))
+        }
+    })
+    .try_flatten()
+    .boxed()
+}
+
+pub struct ExportService {
+    properties: Arc<dyn PropertyRepository>,
+    transfers: Arc<dyn TransferRepository>,
+}
+
+impl ExportService {
+    pub fn new(properties: Arc<dyn PropertyRepository>, transfers: Arc<dyn TransferRepository>) -> Self {
+        Self { properties, transfers }
+    }
+
+    /// Properties matching `filter`, redacted for `context`.
+    pub fn property_records(
+        &self,
+        filter: PropertyFilter,
+        context: &SecurityContext,
+    ) -> Result<BoxStream<'static, Result<PropertyRecord, CoreError>>, CoreError> {
+        if !context.has_permission(&Permission::ViewProperty) {
+            return Err(CoreError::Authorization("Exporting property requires permission to view property".to_string()));
+        }
+        let cleared = context.can_handle_sensitive_items();
+
+        Ok(all_properties(self.properties.clone(), filter)
+            .map_ok(move |property| {
+                let redact = property.is_sensitive && !cleared;
+                PropertyRecord::new(property, redact)
+            })
+            .boxed())
+    }
+
+    /// Transfers matching `filter`. Transfers of sensitive items are
+    /// redacted unless `context` is cleared to handle them.
+    pub fn transfer_records(
+        &self,
+        filter: TransferFilter,
+        context: &SecurityContext,
+    ) -> Result<BoxStream<'static, Result<TransferRecord, CoreError>>, CoreError> {
+        if !context.has_permission(&Permission::ViewTransfer) {
+            return Err(CoreError::Authorization("Exporting transfers requires permission to view transfers".to_string()));
+        }
+        let cleared = context.can_handle_sensitive_items();
+
+        Ok(all_transfers(self.transfers.clone(), filter)
+            .map_ok(move |(transfer, is_sensitive)| TransferRecord::new(transfer, is_sensitive && !cleared))
+            .boxed())
+    }
+
+    /// The encoded property export.
+    pub fn export_properties(
+        &self,
+        format: ExportFormat,
+        filter: PropertyFilter,
+        context: &SecurityContext,
+    ) -> Result<BoxStream<'static, Result<Bytes, CoreError>>, CoreError> {
+        Ok(encode(format, self.property_records(filter, context)?))
+    }
+
+    /// The encoded transfer export.
+    pub fn export_transfers(
+        &self,
+        format: ExportFormat,
+        filter: TransferFilter,
+        context: &SecurityContext,
+    ) -> Result<BoxStream<'static, Result<Bytes, CoreError>>, CoreError> {
+        Ok(encode(format, self.transfer_records(filter, context)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use crate::{
+        domain::{models::location::Location, property::entity::PropertyCategory},
+        test_support::{MockProperties, MockTransfers},
+        types::security::SecurityClassification,
+    };
+
+    fn location() -> Location {
+        Location {
+            latitude: 35.14,
+            longitude: -79.0,
+            building: Some("Arms room".to_string()),
+            ..Location::default()
+        }
+    }
+
+    /// Property 1 is a sensitive rifle, the rest are cots in unit A-1-1.
+    fn service(count: i32) -> ExportService {
+        let properties = (1..=count)
+            .map(|id| {
+                let (name, category) = if id == 1 {
+                    ("Rifle, 5.56mm, M4", PropertyCategory::Weapon)
+                } else {
+                    ("Cot, folding", PropertyCategory::Equipment)
+                };
+                let mut property = Property::new(name.to_string(), String::new(), category, 3, location());
+                property.id = id;
+                property.is_sensitive = id == 1;
+                property.serial_number = Some(format!("SN{}", id));
+                property.metadata = serde_json::json!({ "unit": "A-1-1" });
+                property
+            })
+            .collect();
+        let transfers = vec![
+            Transfer { id: 1, ..Transfer::new(1, 3, 4, location(), Some("Issued at arms room".to_string())) },
+            Transfer { id: 2, ..Transfer::new(2, 3, 4, location(), Some("Field issue".to_string())) },
+        ];
+        ExportService::new(
+            Arc::new(MockProperties::new(properties)),
+            Arc::new(MockTransfers::new(transfers).with_sensitive_properties([1])),
+        )
+    }
+
+    async fn collect(stream: BoxStream<'static, Result<Bytes, CoreError>>) -> (usize, Bytes) {
+        let chunks: Vec<Bytes> = stream.try_collect().await.unwrap();
+        let mut out = BytesMut::new();
+        for chunk in &chunks {
+            out.extend_from_slice(chunk);
+        }
+        (chunks.len(), out.freeze())
+    }
+
+    fn cleared() -> SecurityContext {
+        SecurityContext { classification: SecurityClassification::Secret, ..SecurityContext::new(1) }
+    }
+
+    #[tokio::test]
+    async fn test_csv_pages_filters_and_redacts() {
+        let service = service(1200);
+        let filter = PropertyFilter { unit: Some("A-1-1".to_string()), ..Default::default() };
+
+        let (chunks, csv) = collect(service.export_properties(ExportFormat::Csv, filter, &SecurityContext::new(9)).unwrap()).await;
+        assert_eq!(chunks, 2);
+        let csv = String::from_utf8(csv.to_vec()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 1201);
+        assert!(lines[0].starts_with("id,name,description,category,status,current_holder_id,unit,"));
+        assert!(lines[0].ends_with(",created_at,updated_at,redacted"));
+        assert!(lines[1].starts_with("1,\"Rifle, 5.56mm, M4\",,weapon,available,3,A-1-1,1,,,"));
+        assert!(lines[1].ends_with(",true"));
+        assert!(!lines[1].contains("SN1") && !lines[1].contains("Arms room"));
+        assert!(lines[2].contains("SN2") && lines[2].contains("Arms room") && lines[2].ends_with(",false"));
+
+        let (_, cleared_csv) = collect(service.export_properties(ExportFormat::Csv, PropertyFilter::default(), &cleared()).unwrap()).await;
+        assert!(String::from_utf8(cleared_csv.to_vec()).unwrap().contains("SN1"));
+
+        let other_unit = PropertyFilter { unit: Some("B-1-1".to_string()), ..Default::default() };
+        let (_, empty) = collect(service.export_properties(ExportFormat::Csv, other_unit, &cleared()).unwrap()).await;
+        assert_eq!(String::from_utf8(empty.to_vec()).unwrap().lines().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_transfers_redact_sensitive_items() {
+        let service = service(2);
+        let (_, jsonl) = collect(service.export_transfers(ExportFormat::Jsonl, TransferFilter::default(), &SecurityContext::new(9)).unwrap()).await;
+        let records: Vec<serde_json::Value> = String::from_utf8(jsonl.to_vec())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["redacted"], true);
+        assert!(records[0]["notes"].is_null() && records[0]["latitude"].is_null());
+        assert_eq!(records[1]["redacted"], false);
+        assert_eq!(records[1]["notes"], "Field issue");
+
+        let filter = TransferFilter { property_id: Some(2), ..Default::default() };
+        let (_, jsonl) = collect(service.export_transfers(ExportFormat::Jsonl, filter, &SecurityContext::new(9)).unwrap()).await;
+        assert_eq!(String::from_utf8(jsonl.to_vec()).unwrap().lines().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_parquet_streams_row_groups() {
+        let service = service(1200);
+        let (chunks, parquet) = collect(service.export_properties(ExportFormat::Parquet, PropertyFilter::default(), &cleared()).unwrap()).await;
+        assert!(chunks >= 2);
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(parquet).unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        assert_eq!(reader.schema().as_ref(), PropertyRecord::schema().as_ref());
+        let rows: usize = reader.build().unwrap().map(|batch| batch.unwrap().num_rows()).sum();
+        assert_eq!(rows, 1200);
+    }
+}
@@ -1,4 +1,6 @@
 pub mod blockchain;
+pub mod documents;
+pub mod export;
 pub mod persistence;
 
 use crate::{
@@ -0,0 +1,42 @@
+use sqlx::PgPool;
+use async_trait::async_trait;
+use crate::{
+    domain::audit_log::{entity::AuditLogEntry, repository::AuditLogRepository},
+    error::RepositoryError,
+};
+
+pub struct PgAuditLogRepository {
+    pool: PgPool,
+}
+
+impl PgAuditLogRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AuditLogRepository for PgAuditLogRepository {
+    async fn record(&self, entry: &AuditLogEntry) -> Result<AuditLogEntry, RepositoryError> {
+        let record = sqlx::query!(
+            r#"
+            INSERT INTO audit_log (event_type, description, user_id, metadata)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, created_at
+            "#,
+            entry.event_type,
+            entry.description,
+            entry.user_id,
+            entry.metadata
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(AuditLogEntry {
+            id: record.id,
+            created_at: record.created_at,
+            ..entry.clone()
+        })
+    }
+}
@@ -0,0 +1,203 @@
+use sqlx::{PgExecutor, PgPool};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use crate::{
+    domain::certificate::{
+        entity::{CertificateRecord, RevocationReason},
+        repository::CertificateRepository,
+    },
+    error::RepositoryError,
+    infrastructure::blockchain::certificate::MilitaryCertificate,
+};
+
+pub struct PgCertificateRepository {
+    pool: PgPool,
+}
+
+impl PgCertificateRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+/// Public key of the newest unrevoked, currently valid certificate issued to
+/// `user_id`, i.e. the key that user signs with.
+pub async fn active_public_key<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: i32,
+) -> Result<Option<String>, RepositoryError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT public_key FROM certificates
+        WHERE user_id = $1
+          AND revoked_at IS NULL
+          AND valid_from <= CURRENT_TIMESTAMP
+          AND (valid_until IS NULL OR valid_until > CURRENT_TIMESTAMP)
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+        user_id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| RepositoryError::Database(e.to_string()))
+}
+
+struct CertificateRow {
+    serial: String,
+    user_id: i32,
+    issuer: String,
+    subject: String,
+    public_key: String,
+    unit_id: String,
+    roles: Vec<String>,
+    valid_from: DateTime<Utc>,
+    valid_until: Option<DateTime<Utc>>,
+    signature: String,
+    revoked_at: Option<DateTime<Utc>>,
+    revocation_reason: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl CertificateRow {
+    fn into_record(self) -> Result<CertificateRecord, RepositoryError> {
+        Ok(CertificateRecord {
+            user_id: self.user_id,
+            certificate: MilitaryCertificate {
+                serial: self.serial,
+                issuer: self.issuer,
+                subject: self.subject,
+                public_key: self.public_key,
+                valid_from: self.valid_from,
+                valid_until: self.valid_until,
+                roles: self.roles,
+                unit_id: self.unit_id,
+                signature: self.signature,
+            },
+            revoked_at: self.revoked_at,
+            revocation_reason: self
+                .revocation_reason
+                .map(|reason| reason.parse::<RevocationReason>())
+                .transpose()
+                .map_err(RepositoryError::Serialization)?,
+            created_at: self.created_at,
+        })
+    }
+}
+
+#[async_trait]
+impl CertificateRepository for PgCertificateRepository {
+    async fn create(&self, record: &CertificateRecord) -> Result<CertificateRecord, RepositoryError> {
+        let certificate = &record.certificate;
+        let row = sqlx::query_as!(
+            CertificateRow,
+            r#"
+            INSERT INTO certificates (
+                serial, user_id, issuer, subject, public_key, unit_id,
+                roles, valid_from, valid_until, signature
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING serial, user_id, issuer, subject, public_key, unit_id,
+                      roles, valid_from, valid_until, signature,
+                      revoked_at, revocation_reason, created_at
+            "#,
+            certificate.serial,
+            record.user_id,
+            certificate.issuer,
+            certificate.subject,
+            certificate.public_key,
+            certificate.unit_id,
+            &certificate.roles,
+            certificate.valid_from,
+            certificate.valid_until,
+            certificate.signature
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        row.into_record()
+    }
+
+    async fn get(&self, serial: &str) -> Result<Option<CertificateRecord>, RepositoryError> {
+        let row = sqlx::query_as!(
+            CertificateRow,
+            r#"
+            SELECT serial, user_id, issuer, subject, public_key, unit_id,
+                   roles, valid_from, valid_until, signature,
+                   revoked_at, revocation_reason, created_at
+            FROM certificates
+            WHERE serial = $1
+            "#,
+            serial
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        row.map(CertificateRow::into_record).transpose()
+    }
+
+    async fn list_by_user(&self, user_id: i32) -> Result<Vec<CertificateRecord>, RepositoryError> {
+        let rows = sqlx::query_as!(
+            CertificateRow,
+            r#"
+            SELECT serial, user_id, issuer, subject, public_key, unit_id,
+                   roles, valid_from, valid_until, signature,
+                   revoked_at, revocation_reason, created_at
+            FROM certificates
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        rows.into_iter().map(CertificateRow::into_record).collect()
+    }
+
+    async fn revoke(
+        &self,
+        serial: &str,
+        reason: RevocationReason,
+    ) -> Result<Option<CertificateRecord>, RepositoryError> {
+        let row = sqlx::query_as!(
+            CertificateRow,
+            r#"
+            UPDATE certificates
+            SET revoked_at = CURRENT_TIMESTAMP,
+                revocation_reason = $2
+            WHERE serial = $1 AND revoked_at IS NULL
+            RETURNING serial, user_id, issuer, subject, public_key, unit_id,
+                      roles, valid_from, valid_until, signature,
+                      revoked_at, revocation_reason, created_at
+            "#,
+            serial,
+            reason.as_str()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        row.map(CertificateRow::into_record).transpose()
+    }
+
+    async fn list_revoked(&self, issuer: &str) -> Result<Vec<(String, DateTime<Utc>)>, RepositoryError> {
+        let records = sqlx::query!(
+            r#"
+            SELECT serial, revoked_at as "revoked_at!"
+            FROM certificates
+            WHERE issuer = $1 AND revoked_at IS NOT NULL
+            ORDER BY revoked_at
+            "#,
+            issuer
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(records.into_iter().map(|r| (r.serial, r.revoked_at)).collect())
+    }
+}
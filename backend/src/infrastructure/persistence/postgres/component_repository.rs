@@ -0,0 +1,159 @@
+use sqlx::PgPool;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use crate::{
+    domain::component::{
+        entity::{ComponentKind, PropertyComponent},
+        repository::ComponentRepository,
+    },
+    error::RepositoryError,
+};
+
+pub struct PgComponentRepository {
+    pool: PgPool,
+}
+
+impl PgComponentRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+struct ComponentRow {
+    id: i32,
+    property_id: i32,
+    name: String,
+    nsn: Option<String>,
+    kind: String,
+    unit_of_issue: String,
+    required_quantity: i32,
+    on_hand_quantity: i32,
+    notes: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl ComponentRow {
+    fn into_component(self) -> Result<PropertyComponent, RepositoryError> {
+        Ok(PropertyComponent {
+            id: self.id,
+            property_id: self.property_id,
+            name: self.name,
+            nsn: self.nsn,
+            kind: self.kind.parse::<ComponentKind>().map_err(RepositoryError::Serialization)?,
+            unit_of_issue: self.unit_of_issue,
+            required_quantity: self.required_quantity,
+            on_hand_quantity: self.on_hand_quantity,
+            notes: self.notes,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        })
+    }
+}
+
+#[async_trait]
+impl ComponentRepository for PgComponentRepository {
+    async fn create_component(&self, component: &PropertyComponent) -> Result<PropertyComponent, RepositoryError> {
+        let row = sqlx::query_as!(
+            ComponentRow,
+            r#"
+            INSERT INTO property_components (
+                property_id, name, nsn, kind, unit_of_issue,
+                required_quantity, on_hand_quantity, notes
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, property_id, name, nsn, kind, unit_of_issue,
+                      required_quantity, on_hand_quantity, notes, created_at, updated_at
+            "#,
+            component.property_id,
+            component.name,
+            component.nsn,
+            component.kind.as_str(),
+            component.unit_of_issue,
+            component.required_quantity,
+            component.on_hand_quantity,
+            component.notes
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        row.into_component()
+    }
+
+    async fn get_component(&self, id: i32) -> Result<Option<PropertyComponent>, RepositoryError> {
+        let row = sqlx::query_as!(
+            ComponentRow,
+            r#"
+            SELECT id, property_id, name, nsn, kind, unit_of_issue,
+                   required_quantity, on_hand_quantity, notes, created_at, updated_at
+            FROM property_components
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        row.map(ComponentRow::into_component).transpose()
+    }
+
+    async fn list_components(&self, property_id: i32) -> Result<Vec<PropertyComponent>, RepositoryError> {
+        let rows = sqlx::query_as!(
+            ComponentRow,
+            r#"
+            SELECT id, property_id, name, nsn, kind, unit_of_issue,
+                   required_quantity, on_hand_quantity, notes, created_at, updated_at
+            FROM property_components
+            WHERE property_id = $1
+            ORDER BY id
+            "#,
+            property_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        rows.into_iter().map(ComponentRow::into_component).collect()
+    }
+
+    async fn adjust_on_hand(&self, id: i32, delta: i32) -> Result<Option<PropertyComponent>, RepositoryError> {
+        let row = sqlx::query_as!(
+            ComponentRow,
+            r#"
+            UPDATE property_components
+            SET on_hand_quantity = on_hand_quantity + $2, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND on_hand_quantity + $2 BETWEEN 0 AND required_quantity
+            RETURNING id, property_id, name, nsn, kind, unit_of_issue,
+                      required_quantity, on_hand_quantity, notes, created_at, updated_at
+            "#,
+            id,
+            delta
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        if let Some(row) = row {
+            return row.into_component().map(Some);
+        }
+        // Nothing updated: either no such component or out of range
+        match self.get_component(id).await? {
+            Some(component) => Err(RepositoryError::Validation(format!(
+                "{} has {} of {} on hand; cannot change by {}",
+                component.name, component.on_hand_quantity, component.required_quantity, delta
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    async fn delete_component(&self, id: i32) -> Result<(), RepositoryError> {
+        sqlx::query!("DELETE FROM property_components WHERE id = $1", id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+}
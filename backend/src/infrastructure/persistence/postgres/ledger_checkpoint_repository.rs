@@ -0,0 +1,120 @@
+use sqlx::PgPool;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use crate::{
+    domain::ledger::{entity::BlockCheckpoint, repository::LedgerCheckpointRepository},
+    error::RepositoryError,
+};
+
+pub struct PgLedgerCheckpointRepository {
+    pool: PgPool,
+}
+
+impl PgLedgerCheckpointRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+struct CheckpointRow {
+    block_id: String,
+    block_num: i64,
+    state_root_hash: String,
+    previous_block_id: String,
+    recorded_at: DateTime<Utc>,
+    completed_transfers: serde_json::Value,
+}
+
+impl TryFrom<CheckpointRow> for BlockCheckpoint {
+    type Error = RepositoryError;
+
+    fn try_from(r: CheckpointRow) -> Result<Self, Self::Error> {
+        Ok(BlockCheckpoint {
+            block_id: r.block_id,
+            block_num: r.block_num,
+            state_root_hash: r.state_root_hash,
+            previous_block_id: r.previous_block_id,
+            recorded_at: r.recorded_at,
+            completed_transfers: serde_json::from_value(r.completed_transfers)
+                .map_err(|e| RepositoryError::Serialization(e.to_string()))?,
+        })
+    }
+}
+
+#[async_trait]
+impl LedgerCheckpointRepository for PgLedgerCheckpointRepository {
+    async fn recent(&self, limit: i64) -> Result<Vec<BlockCheckpoint>, RepositoryError> {
+        sqlx::query_as!(
+            CheckpointRow,
+            r#"
+            SELECT block_id, block_num, state_root_hash, previous_block_id, recorded_at,
+                   completed_transfers
+            FROM ledger_checkpoints
+            ORDER BY block_num DESC
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?
+        .into_iter()
+        .map(BlockCheckpoint::try_from)
+        .collect()
+    }
+
+    async fn save(&self, checkpoint: &BlockCheckpoint) -> Result<(), RepositoryError> {
+        let completed_transfers = serde_json::to_value(&checkpoint.completed_transfers)
+            .map_err(|e| RepositoryError::Serialization(e.to_string()))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO ledger_checkpoints (
+                block_id, block_num, state_root_hash, previous_block_id, recorded_at,
+                completed_transfers
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (block_id) DO UPDATE
+            SET block_num = EXCLUDED.block_num,
+                state_root_hash = EXCLUDED.state_root_hash,
+                previous_block_id = EXCLUDED.previous_block_id,
+                recorded_at = EXCLUDED.recorded_at,
+                completed_transfers = ledger_checkpoints.completed_transfers || EXCLUDED.completed_transfers
+            "#,
+            checkpoint.block_id,
+            checkpoint.block_num,
+            checkpoint.state_root_hash,
+            checkpoint.previous_block_id,
+            checkpoint.recorded_at,
+            completed_transfers
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn rewind_to(&self, block_num: i64) -> Result<Vec<BlockCheckpoint>, RepositoryError> {
+        let mut checkpoints = sqlx::query_as!(
+            CheckpointRow,
+            r#"
+            DELETE FROM ledger_checkpoints
+            WHERE block_num >= $1
+            RETURNING block_id, block_num, state_root_hash, previous_block_id, recorded_at,
+                      completed_transfers
+            "#,
+            block_num
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?
+        .into_iter()
+        .map(BlockCheckpoint::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+
+        // RETURNING has no order of its own
+        checkpoints.sort_by_key(|checkpoint| std::cmp::Reverse(checkpoint.block_num));
+        Ok(checkpoints)
+    }
+}
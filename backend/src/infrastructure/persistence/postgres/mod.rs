@@ -1,4 +1,12 @@
+pub mod audit_log_repository;
+pub mod certificate_repository;
+pub mod component_repository;
+pub mod ledger_checkpoint_repository;
+pub mod outbox_repository;
 pub mod property_repository;
+pub mod qr_batch_repository;
+pub mod qr_token_repository;
+pub mod reconciliation_repository;
 pub mod transfer_repository;
 
 use sqlx::PgPool;
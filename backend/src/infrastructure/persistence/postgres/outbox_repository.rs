@@ -0,0 +1,252 @@
+use sqlx::{PgConnection, PgPool};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+use crate::{
+    domain::outbox::{
+        entity::{OutboxEntry, OutboxStatus},
+        repository::OutboxRepository,
+    },
+    error::RepositoryError,
+    infrastructure::blockchain::sawtooth::HandReceiptPayload,
+};
+
+/// Writes a ledger payload to the outbox on `conn`.
+///
+/// Call this on the same transaction as the change the payload mirrors. An
+/// entry whose nonce is already queued is ignored, so repeating the same
+/// logical change never queues it twice.
+pub async fn enqueue(
+    conn: &mut PgConnection,
+    aggregate_type: &str,
+    aggregate_id: i32,
+    nonce: &str,
+    payload: &HandReceiptPayload,
+) -> Result<(), RepositoryError> {
+    let payload = serde_json::to_value(payload)
+        .map_err(|e| RepositoryError::Serialization(e.to_string()))?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO ledger_outbox (aggregate_type, aggregate_id, nonce, payload)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (nonce) DO NOTHING
+        "#,
+        aggregate_type,
+        aggregate_id,
+        nonce,
+        payload
+    )
+    .execute(conn)
+    .await
+    .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+    Ok(())
+}
+
+pub struct PgOutboxRepository {
+    pool: PgPool,
+}
+
+impl PgOutboxRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+struct OutboxRow {
+    id: i64,
+    aggregate_type: String,
+    aggregate_id: i32,
+    nonce: String,
+    payload: serde_json::Value,
+    status: String,
+    attempts: i32,
+    next_attempt_at: DateTime<Utc>,
+    last_error: Option<String>,
+    batch_id: Option<String>,
+    transaction_id: Option<String>,
+    created_at: DateTime<Utc>,
+    committed_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<OutboxRow> for OutboxEntry {
+    type Error = RepositoryError;
+
+    fn try_from(r: OutboxRow) -> Result<Self, Self::Error> {
+        Ok(OutboxEntry {
+            id: r.id,
+            aggregate_type: r.aggregate_type,
+            aggregate_id: r.aggregate_id,
+            nonce: r.nonce,
+            payload: r.payload,
+            status: r.status.parse::<OutboxStatus>().map_err(RepositoryError::Serialization)?,
+            attempts: r.attempts,
+            next_attempt_at: r.next_attempt_at,
+            last_error: r.last_error,
+            batch_id: r.batch_id,
+            transaction_id: r.transaction_id,
+            created_at: r.created_at,
+            committed_at: r.committed_at,
+        })
+    }
+}
+
+#[async_trait]
+impl OutboxRepository for PgOutboxRepository {
+    async fn claim_due(&self, limit: i64, lease: Duration) -> Result<Vec<OutboxEntry>, RepositoryError> {
+        let lease_until = Utc::now()
+            + chrono::Duration::from_std(lease).map_err(|e| RepositoryError::Validation(e.to_string()))?;
+
+        let records = sqlx::query_as!(
+            OutboxRow,
+            r#"
+            UPDATE ledger_outbox
+            SET attempts = attempts + 1, next_attempt_at = $1
+            WHERE id IN (
+                SELECT id FROM ledger_outbox entry
+                WHERE status = 'pending' AND next_attempt_at <= CURRENT_TIMESTAMP
+                  -- An aggregate's entries go out one at a time, in order
+                  AND NOT EXISTS (
+                      SELECT 1 FROM ledger_outbox earlier
+                      WHERE earlier.aggregate_type = entry.aggregate_type
+                        AND earlier.aggregate_id = entry.aggregate_id
+                        AND earlier.status = 'pending'
+                        AND earlier.id < entry.id
+                  )
+                ORDER BY id
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, aggregate_type, aggregate_id, nonce, payload, status,
+                      attempts, next_attempt_at, last_error, batch_id, transaction_id,
+                      created_at, committed_at
+            "#,
+            lease_until,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        let mut entries = records
+            .into_iter()
+            .map(OutboxEntry::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        // RETURNING does not preserve the subquery order
+        entries.sort_by_key(|entry| entry.id);
+        Ok(entries)
+    }
+
+    async fn record_batch(&self, id: i64, batch_id: &str, transaction_id: &str) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            "UPDATE ledger_outbox SET batch_id = $1, transaction_id = $2 WHERE id = $3",
+            batch_id,
+            transaction_id,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn mark_committed(&self, id: i64) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            r#"
+            UPDATE ledger_outbox
+            SET status = 'committed', last_error = NULL, committed_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn schedule_retry(
+        &self,
+        id: i64,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            r#"
+            UPDATE ledger_outbox
+            SET last_error = $1, next_attempt_at = $2
+            WHERE id = $3 AND status = 'pending'
+            "#,
+            error,
+            next_attempt_at,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn mark_failed(&self, id: i64, error: &str) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            "UPDATE ledger_outbox SET status = 'failed', last_error = $1 WHERE id = $2",
+            error,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn list_failed(&self, limit: i64) -> Result<Vec<OutboxEntry>, RepositoryError> {
+        let records = sqlx::query_as!(
+            OutboxRow,
+            r#"
+            SELECT id, aggregate_type, aggregate_id, nonce, payload, status,
+                   attempts, next_attempt_at, last_error, batch_id, transaction_id,
+                   created_at, committed_at
+            FROM ledger_outbox
+            WHERE status = 'failed'
+            ORDER BY id DESC
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        records.into_iter().map(OutboxEntry::try_from).collect()
+    }
+
+    async fn list_by_aggregate(
+        &self,
+        aggregate_type: &str,
+        aggregate_id: i32,
+    ) -> Result<Vec<OutboxEntry>, RepositoryError> {
+        let records = sqlx::query_as!(
+            OutboxRow,
+            r#"
+            SELECT id, aggregate_type, aggregate_id, nonce, payload, status,
+                   attempts, next_attempt_at, last_error, batch_id, transaction_id,
+                   created_at, committed_at
+            FROM ledger_outbox
+            WHERE aggregate_type = $1 AND aggregate_id = $2
+            ORDER BY id
+            "#,
+            aggregate_type,
+            aggregate_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        records.into_iter().map(OutboxEntry::try_from).collect()
+    }
+}
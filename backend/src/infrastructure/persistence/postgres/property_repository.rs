@@ -1,12 +1,17 @@
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use sqlx::types::Json;
 use async_trait::async_trait;
 use crate::{
     domain::property::entity::{Property, PropertyCategory, PropertyStatus},
+    domain::property::filter::PropertyFilter,
     domain::property::repository::PropertyRepository,
+    domain::property::service::{SyncState, SyncStatus},
     domain::models::location::Location,
     error::RepositoryError,
+    infrastructure::blockchain::outbox::{self, AGGREGATE_PROPERTY},
 };
+use super::{certificate_repository, outbox_repository};
+use uuid::Uuid;
 
 pub struct PgPropertyRepository {
     pool: PgPool,
@@ -18,80 +23,133 @@ impl PgPropertyRepository {
     }
 }
 
+/// Inserts a property and queues its ledger record on `conn`, which the
+/// caller commits.
+async fn insert_property(conn: &mut PgConnection, property: &Property) -> Result<Property, RepositoryError> {
+    // Bind custody to the holder's key so their release signature counts
+    let custodian_key = certificate_repository::active_public_key(&mut *conn, property.current_holder_id).await?;
+
+    let record = sqlx::query!(
+        r#"
+        INSERT INTO properties (
+            name, description, category, status, current_holder_id, 
+            location, metadata, is_sensitive, quantity, notes,
+            serial_number, nsn, hand_receipt_number, requires_approval,
+            custody_bound
+        )
+        VALUES (
+            $1, $2, 
+            $3::property_category, 
+            $4::property_status, 
+            $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15
+        )
+        RETURNING 
+            id, name, description, 
+            category as "category: PropertyCategory",
+            status as "status: PropertyStatus",
+            current_holder_id,
+            location as "location: Json<Location>",
+            metadata as "metadata: Json<serde_json::Value>",
+            created_at,
+            updated_at,
+            is_sensitive,
+            quantity,
+            notes,
+            serial_number,
+            nsn,
+            hand_receipt_number,
+            requires_approval
+        "#,
+        property.name,
+        property.description,
+        property.category as PropertyCategory,
+        property.status as PropertyStatus,
+        property.current_holder_id,
+        Json(&property.location) as _,
+        Json(&property.metadata) as _,
+        property.is_sensitive,
+        property.quantity,
+        property.notes.as_deref(),
+        property.serial_number.as_deref(),
+        property.nsn.as_deref(),
+        property.hand_receipt_number.as_deref(),
+        property.requires_approval,
+        custodian_key.is_some()
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+    let created = Property {
+        id: record.id,
+        name: record.name,
+        description: record.description.unwrap_or_default(),
+        category: record.category,
+        status: record.status,
+        current_holder_id: record.current_holder_id,
+        location: record.location.0,
+        metadata: record.metadata.0,
+        created_at: record.created_at,
+        updated_at: record.updated_at,
+        is_sensitive: record.is_sensitive,
+        quantity: record.quantity,
+        notes: record.notes,
+        serial_number: record.serial_number,
+        nsn: record.nsn,
+        hand_receipt_number: record.hand_receipt_number,
+        requires_approval: record.requires_approval,
+    };
+
+    outbox_repository::enqueue(
+        conn,
+        AGGREGATE_PROPERTY,
+        created.id,
+        &outbox::create_nonce(created.id),
+        &outbox::create_payload(&created, custodian_key),
+    )
+    .await?;
+
+    Ok(created)
+}
+
 #[async_trait]
 impl PropertyRepository for PgPropertyRepository {
     async fn create_property(&self, property: Property) -> Result<Property, RepositoryError> {
-        let record = sqlx::query!(
-            r#"
-            INSERT INTO properties (
-                name, description, category, status, current_holder_id, 
-                location, metadata, is_sensitive, quantity, notes,
-                serial_number, nsn, hand_receipt_number, requires_approval
-            )
-            VALUES (
-                $1, $2, 
-                $3::property_category, 
-                $4::property_status, 
-                $5, $6, $7, $8, $9, $10, $11, $12, $13, $14
-            )
-            RETURNING 
-                id, name, description, 
-                category as "category: PropertyCategory",
-                status as "status: PropertyStatus",
-                current_holder_id,
-                location as "location: Json<Location>",
-                metadata as "metadata: Json<serde_json::Value>",
-                created_at,
-                updated_at,
-                is_sensitive,
-                quantity,
-                notes,
-                serial_number,
-                nsn,
-                hand_receipt_number,
-                requires_approval
-            "#,
-            property.name,
-            property.description,
-            property.category as PropertyCategory,
-            property.status as PropertyStatus,
-            property.current_holder_id,
-            Json(&property.location) as _,
-            Json(&property.metadata) as _,
-            property.is_sensitive,
-            property.quantity,
-            property.notes.as_deref(),
-            property.serial_number.as_deref(),
-            property.nsn.as_deref(),
-            property.hand_receipt_number.as_deref(),
-            property.requires_approval
-        )
-        .fetch_one(&self.pool)
-        .await
-        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+        let mut tx = self.pool.begin()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        let created = insert_property(&mut tx, &property).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(created)
+    }
+
+    async fn create_properties(&self, properties: Vec<Property>) -> Result<Vec<Property>, RepositoryError> {
+        let mut tx = self.pool.begin()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        let mut created = Vec::with_capacity(properties.len());
+        for property in &properties {
+            created.push(insert_property(&mut tx, property).await?);
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
 
-        Ok(Property {
-            id: record.id,
-            name: record.name,
-            description: record.description.unwrap_or_default(),
-            category: record.category,
-            status: record.status,
-            current_holder_id: record.current_holder_id,
-            location: record.location.0,
-            metadata: record.metadata.0,
-            created_at: record.created_at,
-            updated_at: record.updated_at,
-            is_sensitive: record.is_sensitive,
-            quantity: record.quantity,
-            notes: record.notes,
-            serial_number: record.serial_number,
-            nsn: record.nsn,
-            hand_receipt_number: record.hand_receipt_number,
-            requires_approval: record.requires_approval,
-        })
+        Ok(created)
     }
 
     async fn update_property(&self, property: &Property) -> Result<(), RepositoryError> {
+        let mut tx = self.pool.begin()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
         sqlx::query!(
             r#"
             UPDATE properties
@@ -128,16 +186,47 @@ impl PropertyRepository for PgPropertyRepository {
             property.requires_approval,
             property.id
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await
         .map_err(|e| RepositoryError::Database(e.to_string()))?;
 
+        // Every update is its own ledger transaction
+        outbox_repository::enqueue(
+            &mut tx,
+            AGGREGATE_PROPERTY,
+            property.id,
+            &format!("property-{}-update-{}", property.id, Uuid::new_v4()),
+            &outbox::update_payload(property),
+        )
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
         Ok(())
     }
 
     async fn delete_property(&self, id: i32) -> Result<(), RepositoryError> {
+        let mut tx = self.pool.begin()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
         sqlx::query!("DELETE FROM properties WHERE id = $1", id)
-            .execute(&self.pool)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        outbox_repository::enqueue(
+            &mut tx,
+            AGGREGATE_PROPERTY,
+            id,
+            &format!("property-{}-delete", id),
+            &outbox::delete_payload(id),
+        )
+        .await?;
+
+        tx.commit()
             .await
             .map_err(|e| RepositoryError::Database(e.to_string()))?;
 
@@ -240,4 +329,121 @@ impl PropertyRepository for PgPropertyRepository {
             requires_approval: r.requires_approval,
         }).collect())
     }
+
+    async fn list_properties_after(
+        &self,
+        filter: &PropertyFilter,
+        after_id: i32,
+        limit: i64,
+    ) -> Result<Vec<Property>, RepositoryError> {
+        let records = sqlx::query!(
+            r#"
+            SELECT 
+                id, name, description, 
+                category as "category: PropertyCategory",
+                status as "status: PropertyStatus",
+                current_holder_id,
+                location as "location: Json<Location>",
+                metadata as "metadata: Json<serde_json::Value>",
+                created_at,
+                updated_at,
+                is_sensitive,
+                quantity,
+                notes,
+                serial_number,
+                nsn,
+                hand_receipt_number,
+                requires_approval
+            FROM properties
+            WHERE id > $1
+              AND ($3::text IS NULL OR metadata->>'unit' = $3)
+              AND ($4::property_category IS NULL OR category = $4)
+              AND ($5::property_status IS NULL OR status = $5)
+              AND ($6::text IS NULL OR hand_receipt_number = $6)
+              AND ($7::int4 IS NULL OR current_holder_id = $7)
+            ORDER BY id
+            LIMIT $2
+            "#,
+            after_id,
+            limit,
+            filter.unit.as_deref(),
+            filter.category as Option<PropertyCategory>,
+            filter.status as Option<PropertyStatus>,
+            filter.hand_receipt_number.as_deref(),
+            filter.holder_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(records.into_iter().map(|r| Property {
+            id: r.id,
+            name: r.name,
+            description: r.description.unwrap_or_default(),
+            category: r.category,
+            status: r.status,
+            current_holder_id: r.current_holder_id,
+            location: r.location.0,
+            metadata: r.metadata.0,
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+            is_sensitive: r.is_sensitive,
+            quantity: r.quantity,
+            notes: r.notes,
+            serial_number: r.serial_number,
+            nsn: r.nsn,
+            hand_receipt_number: r.hand_receipt_number,
+            requires_approval: r.requires_approval,
+        }).collect())
+    }
+
+    async fn get_sync_status(&self, id: i32) -> Result<Option<SyncStatus>, RepositoryError> {
+        let record = sqlx::query!(
+            r#"
+            SELECT sync_state, blockchain_hash, last_sync, sync_error
+            FROM properties
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        record
+            .map(|r| {
+                let state: SyncState = r.sync_state.parse().map_err(RepositoryError::Serialization)?;
+                Ok(SyncStatus {
+                    is_synced: state == SyncState::Committed,
+                    last_sync: r.last_sync,
+                    blockchain_hash: r.blockchain_hash,
+                    state,
+                    error: r.sync_error,
+                })
+            })
+            .transpose()
+    }
+
+    async fn update_sync_status(&self, id: i32, status: &SyncStatus) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            r#"
+            UPDATE properties
+            SET sync_state = $1,
+                blockchain_hash = $2,
+                last_sync = $3,
+                sync_error = $4
+            WHERE id = $5
+            "#,
+            status.state.as_str(),
+            status.blockchain_hash.as_deref(),
+            status.last_sync,
+            status.error.as_deref(),
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(())
+    }
 }
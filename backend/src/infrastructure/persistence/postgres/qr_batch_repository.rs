@@ -0,0 +1,219 @@
+use sqlx::PgPool;
+use sqlx::types::Json;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use crate::{
+    domain::{
+        property::filter::PropertyFilter,
+        qr_batch::{
+            entity::{QRBatchFormat, QRBatchJob, QRBatchStatus},
+            repository::QRBatchRepository,
+        },
+    },
+    error::RepositoryError,
+};
+
+pub struct PgQRBatchRepository {
+    pool: PgPool,
+}
+
+impl PgQRBatchRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+struct QRBatchJobRow {
+    id: i32,
+    requested_by: i32,
+    filter: Json<PropertyFilter>,
+    format: String,
+    template: Option<String>,
+    status: String,
+    total: i32,
+    processed: i32,
+    output_size: Option<i64>,
+    error: Option<String>,
+    created_at: DateTime<Utc>,
+    started_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+impl QRBatchJobRow {
+    fn into_job(self) -> Result<QRBatchJob, RepositoryError> {
+        Ok(QRBatchJob {
+            id: self.id,
+            requested_by: self.requested_by,
+            filter: self.filter.0,
+            format: self.format.parse::<QRBatchFormat>().map_err(RepositoryError::Serialization)?,
+            template: self.template,
+            status: self.status.parse::<QRBatchStatus>().map_err(RepositoryError::Serialization)?,
+            total: self.total,
+            processed: self.processed,
+            output_size: self.output_size,
+            error: self.error,
+            created_at: self.created_at,
+            started_at: self.started_at,
+            completed_at: self.completed_at,
+        })
+    }
+}
+
+#[async_trait]
+impl QRBatchRepository for PgQRBatchRepository {
+    async fn create_job(&self, job: &QRBatchJob) -> Result<QRBatchJob, RepositoryError> {
+        let row = sqlx::query_as!(
+            QRBatchJobRow,
+            r#"
+            INSERT INTO qr_batch_jobs (requested_by, filter, format, template, status)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, requested_by, filter as "filter: Json<PropertyFilter>", format, template,
+                      status, total, processed, octet_length(output)::BIGINT as output_size,
+                      error, created_at, started_at, completed_at
+            "#,
+            job.requested_by,
+            Json(&job.filter) as _,
+            job.format.as_str(),
+            job.template.as_deref(),
+            job.status.as_str()
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        row.into_job()
+    }
+
+    async fn get_job(&self, id: i32) -> Result<Option<QRBatchJob>, RepositoryError> {
+        let row = sqlx::query_as!(
+            QRBatchJobRow,
+            r#"
+            SELECT id, requested_by, filter as "filter: Json<PropertyFilter>", format, template,
+                   status, total, processed, octet_length(output)::BIGINT as output_size,
+                   error, created_at, started_at, completed_at
+            FROM qr_batch_jobs
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        row.map(QRBatchJobRow::into_job).transpose()
+    }
+
+    async fn list_jobs(&self, user_id: i32, limit: i64) -> Result<Vec<QRBatchJob>, RepositoryError> {
+        let rows = sqlx::query_as!(
+            QRBatchJobRow,
+            r#"
+            SELECT id, requested_by, filter as "filter: Json<PropertyFilter>", format, template,
+                   status, total, processed, octet_length(output)::BIGINT as output_size,
+                   error, created_at, started_at, completed_at
+            FROM qr_batch_jobs
+            WHERE requested_by = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2
+            "#,
+            user_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        rows.into_iter().map(QRBatchJobRow::into_job).collect()
+    }
+
+    async fn start_job(&self, id: i32, total: i32) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            r#"
+            UPDATE qr_batch_jobs
+            SET status = 'running', total = $2, processed = 0, started_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+            id,
+            total
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn update_progress(&self, id: i32, processed: i32) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            "UPDATE qr_batch_jobs SET processed = $2 WHERE id = $1",
+            id,
+            processed
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn complete_job(&self, id: i32, output: &[u8]) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            r#"
+            UPDATE qr_batch_jobs
+            SET status = 'completed', processed = total, output = $2, completed_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+            id,
+            output
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn fail_job(&self, id: i32, error: &str) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            r#"
+            UPDATE qr_batch_jobs
+            SET status = 'failed', error = $2, completed_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+            id,
+            error
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn fail_unfinished_jobs(&self, error: &str) -> Result<u64, RepositoryError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE qr_batch_jobs
+            SET status = 'failed', error = $1, completed_at = CURRENT_TIMESTAMP
+            WHERE status IN ('queued', 'running')
+            "#,
+            error
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn get_output(&self, id: i32) -> Result<Option<Vec<u8>>, RepositoryError> {
+        let output = sqlx::query_scalar!(
+            "SELECT output FROM qr_batch_jobs WHERE id = $1",
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(output.flatten())
+    }
+}
@@ -0,0 +1,161 @@
+use sqlx::{PgExecutor, PgPool};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use crate::{
+    domain::models::qr_token::{QRToken, QRTokenRepository, QRTokenRevocation},
+    error::RepositoryError,
+};
+
+pub struct PgQRTokenRepository {
+    pool: PgPool,
+}
+
+impl PgQRTokenRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+struct QRTokenRow {
+    nonce: Uuid,
+    property_id: i32,
+    custodian_id: String,
+    issued_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    consumed_at: Option<DateTime<Utc>>,
+    consumed_by: Option<i32>,
+    revoked_at: Option<DateTime<Utc>>,
+    revocation_reason: Option<String>,
+}
+
+impl QRTokenRow {
+    fn into_token(self) -> Result<QRToken, RepositoryError> {
+        Ok(QRToken {
+            nonce: self.nonce,
+            property_id: self.property_id,
+            custodian_id: self.custodian_id,
+            issued_at: self.issued_at,
+            expires_at: self.expires_at,
+            consumed_at: self.consumed_at,
+            consumed_by: self.consumed_by,
+            revoked_at: self.revoked_at,
+            revocation_reason: self
+                .revocation_reason
+                .map(|reason| reason.parse::<QRTokenRevocation>())
+                .transpose()
+                .map_err(RepositoryError::Serialization)?,
+        })
+    }
+}
+
+/// Marks a redeemable token as used on `executor`, so a scan can redeem its
+/// code in the same transaction that records the transfer. Returns `None`
+/// if the token is unknown or no longer redeemable at `at`.
+pub async fn consume_token<'e>(
+    executor: impl PgExecutor<'e>,
+    nonce: Uuid,
+    consumed_by: i32,
+    at: DateTime<Utc>,
+) -> Result<Option<QRToken>, RepositoryError> {
+    // The guarded UPDATE is the single-use check: a second scan matches
+    // no row because consumed_at is already set
+    let row = sqlx::query_as!(
+        QRTokenRow,
+        r#"
+        UPDATE qr_tokens
+        SET consumed_at = $3,
+            consumed_by = $2
+        WHERE nonce = $1
+          AND consumed_at IS NULL
+          AND revoked_at IS NULL
+          AND expires_at > $3
+        RETURNING nonce, property_id, custodian_id, issued_at, expires_at,
+                  consumed_at, consumed_by, revoked_at, revocation_reason
+        "#,
+        nonce,
+        consumed_by,
+        at
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+    row.map(QRTokenRow::into_token).transpose()
+}
+
+#[async_trait]
+impl QRTokenRepository for PgQRTokenRepository {
+    async fn create(&self, token: &QRToken) -> Result<QRToken, RepositoryError> {
+        let row = sqlx::query_as!(
+            QRTokenRow,
+            r#"
+            INSERT INTO qr_tokens (nonce, property_id, custodian_id, issued_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING nonce, property_id, custodian_id, issued_at, expires_at,
+                      consumed_at, consumed_by, revoked_at, revocation_reason
+            "#,
+            token.nonce,
+            token.property_id,
+            token.custodian_id,
+            token.issued_at,
+            token.expires_at
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        row.into_token()
+    }
+
+    async fn get(&self, nonce: Uuid) -> Result<Option<QRToken>, RepositoryError> {
+        let row = sqlx::query_as!(
+            QRTokenRow,
+            r#"
+            SELECT nonce, property_id, custodian_id, issued_at, expires_at,
+                   consumed_at, consumed_by, revoked_at, revocation_reason
+            FROM qr_tokens
+            WHERE nonce = $1
+            "#,
+            nonce
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        row.map(QRTokenRow::into_token).transpose()
+    }
+
+    async fn consume(
+        &self,
+        nonce: Uuid,
+        consumed_by: i32,
+        at: DateTime<Utc>,
+    ) -> Result<Option<QRToken>, RepositoryError> {
+        consume_token(&self.pool, nonce, consumed_by, at).await
+    }
+
+    async fn revoke_for_property(
+        &self,
+        property_id: i32,
+        reason: QRTokenRevocation,
+    ) -> Result<u64, RepositoryError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE qr_tokens
+            SET revoked_at = CURRENT_TIMESTAMP,
+                revocation_reason = $2
+            WHERE property_id = $1
+              AND consumed_at IS NULL
+              AND revoked_at IS NULL
+            "#,
+            property_id,
+            reason.as_str()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,233 @@
+use sqlx::PgPool;
+use async_trait::async_trait;
+use crate::{
+    domain::{
+        property::entity::Property,
+        reconciliation::{
+            entity::{DriftType, LedgerDrift, ReconciliationReport, ReconciliationRun},
+            repository::ReconciliationRepository,
+        },
+    },
+    error::RepositoryError,
+    infrastructure::blockchain::outbox::{self, AGGREGATE_PROPERTY},
+};
+use super::{certificate_repository, outbox_repository};
+
+pub struct PgReconciliationRepository {
+    pool: PgPool,
+}
+
+impl PgReconciliationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    async fn get_run(&self, run_id: i32) -> Result<Option<ReconciliationRun>, RepositoryError> {
+        let record = sqlx::query_as!(
+            ReconciliationRun,
+            r#"
+            SELECT id, started_at, completed_at, properties_checked, drift_count, error
+            FROM reconciliation_runs
+            WHERE id = $1
+            "#,
+            run_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(record)
+    }
+
+    async fn list_drift(&self, run_id: i32) -> Result<Vec<LedgerDrift>, RepositoryError> {
+        let records = sqlx::query!(
+            r#"
+            SELECT id, run_id, property_id, drift_type, database_value,
+                   ledger_value, resubmitted, detected_at
+            FROM ledger_drift
+            WHERE run_id = $1
+            ORDER BY id
+            "#,
+            run_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        records
+            .into_iter()
+            .map(|r| {
+                Ok(LedgerDrift {
+                    id: r.id,
+                    run_id: r.run_id,
+                    property_id: r.property_id,
+                    drift_type: r.drift_type.parse::<DriftType>().map_err(RepositoryError::Serialization)?,
+                    database_value: r.database_value,
+                    ledger_value: r.ledger_value,
+                    resubmitted: r.resubmitted,
+                    detected_at: r.detected_at,
+                })
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl ReconciliationRepository for PgReconciliationRepository {
+    async fn start_run(&self) -> Result<ReconciliationRun, RepositoryError> {
+        sqlx::query_as!(
+            ReconciliationRun,
+            r#"
+            INSERT INTO reconciliation_runs DEFAULT VALUES
+            RETURNING id, started_at, completed_at, properties_checked, drift_count, error
+            "#
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))
+    }
+
+    async fn record_drift(&self, run_id: i32, drift: &LedgerDrift) -> Result<LedgerDrift, RepositoryError> {
+        let record = sqlx::query!(
+            r#"
+            INSERT INTO ledger_drift (
+                run_id, property_id, drift_type, database_value, ledger_value, resubmitted
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, detected_at
+            "#,
+            run_id,
+            drift.property_id,
+            drift.drift_type.as_str(),
+            drift.database_value.as_deref(),
+            drift.ledger_value.as_deref(),
+            drift.resubmitted
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(LedgerDrift {
+            id: record.id,
+            run_id,
+            detected_at: record.detected_at,
+            ..drift.clone()
+        })
+    }
+
+    async fn complete_run(
+        &self,
+        run_id: i32,
+        properties_checked: i32,
+        error: Option<&str>,
+    ) -> Result<ReconciliationRun, RepositoryError> {
+        sqlx::query_as!(
+            ReconciliationRun,
+            r#"
+            UPDATE reconciliation_runs
+            SET completed_at = CURRENT_TIMESTAMP,
+                properties_checked = $1,
+                drift_count = (SELECT COUNT(*)::INTEGER FROM ledger_drift WHERE run_id = $2),
+                error = $3
+            WHERE id = $2
+            RETURNING id, started_at, completed_at, properties_checked, drift_count, error
+            "#,
+            properties_checked,
+            run_id,
+            error
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))
+    }
+
+    async fn list_runs(&self, limit: i64) -> Result<Vec<ReconciliationRun>, RepositoryError> {
+        sqlx::query_as!(
+            ReconciliationRun,
+            r#"
+            SELECT id, started_at, completed_at, properties_checked, drift_count, error
+            FROM reconciliation_runs
+            ORDER BY id DESC
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))
+    }
+
+    async fn get_report(&self, run_id: i32) -> Result<Option<ReconciliationReport>, RepositoryError> {
+        match self.get_run(run_id).await? {
+            Some(run) => {
+                let drift = self.list_drift(run.id).await?;
+                Ok(Some(ReconciliationReport { run, drift }))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn get_latest_report(&self) -> Result<Option<ReconciliationReport>, RepositoryError> {
+        let latest = sqlx::query_scalar!("SELECT MAX(id) FROM reconciliation_runs")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        match latest {
+            Some(run_id) => self.get_report(run_id).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn resubmit_property(&self, property: &Property) -> Result<(), RepositoryError> {
+        let mut tx = self.pool.begin()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        let custodian_key = certificate_repository::active_public_key(&mut *tx, property.current_holder_id).await?;
+        let nonce = outbox::create_nonce(property.id);
+        let payload = outbox::create_payload(property, custodian_key.clone());
+        let json = serde_json::to_value(&payload)
+            .map_err(|e| RepositoryError::Serialization(e.to_string()))?;
+
+        // A Create that failed, or committed to a ledger that has since lost
+        // it, goes out again under its own nonce
+        sqlx::query!(
+            r#"
+            UPDATE ledger_outbox
+            SET status = 'pending', payload = $1, attempts = 0, next_attempt_at = CURRENT_TIMESTAMP,
+                last_error = NULL, batch_id = NULL, transaction_id = NULL, committed_at = NULL
+            WHERE nonce = $2 AND status <> 'pending'
+            "#,
+            json,
+            nonce
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        outbox_repository::enqueue(
+            &mut tx,
+            AGGREGATE_PROPERTY,
+            property.id,
+            &nonce,
+            &payload,
+        )
+        .await?;
+
+        sqlx::query!(
+            "UPDATE properties SET custody_bound = $1 WHERE id = $2",
+            custodian_key.is_some(),
+            property.id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+}
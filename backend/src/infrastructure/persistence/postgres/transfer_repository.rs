@@ -1,13 +1,19 @@
 use async_trait::async_trait;
-use sqlx::{PgPool, Row};
+use handreceipt_protocol::signing::TransferSignature;
+use chrono::Utc;
+use sqlx::{PgConnection, PgExecutor, PgPool, Row};
+use uuid::Uuid;
 use crate::{
     domain::transfer::{
-        entity::{Transfer, TransferStatus},
+        entity::{ApprovalRequirements, Transfer, TransferStatus},
+        filter::TransferFilter,
         repository::TransferRepository,
     },
     error::repository::RepositoryError,
     domain::models::location::Location,
+    infrastructure::blockchain::outbox::{self, AGGREGATE_TRANSFER},
 };
+use super::{outbox_repository, qr_token_repository};
 
 pub struct PgTransferRepository {
     pool: PgPool,
@@ -19,47 +25,249 @@ impl PgTransferRepository {
     }
 }
 
+async fn fetch_signatures<'e>(
+    executor: impl PgExecutor<'e>,
+    transfer_id: i32,
+) -> Result<Vec<TransferSignature>, RepositoryError> {
+    let records = sqlx::query!(
+        r#"
+        SELECT role, public_key, signature
+        FROM transfer_signatures
+        WHERE transfer_id = $1
+        ORDER BY id
+        "#,
+        transfer_id
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+    records
+        .into_iter()
+        .map(|r| {
+            Ok(TransferSignature {
+                role: r.role.parse().map_err(RepositoryError::Serialization)?,
+                public_key: r.public_key,
+                signature: r.signature,
+            })
+        })
+        .collect()
+}
+
+async fn fetch_transfer<'e>(
+    executor: impl PgExecutor<'e>,
+    id: i32,
+) -> Result<Option<Transfer>, RepositoryError> {
+    let record = sqlx::query!(
+        r#"
+        SELECT 
+            id, property_id, from_holder_id, to_holder_id,
+            status as "status: TransferStatus",
+            location, created_at, updated_at,
+            approved_at, approved_by_id, notes, metadata
+        FROM transfers 
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+    match record {
+        Some(r) => {
+            let location: Location = serde_json::from_value(r.location)
+                .map_err(|e| RepositoryError::Serialization(e.to_string()))?;
+
+            Ok(Some(Transfer {
+                id: r.id,
+                property_id: r.property_id,
+                from_holder_id: r.from_holder_id,
+                to_holder_id: r.to_holder_id,
+                status: r.status,
+                location,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+                approved_at: r.approved_at,
+                approved_by_id: r.approved_by_id,
+                notes: r.notes,
+                metadata: r.metadata,
+            }))
+        }
+        None => Ok(None),
+    }
+}
+
+/// What the ledger will ask of a transfer of `property_id`.
+async fn approval_requirements<'e>(
+    executor: impl PgExecutor<'e>,
+    property_id: i32,
+) -> Result<ApprovalRequirements, RepositoryError> {
+    let property = sqlx::query!(
+        "SELECT is_sensitive, requires_approval, custody_bound FROM properties WHERE id = $1",
+        property_id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| RepositoryError::Database(e.to_string()))?
+    .ok_or_else(|| RepositoryError::NotFound(format!("Property {} not found", property_id)))?;
+
+    Ok(ApprovalRequirements::new(
+        property.is_sensitive,
+        property.requires_approval,
+        property.custody_bound,
+    ))
+}
+
+/// Queues the ledger entry that moves custody for a completed transfer,
+/// once every party the property needs has signed. Until then the
+/// processor would only reject it; the last signature queues it instead.
+async fn enqueue_completion(conn: &mut PgConnection, transfer: &Transfer) -> Result<(), RepositoryError> {
+    let signatures = fetch_signatures(&mut *conn, transfer.id).await?;
+    if !approval_requirements(&mut *conn, transfer.property_id)
+        .await?
+        .is_satisfied_by(&signatures)
+    {
+        return Ok(());
+    }
+
+    outbox_repository::enqueue(
+        &mut *conn,
+        AGGREGATE_TRANSFER,
+        transfer.id,
+        &format!("transfer-{}-complete", transfer.id),
+        &outbox::transfer_payload(transfer, signatures),
+    )
+    .await?;
+
+    // The ledger binds custody to the receiving key as it applies this, and
+    // applies queued entries in order, so later transfers see it bound
+    mark_custody_bound(conn, transfer.property_id).await
+}
+
+async fn mark_custody_bound<'e>(executor: impl PgExecutor<'e>, property_id: i32) -> Result<(), RepositoryError> {
+    sqlx::query!(
+        "UPDATE properties SET custody_bound = TRUE WHERE id = $1",
+        property_id
+    )
+    .execute(executor)
+    .await
+    .map_err(|e| RepositoryError::Database(e.to_string()))?;
+    Ok(())
+}
+
+/// Inserts `transfer` on `conn`, which the caller commits. A new row has no
+/// party signatures yet, so a transfer created completed is queued for the
+/// ledger once they are added.
+async fn insert_transfer(conn: &mut PgConnection, transfer: Transfer) -> Result<Transfer, RepositoryError> {
+    let record = sqlx::query!(
+        r#"
+        INSERT INTO transfers (
+            property_id, from_holder_id, to_holder_id, status, location,
+            notes, metadata
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, created_at, updated_at
+        "#,
+        transfer.property_id,
+        transfer.from_holder_id,
+        transfer.to_holder_id,
+        transfer.status as TransferStatus,
+        serde_json::to_value(&transfer.location).map_err(|e| RepositoryError::Serialization(e.to_string()))?,
+        transfer.notes,
+        transfer.metadata,
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+    Ok(Transfer {
+        id: record.id,
+        property_id: transfer.property_id,
+        from_holder_id: transfer.from_holder_id,
+        to_holder_id: transfer.to_holder_id,
+        status: transfer.status,
+        location: transfer.location,
+        created_at: record.created_at,
+        updated_at: record.updated_at,
+        approved_at: None,
+        approved_by_id: None,
+        notes: transfer.notes,
+        metadata: transfer.metadata,
+    })
+}
+
 #[async_trait]
 impl TransferRepository for PgTransferRepository {
     async fn create_transfer(&self, transfer: Transfer) -> Result<Transfer, RepositoryError> {
-        let record = sqlx::query!(
-            r#"
-            INSERT INTO transfers (
-                property_id, from_holder_id, to_holder_id, status, location,
-                notes, metadata
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-            RETURNING id, created_at, updated_at
-            "#,
-            transfer.property_id,
-            transfer.from_holder_id,
-            transfer.to_holder_id,
-            transfer.status as TransferStatus,
-            serde_json::to_value(&transfer.location).map_err(|e| RepositoryError::Serialization(e.to_string()))?,
-            transfer.notes,
-            transfer.metadata,
+        let mut tx = self.pool.begin()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        let created = insert_transfer(&mut tx, transfer).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(created)
+    }
+
+    async fn create_scanned_transfer(&self, transfer: Transfer, qr_nonce: Uuid) -> Result<Transfer, RepositoryError> {
+        let mut tx = self.pool.begin()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        // Locking the property queues concurrent scans of it behind this one,
+        // so the pending check below cannot race another scan's insert
+        let holder = sqlx::query_scalar!(
+            "SELECT current_holder_id FROM properties WHERE id = $1 FOR UPDATE",
+            transfer.property_id
         )
-        .fetch_one(&self.pool)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?
+        .ok_or_else(|| RepositoryError::NotFound(format!("Property {} not found", transfer.property_id)))?;
+        if holder != transfer.from_holder_id {
+            return Err(RepositoryError::Validation(
+                "QR code is stale: property has changed hands since it was issued".to_string(),
+            ));
+        }
+
+        let pending = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM transfers WHERE property_id = $1 AND status = 'pending') as "pending!""#,
+            transfer.property_id
+        )
+        .fetch_one(&mut *tx)
         .await
         .map_err(|e| RepositoryError::Database(e.to_string()))?;
+        if pending {
+            return Err(RepositoryError::Validation(
+                "Property already has a pending transfer".to_string(),
+            ));
+        }
 
-        Ok(Transfer {
-            id: record.id,
-            property_id: transfer.property_id,
-            from_holder_id: transfer.from_holder_id,
-            to_holder_id: transfer.to_holder_id,
-            status: transfer.status,
-            location: transfer.location,
-            created_at: record.created_at,
-            updated_at: record.updated_at,
-            approved_at: None,
-            approved_by_id: None,
-            notes: transfer.notes,
-            metadata: transfer.metadata,
-        })
+        qr_token_repository::consume_token(&mut *tx, qr_nonce, transfer.to_holder_id, Utc::now())
+            .await?
+            .ok_or_else(|| RepositoryError::Validation("QR code has already been used".to_string()))?;
+        let created = insert_transfer(&mut tx, transfer).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(created)
+    }
+
+    async fn approval_requirements(&self, property_id: i32) -> Result<ApprovalRequirements, RepositoryError> {
+        approval_requirements(&self.pool, property_id).await
     }
 
     async fn update_transfer(&self, transfer: &Transfer) -> Result<(), RepositoryError> {
+        let mut tx = self.pool.begin()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
         sqlx::query!(
             r#"
             UPDATE transfers
@@ -75,52 +283,71 @@ impl TransferRepository for PgTransferRepository {
             transfer.approved_by_id,
             transfer.id,
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await
         .map_err(|e| RepositoryError::Database(e.to_string()))?;
 
+        // Custody only moves on the ledger once the transfer completes
+        if transfer.status == TransferStatus::Completed {
+            enqueue_completion(&mut tx, transfer).await?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
         Ok(())
     }
 
-    async fn get_transfer(&self, id: i32) -> Result<Option<Transfer>, RepositoryError> {
-        let record = sqlx::query!(
+    async fn complete_transfers(&self, ids: &[i32]) -> Result<Vec<(i32, TransferStatus)>, RepositoryError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut tx = self.pool.begin()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        let records = sqlx::query!(
             r#"
-            SELECT 
-                id, property_id, from_holder_id, to_holder_id,
-                status as "status: TransferStatus",
-                location, created_at, updated_at,
-                approved_at, approved_by_id, notes, metadata
-            FROM transfers 
-            WHERE id = $1
+            WITH prior AS (
+                SELECT id, status FROM transfers
+                WHERE id = ANY($1) AND status <> 'completed'
+                FOR UPDATE
+            )
+            UPDATE transfers
+            SET status = 'completed', updated_at = CURRENT_TIMESTAMP
+            FROM prior
+            WHERE transfers.id = prior.id
+            RETURNING transfers.id, prior.status as "previous_status!: TransferStatus"
             "#,
-            id
+            ids
         )
-        .fetch_optional(&self.pool)
+        .fetch_all(&mut *tx)
         .await
         .map_err(|e| RepositoryError::Database(e.to_string()))?;
 
-        match record {
-            Some(r) => {
-                let location: Location = serde_json::from_value(r.location)
-                    .map_err(|e| RepositoryError::Serialization(e.to_string()))?;
+        // Applying them bound custody to each receiving key
+        sqlx::query!(
+            r#"
+            UPDATE properties SET custody_bound = TRUE
+            WHERE id IN (SELECT property_id FROM transfers WHERE id = ANY($1))
+            "#,
+            ids
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
 
-                Ok(Some(Transfer {
-                    id: r.id,
-                    property_id: r.property_id,
-                    from_holder_id: r.from_holder_id,
-                    to_holder_id: r.to_holder_id,
-                    status: r.status,
-                    location,
-                    created_at: r.created_at,
-                    updated_at: r.updated_at,
-                    approved_at: r.approved_at,
-                    approved_by_id: r.approved_by_id,
-                    notes: r.notes,
-                    metadata: r.metadata,
-                }))
-            }
-            None => Ok(None),
-        }
+        tx.commit()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(records.into_iter().map(|r| (r.id, r.previous_status)).collect())
+    }
+
+    async fn get_transfer(&self, id: i32) -> Result<Option<Transfer>, RepositoryError> {
+        fetch_transfer(&self.pool, id).await
     }
 
     async fn delete_transfer(&self, id: i32) -> Result<(), RepositoryError> {
@@ -175,6 +402,70 @@ impl TransferRepository for PgTransferRepository {
         Ok(transfers)
     }
 
+    async fn list_transfers_after(
+        &self,
+        filter: &TransferFilter,
+        after_id: i32,
+        limit: i64,
+    ) -> Result<Vec<(Transfer, bool)>, RepositoryError> {
+        let records = sqlx::query!(
+            r#"
+            SELECT 
+                t.id, t.property_id, t.from_holder_id, t.to_holder_id,
+                t.status as "status: TransferStatus",
+                t.location, t.created_at, t.updated_at,
+                t.approved_at, t.approved_by_id, t.notes, t.metadata,
+                p.is_sensitive
+            FROM transfers t
+            JOIN properties p ON p.id = t.property_id
+            WHERE t.id > $1
+              AND ($3::int4 IS NULL OR t.property_id = $3)
+              AND ($4::transfer_status IS NULL OR t.status = $4)
+              AND ($5::int4 IS NULL OR t.from_holder_id = $5 OR t.to_holder_id = $5)
+              AND ($6::timestamptz IS NULL OR t.created_at >= $6)
+              AND ($7::timestamptz IS NULL OR t.created_at < $7)
+            ORDER BY t.id
+            LIMIT $2
+            "#,
+            after_id,
+            limit,
+            filter.property_id,
+            filter.status as Option<TransferStatus>,
+            filter.holder_id,
+            filter.since,
+            filter.until
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        let mut transfers = Vec::new();
+        for r in records {
+            let location: Location = serde_json::from_value(r.location)
+                .map_err(|e| RepositoryError::Serialization(e.to_string()))?;
+
+            transfers.push((
+                Transfer {
+                    id: r.id,
+                    property_id: r.property_id,
+                    from_holder_id: r.from_holder_id,
+                    to_holder_id: r.to_holder_id,
+                    status: r.status,
+                    location,
+                    created_at: r.created_at,
+                    updated_at: r.updated_at,
+                    approved_at: r.approved_at,
+                    approved_by_id: r.approved_by_id,
+                    notes: r.notes,
+                    metadata: r.metadata,
+                },
+                r.is_sensitive,
+            ));
+        }
+
+        Ok(transfers)
+    }
+
     async fn list_by_property(&self, property_id: i32) -> Result<Vec<Transfer>, RepositoryError> {
         let records = sqlx::query!(
             r#"
@@ -216,4 +507,51 @@ impl TransferRepository for PgTransferRepository {
 
         Ok(transfers)
     }
+
+    async fn add_signature(&self, transfer_id: i32, signature: &TransferSignature) -> Result<(), RepositoryError> {
+        let mut tx = self.pool.begin()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO transfer_signatures (transfer_id, role, public_key, signature)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (transfer_id, role) DO NOTHING
+            "#,
+            transfer_id,
+            signature.role.as_str(),
+            signature.public_key,
+            signature.signature
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| RepositoryError::Database(e.to_string()))?
+        .rows_affected();
+
+        if inserted == 0 {
+            return Err(RepositoryError::Validation(format!(
+                "Transfer {} already has a {} signature",
+                transfer_id, signature.role
+            )));
+        }
+
+        // The last signature on a completed transfer queues it
+        let transfer = fetch_transfer(&mut *tx, transfer_id)
+            .await?
+            .ok_or_else(|| RepositoryError::NotFound(format!("Transfer {} not found", transfer_id)))?;
+        if transfer.status == TransferStatus::Completed {
+            enqueue_completion(&mut tx, &transfer).await?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| RepositoryError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn list_signatures(&self, transfer_id: i32) -> Result<Vec<TransferSignature>, RepositoryError> {
+        fetch_signatures(&self.pool, transfer_id).await
+    }
 }
@@ -6,6 +6,9 @@ pub mod infrastructure;
 pub mod security;
 pub mod types;
 
+#[cfg(test)]
+pub(crate) mod test_support;
+
 // Re-export commonly used items
 pub use security::context::SecurityContext;
 pub use error::api::ApiError;
@@ -9,6 +9,8 @@ mod error;
 mod types;
 mod utils;
 mod app_builder;
+#[cfg(test)]
+mod test_support;
 
 use actix_web::web;
 use dotenv::dotenv;
@@ -61,10 +63,12 @@ async fn main() -> std::io::Result<()> {
         .expect("Failed to build application state");
 
     info!("Application state built successfully");
-    
+
+    let services = app_state.services.clone();
     let server = actix_web::HttpServer::new(move || {
         actix_web::App::new()
             .app_data(app_state.clone())
+            .configure(|cfg| services.register(cfg))
             .configure(api::configure)
     })
     .bind(format!(
@@ -0,0 +1,414 @@
+//! In-memory repositories and service stubs shared by the unit tests.
+//!
+//! Each keeps its rows in memory and assigns ids in insertion order
+//! starting from 1, so tests can seed them with fixed ids and read back
+//! what the code under test stored.
+
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use handreceipt_protocol::signing::TransferSignature;
+use parking_lot::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        certificate::{
+            entity::{CertificateRecord, RevocationReason},
+            repository::CertificateRepository,
+        },
+        component::{entity::PropertyComponent, repository::ComponentRepository},
+        models::{
+            qr::{QRCodeService, QRData, QRFormat, QRResponse, VerifyQRRequest},
+            qr_token::{QRTokenRevocation, QRUsage},
+        },
+        property::{
+            entity::Property,
+            filter::PropertyFilter,
+            repository::PropertyRepository,
+            service::{PropertyService, SyncStatus},
+        },
+        transfer::{
+            entity::{ApprovalRequirements, Transfer, TransferStatus},
+            filter::TransferFilter,
+            repository::TransferRepository,
+        },
+    },
+    error::{CoreError, RepositoryError},
+    types::security::SecurityContext,
+};
+
+/// Properties kept in a list, served both as the repository and as the
+/// property service.
+#[derive(Default)]
+pub(crate) struct MockProperties {
+    pub properties: Mutex<Vec<Property>>,
+    pub sync_statuses: Mutex<HashMap<i32, SyncStatus>>,
+}
+
+impl MockProperties {
+    pub fn new(properties: Vec<Property>) -> Self {
+        Self { properties: Mutex::new(properties), ..Default::default() }
+    }
+
+    fn insert(&self, mut property: Property) -> Property {
+        let mut properties = self.properties.lock();
+        property.id = properties.len() as i32 + 1;
+        properties.push(property.clone());
+        property
+    }
+
+    fn find(&self, id: i32) -> Option<Property> {
+        self.properties.lock().iter().find(|property| property.id == id).cloned()
+    }
+
+    fn replace(&self, property: &Property) -> Result<(), RepositoryError> {
+        let mut properties = self.properties.lock();
+        let stored = properties
+            .iter_mut()
+            .find(|stored| stored.id == property.id)
+            .ok_or_else(|| RepositoryError::NotFound(format!("Property {} not found", property.id)))?;
+        *stored = property.clone();
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PropertyRepository for MockProperties {
+    async fn create_property(&self, property: Property) -> Result<Property, RepositoryError> {
+        Ok(self.insert(property))
+    }
+
+    async fn create_properties(&self, properties: Vec<Property>) -> Result<Vec<Property>, RepositoryError> {
+        Ok(properties.into_iter().map(|property| self.insert(property)).collect())
+    }
+
+    async fn update_property(&self, property: &Property) -> Result<(), RepositoryError> {
+        self.replace(property)
+    }
+
+    async fn delete_property(&self, id: i32) -> Result<(), RepositoryError> {
+        self.properties.lock().retain(|property| property.id != id);
+        Ok(())
+    }
+
+    async fn get_property(&self, id: i32) -> Result<Option<Property>, RepositoryError> {
+        Ok(self.find(id))
+    }
+
+    async fn list_properties(&self) -> Result<Vec<Property>, RepositoryError> {
+        Ok(self.properties.lock().clone())
+    }
+
+    async fn list_properties_after(
+        &self,
+        filter: &PropertyFilter,
+        after_id: i32,
+        limit: i64,
+    ) -> Result<Vec<Property>, RepositoryError> {
+        Ok(self
+            .properties
+            .lock()
+            .iter()
+            .filter(|property| property.id > after_id && filter.matches(property))
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_sync_status(&self, id: i32) -> Result<Option<SyncStatus>, RepositoryError> {
+        Ok(self.sync_statuses.lock().get(&id).cloned())
+    }
+
+    async fn update_sync_status(&self, id: i32, status: &SyncStatus) -> Result<(), RepositoryError> {
+        self.sync_statuses.lock().insert(id, status.clone());
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PropertyService for MockProperties {
+    async fn create_property(&self, property: &Property, _: &SecurityContext) -> Result<Property, RepositoryError> {
+        Ok(self.insert(property.clone()))
+    }
+
+    async fn get_property(&self, id: i32, _: &SecurityContext) -> Result<Option<Property>, RepositoryError> {
+        Ok(self.find(id))
+    }
+
+    async fn update_property(&self, property: &Property, _: &SecurityContext) -> Result<Property, RepositoryError> {
+        self.replace(property)?;
+        Ok(property.clone())
+    }
+
+    async fn delete_property(&self, id: i32, _: &SecurityContext) -> Result<(), RepositoryError> {
+        self.properties.lock().retain(|property| property.id != id);
+        Ok(())
+    }
+
+    async fn list_properties(&self, _: &SecurityContext) -> Result<Vec<Property>, RepositoryError> {
+        Ok(self.properties.lock().clone())
+    }
+
+    async fn generate_qr(&self, _: i32, _: &SecurityContext) -> Result<String, RepositoryError> {
+        unimplemented!()
+    }
+
+    async fn get_sync_status(&self, _: i32, _: &SecurityContext) -> Result<SyncStatus, RepositoryError> {
+        unimplemented!()
+    }
+}
+
+/// Transfers and their party signatures. Every property is treated as
+/// custody bound with no extra approval.
+#[derive(Default)]
+pub(crate) struct MockTransfers {
+    pub transfers: Mutex<Vec<Transfer>>,
+    pub signatures: Mutex<HashMap<i32, Vec<TransferSignature>>>,
+    /// Ids of the properties that are sensitive items.
+    pub sensitive_properties: HashSet<i32>,
+}
+
+impl MockTransfers {
+    pub fn new(transfers: Vec<Transfer>) -> Self {
+        Self { transfers: Mutex::new(transfers), ..Default::default() }
+    }
+
+    pub fn with_sensitive_properties(mut self, ids: impl IntoIterator<Item = i32>) -> Self {
+        self.sensitive_properties.extend(ids);
+        self
+    }
+}
+
+#[async_trait]
+impl TransferRepository for MockTransfers {
+    async fn create_transfer(&self, transfer: Transfer) -> Result<Transfer, RepositoryError> {
+        let mut transfers = self.transfers.lock();
+        let transfer = Transfer { id: transfers.len() as i32 + 1, ..transfer };
+        transfers.push(transfer.clone());
+        Ok(transfer)
+    }
+
+    async fn create_scanned_transfer(&self, transfer: Transfer, _: Uuid) -> Result<Transfer, RepositoryError> {
+        self.create_transfer(transfer).await
+    }
+
+    async fn update_transfer(&self, transfer: &Transfer) -> Result<(), RepositoryError> {
+        let mut transfers = self.transfers.lock();
+        let stored = transfers
+            .iter_mut()
+            .find(|stored| stored.id == transfer.id)
+            .ok_or_else(|| RepositoryError::NotFound(format!("Transfer {} not found", transfer.id)))?;
+        *stored = transfer.clone();
+        Ok(())
+    }
+
+    async fn delete_transfer(&self, id: i32) -> Result<(), RepositoryError> {
+        self.transfers.lock().retain(|transfer| transfer.id != id);
+        Ok(())
+    }
+
+    async fn get_transfer(&self, id: i32) -> Result<Option<Transfer>, RepositoryError> {
+        Ok(self.transfers.lock().iter().find(|transfer| transfer.id == id).cloned())
+    }
+
+    async fn list_transfers(&self) -> Result<Vec<Transfer>, RepositoryError> {
+        Ok(self.transfers.lock().clone())
+    }
+
+    async fn list_transfers_after(
+        &self,
+        filter: &TransferFilter,
+        after_id: i32,
+        limit: i64,
+    ) -> Result<Vec<(Transfer, bool)>, RepositoryError> {
+        Ok(self
+            .transfers
+            .lock()
+            .iter()
+            .filter(|transfer| transfer.id > after_id && filter.matches(transfer))
+            .take(limit as usize)
+            .map(|transfer| (transfer.clone(), self.sensitive_properties.contains(&transfer.property_id)))
+            .collect())
+    }
+
+    async fn list_by_property(&self, property_id: i32) -> Result<Vec<Transfer>, RepositoryError> {
+        Ok(self.transfers.lock().iter().filter(|transfer| transfer.property_id == property_id).cloned().collect())
+    }
+
+    async fn approval_requirements(&self, _: i32) -> Result<ApprovalRequirements, RepositoryError> {
+        Ok(ApprovalRequirements::new(false, false, true))
+    }
+
+    async fn complete_transfers(&self, ids: &[i32]) -> Result<Vec<(i32, TransferStatus)>, RepositoryError> {
+        let mut changed = Vec::new();
+        for transfer in self.transfers.lock().iter_mut() {
+            if ids.contains(&transfer.id) && transfer.status != TransferStatus::Completed {
+                changed.push((transfer.id, transfer.status));
+                transfer.complete();
+            }
+        }
+        Ok(changed)
+    }
+
+    async fn add_signature(&self, transfer_id: i32, signature: &TransferSignature) -> Result<(), RepositoryError> {
+        let mut signatures = self.signatures.lock();
+        let signed = signatures.entry(transfer_id).or_default();
+        if signed.iter().any(|existing| existing.role == signature.role) {
+            return Err(RepositoryError::Validation(format!(
+                "Transfer {} is already signed by the {:?} party",
+                transfer_id, signature.role
+            )));
+        }
+        signed.push(signature.clone());
+        Ok(())
+    }
+
+    async fn list_signatures(&self, transfer_id: i32) -> Result<Vec<TransferSignature>, RepositoryError> {
+        Ok(self.signatures.lock().get(&transfer_id).cloned().unwrap_or_default())
+    }
+}
+
+/// Components kept in a list, with the repository's range check on
+/// on-hand adjustments.
+#[derive(Default)]
+pub(crate) struct MockComponents(pub Mutex<Vec<PropertyComponent>>);
+
+impl MockComponents {
+    pub fn new(components: Vec<PropertyComponent>) -> Self {
+        Self(Mutex::new(components))
+    }
+}
+
+#[async_trait]
+impl ComponentRepository for MockComponents {
+    async fn create_component(&self, component: &PropertyComponent) -> Result<PropertyComponent, RepositoryError> {
+        let mut components = self.0.lock();
+        let component = PropertyComponent { id: components.len() as i32 + 1, ..component.clone() };
+        components.push(component.clone());
+        Ok(component)
+    }
+
+    async fn get_component(&self, id: i32) -> Result<Option<PropertyComponent>, RepositoryError> {
+        Ok(self.0.lock().iter().find(|component| component.id == id).cloned())
+    }
+
+    async fn list_components(&self, property_id: i32) -> Result<Vec<PropertyComponent>, RepositoryError> {
+        Ok(self.0.lock().iter().filter(|component| component.property_id == property_id).cloned().collect())
+    }
+
+    async fn adjust_on_hand(&self, id: i32, delta: i32) -> Result<Option<PropertyComponent>, RepositoryError> {
+        let mut components = self.0.lock();
+        let Some(component) = components.iter_mut().find(|component| component.id == id) else {
+            return Ok(None);
+        };
+        let on_hand = component.on_hand_quantity + delta;
+        if !(0..=component.required_quantity).contains(&on_hand) {
+            return Err(RepositoryError::Validation("out of range".to_string()));
+        }
+        component.on_hand_quantity = on_hand;
+        Ok(Some(component.clone()))
+    }
+
+    async fn delete_component(&self, id: i32) -> Result<(), RepositoryError> {
+        self.0.lock().retain(|component| component.id != id);
+        Ok(())
+    }
+}
+
+/// Certificate records keyed by serial.
+#[derive(Default)]
+pub(crate) struct MockCertificates {
+    pub records: Mutex<HashMap<String, CertificateRecord>>,
+}
+
+#[async_trait]
+impl CertificateRepository for MockCertificates {
+    async fn create(&self, record: &CertificateRecord) -> Result<CertificateRecord, RepositoryError> {
+        self.records.lock().insert(record.serial().to_string(), record.clone());
+        Ok(record.clone())
+    }
+
+    async fn get(&self, serial: &str) -> Result<Option<CertificateRecord>, RepositoryError> {
+        Ok(self.records.lock().get(serial).cloned())
+    }
+
+    async fn list_by_user(&self, user_id: i32) -> Result<Vec<CertificateRecord>, RepositoryError> {
+        Ok(self
+            .records
+            .lock()
+            .values()
+            .filter(|record| record.user_id == user_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn revoke(
+        &self,
+        serial: &str,
+        reason: RevocationReason,
+    ) -> Result<Option<CertificateRecord>, RepositoryError> {
+        let mut records = self.records.lock();
+        match records.get_mut(serial) {
+            Some(record) if !record.is_revoked() => {
+                record.revoked_at = Some(Utc::now());
+                record.revocation_reason = Some(reason);
+                Ok(Some(record.clone()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn list_revoked(&self, issuer: &str) -> Result<Vec<(String, DateTime<Utc>)>, RepositoryError> {
+        Ok(self
+            .records
+            .lock()
+            .values()
+            .filter(|record| record.certificate.issuer == issuer)
+            .filter_map(|record| record.revoked_at.map(|at| (record.serial().to_string(), at)))
+            .collect())
+    }
+}
+
+/// Issues `HR1:<property id>` for every code and accepts only codes of that
+/// form back, as unsigned data for the named property.
+#[derive(Default)]
+pub(crate) struct StubQRService {
+    pub issued: Mutex<Vec<QRUsage>>,
+}
+
+impl StubQRService {
+    fn parse(request: &VerifyQRRequest) -> Result<QRData, CoreError> {
+        request
+            .qr_data
+            .strip_prefix("HR1:")
+            .and_then(|id| id.parse().ok())
+            .map(|property_id| QRData::new(property_id, "1".to_string(), serde_json::json!({})))
+            .ok_or_else(|| CoreError::SecurityError("Invalid QR code signature".to_string()))
+    }
+}
+
+#[async_trait]
+impl QRCodeService for StubQRService {
+    async fn issue_qr(&self, data: &QRData, usage: QRUsage, _: &SecurityContext) -> Result<String, CoreError> {
+        self.issued.lock().push(usage);
+        Ok(format!("HR1:{}", data.property_id))
+    }
+
+    async fn generate_qr(&self, _: &QRData, _: QRFormat, _: &SecurityContext) -> Result<QRResponse, CoreError> {
+        unimplemented!()
+    }
+
+    async fn validate_qr(&self, request: VerifyQRRequest, _: &SecurityContext) -> Result<QRData, CoreError> {
+        Self::parse(&request)
+    }
+
+    async fn consume_qr(&self, request: VerifyQRRequest, _: &SecurityContext) -> Result<QRData, CoreError> {
+        Self::parse(&request)
+    }
+
+    async fn revoke_property_qr(&self, _: i32, _: QRTokenRevocation, _: &SecurityContext) -> Result<u64, CoreError> {
+        Ok(0)
+    }
+}
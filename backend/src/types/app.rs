@@ -19,7 +19,9 @@ use crate::{
             entity::Transfer,
             repository::TransferRepository,
         },
+        reconciliation::repository::ReconciliationRepository,
     },
+    api::services::ApiServices,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -98,6 +100,8 @@ pub struct AppState {
     pub security: Arc<dyn SecurityService>,
     pub property_repo: Arc<dyn PropertyRepository>,
     pub transfer_repo: Arc<dyn TransferRepository>,
+    pub reconciliation_repo: Arc<dyn ReconciliationRepository>,
+    pub services: ApiServices,
 }
 
 // Configuration types
@@ -1,264 +1,361 @@
 use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use async_trait::async_trait;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
-use rand;
+use handreceipt_protocol::signing::TransferSignature;
 
 use handreceipt::{
     domain::{
-        property::{Property, PropertyService, PropertySearchCriteria},
-        transfer::{Transfer, TransferService, TransferStatus, PropertyTransferRecord},
-        models::qr::{QRCodeService, QRFormat, QRResponse, QRData, VerifyQRRequest},
+        component::{ComponentRepository, PropertyComponent},
+        property::{Property, PropertyFilter, PropertyRepository, service::SyncStatus},
+        transfer::{ApprovalRequirements, Transfer, TransferFilter, TransferRepository, TransferStatus},
+        models::{
+            location::Location,
+            qr_token::{QRToken, QRTokenRepository, QRTokenRevocation},
+            transfer::PropertyTransferRecord,
+        },
     },
-    error::CoreError,
-    types::security::SecurityContext,
+    error::RepositoryError,
 };
 
-/// Mock property service for testing
-pub struct MockPropertyService {
-    properties: Arc<Mutex<HashMap<Uuid, Property>>>,
+pub fn create_mock_transfer() -> PropertyTransferRecord {
+    PropertyTransferRecord::new(
+        rand::random::<u16>() as i32,
+        1,
+        2,
+        Location::default(),
+        String::new(),
+        String::new(),
+        format!("Node_{}", Uuid::new_v4()),
+        format!("Node_{}", Uuid::new_v4()),
+    )
+}
+
+pub fn create_mock_transfers(count: usize) -> Vec<PropertyTransferRecord> {
+    (0..count).map(|_| create_mock_transfer()).collect()
 }
 
-impl MockPropertyService {
+/// In-memory QR token repository for testing
+#[derive(Default)]
+pub struct MockQRTokenRepository {
+    tokens: Mutex<HashMap<Uuid, QRToken>>,
+}
+
+impl MockQRTokenRepository {
     pub fn new() -> Self {
-        Self {
-            properties: Arc::new(Mutex::new(HashMap::new())),
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl QRTokenRepository for MockQRTokenRepository {
+    async fn create(&self, token: &QRToken) -> Result<QRToken, RepositoryError> {
+        self.tokens.lock().unwrap().insert(token.nonce, token.clone());
+        Ok(token.clone())
+    }
+
+    async fn get(&self, nonce: Uuid) -> Result<Option<QRToken>, RepositoryError> {
+        Ok(self.tokens.lock().unwrap().get(&nonce).cloned())
+    }
+
+    async fn consume(
+        &self,
+        nonce: Uuid,
+        consumed_by: i32,
+        at: DateTime<Utc>,
+    ) -> Result<Option<QRToken>, RepositoryError> {
+        let mut tokens = self.tokens.lock().unwrap();
+        match tokens.get_mut(&nonce) {
+            Some(token) if token.is_redeemable(at) => {
+                token.consumed_at = Some(at);
+                token.consumed_by = Some(consumed_by);
+                Ok(Some(token.clone()))
+            }
+            _ => Ok(None),
         }
     }
+
+    async fn revoke_for_property(
+        &self,
+        property_id: i32,
+        reason: QRTokenRevocation,
+    ) -> Result<u64, RepositoryError> {
+        let mut revoked = 0;
+        for token in self.tokens.lock().unwrap().values_mut() {
+            if token.property_id == property_id && token.consumed_at.is_none() && token.revoked_at.is_none() {
+                token.revoked_at = Some(Utc::now());
+                token.revocation_reason = Some(reason);
+                revoked += 1;
+            }
+        }
+        Ok(revoked)
+    }
+}
+
+/// In-memory property repository for testing
+#[derive(Default)]
+pub struct MockPropertyRepository {
+    properties: Mutex<HashMap<i32, Property>>,
+    sync_status: Mutex<HashMap<i32, SyncStatus>>,
+}
+
+impl MockPropertyRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
 }
 
 #[async_trait]
-impl PropertyService for MockPropertyService {
-    async fn create_property(&self, property: Property, _context: &SecurityContext) -> Result<Property, CoreError> {
-        let mut properties = self.properties.lock().unwrap();
-        properties.insert(property.id(), property.clone());
+impl PropertyRepository for MockPropertyRepository {
+    async fn create_property(&self, property: Property) -> Result<Property, RepositoryError> {
+        self.properties.lock().unwrap().insert(property.id, property.clone());
         Ok(property)
     }
 
-    async fn get_property(&self, id: Uuid, _context: &SecurityContext) -> Result<Option<Property>, CoreError> {
-        let properties = self.properties.lock().unwrap();
-        Ok(properties.get(&id).cloned())
+    async fn create_properties(&self, properties: Vec<Property>) -> Result<Vec<Property>, RepositoryError> {
+        let mut stored = self.properties.lock().unwrap();
+        for property in &properties {
+            stored.insert(property.id, property.clone());
+        }
+        Ok(properties)
     }
 
-    async fn update_property(&self, property: &Property, _context: &SecurityContext) -> Result<(), CoreError> {
-        let mut properties = self.properties.lock().unwrap();
-        properties.insert(property.id(), property.clone());
+    async fn update_property(&self, property: &Property) -> Result<(), RepositoryError> {
+        self.properties.lock().unwrap().insert(property.id, property.clone());
         Ok(())
     }
 
-    async fn delete_property(&self, id: Uuid, _context: &SecurityContext) -> Result<(), CoreError> {
-        let mut properties = self.properties.lock().unwrap();
-        properties.remove(&id);
+    async fn delete_property(&self, id: i32) -> Result<(), RepositoryError> {
+        self.properties.lock().unwrap().remove(&id);
         Ok(())
     }
 
-    async fn list_properties(&self, _context: &SecurityContext) -> Result<Vec<Property>, CoreError> {
-        let properties = self.properties.lock().unwrap();
-        Ok(properties.values().cloned().collect())
+    async fn get_property(&self, id: i32) -> Result<Option<Property>, RepositoryError> {
+        Ok(self.properties.lock().unwrap().get(&id).cloned())
     }
 
-    async fn get_properties_by_custodian(&self, custodian: &str, _context: &SecurityContext) -> Result<Vec<Property>, CoreError> {
-        let properties = self.properties.lock().unwrap();
-        Ok(properties.values()
-            .filter(|p| p.custodian() == Some(&custodian.to_string()))
-            .cloned()
-            .collect())
+    async fn list_properties(&self) -> Result<Vec<Property>, RepositoryError> {
+        Ok(self.properties.lock().unwrap().values().cloned().collect())
     }
 
-    async fn get_sensitive_items(&self, _context: &SecurityContext) -> Result<Vec<Property>, CoreError> {
-        let properties = self.properties.lock().unwrap();
-        Ok(properties.values()
-            .filter(|p| p.is_sensitive())
+    async fn list_properties_after(
+        &self,
+        filter: &PropertyFilter,
+        after_id: i32,
+        limit: i64,
+    ) -> Result<Vec<Property>, RepositoryError> {
+        let mut properties: Vec<Property> = self.properties.lock().unwrap()
+            .values()
+            .filter(|property| property.id > after_id && filter.matches(property))
             .cloned()
-            .collect())
+            .collect();
+        properties.sort_by_key(|property| property.id);
+        properties.truncate(limit as usize);
+        Ok(properties)
     }
 
-    async fn get_properties_by_command(&self, command_id: &str, _context: &SecurityContext) -> Result<Vec<Property>, CoreError> {
-        let properties = self.properties.lock().unwrap();
-        Ok(properties.values()
-            .filter(|p| p.command_id() == Some(&command_id.to_string()))
-            .cloned()
-            .collect())
+    async fn get_sync_status(&self, id: i32) -> Result<Option<SyncStatus>, RepositoryError> {
+        Ok(self.sync_status.lock().unwrap().get(&id).cloned())
     }
 
-    async fn get_current_holder(&self, property: &Property, _context: &SecurityContext) -> Result<Option<(String, DateTime<Utc>, String)>, CoreError> {
-        Ok(property.custodian().map(|c| (
-            c.to_string(),
-            Utc::now(),
-            "Test Location".to_string(),
-        )))
+    async fn update_sync_status(&self, id: i32, status: &SyncStatus) -> Result<(), RepositoryError> {
+        self.sync_status.lock().unwrap().insert(id, status.clone());
+        Ok(())
     }
 }
 
-/// Mock transfer service for testing
-pub struct MockTransferService {
-    transfers: Arc<Mutex<HashMap<Uuid, Transfer>>>,
+/// In-memory transfer repository for testing; ids are assigned on create.
+/// Every property is treated as custody bound, and only those marked
+/// sensitive need an approver. Scanned transfers redeem their QR token in
+/// `tokens`, if set, as the Postgres repository does.
+#[derive(Default)]
+pub struct MockTransferRepository {
+    transfers: Mutex<HashMap<i32, Transfer>>,
+    signatures: Mutex<HashMap<i32, Vec<TransferSignature>>>,
+    sensitive_properties: HashSet<i32>,
+    tokens: Option<Arc<MockQRTokenRepository>>,
 }
 
-impl MockTransferService {
+impl MockTransferRepository {
     pub fn new() -> Self {
-        Self {
-            transfers: Arc::new(Mutex::new(HashMap::new())),
-        }
+        Self::default()
+    }
+
+    pub fn with_sensitive_properties(mut self, ids: impl IntoIterator<Item = i32>) -> Self {
+        self.sensitive_properties.extend(ids);
+        self
+    }
+
+    pub fn with_tokens(mut self, tokens: Arc<MockQRTokenRepository>) -> Self {
+        self.tokens = Some(tokens);
+        self
     }
 }
 
 #[async_trait]
-impl TransferService for MockTransferService {
-    async fn initiate_transfer(
-        &self,
-        property_id: Uuid,
-        new_custodian: String,
-        _context: &SecurityContext,
-    ) -> Result<Transfer, CoreError> {
-        let transfer = Transfer::new(
-            property_id,
-            None,
-            new_custodian,
-            true,
-            None,
-            None,
-        );
-
+impl TransferRepository for MockTransferRepository {
+    async fn create_transfer(&self, mut transfer: Transfer) -> Result<Transfer, RepositoryError> {
         let mut transfers = self.transfers.lock().unwrap();
-        transfers.insert(transfer.id(), transfer.clone());
+        transfer.id = transfers.len() as i32 + 1;
+        transfers.insert(transfer.id, transfer.clone());
         Ok(transfer)
     }
 
-    async fn approve_transfer(
-        &self,
-        transfer_id: Uuid,
-        _context: &SecurityContext,
-    ) -> Result<Transfer, CoreError> {
-        let mut transfers = self.transfers.lock().unwrap();
-        let mut transfer = transfers.get(&transfer_id)
-            .cloned()
-            .ok_or_else(|| CoreError::NotFound("Transfer not found".into()))?;
+    async fn create_scanned_transfer(&self, transfer: Transfer, qr_nonce: uuid::Uuid) -> Result<Transfer, RepositoryError> {
+        let pending = self.transfers.lock().unwrap().values().any(|t| {
+            t.property_id == transfer.property_id && t.status == TransferStatus::Pending
+        });
+        if pending {
+            return Err(RepositoryError::Validation("Property already has a pending transfer".to_string()));
+        }
+        if let Some(tokens) = &self.tokens {
+            tokens
+                .consume(qr_nonce, transfer.to_holder_id, Utc::now())
+                .await?
+                .ok_or_else(|| RepositoryError::Validation("QR code has already been used".to_string()))?;
+        }
+        self.create_transfer(transfer).await
+    }
 
-        transfer.update_status(TransferStatus::Completed);
-        transfers.insert(transfer_id, transfer.clone());
-        Ok(transfer)
+    async fn approval_requirements(&self, property_id: i32) -> Result<ApprovalRequirements, RepositoryError> {
+        let is_sensitive = self.sensitive_properties.contains(&property_id);
+        Ok(ApprovalRequirements::new(is_sensitive, false, true))
     }
 
-    async fn complete_transfer(
-        &self,
-        transfer_id: Uuid,
-        blockchain_verification: String,
-        _context: &SecurityContext,
-    ) -> Result<Transfer, CoreError> {
-        let mut transfers = self.transfers.lock().unwrap();
-        let mut transfer = transfers.get(&transfer_id)
-            .cloned()
-            .ok_or_else(|| CoreError::NotFound("Transfer not found".into()))?;
+    async fn update_transfer(&self, transfer: &Transfer) -> Result<(), RepositoryError> {
+        self.transfers.lock().unwrap().insert(transfer.id, transfer.clone());
+        Ok(())
+    }
 
-        transfer.update_status(TransferStatus::Confirmed);
-        transfer.set_blockchain_verification(blockchain_verification);
-        transfers.insert(transfer_id, transfer.clone());
-        Ok(transfer)
+    async fn delete_transfer(&self, id: i32) -> Result<(), RepositoryError> {
+        self.transfers.lock().unwrap().remove(&id);
+        Ok(())
+    }
+
+    async fn get_transfer(&self, id: i32) -> Result<Option<Transfer>, RepositoryError> {
+        Ok(self.transfers.lock().unwrap().get(&id).cloned())
     }
 
-    async fn get_property_transfers(
+    async fn list_transfers(&self) -> Result<Vec<Transfer>, RepositoryError> {
+        Ok(self.transfers.lock().unwrap().values().cloned().collect())
+    }
+
+    async fn list_transfers_after(
         &self,
-        property_id: Uuid,
-        _context: &SecurityContext,
-    ) -> Result<Vec<Transfer>, CoreError> {
-        let transfers = self.transfers.lock().unwrap();
-        Ok(transfers.values()
-            .filter(|t| t.property_id() == property_id)
+        filter: &TransferFilter,
+        after_id: i32,
+        limit: i64,
+    ) -> Result<Vec<(Transfer, bool)>, RepositoryError> {
+        let mut transfers: Vec<Transfer> = self.transfers.lock().unwrap()
+            .values()
+            .filter(|transfer| transfer.id > after_id && filter.matches(transfer))
             .cloned()
+            .collect();
+        transfers.sort_by_key(|transfer| transfer.id);
+        transfers.truncate(limit as usize);
+        Ok(transfers
+            .into_iter()
+            .map(|transfer| {
+                let is_sensitive = self.sensitive_properties.contains(&transfer.property_id);
+                (transfer, is_sensitive)
+            })
             .collect())
     }
 
-    async fn get_pending_approvals(
-        &self,
-        _context: &SecurityContext,
-    ) -> Result<Vec<Transfer>, CoreError> {
-        let transfers = self.transfers.lock().unwrap();
-        Ok(transfers.values()
-            .filter(|t| t.status() == TransferStatus::Pending)
+    async fn list_by_property(&self, property_id: i32) -> Result<Vec<Transfer>, RepositoryError> {
+        Ok(self.transfers
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|t| t.property_id == property_id)
             .cloned()
             .collect())
     }
+
+    async fn complete_transfers(&self, ids: &[i32]) -> Result<Vec<(i32, TransferStatus)>, RepositoryError> {
+        let mut changed = Vec::new();
+        for transfer in self.transfers.lock().unwrap().values_mut() {
+            if ids.contains(&transfer.id) && transfer.status != TransferStatus::Completed {
+                changed.push((transfer.id, transfer.status));
+                transfer.complete();
+            }
+        }
+        Ok(changed)
+    }
+
+    async fn add_signature(&self, transfer_id: i32, signature: &TransferSignature) -> Result<(), RepositoryError> {
+        let mut signatures = self.signatures.lock().unwrap();
+        let signed = signatures.entry(transfer_id).or_default();
+        if signed.iter().any(|existing| existing.role == signature.role) {
+            return Err(RepositoryError::Validation(format!(
+                "Transfer {} is already signed by the {:?} party",
+                transfer_id, signature.role
+            )));
+        }
+        signed.push(signature.clone());
+        Ok(())
+    }
+
+    async fn list_signatures(&self, transfer_id: i32) -> Result<Vec<TransferSignature>, RepositoryError> {
+        Ok(self.signatures.lock().unwrap().get(&transfer_id).cloned().unwrap_or_default())
+    }
 }
 
-/// Mock QR code service for testing
-pub struct MockQRCodeService {
-    signing_key: ed25519_dalek::SigningKey,
+/// In-memory component repository for testing; ids are assigned on create
+#[derive(Default)]
+pub struct MockComponentRepository {
+    components: Mutex<HashMap<i32, PropertyComponent>>,
 }
 
-impl MockQRCodeService {
+impl MockComponentRepository {
     pub fn new() -> Self {
-        Self {
-            signing_key: ed25519_dalek::SigningKey::generate(&mut rand::thread_rng()),
-        }
+        Self::default()
     }
 }
 
 #[async_trait]
-impl QRCodeService for MockQRCodeService {
-    async fn generate_qr(
-        &self,
-        property_id: Uuid,
-        format: QRFormat,
-        _context: &SecurityContext,
-    ) -> Result<QRResponse, CoreError> {
-        let timestamp = Utc::now();
-        let msg = format!("{}:{}", property_id, timestamp.timestamp());
-        let signature = self.signing_key.sign(msg.as_bytes());
-
-        let qr_data = QRData {
-            property_id,
-            timestamp,
-            signature: base64::Engine::encode(&base64::engine::general_purpose::STANDARD, signature.to_bytes()),
-        };
-
-        Ok(QRResponse {
-            qr_code: serde_json::to_string(&qr_data)?,
-            property_id,
-            generated_at: timestamp,
-            format,
-        })
+impl ComponentRepository for MockComponentRepository {
+    async fn create_component(&self, component: &PropertyComponent) -> Result<PropertyComponent, RepositoryError> {
+        let mut components = self.components.lock().unwrap();
+        let mut component = component.clone();
+        component.id = components.len() as i32 + 1;
+        components.insert(component.id, component.clone());
+        Ok(component)
     }
 
-    async fn validate_qr(
-        &self,
-        request: VerifyQRRequest,
-        _context: &SecurityContext,
-    ) -> Result<QRData, CoreError> {
-        let qr_data: QRData = serde_json::from_str(&request.qr_data)?;
-
-        // Verify signature
-        let msg = format!("{}:{}", qr_data.property_id, qr_data.timestamp.timestamp());
-        let signature_bytes = base64::Engine::decode(
-            &base64::engine::general_purpose::STANDARD,
-            qr_data.signature.as_bytes(),
-        )?;
-        let signature = ed25519_dalek::Signature::from_bytes(&signature_bytes)?;
-
-        self.signing_key.verify_strict(msg.as_bytes(), &signature)
-            .map_err(|_| CoreError::Security("Invalid QR code signature".into()))?;
-
-        // Check expiration
-        let age = request.scanned_at - qr_data.timestamp;
-        if age.num_hours() > 24 {
-            return Err(CoreError::Security("QR code has expired".into()));
-        }
+    async fn get_component(&self, id: i32) -> Result<Option<PropertyComponent>, RepositoryError> {
+        Ok(self.components.lock().unwrap().get(&id).cloned())
+    }
 
-        Ok(qr_data)
+    async fn list_components(&self, property_id: i32) -> Result<Vec<PropertyComponent>, RepositoryError> {
+        let mut components: Vec<PropertyComponent> = self.components.lock().unwrap()
+            .values()
+            .filter(|c| c.property_id == property_id)
+            .cloned()
+            .collect();
+        components.sort_by_key(|c| c.id);
+        Ok(components)
     }
-}
 
-pub fn create_mock_transfer() -> PropertyTransferRecord {
-    PropertyTransferRecord {
-        id: rand::random::<i64>(),
-        from_node: format!("Node_{}", Uuid::new_v4()),
-        to_node: format!("Node_{}", Uuid::new_v4()),
-        timestamp: Utc::now(),
-        status: TransferStatus::Pending,
+    async fn adjust_on_hand(&self, id: i32, delta: i32) -> Result<Option<PropertyComponent>, RepositoryError> {
+        let mut components = self.components.lock().unwrap();
+        let Some(component) = components.get_mut(&id) else {
+            return Ok(None);
+        };
+        let on_hand = component.on_hand_quantity + delta;
+        if on_hand < 0 || on_hand > component.required_quantity {
+            return Err(RepositoryError::Validation(format!(
+                "{} has {} of {} on hand; cannot change by {}",
+                component.name, component.on_hand_quantity, component.required_quantity, delta
+            )));
+        }
+        component.on_hand_quantity = on_hand;
+        Ok(Some(component.clone()))
     }
-}
 
-pub fn create_mock_transfers(count: usize) -> Vec<PropertyTransferRecord> {
-    (0..count).map(|_| create_mock_transfer()).collect()
+    async fn delete_component(&self, id: i32) -> Result<(), RepositoryError> {
+        self.components.lock().unwrap().remove(&id);
+        Ok(())
+    }
 }
@@ -1,3 +1,5 @@
+pub mod mocks;
+
 #[cfg(test)]
 pub mod test_utils {
     use actix_web::{App, web::Data, body::BoxBody};
@@ -6,11 +8,14 @@ pub mod test_utils {
     use std::collections::{HashMap, HashSet};
     use std::sync::Arc;
     use async_trait::async_trait;
+    use ed25519_dalek::SigningKey;
+    use handreceipt_protocol::qr::{QrKeyring, SignedQr};
     use handreceipt::{
         domain::{
             property::entity::{Property, PropertyCategory, PropertyStatus},
             models::{
-                qr::QRData,
+                qr::{QRCodeServiceImpl, QRData},
+                qr_token::QRTokenPolicy,
                 location::Location,
             },
         },
@@ -22,6 +27,7 @@ pub mod test_utils {
         api::routes,
         error::CoreError,
     };
+    use super::mocks::MockQRTokenRepository;
 
     pub struct TestContext {
         pub user: TestUser,
@@ -139,35 +145,44 @@ pub mod test_utils {
         }
     }
 
+    /// Key the QR fixtures are signed with.
+    pub fn test_qr_key() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32])
+    }
+
+    /// Keyring trusting only [`test_qr_key`].
+    pub fn test_qr_keyring() -> QrKeyring {
+        let mut keyring = QrKeyring::new();
+        keyring.trust(test_qr_key().verifying_key());
+        keyring
+    }
+
     pub async fn create_test_qr_code(property_id: i32) -> QRData {
-        QRData {
-            id: Uuid::new_v4(),
-            property_id,
-            custodian_id: "1".to_string(),
-            timestamp: Utc::now(),
-            metadata: serde_json::json!({
-                "qr_code": format!("QR_{}", Uuid::new_v4())
-            }),
-            signature: "test_signature".to_string(),
-            public_key: "test_public_key".to_string(),
-        }
+        create_test_qr_code_with_timestamp(property_id, Utc::now()).await
     }
 
+    /// QR data issued at `timestamp`, signed with [`test_qr_key`] and read
+    /// back through [`test_qr_keyring`] the way a scanner would.
     pub async fn create_test_qr_code_with_timestamp(
         property_id: i32,
         timestamp: chrono::DateTime<Utc>,
     ) -> QRData {
-        QRData {
-            id: Uuid::new_v4(),
+        let mut data = QRData::new(
             property_id,
-            custodian_id: "1".to_string(),
-            timestamp,
-            metadata: serde_json::json!({
-                "qr_code": format!("QR_{}", Uuid::new_v4())
-            }),
-            signature: "test_signature".to_string(),
-            public_key: "test_public_key".to_string(),
-        }
+            "1".to_string(),
+            serde_json::json!({ "name": "test_property" }),
+        );
+        data.timestamp = timestamp;
+
+        let service = QRCodeServiceImpl::new(
+            test_qr_key(),
+            Arc::new(MockQRTokenRepository::new()),
+            QRTokenPolicy::default(),
+        );
+        let encoded = service.encode(&data).expect("test QR code encodes");
+        let signed = SignedQr::decode(&encoded).expect("test QR code decodes");
+        signed.verify(&test_qr_keyring()).expect("test QR code verifies");
+        data.with_signature(hex::encode(signed.signature), signed.payload.kid)
     }
 
     impl TestContext {
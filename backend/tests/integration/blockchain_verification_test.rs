@@ -77,12 +77,15 @@ async fn test_transfer_verification() {
     // Create test authority node
     let signing_key = SigningKey::generate(&mut rand::thread_rng());
     let certificate = MilitaryCertificate {
+        serial: "TEST-SERIAL".to_string(),
         issuer: "TEST-AUTHORITY".to_string(),
         subject: "TEST-KEY".to_string(),
+        public_key: hex::encode(signing_key.verifying_key().to_bytes()),
         valid_from: Utc::now(),
         valid_until: Some(Utc::now() + chrono::Duration::days(365)),
         roles: vec!["OFFICER".to_string()],
         unit_id: "TEST-UNIT".to_string(),
+        signature: String::new(),
     };
     
     let authority = Arc::new(AuthorityNode::new(
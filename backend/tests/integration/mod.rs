@@ -5,6 +5,7 @@ mod mobile_workflow_test;
 mod transfer_edge_test;
 mod transfer_workflow_test;
 mod blockchain_verification_test;
+mod qr_transfer_test;
 
 pub use security_test::*;
 pub use mobile_workflow_test::*;
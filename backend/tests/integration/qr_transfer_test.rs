@@ -0,0 +1,244 @@
+use std::sync::Arc;
+use chrono::{Duration, Utc};
+use ed25519_dalek::SigningKey;
+use serde_json::json;
+use handreceipt::{
+    domain::{
+        component::{ComponentKind, ComponentRepository, PropertyComponent},
+        models::{
+            location::Location,
+            qr::{QRCodeService, QRCodeServiceImpl, QRData},
+            qr_token::{QRTokenPolicy, QRUsage},
+        },
+        property::{Property, PropertyCategory, PropertyRepository},
+        transfer::{TransferRepository, TransferService, TransferServiceImpl, TransferStatus},
+    },
+    error::RepositoryError,
+    types::security::SecurityContext,
+};
+use handreceipt_protocol::signing::TransferRole;
+use crate::common::mocks::{
+    MockComponentRepository, MockPropertyRepository, MockQRTokenRepository, MockTransferRepository,
+};
+
+const HOLDER: i32 = 1;
+const SCANNER: i32 = 2;
+
+struct Harness {
+    qr: Arc<QRCodeServiceImpl>,
+    tokens: Arc<MockQRTokenRepository>,
+    properties: Arc<MockPropertyRepository>,
+    transfers: Arc<MockTransferRepository>,
+    components: Arc<MockComponentRepository>,
+    service: TransferServiceImpl,
+    property: Property,
+}
+
+impl Harness {
+    async fn new(category: PropertyCategory, is_sensitive: bool) -> Self {
+        let tokens = Arc::new(MockQRTokenRepository::new());
+        let qr = Arc::new(QRCodeServiceImpl::new(
+            SigningKey::from_bytes(&[7u8; 32]),
+            tokens.clone(),
+            QRTokenPolicy::default(),
+        ));
+        let properties = Arc::new(MockPropertyRepository::new());
+        let components = Arc::new(MockComponentRepository::new());
+
+        let mut property = Property::new(
+            "Rifle, 5.56mm, M4".to_string(),
+            "Carbine".to_string(),
+            category,
+            HOLDER,
+            Location::default(),
+        );
+        property.id = 42;
+        let transfers = Arc::new(
+            MockTransferRepository::new()
+                .with_sensitive_properties(is_sensitive.then_some(property.id))
+                .with_tokens(tokens.clone()),
+        );
+        property.is_sensitive = is_sensitive;
+        let property = properties.create_property(property).await.unwrap();
+
+        let service = TransferServiceImpl::new(
+            transfers.clone(),
+            properties.clone(),
+            components.clone(),
+            qr.clone(),
+        );
+        Self { qr, tokens, properties, transfers, components, service, property }
+    }
+
+    fn qr_data(&self) -> QRData {
+        QRData::new(self.property.id, HOLDER.to_string(), json!({"name": self.property.name}))
+            .with_category(self.property.category)
+    }
+
+    async fn issue(&self, data: &QRData) -> String {
+        self.qr
+            .issue_qr(data, QRUsage::Transfer, &SecurityContext::new(HOLDER))
+            .await
+            .unwrap()
+    }
+
+    async fn scan(&self, payload: &str) -> Result<handreceipt::domain::transfer::QRScanTransfer, RepositoryError> {
+        self.service
+            .scan_qr_transfer(payload, &scan_location(), &SecurityContext::new(SCANNER))
+            .await
+    }
+
+    async fn transfer_count(&self) -> usize {
+        self.transfers.list_transfers().await.unwrap().len()
+    }
+}
+
+fn scan_location() -> Location {
+    Location {
+        latitude: 35.1391,
+        longitude: -79.0063,
+        building: Some("Arms Room 4".to_string()),
+        ..Location::default()
+    }
+}
+
+fn assert_rejected(result: Result<impl std::fmt::Debug, RepositoryError>, expected: &str) {
+    match result {
+        Err(RepositoryError::Validation(message)) => {
+            assert!(message.contains(expected), "expected {:?} in {:?}", expected, message)
+        }
+        other => panic!("expected validation error containing {:?}, got {:?}", expected, other),
+    }
+}
+
+#[tokio::test]
+async fn test_scan_creates_pending_transfer_to_scanner() {
+    let harness = Harness::new(PropertyCategory::Weapon, true).await;
+    let payload = harness.issue(&harness.qr_data()).await;
+
+    let scan = harness.scan(&payload).await.unwrap();
+    assert_eq!(scan.transfer.status, TransferStatus::Pending);
+    assert_eq!(scan.transfer.property_id, harness.property.id);
+    assert_eq!(scan.transfer.from_holder_id, HOLDER);
+    assert_eq!(scan.transfer.to_holder_id, SCANNER);
+    assert_eq!(scan.transfer.location, scan_location());
+    assert_eq!(harness.transfer_count().await, 1);
+
+    assert!(scan.approval.requires_approval);
+    assert_eq!(
+        scan.approval.required_signatures,
+        vec![TransferRole::Releasing, TransferRole::Receiving, TransferRole::Approver]
+    );
+
+    // The code is spent once the transfer exists
+    assert_rejected(harness.scan(&payload).await, "already been used");
+    assert_eq!(harness.transfer_count().await, 1);
+}
+
+#[tokio::test]
+async fn test_routine_item_needs_only_both_holders() {
+    let harness = Harness::new(PropertyCategory::Equipment, false).await;
+    let payload = harness.issue(&harness.qr_data()).await;
+
+    let scan = harness.scan(&payload).await.unwrap();
+    assert!(!scan.approval.requires_approval);
+    assert_eq!(
+        scan.approval.required_signatures,
+        vec![TransferRole::Releasing, TransferRole::Receiving]
+    );
+}
+
+#[tokio::test]
+async fn test_scan_carries_shortage_annex() {
+    let harness = Harness::new(PropertyCategory::Weapon, true).await;
+    let id = harness.property.id;
+    let mut sling = PropertyComponent::new(id, "Sling, multipoint".to_string(), ComponentKind::BasicIssueItem, 1);
+    sling.on_hand_quantity = 0;
+    harness.components.create_component(&sling).await.unwrap();
+    let optic = PropertyComponent::new(id, "Optic, M68".to_string(), ComponentKind::Component, 1);
+    harness.components.create_component(&optic).await.unwrap();
+
+    let payload = harness.issue(&harness.qr_data()).await;
+    let scan = harness.scan(&payload).await.unwrap();
+
+    // Only the missing sling is on the annex the scanner signs for
+    let annex = scan.transfer.shortage_annex().expect("annex attached");
+    assert_eq!(annex.lines.len(), 1);
+    assert_eq!(annex.lines[0].name, "Sling, multipoint");
+    assert_eq!(annex.lines[0].shortage, 1);
+    assert!(scan.transfer.metadata.get("qr_id").is_some());
+
+    // Filling the shortage later does not change what was signed for
+    harness.components.adjust_on_hand(1, 1).await.unwrap();
+    let stored = harness.transfers.get_transfer(scan.transfer.id).await.unwrap().unwrap();
+    assert_eq!(stored.shortage_annex(), Some(annex));
+}
+
+#[tokio::test]
+async fn test_expired_qr_is_rejected() {
+    let harness = Harness::new(PropertyCategory::Weapon, true).await;
+    // Weapon transfer codes live 15 minutes
+    let mut data = harness.qr_data();
+    data.timestamp = Utc::now() - Duration::minutes(20);
+    let payload = harness.issue(&data).await;
+
+    assert_rejected(harness.scan(&payload).await, "expired");
+    assert_eq!(harness.transfer_count().await, 0);
+}
+
+#[tokio::test]
+async fn test_forged_qr_is_rejected() {
+    let harness = Harness::new(PropertyCategory::Weapon, true).await;
+
+    // Signed by a key this server does not trust, even though a token exists
+    let rogue = QRCodeServiceImpl::new(
+        SigningKey::from_bytes(&[9u8; 32]),
+        harness.tokens.clone(),
+        QRTokenPolicy::default(),
+    );
+    let forged = rogue
+        .issue_qr(&harness.qr_data(), QRUsage::Transfer, &SecurityContext::new(HOLDER))
+        .await
+        .unwrap();
+    assert_rejected(harness.scan(&forged).await, "unknown key");
+
+    // A genuine code with one character changed
+    let genuine = harness.issue(&harness.qr_data()).await;
+    let mut tampered: Vec<char> = genuine.chars().collect();
+    let last = tampered.len() - 1;
+    tampered[last] = if tampered[last] == 'A' { 'B' } else { 'A' };
+    let tampered: String = tampered.into_iter().collect();
+    assert!(harness.scan(&tampered).await.is_err());
+
+    assert_eq!(harness.transfer_count().await, 0);
+    // The untouched code still works afterwards
+    assert!(harness.scan(&genuine).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_stale_holder_qr_is_rejected() {
+    let harness = Harness::new(PropertyCategory::Equipment, false).await;
+    let payload = harness.issue(&harness.qr_data()).await;
+
+    // The item changed hands after the code was issued
+    let mut property = harness.property.clone();
+    property.current_holder_id = 3;
+    harness.properties.update_property(&property).await.unwrap();
+
+    assert_rejected(harness.scan(&payload).await, "stale");
+    assert_eq!(harness.transfer_count().await, 0);
+}
+
+#[tokio::test]
+async fn test_holder_cannot_scan_own_property() {
+    let harness = Harness::new(PropertyCategory::Equipment, false).await;
+    let payload = harness.issue(&harness.qr_data()).await;
+
+    let result = harness.service
+        .scan_qr_transfer(&payload, &scan_location(), &SecurityContext::new(HOLDER))
+        .await;
+    assert_rejected(result, "already holds");
+
+    // Rejected scans do not burn the code
+    assert!(harness.scan(&payload).await.is_ok());
+}
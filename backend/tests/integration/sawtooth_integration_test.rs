@@ -10,7 +10,7 @@ use handreceipt::{
         BlockchainService,
         BlockchainConfig,
         sawtooth::{
-            SawtoothService,
+            EmbeddedLedgerService,
             state::PropertyMetadata,
         },
     },
@@ -19,6 +19,7 @@ use handreceipt::{
 
 use crate::common::mocks::create_mock_transfer;
 
+/// Runs against an in-process embedded ledger, so no validator is needed.
 async fn setup_sawtooth_service() -> Arc<dyn BlockchainService> {
     let config = BlockchainConfig {
         node_id: Uuid::new_v4().to_string(),
@@ -27,15 +28,12 @@ async fn setup_sawtooth_service() -> Arc<dyn BlockchainService> {
         batch_timeout: std::time::Duration::from_secs(5),
         min_validators: 1,
         max_validators: 3,
-        validator_url: "tcp://localhost:4004".to_string(),
-        validator_private_key: "test_key".to_string(),
+        ..BlockchainConfig::default()
     };
 
-    let service = SawtoothService::new(
-        config.validator_url.clone(),
-        config.validator_private_key.clone(),
-        config,
-    ).expect("Failed to create Sawtooth service");
+    let service = EmbeddedLedgerService::start(config)
+        .await
+        .expect("Failed to start embedded ledger");
 
     Arc::new(service)
 }
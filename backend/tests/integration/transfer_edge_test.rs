@@ -1,6 +1,3 @@
-#[path = "../common/mod.rs"]
-mod common;
-
 use std::collections::{HashMap, HashSet};
 use chrono::Utc;
 use uuid::Uuid;
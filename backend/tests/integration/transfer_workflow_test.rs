@@ -49,12 +49,15 @@ async fn test_transfer_workflow() {
     };
 
     let certificate = MilitaryCertificate {
+        serial: "TEST-SERIAL".to_string(),
         issuer: "TEST-CA".to_string(),
         subject: "TEST-UNIT".to_string(),
+        public_key: String::new(),
         valid_from: Utc::now(),
         valid_until: Some(Utc::now() + chrono::Duration::days(365)),
         roles: vec!["COMMANDER".to_string()],
         unit_id: "TEST-UNIT-ID".to_string(),
+        signature: String::new(),
     };
 
     // ... rest of test ...
@@ -4,10 +4,13 @@ use handreceipt::{
         security::{SecurityContext, SecurityClassification},
     },
     domain::models::{
-        qr::{QRData, QRCodeService, QRCodeServiceImpl, QRFormat},
+        qr::{QRData, QRCodeService, QRCodeServiceImpl, QRFormat, VerifyQRRequest},
+        qr_token::QRTokenPolicy,
         Location,
     },
 };
+use crate::common::mocks::MockQRTokenRepository;
+use std::sync::Arc;
 use ed25519_dalek::SigningKey;
 use chrono::Utc;
 use serde_json::json;
@@ -16,7 +19,11 @@ use serde_json::json;
 async fn test_qr_code_generation() {
     // Generate a test signing key
     let signing_key = SigningKey::generate(&mut rand::thread_rng());
-    let service = QRCodeServiceImpl::new(signing_key);
+    let service = QRCodeServiceImpl::new(
+        signing_key,
+        Arc::new(MockQRTokenRepository::new()),
+        QRTokenPolicy::default(),
+    );
 
     let property_id = 1;  // Using i32 instead of UUID
     let custodian_id = "test_custodian".to_string();  // Using String instead of UUID
@@ -31,3 +38,27 @@ async fn test_qr_code_generation() {
     let qr_code = service.generate_qr(&qr_data, QRFormat::PNG, &context).await.unwrap();
     assert!(!qr_code.data.is_empty());
 }
+
+#[tokio::test]
+async fn test_qr_code_is_single_use() {
+    let signing_key = SigningKey::generate(&mut rand::thread_rng());
+    let service = QRCodeServiceImpl::new(
+        signing_key,
+        Arc::new(MockQRTokenRepository::new()),
+        QRTokenPolicy::default(),
+    );
+    let context = SecurityContext::new(1);
+
+    let qr_data = QRData::new(1, "test_custodian".to_string(), json!({"test": "metadata"}));
+    service.generate_qr(&qr_data, QRFormat::SVG, &context).await.unwrap();
+
+    let request = VerifyQRRequest {
+        qr_data: service.encode(&qr_data).unwrap(),
+        signature: None,
+        timestamp: Utc::now(),
+        scanner_id: "scanner".to_string(),
+        location: None,
+    };
+    assert!(service.consume_qr(request.clone(), &context).await.is_ok());
+    assert!(service.consume_qr(request, &context).await.is_err());
+}
@@ -11,8 +11,7 @@ use protobuf::Message;
 use crate::blockchain::sawtooth::merkle::MerkleTree;
 use crate::blockchain::sawtooth::wrappers::{ThreadSafeContext, ThreadSafePrivateKey, ThreadSafeSigner};
 
-const FAMILY_NAME: &str = "handreceipt";
-const FAMILY_VERSION: &str = "1.0";
+use handreceipt_protocol::addressing::{self, AddressSpace, FAMILY_NAME, FAMILY_VERSION};
 pub const REST_API_URL: &str = "http://localhost:8008";
 
 #[async_trait]
@@ -43,19 +42,19 @@ impl SawtoothAdapter {
         })
     }
 
-    fn compute_namespace() -> String {
-        let mut hasher = Sha512::new();
-        hasher.update(FAMILY_NAME.as_bytes());
-        let hash = hex::encode(&hasher.finalize());
-        hash[..6].to_string()
-    }
-
+    /// Resolves the state address touched by a serialized payload. Payloads
+    /// that don't name a property fall back to the property sub-namespace.
     fn compute_address(&self, payload: &[u8]) -> String {
-        let namespace = Self::compute_namespace();
-        let mut hasher = Sha512::new();
-        hasher.update(payload);
-        let hash = hex::encode(&hasher.finalize());
-        format!("{}{}", namespace, &hash[..64])
+        serde_json::from_slice::<serde_json::Value>(payload)
+            .ok()
+            .and_then(|value| {
+                value.get("data")
+                    .and_then(|data| data.get("property_id"))
+                    .or_else(|| value.get("property_id"))
+                    .and_then(|id| id.as_str())
+                    .map(addressing::property_address)
+            })
+            .unwrap_or_else(|| AddressSpace::Property.prefix())
     }
 
     fn create_transaction_header(&self, payload: &[u8]) -> Result<TransactionHeader, Error> {
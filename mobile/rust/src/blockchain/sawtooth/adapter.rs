@@ -14,9 +14,10 @@ use sawtooth_sdk::messages::transaction::TransactionHeader;
 use sha2::{Sha512, Digest};
 use sawtooth_sdk::messages::batch::{Batch, BatchHeader, BatchList};
 use crate::blockchain::sawtooth::{
+    addressing,
+    AddressSpace,
     FAMILY_NAME,
     FAMILY_VERSION,
-    get_address_prefix
 };
 use sawtooth_sdk::messages::transaction::Transaction;
 use crate::blockchain::MerkleProof;
@@ -51,10 +52,16 @@ impl SawtoothAdapterImpl {
     }
 
     fn calculate_address(&self, property_id: &str) -> String {
-        let mut hasher = Sha512::new();
-        hasher.update(property_id.as_bytes());
-        let hash = hex::encode(hasher.finalize());
-        format!("{}{}", get_address_prefix(), &hash[..64])
+        addressing::property_address(property_id)
+    }
+
+    /// Inputs/outputs for a payload: the property address when the payload
+    /// names one, otherwise the whole property sub-namespace.
+    fn payload_addresses(&self, payload: &TransactionPayload) -> Vec<String> {
+        match payload.data.get("property_id").and_then(|id| id.as_str()) {
+            Some(property_id) => vec![self.calculate_address(property_id)],
+            None => vec![AddressSpace::Property.prefix()],
+        }
     }
 
     async fn create_batch(&self, transaction: Transaction) -> Result<Batch, Error> {
@@ -145,9 +152,9 @@ impl BlockchainAdapter for SawtoothAdapterImpl {
             .map_err(|e| Error::Serialization(e.to_string()))?;
         header.set_payload_sha512(hex::encode(Sha512::digest(&payload_bytes)));
 
-        let address = self.calculate_address(&Uuid::new_v4().to_string());
-        header.set_inputs(protobuf::RepeatedField::from_vec(vec![address.clone()]));
-        header.set_outputs(protobuf::RepeatedField::from_vec(vec![address]));
+        let addresses = self.payload_addresses(&payload);
+        header.set_inputs(protobuf::RepeatedField::from_vec(addresses.clone()));
+        header.set_outputs(protobuf::RepeatedField::from_vec(addresses));
 
         let header_bytes = header.write_to_bytes()
             .map_err(|e| Error::Serialization(e.to_string()))?;
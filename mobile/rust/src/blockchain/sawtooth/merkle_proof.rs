@@ -1,4 +1,5 @@
 use crate::error::Error;
+use handreceipt_protocol::merkle::BatchInclusionProof;
 use sha2::{Sha512, Digest};
 use std::collections::HashMap;
 
@@ -65,6 +66,24 @@ impl MerkleProofVerifier {
         }
     }
 
+    /// Verifies a transfer proof from `GET /transfers/{id}/proof` without
+    /// network access. The batch header must be signed by
+    /// `trusted_batcher_key`, its transaction ids must hash to the proof's
+    /// root and the transaction id must hash to the proof's leaf.
+    pub fn verify_batch_inclusion(&self, inclusion: &BatchInclusionProof, trusted_batcher_key: &str) -> Consistency {
+        if inclusion.verify(trusted_batcher_key) {
+            Consistency::Valid
+        } else {
+            Consistency::Invalid
+        }
+    }
+
+    /// Like `verify_batch_inclusion`, for the raw JSON response body.
+    pub fn verify_batch_inclusion_json(&self, json: &str, trusted_batcher_key: &str) -> Result<Consistency, Error> {
+        let inclusion: BatchInclusionProof = serde_json::from_str(json)?;
+        Ok(self.verify_batch_inclusion(&inclusion, trusted_batcher_key))
+    }
+
     pub fn add_known_root(&mut self, root_hash: String, valid_hashes: Vec<String>) {
         self.known_roots.insert(root_hash, valid_hashes);
     }
@@ -127,6 +146,59 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_batch_inclusion_from_api_response() -> Result<(), Error> {
+        use handreceipt_protocol::merkle::{hash_leaf, prove, tree_levels, BatchHeader};
+        use prost::Message as _;
+        use sawtooth_sdk::signing::{create_context, secp256k1::Secp256k1PrivateKey, Signer};
+
+        let context = create_context("secp256k1").unwrap();
+        let private_key = Secp256k1PrivateKey::from_hex(
+            "2f1e7b7a130d7ba9da0068b3bb0ba1d79e7e77110302c9f746c3c2a63fe40088",
+        )
+        .unwrap();
+        let signer = Signer::new(&*context, &private_key);
+        let batcher = signer.get_public_key().unwrap().as_hex();
+
+        let ids = vec!["txn-a".to_string(), "txn-b".to_string(), "txn-c".to_string()];
+        let header = BatchHeader {
+            signer_public_key: batcher.clone(),
+            transaction_ids: ids.clone(),
+        };
+        let levels = tree_levels(ids.iter().map(|id| hash_leaf(id.as_bytes())).collect());
+        let response = serde_json::json!({
+            "transfer_id": 42,
+            "batch_id": signer.sign(&header.encode_to_vec()).unwrap(),
+            "transaction_id": "txn-c",
+            "batcher_public_key": batcher,
+            "transaction_ids": ids,
+            "proof": prove(&levels, 2).unwrap(),
+        });
+
+        let verifier = MerkleProofVerifier::new();
+        assert_eq!(
+            verifier.verify_batch_inclusion_json(&response.to_string(), &batcher)?,
+            Consistency::Valid
+        );
+
+        let mut forged = response.clone();
+        forged["transaction_id"] = "txn-d".into();
+        assert_eq!(
+            verifier.verify_batch_inclusion_json(&forged.to_string(), &batcher)?,
+            Consistency::Invalid
+        );
+
+        let mut unsigned = response.clone();
+        unsigned["transaction_ids"] = serde_json::json!(["txn-c"]);
+        unsigned["proof"] = serde_json::to_value(prove(&tree_levels(vec![hash_leaf(b"txn-c")]), 0).unwrap())?;
+        assert_eq!(
+            verifier.verify_batch_inclusion_json(&unsigned.to_string(), &batcher)?,
+            Consistency::Invalid
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_state_update_verification() -> Result<(), Error> {
         let mut verifier = MerkleProofVerifier::new();
@@ -1,7 +1,6 @@
 use crate::error::Error;
 use crate::blockchain::{BlockchainAdapter, TransactionPayload};
 use sawtooth_sdk::signing::Signer;
-use sha2::{Sha512, Digest};
 use std::sync::Arc;
 
 pub mod processor;
@@ -24,14 +23,11 @@ use sawtooth_sdk::messages::transaction::Transaction;
 use sawtooth_sdk::messages::batch::BatchHeader;
 use sawtooth_sdk::signing::secp256k1::Secp256k1PrivateKey;
 
-const FAMILY_NAME: &str = "handreceipt";
-const FAMILY_VERSION: &str = "1.0";
-const NAMESPACE: &str = "handreceipt";
+pub use handreceipt_protocol::addressing::{self, AddressSpace, FAMILY_NAME, FAMILY_VERSION};
 
+/// Family namespace prefix shared with the backend processor.
 pub fn get_address_prefix() -> String {
-    let mut hasher = Sha512::new();
-    hasher.update(FAMILY_NAME.as_bytes());
-    hex::encode(&hasher.finalize())[..6].to_string()
+    addressing::namespace_prefix()
 }
 
 #[derive(Debug, Clone)]
@@ -53,12 +49,7 @@ impl Default for TransactionFamily {
 
 impl TransactionFamily {
     pub fn calculate_address(&self, name: &str) -> String {
-        let mut hasher = Sha512::new();
-        hasher.update(name.as_bytes());
-        format!("{}{}", 
-            self.prefix,
-            hex::encode(&hasher.finalize())[..64].to_string()
-        )
+        addressing::property_address(name)
     }
 }
 
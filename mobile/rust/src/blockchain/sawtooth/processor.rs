@@ -1,12 +1,9 @@
 use sawtooth_sdk::messages::processor::TpProcessRequest;
 use sawtooth_sdk::processor::handler::{ApplyError, ContextError, TransactionContext, TransactionHandler};
 use serde::{Deserialize, Serialize};
-use sha2::{Sha512, Digest};
+use handreceipt_protocol::addressing::{self, FAMILY_NAME, FAMILY_VERSION};
 use crate::error::Error;
 
-const FAMILY_NAME: &str = "handreceipt";
-const FAMILY_VERSION: &str = "1.0";
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PropertyState {
     pub owner_public_key: String,
@@ -64,7 +61,7 @@ pub struct HandReceiptTransactionHandler {
 
 impl HandReceiptTransactionHandler {
     pub fn new() -> Self {
-        let namespace = Self::compute_namespace(FAMILY_NAME);
+        let namespace = addressing::namespace_prefix();
         Self {
             family_name: String::from(FAMILY_NAME),
             version: String::from(FAMILY_VERSION),
@@ -72,21 +69,8 @@ impl HandReceiptTransactionHandler {
         }
     }
 
-    fn compute_namespace(family_name: &str) -> String {
-        let mut hasher = Sha512::new();
-        hasher.update(family_name.as_bytes());
-        hex::encode(&hasher.finalize())[..6].to_string()
-    }
-
     fn get_property_address(&self, property_id: &str) -> String {
-        let mut hasher = Sha512::new();
-        hasher.update(property_id.as_bytes());
-        let hash = hex::encode(hasher.finalize());
-        let address = format!("{}{}", 
-            self.namespaces[0],
-            &hash[..64]
-        );
-        address
+        addressing::property_address(property_id)
     }
 
     fn verify_transfer(
@@ -315,6 +299,18 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_property_address_matches_backend() {
+        let handler = HandReceiptTransactionHandler::new();
+
+        // Same golden vector the backend processor is pinned to
+        assert_eq!(
+            handler.get_property_address("property-1"),
+            "a0779e00e0ddb57859914a15b77c4e69b6a6ce7ae62ca16a140d1b7924d80edcd85713"
+        );
+        assert_eq!(handler.namespaces(), vec!["a0779e".to_string()]);
+    }
+
     #[test]
     fn test_unauthorized_transfer() -> Result<(), Error> {
         let handler = HandReceiptTransactionHandler::new();
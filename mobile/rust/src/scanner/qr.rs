@@ -1,7 +1,7 @@
 use std::sync::Arc;
 use async_trait::async_trait;
 use image::GrayImage;
-use quircs::Quirc;
+use handreceipt_protocol::scan::{decode_qr_codes, ScanError};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
@@ -43,19 +43,11 @@ impl QRScanner {
     }
 
     fn decode_qr(image: &GrayImage) -> Result<String> {
-        let mut quirc = Quirc::new();
-        let codes = quirc.identify(image.width() as usize, image.height() as usize, &image);
-
-        for code in codes {
+        for code in decode_qr_codes(image) {
             match code {
-                Ok(code) => {
-                    if let Ok(decoded) = code.decode() {
-                        if let Ok(content) = String::from_utf8(decoded.payload) {
-                            return Ok(content);
-                        }
-                    }
-                }
-                Err(e) => return Err(Error::Scanner(format!("QR decode error: {}", e))),
+                Ok(content) => return Ok(content),
+                Err(ScanError::Extract(e)) => return Err(Error::Scanner(format!("QR decode error: {}", e))),
+                Err(_) => continue,
             }
         }
 
@@ -0,0 +1,218 @@
+//! Sawtooth state addressing for the `handreceipt` transaction family.
+//!
+//! Every address is 70 hex characters:
+//!
+//! ```text
+//! | namespace prefix (6) | sub-namespace (2) | sha512(key)[..62] |
+//! ```
+//!
+//! The namespace prefix is the first 6 hex characters of `sha512(FAMILY_NAME)`.
+//! The sub-namespace separates property records, signer identities and audit
+//! records so they can never collide and can be scanned independently.
+
+use sha2::{Digest, Sha256, Sha512};
+
+/// Transaction family name registered with the validator.
+pub const FAMILY_NAME: &str = "handreceipt";
+
+/// Transaction family version registered with the validator.
+pub const FAMILY_VERSION: &str = "1.0";
+
+/// Length of the family namespace prefix in hex characters.
+pub const NAMESPACE_PREFIX_LEN: usize = 6;
+
+/// Length of the sub-namespace code in hex characters.
+pub const SUB_NAMESPACE_LEN: usize = 2;
+
+/// Length of a full state address in hex characters.
+pub const ADDRESS_LEN: usize = 70;
+
+const KEY_HASH_LEN: usize = ADDRESS_LEN - NAMESPACE_PREFIX_LEN - SUB_NAMESPACE_LEN;
+
+const SETTINGS_NAMESPACE: &str = "000000";
+
+const SETTING_KEY_PARTS: usize = 4;
+
+/// Sub-namespaces within the family namespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AddressSpace {
+    Property,
+    Identity,
+    Audit,
+}
+
+impl AddressSpace {
+    pub const ALL: [AddressSpace; 3] = [
+        AddressSpace::Property,
+        AddressSpace::Identity,
+        AddressSpace::Audit,
+    ];
+
+    /// Two hex character code placed directly after the namespace prefix.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AddressSpace::Property => "00",
+            AddressSpace::Identity => "01",
+            AddressSpace::Audit => "02",
+        }
+    }
+
+    /// Address prefix (namespace + sub-namespace) covering this space.
+    pub fn prefix(&self) -> String {
+        format!("{}{}", namespace_prefix(), self.code())
+    }
+
+    /// Builds the full address of `key` within this space.
+    pub fn address(&self, key: &str) -> String {
+        let hash = hex::encode(Sha512::digest(key.as_bytes()));
+        format!("{}{}", self.prefix(), &hash[..KEY_HASH_LEN])
+    }
+
+    fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|space| space.code() == code)
+    }
+}
+
+/// Family namespace prefix: first 6 hex characters of `sha512(FAMILY_NAME)`.
+pub fn namespace_prefix() -> String {
+    let hash = hex::encode(Sha512::digest(FAMILY_NAME.as_bytes()));
+    hash[..NAMESPACE_PREFIX_LEN].to_string()
+}
+
+/// State address of a property record.
+pub fn property_address(property_id: &str) -> String {
+    AddressSpace::Property.address(property_id)
+}
+
+/// State address of a signer identity, keyed by hex encoded public key.
+pub fn identity_address(public_key: &str) -> String {
+    AddressSpace::Identity.address(public_key)
+}
+
+/// State address of an audit record.
+pub fn audit_address(record_id: &str) -> String {
+    AddressSpace::Audit.address(record_id)
+}
+
+/// State address of the revocation list anchor published by `issuer`.
+pub fn revocation_anchor_address(issuer: &str) -> String {
+    audit_address(&format!("revocations/{}", issuer))
+}
+
+/// State address of a `sawtooth_settings` entry.
+///
+/// Settings live outside the family namespace: `000000` followed by the first
+/// 16 hex characters of `sha256` of each of up to four dot separated key
+/// parts, padded with empty parts.
+pub fn setting_address(key: &str) -> String {
+    let mut parts: Vec<&str> = key.splitn(SETTING_KEY_PARTS, '.').collect();
+    parts.resize(SETTING_KEY_PARTS, "");
+
+    let mut address = String::from(SETTINGS_NAMESPACE);
+    for part in parts {
+        address.push_str(&hex::encode(Sha256::digest(part.as_bytes()))[..16]);
+    }
+    address
+}
+
+/// Returns true if `address` is a well-formed address in this family.
+pub fn is_valid_address(address: &str) -> bool {
+    address_space(address).is_some()
+}
+
+/// Returns the sub-namespace `address` belongs to, if it is a valid address.
+pub fn address_space(address: &str) -> Option<AddressSpace> {
+    if address.len() != ADDRESS_LEN
+        || !address
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+        || !address.starts_with(&namespace_prefix())
+    {
+        return None;
+    }
+
+    let code = &address[NAMESPACE_PREFIX_LEN..NAMESPACE_PREFIX_LEN + SUB_NAMESPACE_LEN];
+    AddressSpace::from_code(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Golden vectors computed independently with Python's hashlib.sha512.
+    // Any change to these values is a breaking change to on-chain state.
+
+    #[test]
+    fn test_namespace_prefix() {
+        assert_eq!(namespace_prefix(), "a0779e");
+    }
+
+    #[test]
+    fn test_property_address_golden_vectors() {
+        assert_eq!(
+            property_address("property-1"),
+            "a0779e00e0ddb57859914a15b77c4e69b6a6ce7ae62ca16a140d1b7924d80edcd85713"
+        );
+        assert_eq!(
+            property_address("550e8400-e29b-41d4-a716-446655440000"),
+            "a0779e00430d932ee5602547859d715df1283092beb94f0bd40fbe40b4d24e8bbad9b1"
+        );
+    }
+
+    #[test]
+    fn test_identity_address_golden_vector() {
+        assert_eq!(
+            identity_address("02a1633cafcc01ebfb6d78e39f687a1f0995c62fc95f51ead10a02ee0be551b5dc"),
+            "a0779e01c70314fe8c80bdaecda3be0f5a10f1c6f7c3049a95900b032ef43e32b262bf"
+        );
+    }
+
+    #[test]
+    fn test_audit_address_golden_vector() {
+        assert_eq!(
+            audit_address("audit-1"),
+            "a0779e02310f227299d5210120af6b5c4efd0ca479fd419c0844607c22fd5d0a882a27"
+        );
+    }
+
+    #[test]
+    fn test_revocation_anchor_address() {
+        let address = revocation_anchor_address("brigade-ca");
+        assert_eq!(address, audit_address("revocations/brigade-ca"));
+        assert_eq!(address_space(&address), Some(AddressSpace::Audit));
+    }
+
+    #[test]
+    fn test_setting_address_golden_vectors() {
+        // Published by the Sawtooth settings family specification
+        assert_eq!(
+            setting_address("sawtooth.settings.vote.authorized_keys"),
+            "000000a87cb5eafdcca6a8cde0fb0dec1400c5ab274474a6aa82c12840f169a04216b7"
+        );
+        assert_eq!(
+            setting_address("handreceipt.transfer.approvers"),
+            "000000d7325b9cb8495f1427f576cafbb263ed45522a36c3195178e3b0c44298fc1c14"
+        );
+        assert!(!is_valid_address(&setting_address("handreceipt.transfer.approvers")));
+    }
+
+    #[test]
+    fn test_address_space_round_trip() {
+        for space in AddressSpace::ALL {
+            let address = space.address("key");
+            assert_eq!(address.len(), ADDRESS_LEN);
+            assert!(address.starts_with(&space.prefix()));
+            assert_eq!(address_space(&address), Some(space));
+        }
+    }
+
+    #[test]
+    fn test_invalid_addresses() {
+        let valid = property_address("property-1");
+        assert!(is_valid_address(&valid));
+        assert!(!is_valid_address(&valid[..69]));
+        assert!(!is_valid_address(&valid.to_uppercase()));
+        assert!(!is_valid_address(&format!("ffffff{}", &valid[6..])));
+        assert!(!is_valid_address(&format!("a0779eff{}", &valid[8..])));
+    }
+}
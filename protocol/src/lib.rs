@@ -0,0 +1,24 @@
+//! Protocol definitions shared by the HandReceipt backend and mobile clients.
+//!
+//! Anything that has to agree bit-for-bit between the transaction processor,
+//! the backend submitting transactions, and the mobile apps lives here.
+
+pub mod addressing;
+pub mod merkle;
+pub mod messages;
+pub mod qr;
+#[cfg(feature = "scan")]
+pub mod scan;
+pub mod signing;
+
+pub use addressing::{
+    address_space, audit_address, identity_address, is_valid_address, namespace_prefix,
+    property_address, revocation_anchor_address, setting_address, AddressSpace, ADDRESS_LEN, FAMILY_NAME, FAMILY_VERSION,
+    NAMESPACE_PREFIX_LEN,
+};
+pub use merkle::{BatchInclusionProof, MerkleProof, Side};
+pub use messages::{PayloadEncoding, FAMILY_VERSION_PROTOBUF, SCHEMA_VERSION};
+pub use qr::{key_id, QrError, QrKeyring, QrPayload, SignedQr, QR_FORMAT_VERSION};
+#[cfg(feature = "scan")]
+pub use scan::{decode_qr_codes, ScanError};
+pub use signing::{transfer_digest, ApprovalPolicy, SignatureError, TransferRole, TransferSignature};
@@ -0,0 +1,327 @@
+//! Protobuf messages for the handreceipt transaction family.
+//!
+//! These are the prost encoding of `proto/handreceipt.proto`. Family version
+//! `1.0` carries serde JSON payloads and is still accepted by the processor
+//! while clients migrate; family version `2.0` carries these messages.
+
+use prost::Message;
+
+use crate::addressing::FAMILY_VERSION;
+
+/// Family version whose payloads are protobuf encoded.
+pub const FAMILY_VERSION_PROTOBUF: &str = "2.0";
+
+/// Schema version written into every protobuf payload and state entry.
+pub const SCHEMA_VERSION: u32 = 4;
+
+/// Payload encoding selected by the transaction's family version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadEncoding {
+    /// Legacy serde JSON, family version `1.0`.
+    Json,
+    /// Protobuf, family version `2.0`.
+    Protobuf,
+}
+
+impl PayloadEncoding {
+    pub fn from_family_version(version: &str) -> Option<Self> {
+        match version {
+            FAMILY_VERSION => Some(PayloadEncoding::Json),
+            FAMILY_VERSION_PROTOBUF => Some(PayloadEncoding::Protobuf),
+            _ => None,
+        }
+    }
+
+    pub fn family_version(&self) -> &'static str {
+        match self {
+            PayloadEncoding::Json => FAMILY_VERSION,
+            PayloadEncoding::Protobuf => FAMILY_VERSION_PROTOBUF,
+        }
+    }
+}
+
+/// Family versions a processor registers for.
+pub fn supported_family_versions() -> Vec<String> {
+    vec![
+        FAMILY_VERSION.to_string(),
+        FAMILY_VERSION_PROTOBUF.to_string(),
+    ]
+}
+
+/// Returns true if a state entry was written with the legacy JSON encoding.
+///
+/// Protobuf state always starts with the `schema_version` field tag (`0x08`),
+/// legacy JSON state always starts with `{`.
+pub fn is_legacy_json(bytes: &[u8]) -> bool {
+    bytes.first() == Some(&b'{')
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct PropertyMetadata {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub description: String,
+    #[prost(string, tag = "3")]
+    pub category: String,
+    #[prost(string, optional, tag = "4")]
+    pub serial_number: Option<String>,
+    #[prost(bool, tag = "5")]
+    pub is_sensitive_item: bool,
+    #[prost(int64, tag = "6")]
+    pub created_at: i64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct CreateProperty {
+    #[prost(string, tag = "1")]
+    pub property_id: String,
+    #[prost(string, tag = "2")]
+    pub initial_custodian: String,
+    #[prost(message, optional, tag = "3")]
+    pub metadata: Option<PropertyMetadata>,
+    #[prost(string, tag = "4")]
+    pub custodian_key: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct TransferSignature {
+    #[prost(string, tag = "1")]
+    pub role: String,
+    #[prost(string, tag = "2")]
+    pub public_key: String,
+    #[prost(string, tag = "3")]
+    pub signature: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct TransferProperty {
+    #[prost(string, tag = "1")]
+    pub property_id: String,
+    #[prost(string, tag = "2")]
+    pub to_custodian: String,
+    #[prost(string, tag = "3")]
+    pub transfer_id: String,
+    #[prost(message, repeated, tag = "4")]
+    pub signatures: Vec<TransferSignature>,
+    #[prost(int64, tag = "5")]
+    pub timestamp: i64,
+    #[prost(string, tag = "6")]
+    pub annex_digest: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct UpdateProperty {
+    #[prost(string, tag = "1")]
+    pub property_id: String,
+    #[prost(message, optional, tag = "2")]
+    pub metadata: Option<PropertyMetadata>,
+    #[prost(int64, tag = "3")]
+    pub updated_at: i64,
+    #[prost(string, tag = "4")]
+    pub status: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct DeleteProperty {
+    #[prost(string, tag = "1")]
+    pub property_id: String,
+}
+
+/// Sawtooth setting holding the hex public key of the certificate
+/// authority's ledger signer, the only signer allowed to anchor revocation
+/// lists.
+pub const REVOCATION_AUTHORITY_SETTING: &str = "handreceipt.revocation.authority";
+
+#[derive(Clone, PartialEq, Message)]
+pub struct RevocationAnchor {
+    #[prost(string, tag = "1")]
+    pub issuer: String,
+    #[prost(string, tag = "2")]
+    pub digest: String,
+    #[prost(uint32, tag = "3")]
+    pub revoked_count: u32,
+    #[prost(int64, tag = "4")]
+    pub updated_at: i64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct HandReceiptPayload {
+    #[prost(uint32, tag = "1")]
+    pub schema_version: u32,
+    #[prost(oneof = "hand_receipt_payload::Action", tags = "2, 3, 4, 5, 6")]
+    pub action: Option<hand_receipt_payload::Action>,
+}
+
+pub mod hand_receipt_payload {
+    use super::{CreateProperty, DeleteProperty, RevocationAnchor, TransferProperty, UpdateProperty};
+
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Action {
+        #[prost(message, tag = "2")]
+        Create(CreateProperty),
+        #[prost(message, tag = "3")]
+        Transfer(TransferProperty),
+        #[prost(message, tag = "4")]
+        Update(UpdateProperty),
+        #[prost(message, tag = "5")]
+        Delete(DeleteProperty),
+        #[prost(message, tag = "6")]
+        AnchorRevocations(RevocationAnchor),
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct TransferRecord {
+    #[prost(string, tag = "1")]
+    pub transfer_id: String,
+    #[prost(string, tag = "2")]
+    pub from_custodian: String,
+    #[prost(string, tag = "3")]
+    pub to_custodian: String,
+    #[prost(int64, tag = "4")]
+    pub timestamp: i64,
+    #[prost(string, tag = "5")]
+    pub status: String,
+    #[prost(string, repeated, tag = "6")]
+    pub signatures: Vec<String>,
+    #[prost(message, repeated, tag = "7")]
+    pub transfer_signatures: Vec<TransferSignature>,
+    #[prost(string, tag = "8")]
+    pub annex_digest: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct PropertyState {
+    #[prost(uint32, tag = "1")]
+    pub schema_version: u32,
+    #[prost(string, tag = "2")]
+    pub id: String,
+    #[prost(string, tag = "3")]
+    pub owner: String,
+    #[prost(string, tag = "4")]
+    pub custodian: String,
+    #[prost(string, tag = "5")]
+    pub status: String,
+    #[prost(int64, tag = "6")]
+    pub timestamp: i64,
+    #[prost(message, repeated, tag = "7")]
+    pub transfer_history: Vec<TransferRecord>,
+    #[prost(int64, tag = "8")]
+    pub last_updated: i64,
+    #[prost(string, tag = "9")]
+    pub custodian_key: String,
+    #[prost(bool, tag = "10")]
+    pub requires_approval: bool,
+    #[prost(message, optional, tag = "11")]
+    pub metadata: Option<PropertyMetadata>,
+}
+
+impl HandReceiptPayload {
+    pub fn new(action: hand_receipt_payload::Action) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            action: Some(action),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.encode_to_vec()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, prost::DecodeError> {
+        Self::decode(bytes)
+    }
+}
+
+impl RevocationAnchor {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.encode_to_vec()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, prost::DecodeError> {
+        Self::decode(bytes)
+    }
+}
+
+impl PropertyState {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.encode_to_vec()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, prost::DecodeError> {
+        Self::decode(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::hand_receipt_payload::Action;
+    use super::*;
+
+    #[test]
+    fn test_encoding_from_family_version() {
+        assert_eq!(PayloadEncoding::from_family_version("1.0"), Some(PayloadEncoding::Json));
+        assert_eq!(PayloadEncoding::from_family_version("2.0"), Some(PayloadEncoding::Protobuf));
+        assert_eq!(PayloadEncoding::from_family_version("3.0"), None);
+        assert_eq!(supported_family_versions(), vec!["1.0", "2.0"]);
+    }
+
+    #[test]
+    fn test_payload_golden_bytes() {
+        // 08 04          schema_version = 4
+        // 2a 04          field 5 (delete), length 4
+        //   0a 02 70 31  property_id = "p1"
+        let payload = HandReceiptPayload::new(Action::Delete(DeleteProperty {
+            property_id: "p1".to_string(),
+        }));
+        assert_eq!(hex::encode(payload.to_bytes()), "08042a040a027031");
+    }
+
+    #[test]
+    fn test_payload_round_trip() {
+        let payload = HandReceiptPayload::new(Action::Create(CreateProperty {
+            property_id: "property-1".to_string(),
+            initial_custodian: "custodian".to_string(),
+            metadata: Some(PropertyMetadata {
+                name: "M4 Carbine".to_string(),
+                description: "Rifle".to_string(),
+                category: "Weapon".to_string(),
+                serial_number: Some("W123".to_string()),
+                is_sensitive_item: true,
+                created_at: 1_700_000_000_000,
+            }),
+            custodian_key: String::new(),
+        }));
+
+        let bytes = payload.to_bytes();
+        assert_eq!(bytes, payload.clone().to_bytes());
+        assert_eq!(HandReceiptPayload::from_bytes(&bytes).unwrap(), payload);
+    }
+
+    #[test]
+    fn test_revocation_anchor_round_trip() {
+        let payload = HandReceiptPayload::new(Action::AnchorRevocations(RevocationAnchor {
+            issuer: "brigade-ca".to_string(),
+            digest: "ab".repeat(32),
+            revoked_count: 2,
+            updated_at: 1_700_000_000_000,
+        }));
+
+        let bytes = payload.to_bytes();
+        assert_eq!(HandReceiptPayload::from_bytes(&bytes).unwrap(), payload);
+    }
+
+    #[test]
+    fn test_state_detection() {
+        let state = PropertyState {
+            schema_version: SCHEMA_VERSION,
+            id: "property-1".to_string(),
+            ..Default::default()
+        };
+        let bytes = state.to_bytes();
+        assert!(!is_legacy_json(&bytes));
+        assert_eq!(PropertyState::from_bytes(&bytes).unwrap(), state);
+        assert!(is_legacy_json(br#"{"id":"property-1"}"#));
+    }
+}
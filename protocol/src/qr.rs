@@ -0,0 +1,339 @@
+//! Signed property QR codes.
+//!
+//! A QR code carries a property reference signed by a key the server trusts.
+//! The payload is a positional CBOR array, base45 encoded so it fits the QR
+//! alphanumeric mode, behind a short versioned prefix:
+//!
+//! ```text
+//! HR1:<base45(cbor([version, kid, id, property_id, custodian_id, issued_at, metadata, signature]))>
+//! ```
+//!
+//! The signer's public key is never embedded. Instead the `kid` names it, and
+//! verification only succeeds if the kid resolves to a key in the verifier's
+//! [`QrKeyring`] that has not been revoked.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use ciborium::value::Value;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+
+/// Current QR payload version.
+pub const QR_FORMAT_VERSION: u8 = 1;
+
+/// Prefix of every encoded QR payload. Only characters from the QR
+/// alphanumeric set are used so the whole code stays in that mode.
+pub const QR_PREFIX: &str = "HR1:";
+
+/// Domain separation tag for QR digests.
+pub const QR_DIGEST_DOMAIN: &[u8] = b"handreceipt/qr/v1";
+
+/// Number of elements in the encoded CBOR array.
+const QR_FIELDS: usize = 8;
+
+/// Key identifier: the first 8 bytes of the SHA-256 of the raw public key,
+/// hex encoded.
+pub fn key_id(key: &VerifyingKey) -> String {
+    let hash = Sha256::digest(key.to_bytes());
+    hex::encode(&hash[..8])
+}
+
+/// The signed contents of a QR code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrPayload {
+    pub version: u8,
+    pub kid: String,
+    pub id: String,
+    pub property_id: i64,
+    pub custodian_id: String,
+    /// Issue time in milliseconds since the Unix epoch.
+    pub issued_at: i64,
+    /// Canonical JSON of the property metadata shown to the scanner.
+    pub metadata: String,
+}
+
+impl QrPayload {
+    /// Canonical digest over every field. Strings are length prefixed
+    /// (big-endian `u32`) and integers are fixed width, so no two payloads
+    /// share a byte string.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(QR_DIGEST_DOMAIN);
+        hasher.update([self.version]);
+        for field in [&self.kid, &self.id] {
+            hasher.update((field.len() as u32).to_be_bytes());
+            hasher.update(field.as_bytes());
+        }
+        hasher.update(self.property_id.to_be_bytes());
+        hasher.update((self.custodian_id.len() as u32).to_be_bytes());
+        hasher.update(self.custodian_id.as_bytes());
+        hasher.update(self.issued_at.to_be_bytes());
+        hasher.update((self.metadata.len() as u32).to_be_bytes());
+        hasher.update(self.metadata.as_bytes());
+        hasher.finalize().into()
+    }
+}
+
+/// A payload together with the signature over its digest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedQr {
+    pub payload: QrPayload,
+    pub signature: [u8; 64],
+}
+
+impl SignedQr {
+    /// Signs `payload` with `key`, stamping the current version and the
+    /// key's identifier first.
+    pub fn sign(mut payload: QrPayload, key: &SigningKey) -> Self {
+        payload.version = QR_FORMAT_VERSION;
+        payload.kid = key_id(&key.verifying_key());
+        let signature = key.sign(&payload.digest()).to_bytes();
+        Self { payload, signature }
+    }
+
+    /// Encodes the signed payload as the string placed in the QR code.
+    pub fn encode(&self) -> Result<String, QrError> {
+        let payload = &self.payload;
+        let value = Value::Array(vec![
+            Value::Integer(payload.version.into()),
+            Value::Text(payload.kid.clone()),
+            Value::Text(payload.id.clone()),
+            Value::Integer(payload.property_id.into()),
+            Value::Text(payload.custodian_id.clone()),
+            Value::Integer(payload.issued_at.into()),
+            Value::Text(payload.metadata.clone()),
+            Value::Bytes(self.signature.to_vec()),
+        ]);
+
+        let mut cbor = Vec::new();
+        ciborium::ser::into_writer(&value, &mut cbor)
+            .map_err(|e| QrError::Encoding(e.to_string()))?;
+        Ok(format!("{}{}", QR_PREFIX, base45::encode(&cbor)))
+    }
+
+    /// Parses a scanned QR string. This only checks the format; call
+    /// [`SignedQr::verify`] before trusting the contents.
+    pub fn decode(encoded: &str) -> Result<Self, QrError> {
+        let body = match encoded.trim().strip_prefix(QR_PREFIX) {
+            Some(body) => body,
+            None if encoded.starts_with("HR") => return Err(QrError::UnsupportedVersion),
+            None => return Err(QrError::Encoding("Missing QR payload prefix".to_string())),
+        };
+        let cbor = base45::decode(body).map_err(|e| QrError::Encoding(format!("{:?}", e)))?;
+        let value: Value = ciborium::de::from_reader(cbor.as_slice())
+            .map_err(|e| QrError::Encoding(e.to_string()))?;
+
+        let fields = match value {
+            Value::Array(fields) if fields.len() == QR_FIELDS => fields,
+            _ => return Err(QrError::Encoding("Malformed QR payload".to_string())),
+        };
+        let mut fields = fields.into_iter();
+        let mut next = || fields.next().expect("length checked above");
+
+        let version = u8::try_from(integer(next())?).map_err(|_| QrError::UnsupportedVersion)?;
+        if version != QR_FORMAT_VERSION {
+            return Err(QrError::UnsupportedVersion);
+        }
+        let payload = QrPayload {
+            version,
+            kid: text(next())?,
+            id: text(next())?,
+            property_id: integer(next())?,
+            custodian_id: text(next())?,
+            issued_at: integer(next())?,
+            metadata: text(next())?,
+        };
+        let signature = match next() {
+            Value::Bytes(bytes) => bytes
+                .try_into()
+                .map_err(|_| QrError::Encoding("Invalid signature length".to_string()))?,
+            _ => return Err(QrError::Encoding("Malformed QR signature".to_string())),
+        };
+
+        Ok(Self { payload, signature })
+    }
+
+    /// Checks that the kid names a trusted, unrevoked key and that the
+    /// signature covers the payload.
+    pub fn verify(&self, keyring: &QrKeyring) -> Result<(), QrError> {
+        let key = keyring.resolve(&self.payload.kid)?;
+        key.verify(&self.payload.digest(), &Signature::from_bytes(&self.signature))
+            .map_err(|_| QrError::InvalidSignature)
+    }
+}
+
+fn integer(value: Value) -> Result<i64, QrError> {
+    match value {
+        Value::Integer(integer) => {
+            i64::try_from(integer).map_err(|_| QrError::Encoding("Integer out of range".to_string()))
+        }
+        _ => Err(QrError::Encoding("Expected an integer".to_string())),
+    }
+}
+
+fn text(value: Value) -> Result<String, QrError> {
+    match value {
+        Value::Text(text) => Ok(text),
+        _ => Err(QrError::Encoding("Expected a string".to_string())),
+    }
+}
+
+/// Public keys the verifier accepts QR codes from, by kid.
+#[derive(Debug, Clone, Default)]
+pub struct QrKeyring {
+    trusted: HashMap<String, VerifyingKey>,
+    revoked: HashSet<String>,
+}
+
+impl QrKeyring {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trusts `key` and returns its kid. A revoked key stays revoked.
+    pub fn trust(&mut self, key: VerifyingKey) -> String {
+        let kid = key_id(&key);
+        self.trusted.insert(kid.clone(), key);
+        kid
+    }
+
+    /// Revokes `kid`. Codes signed by it are rejected from now on, even if
+    /// the key is trusted again.
+    pub fn revoke(&mut self, kid: &str) {
+        self.revoked.insert(kid.to_string());
+    }
+
+    pub fn is_revoked(&self, kid: &str) -> bool {
+        self.revoked.contains(kid)
+    }
+
+    /// Returns the key for `kid` if it is trusted and not revoked.
+    pub fn resolve(&self, kid: &str) -> Result<&VerifyingKey, QrError> {
+        if self.is_revoked(kid) {
+            return Err(QrError::RevokedKey(kid.to_string()));
+        }
+        self.trusted
+            .get(kid)
+            .ok_or_else(|| QrError::UnknownKey(kid.to_string()))
+    }
+}
+
+/// Why a QR payload was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QrError {
+    Encoding(String),
+    UnsupportedVersion,
+    UnknownKey(String),
+    RevokedKey(String),
+    InvalidSignature,
+}
+
+impl fmt::Display for QrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QrError::Encoding(msg) => write!(f, "Invalid QR payload: {}", msg),
+            QrError::UnsupportedVersion => write!(f, "Unsupported QR payload version"),
+            QrError::UnknownKey(kid) => write!(f, "QR code signed by unknown key {}", kid),
+            QrError::RevokedKey(kid) => write!(f, "QR code signed by revoked key {}", kid),
+            QrError::InvalidSignature => write!(f, "Invalid QR code signature"),
+        }
+    }
+}
+
+impl std::error::Error for QrError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn payload() -> QrPayload {
+        QrPayload {
+            version: 0,
+            kid: String::new(),
+            id: "2f1c7c9e-4d0b-4f5e-9b1a-6c0d8f3e2a71".to_string(),
+            property_id: 42,
+            custodian_id: "custodian-1".to_string(),
+            issued_at: 1_700_000_000_000,
+            metadata: r#"{"name":"Rifle","serial_number":"W123"}"#.to_string(),
+        }
+    }
+
+    fn keyring(seed: u8) -> QrKeyring {
+        let mut keyring = QrKeyring::new();
+        keyring.trust(key(seed).verifying_key());
+        keyring
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let signed = SignedQr::sign(payload(), &key(1));
+        assert_eq!(signed.payload.version, QR_FORMAT_VERSION);
+        assert_eq!(signed.payload.kid, key_id(&key(1).verifying_key()));
+
+        let encoded = signed.encode().unwrap();
+        assert!(encoded.starts_with(QR_PREFIX));
+        assert!(encoded
+            .chars()
+            .all(|c| c.is_ascii_digit() || c.is_ascii_uppercase() || " $%*+-./:".contains(c)));
+
+        let decoded = SignedQr::decode(&encoded).unwrap();
+        assert_eq!(decoded, signed);
+        assert!(decoded.verify(&keyring(1)).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_unknown_and_revoked_keys() {
+        let signed = SignedQr::sign(payload(), &key(2));
+        let kid = signed.payload.kid.clone();
+        assert_eq!(signed.verify(&keyring(1)), Err(QrError::UnknownKey(kid.clone())));
+
+        let mut keyring = keyring(2);
+        keyring.revoke(&kid);
+        assert_eq!(signed.verify(&keyring), Err(QrError::RevokedKey(kid.clone())));
+        keyring.trust(key(2).verifying_key());
+        assert!(keyring.is_revoked(&kid));
+    }
+
+    #[test]
+    fn test_signature_covers_every_field() {
+        let signed = SignedQr::sign(payload(), &key(1));
+        let keyring = keyring(1);
+
+        let tampered: [fn(&mut QrPayload); 5] = [
+            |p| p.id.push('0'),
+            |p| p.property_id += 1,
+            |p| p.custodian_id = "custodian-2".to_string(),
+            |p| p.issued_at += 1,
+            |p| p.metadata = r#"{"name":"Rifle","serial_number":"W124"}"#.to_string(),
+        ];
+        for tamper in tampered {
+            let mut forged = signed.clone();
+            tamper(&mut forged.payload);
+            assert_eq!(forged.verify(&keyring), Err(QrError::InvalidSignature));
+        }
+    }
+
+    #[test]
+    fn test_kid_cannot_be_swapped() {
+        let mut forged = SignedQr::sign(payload(), &key(9));
+        forged.payload.kid = key_id(&key(1).verifying_key());
+        assert_eq!(forged.verify(&keyring(1)), Err(QrError::InvalidSignature));
+    }
+
+    #[test]
+    fn test_rejects_malformed_and_unknown_versions() {
+        assert!(matches!(SignedQr::decode("not a qr"), Err(QrError::Encoding(_))));
+        assert!(matches!(SignedQr::decode("HR1:!!"), Err(QrError::Encoding(_))));
+        assert_eq!(SignedQr::decode("HR2:00"), Err(QrError::UnsupportedVersion));
+
+        let mut signed = SignedQr::sign(payload(), &key(1));
+        signed.payload.version = 2;
+        let encoded = signed.encode().unwrap();
+        assert_eq!(SignedQr::decode(&encoded), Err(QrError::UnsupportedVersion));
+    }
+}
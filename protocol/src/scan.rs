@@ -0,0 +1,99 @@
+//! Finding and reading QR codes in images.
+//!
+//! Shared by the mobile scanner and the backend's image upload endpoint so
+//! both read a photographed label the same way. Only available with the
+//! `scan` feature, which pulls in `quircs` and `image`.
+
+use std::fmt;
+
+use image::GrayImage;
+use quircs::Quirc;
+
+/// Why a symbol found in an image could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanError {
+    /// A finder pattern was found but the grid could not be sampled.
+    Extract(String),
+    /// The grid was sampled but error correction failed.
+    Decode(String),
+    /// The symbol decoded to bytes that are not UTF-8.
+    NotUtf8,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::Extract(msg) => write!(f, "QR code could not be extracted: {}", msg),
+            ScanError::Decode(msg) => write!(f, "QR code could not be decoded: {}", msg),
+            ScanError::NotUtf8 => write!(f, "QR code content is not UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for ScanError {}
+
+/// Reads every QR code in `image`, in the order they are found. A symbol
+/// that is detected but unreadable yields an error in its place, so callers
+/// can tell "no code" apart from "a damaged code".
+pub fn decode_qr_codes(image: &GrayImage) -> Vec<Result<String, ScanError>> {
+    let mut quirc = Quirc::new();
+    quirc
+        .identify(image.width() as usize, image.height() as usize, image.as_raw())
+        .map(|code| {
+            let code = code.map_err(|e| ScanError::Extract(e.to_string()))?;
+            let data = code.decode().map_err(|e| ScanError::Decode(e.to_string()))?;
+            String::from_utf8(data.payload).map_err(|_| ScanError::NotUtf8)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+    use qrcode::{Color, QrCode};
+
+    const SCALE: u32 = 4;
+    const QUIET_ZONE: u32 = 4;
+
+    /// Draws `content` as a QR code with its top-left module at `origin`.
+    fn draw(image: &mut GrayImage, origin: (u32, u32), content: &str) {
+        let code = QrCode::new(content.as_bytes()).unwrap();
+        let width = code.width() as u32;
+        for (index, color) in code.to_colors().into_iter().enumerate() {
+            if color != Color::Dark {
+                continue;
+            }
+            let (x, y) = (index as u32 % width, index as u32 / width);
+            for dy in 0..SCALE {
+                for dx in 0..SCALE {
+                    image.put_pixel(origin.0 + x * SCALE + dx, origin.1 + y * SCALE + dy, Luma([0]));
+                }
+            }
+        }
+    }
+
+    fn blank(width: u32, height: u32) -> GrayImage {
+        GrayImage::from_pixel(width, height, Luma([255]))
+    }
+
+    #[test]
+    fn test_decodes_every_code_in_image() {
+        let mut image = blank(600, 300);
+        let margin = QUIET_ZONE * SCALE;
+        draw(&mut image, (margin, margin), "HR1:FIRST");
+        draw(&mut image, (300 + margin, margin), "HR1:SECOND");
+
+        let mut decoded: Vec<String> = decode_qr_codes(&image)
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        decoded.sort();
+        assert_eq!(decoded, vec!["HR1:FIRST".to_string(), "HR1:SECOND".to_string()]);
+    }
+
+    #[test]
+    fn test_blank_image_has_no_codes() {
+        assert!(decode_qr_codes(&blank(200, 200)).is_empty());
+    }
+}
@@ -0,0 +1,370 @@
+//! Multi-party transfer signatures.
+//!
+//! A custody transfer is signed with Ed25519 by the releasing holder, the
+//! receiving holder and, where the approval policy asks for one, an approver.
+//! Every party signs the same canonical digest, so signatures can be gathered
+//! on different devices in any order and checked by the transaction processor
+//! against the state it is about to change.
+
+use std::fmt;
+use std::str::FromStr;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::messages;
+
+/// Domain separation tag for transfer digests. Bump the suffix if the
+/// digest layout ever changes.
+pub const TRANSFER_DIGEST_DOMAIN: &[u8] = b"handreceipt/transfer/v2";
+
+/// Sawtooth setting listing the hex public keys allowed to approve
+/// transfers, comma separated. The processor also accepts property updates
+/// and deletes signed by a listed key, so the backend's transaction signer
+/// belongs here too.
+pub const APPROVERS_SETTING: &str = "handreceipt.transfer.approvers";
+
+/// The part a signer plays in a transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferRole {
+    Releasing,
+    Receiving,
+    Approver,
+}
+
+impl TransferRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransferRole::Releasing => "releasing",
+            TransferRole::Receiving => "receiving",
+            TransferRole::Approver => "approver",
+        }
+    }
+}
+
+impl fmt::Display for TransferRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TransferRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "releasing" => Ok(TransferRole::Releasing),
+            "receiving" => Ok(TransferRole::Receiving),
+            "approver" => Ok(TransferRole::Approver),
+            _ => Err(format!("Invalid transfer role: {}", s)),
+        }
+    }
+}
+
+/// Canonical digest signed by every party to a transfer.
+///
+/// `annex_digest` is the hex SHA-256 of the shortage annex the receiver
+/// signs for along with the item, or empty when it is short nothing. Each
+/// field is length prefixed (big-endian `u32`) so that no two distinct
+/// transfers can produce the same byte string.
+pub fn transfer_digest(
+    property_id: &str,
+    from_custodian: &str,
+    to_custodian: &str,
+    transfer_id: &str,
+    annex_digest: &str,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(TRANSFER_DIGEST_DOMAIN);
+    for field in [property_id, from_custodian, to_custodian, transfer_id, annex_digest] {
+        hasher.update((field.len() as u32).to_be_bytes());
+        hasher.update(field.as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+/// One party's signature over a transfer digest. Keys and signatures are
+/// hex encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferSignature {
+    pub role: TransferRole,
+    pub public_key: String,
+    pub signature: String,
+}
+
+impl TransferSignature {
+    pub fn sign(role: TransferRole, key: &SigningKey, digest: &[u8; 32]) -> Self {
+        Self {
+            role,
+            public_key: hex::encode(key.verifying_key().to_bytes()),
+            signature: hex::encode(key.sign(digest).to_bytes()),
+        }
+    }
+
+    /// Checks the signature against `digest`.
+    pub fn verify(&self, digest: &[u8; 32]) -> Result<(), SignatureError> {
+        let invalid = || SignatureError::Invalid(self.role);
+
+        let key_bytes: [u8; 32] = hex::decode(&self.public_key)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(invalid)?;
+        let signature_bytes: [u8; 64] = hex::decode(&self.signature)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(invalid)?;
+
+        let key = VerifyingKey::from_bytes(&key_bytes).map_err(|_| invalid())?;
+        key.verify(digest, &Signature::from_bytes(&signature_bytes))
+            .map_err(|_| invalid())
+    }
+
+    pub fn to_proto(&self) -> messages::TransferSignature {
+        messages::TransferSignature {
+            role: self.role.as_str().to_string(),
+            public_key: self.public_key.clone(),
+            signature: self.signature.clone(),
+        }
+    }
+
+    pub fn from_proto(signature: messages::TransferSignature) -> Result<Self, String> {
+        Ok(Self {
+            role: signature.role.parse()?,
+            public_key: signature.public_key,
+            signature: signature.signature,
+        })
+    }
+}
+
+/// Who has to sign a transfer of one property.
+#[derive(Debug, Clone, Default)]
+pub struct ApprovalPolicy {
+    /// Key of the current holder, if custody has been bound to a key.
+    pub custodian_key: Option<String>,
+    /// Whether the property needs an approver on every transfer.
+    pub requires_approval: bool,
+    /// Keys allowed to approve transfers.
+    pub approvers: Vec<String>,
+}
+
+impl ApprovalPolicy {
+    /// Verifies `signatures` over `digest` and checks that they cover the
+    /// required signer set. Returns the receiving key, which becomes the
+    /// custodian key once the transfer is applied.
+    ///
+    /// The receiving holder always signs. The releasing holder always signs
+    /// and must hold the custodian key when one is bound. An approver signs
+    /// when the policy asks for one, and also when no custodian key is bound,
+    /// since nothing else then ties the release to the current holder.
+    pub fn check<'a>(
+        &self,
+        digest: &[u8; 32],
+        signatures: &'a [TransferSignature],
+    ) -> Result<&'a str, SignatureError> {
+        for (index, signature) in signatures.iter().enumerate() {
+            if signatures[..index].iter().any(|other| other.role == signature.role) {
+                return Err(SignatureError::Duplicate(signature.role));
+            }
+            signature.verify(digest)?;
+        }
+        let by_role = |role| signatures.iter().find(|signature| signature.role == role);
+
+        let releasing = by_role(TransferRole::Releasing).ok_or(SignatureError::Missing(TransferRole::Releasing))?;
+        if let Some(custodian_key) = &self.custodian_key {
+            if !releasing.public_key.eq_ignore_ascii_case(custodian_key) {
+                return Err(SignatureError::Unauthorized(TransferRole::Releasing));
+            }
+        }
+
+        if self.requires_approval || self.custodian_key.is_none() {
+            let approver = by_role(TransferRole::Approver).ok_or(SignatureError::Missing(TransferRole::Approver))?;
+            if !self
+                .approvers
+                .iter()
+                .any(|key| key.eq_ignore_ascii_case(&approver.public_key))
+            {
+                return Err(SignatureError::Unauthorized(TransferRole::Approver));
+            }
+        }
+
+        let receiving = by_role(TransferRole::Receiving).ok_or(SignatureError::Missing(TransferRole::Receiving))?;
+        Ok(&receiving.public_key)
+    }
+}
+
+/// Parses the value of [`APPROVERS_SETTING`].
+pub fn parse_approvers(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|key| key.trim().to_ascii_lowercase())
+        .filter(|key| !key.is_empty())
+        .collect()
+}
+
+/// Why a set of transfer signatures was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    Missing(TransferRole),
+    Duplicate(TransferRole),
+    Invalid(TransferRole),
+    Unauthorized(TransferRole),
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::Missing(role) => write!(f, "Missing {} signature", role),
+            SignatureError::Duplicate(role) => write!(f, "Duplicate {} signature", role),
+            SignatureError::Invalid(role) => write!(f, "Invalid {} signature", role),
+            SignatureError::Unauthorized(role) => write!(f, "{} signature is from an unauthorized key", role),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn public_key(seed: u8) -> String {
+        hex::encode(key(seed).verifying_key().to_bytes())
+    }
+
+    fn digest() -> [u8; 32] {
+        transfer_digest("property-1", "custodian-1", "custodian-2", "transfer-1", "")
+    }
+
+    fn signed(roles: &[(TransferRole, u8)]) -> Vec<TransferSignature> {
+        roles
+            .iter()
+            .map(|(role, seed)| TransferSignature::sign(*role, &key(*seed), &digest()))
+            .collect()
+    }
+
+    fn bound_policy() -> ApprovalPolicy {
+        ApprovalPolicy {
+            custodian_key: Some(public_key(1)),
+            requires_approval: false,
+            approvers: vec![public_key(3)],
+        }
+    }
+
+    #[test]
+    fn test_digest_is_unambiguous() {
+        assert_ne!(
+            transfer_digest("ab", "c", "d", "e", ""),
+            transfer_digest("a", "bc", "d", "e", "")
+        );
+        assert_eq!(digest(), digest());
+        // Signing for the item with a shortage annex is a different transfer
+        assert_ne!(
+            digest(),
+            transfer_digest("property-1", "custodian-1", "custodian-2", "transfer-1", "ab12")
+        );
+    }
+
+    #[test]
+    fn test_holders_sign_bound_transfer() {
+        let signatures = signed(&[(TransferRole::Releasing, 1), (TransferRole::Receiving, 2)]);
+        assert_eq!(bound_policy().check(&digest(), &signatures), Ok(public_key(2).as_str()));
+    }
+
+    #[test]
+    fn test_missing_signers() {
+        let signatures = signed(&[(TransferRole::Releasing, 1)]);
+        assert_eq!(
+            bound_policy().check(&digest(), &signatures),
+            Err(SignatureError::Missing(TransferRole::Receiving))
+        );
+
+        let policy = ApprovalPolicy {
+            requires_approval: true,
+            ..bound_policy()
+        };
+        let signatures = signed(&[(TransferRole::Releasing, 1), (TransferRole::Receiving, 2)]);
+        assert_eq!(
+            policy.check(&digest(), &signatures),
+            Err(SignatureError::Missing(TransferRole::Approver))
+        );
+
+        let signatures = signed(&[
+            (TransferRole::Releasing, 1),
+            (TransferRole::Receiving, 2),
+            (TransferRole::Approver, 3),
+        ]);
+        assert!(policy.check(&digest(), &signatures).is_ok());
+    }
+
+    #[test]
+    fn test_unbound_custody_needs_approver() {
+        let policy = ApprovalPolicy {
+            custodian_key: None,
+            ..bound_policy()
+        };
+        let signatures = signed(&[(TransferRole::Releasing, 9), (TransferRole::Receiving, 2)]);
+        assert_eq!(
+            policy.check(&digest(), &signatures),
+            Err(SignatureError::Missing(TransferRole::Approver))
+        );
+
+        let signatures = signed(&[
+            (TransferRole::Releasing, 9),
+            (TransferRole::Receiving, 2),
+            (TransferRole::Approver, 4),
+        ]);
+        assert_eq!(
+            policy.check(&digest(), &signatures),
+            Err(SignatureError::Unauthorized(TransferRole::Approver))
+        );
+    }
+
+    #[test]
+    fn test_releasing_key_must_hold_custody() {
+        let signatures = signed(&[(TransferRole::Releasing, 9), (TransferRole::Receiving, 2)]);
+        assert_eq!(
+            bound_policy().check(&digest(), &signatures),
+            Err(SignatureError::Unauthorized(TransferRole::Releasing))
+        );
+    }
+
+    #[test]
+    fn test_rejects_forged_and_duplicate_signatures() {
+        let mut signatures = signed(&[(TransferRole::Releasing, 1), (TransferRole::Receiving, 2)]);
+        signatures[1].signature = signatures[0].signature.clone();
+        assert_eq!(
+            bound_policy().check(&digest(), &signatures),
+            Err(SignatureError::Invalid(TransferRole::Receiving))
+        );
+
+        let other = transfer_digest("property-1", "custodian-1", "custodian-3", "transfer-1", "");
+        let signatures = vec![
+            TransferSignature::sign(TransferRole::Releasing, &key(1), &digest()),
+            TransferSignature::sign(TransferRole::Receiving, &key(2), &other),
+        ];
+        assert!(bound_policy().check(&digest(), &signatures).is_err());
+
+        let signatures = signed(&[
+            (TransferRole::Releasing, 1),
+            (TransferRole::Receiving, 2),
+            (TransferRole::Receiving, 3),
+        ]);
+        assert_eq!(
+            bound_policy().check(&digest(), &signatures),
+            Err(SignatureError::Duplicate(TransferRole::Receiving))
+        );
+    }
+
+    #[test]
+    fn test_proto_round_trip() {
+        let signature = signed(&[(TransferRole::Approver, 3)]).remove(0);
+        assert_eq!(TransferSignature::from_proto(signature.to_proto()).unwrap(), signature);
+        assert_eq!(parse_approvers(" AB, ,cd "), vec!["ab", "cd"]);
+    }
+}